@@ -1,8 +1,6 @@
 use cosmwasm_schema::write_api;
 
-use cw1_subkeys::msg::{ExecuteMsg, QueryMsg};
-
-use cw1_whitelist::msg::InstantiateMsg;
+use cw1_subkeys::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 
 fn main() {
     write_api! {
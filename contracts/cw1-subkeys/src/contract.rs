@@ -5,29 +5,35 @@ use std::ops::{AddAssign, Sub};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    ensure, ensure_ne, to_binary, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, DistributionMsg,
-    Empty, Env, MessageInfo, Order, Response, StakingMsg, StdResult,
+    ensure, ensure_ne, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg,
+    Deps, DepsMut, DistributionMsg, Empty, Env, MessageInfo, Order, Response, StakingMsg, StdError,
+    StdResult, Storage, Uint128, WasmMsg,
 };
 use cw1::CanExecuteResponse;
 use cw1_whitelist::{
     contract::{
-        execute_freeze, execute_update_admins, instantiate as whitelist_instantiate,
-        query_admin_list,
+        can_execute as whitelist_can_execute, execute_freeze, execute_update_admins,
+        instantiate as whitelist_instantiate, query_admin_list,
     },
-    msg::InstantiateMsg,
     state::ADMIN_LIST,
 };
 use cw2::{get_contract_version, set_contract_version};
+use cw20::{Amount, Cw20ExecuteMsg};
 use cw_storage_plus::Bound;
-use cw_utils::Expiration;
+use cw_utils::{maybe_addr, Duration, Expiration, NativeBalance};
 use semver::Version;
 
 use crate::error::ContractError;
 use crate::msg::{
-    AllAllowancesResponse, AllPermissionsResponse, AllowanceInfo, ExecuteMsg, PermissionsInfo,
-    QueryMsg,
+    AccrualConfig, AllAllowancesResponse, AllPermissionsResponse, AllowanceInfo,
+    EffectiveAllowanceResponse, ExecuteMsg, InstantiateMsg, PermissionsInfo, QueryMsg,
+    SpendHistoryResponse, SpendInfo,
+};
+use crate::state::{
+    Accrual, Allowance, Cw20Allowance, Permissions, SpendRecord, ACCRUALS, ALLOWANCES,
+    CW20_ALLOWANCES, DEFAULT_SPEND_HISTORY_LIMIT, PERMISSIONS, SPEND_COUNT, SPEND_HISTORY,
+    SPEND_HISTORY_LIMIT,
 };
-use crate::state::{Allowance, Permissions, ALLOWANCES, PERMISSIONS};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw1-subkeys";
@@ -40,7 +46,17 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
-    let result = whitelist_instantiate(deps.branch(), env, info, msg)?;
+    let whitelist_msg = cw1_whitelist::msg::InstantiateMsg {
+        admins: msg.admins,
+        mutable: msg.mutable,
+        admin_change_threshold: None,
+    };
+    let result = whitelist_instantiate(deps.branch(), env, info, whitelist_msg)?;
+    SPEND_HISTORY_LIMIT.save(
+        deps.storage,
+        &msg.spend_history_limit
+            .unwrap_or(DEFAULT_SPEND_HISTORY_LIMIT),
+    )?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(result)
 }
@@ -72,6 +88,9 @@ pub fn execute(
             spender,
             permissions,
         } => execute_set_permissions(deps, env, info, spender, permissions),
+        ExecuteMsg::SetAccrual { spender, accrual } => {
+            execute_set_accrual(deps, env, info, spender, accrual)
+        }
     }
 }
 
@@ -86,35 +105,56 @@ where
 {
     let cfg = ADMIN_LIST.load(deps.storage)?;
 
-    // Not an admin - need to check for permissions
+    // Not an admin - need to check for permissions, on every single message
     if !cfg.is_admin(info.sender.as_ref()) {
+        let perm = PERMISSIONS.may_load(deps.storage, &info.sender)?;
+        let perm = || perm.as_ref().ok_or(ContractError::NotAllowed {});
         for msg in &msgs {
             match msg {
                 CosmosMsg::Staking(staking_msg) => {
-                    let perm = PERMISSIONS.may_load(deps.storage, &info.sender)?;
-                    let perm = perm.ok_or(ContractError::NotAllowed {})?;
-                    check_staking_permissions(staking_msg, perm)?;
+                    check_staking_permissions(staking_msg, perm()?)?;
                 }
                 CosmosMsg::Distribution(distribution_msg) => {
-                    let perm = PERMISSIONS.may_load(deps.storage, &info.sender)?;
-                    let perm = perm.ok_or(ContractError::NotAllowed {})?;
-                    check_distribution_permissions(distribution_msg, perm)?;
+                    check_distribution_permissions(distribution_msg, perm()?)?;
                 }
-                CosmosMsg::Bank(BankMsg::Send {
-                    to_address: _,
-                    amount,
-                }) => {
-                    ALLOWANCES.update::<_, ContractError>(deps.storage, &info.sender, |allow| {
-                        let mut allowance = allow.ok_or(ContractError::NoAllowance {})?;
-                        ensure!(
-                            !allowance.expires.is_expired(&env.block),
-                            ContractError::NoAllowance {}
-                        );
-
-                        // Decrease allowance
-                        allowance.balance = allowance.balance.sub(amount.clone())?;
-                        Ok(allowance)
-                    })?;
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    deduct_allowance(deps.storage, &env, &info.sender, amount)?;
+                    record_spend(deps.storage, &env.block, &info.sender, to_address, amount)?;
+                }
+                CosmosMsg::Bank(BankMsg::Burn { .. }) => {
+                    ensure!(perm()?.allow_bank_burn, ContractError::BurnPerm {});
+                }
+                CosmosMsg::Wasm(wasm_msg) => {
+                    // tokens the subkey has a cw20 allowance for need no wasm permission
+                    match cw20_spend(deps.storage, &info.sender, wasm_msg)? {
+                        Some((token, amount)) => {
+                            deduct_cw20_allowance(
+                                deps.storage,
+                                &env.block,
+                                &info.sender,
+                                &token,
+                                amount,
+                            )?;
+                        }
+                        None => check_wasm_permissions(wasm_msg, perm()?)?,
+                    }
+                    if let WasmMsg::Execute {
+                        contract_addr,
+                        funds,
+                        ..
+                    } = wasm_msg
+                    {
+                        if !funds.is_empty() {
+                            deduct_allowance(deps.storage, &env, &info.sender, funds)?;
+                            record_spend(
+                                deps.storage,
+                                &env.block,
+                                &info.sender,
+                                contract_addr,
+                                funds,
+                            )?;
+                        }
+                    }
                 }
                 _ => {
                     return Err(ContractError::MessageTypeRejected {});
@@ -130,9 +170,143 @@ where
     Ok(res)
 }
 
+/// Returns the allowance (not filtered by expiration) and accrual of the spender, with all
+/// the accrual elapsed up to this block added, without storing anything
+fn settled_allowance(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    spender: &Addr,
+) -> StdResult<(Option<Allowance>, Option<Accrual>)> {
+    let allowance = ALLOWANCES.may_load(storage, spender)?;
+    let mut accrual = ACCRUALS.may_load(storage, spender)?;
+    let allowance = match accrual.as_mut() {
+        Some(accrual) => {
+            let allowance = allowance.unwrap_or_default();
+            let balance = accrual.settle(block, allowance.balance.clone());
+            Some(Allowance {
+                balance,
+                ..allowance
+            })
+            .filter(|allowance| !allowance.balance.is_empty())
+        }
+        None => allowance,
+    };
+    Ok((allowance, accrual))
+}
+
+/// Stores the accrual elapsed since it was last settled. A no-op for subkeys without accrual
+fn settle_accrual(storage: &mut dyn Storage, block: &BlockInfo, spender: &Addr) -> StdResult<()> {
+    if let (allowance, Some(accrual)) = settled_allowance(storage, block, spender)? {
+        if let Some(allowance) = allowance {
+            ALLOWANCES.save(storage, spender, &allowance)?;
+        }
+        ACCRUALS.save(storage, spender, &accrual)?;
+    }
+    Ok(())
+}
+
+fn deduct_allowance(
+    storage: &mut dyn Storage,
+    env: &Env,
+    spender: &Addr,
+    amount: &[Coin],
+) -> Result<Allowance, ContractError> {
+    settle_accrual(storage, &env.block, spender)?;
+    ALLOWANCES.update::<_, ContractError>(storage, spender, |allow| {
+        let mut allowance = allow.ok_or(ContractError::NoAllowance {})?;
+        ensure!(
+            !allowance.expires.is_expired(&env.block),
+            ContractError::NoAllowance {}
+        );
+
+        // Decrease allowance
+        allowance.balance = allowance.balance.sub(amount.to_vec())?;
+        Ok(allowance)
+    })
+}
+
+/// Returns the token and amount spent by a `WasmMsg::Execute` to a cw20 token the spender has
+/// an allowance for, or None for any other message. Only `Transfer`, `Send` and `Burn` may be
+/// relayed to such a token, anything else (including unparseable messages) is rejected
+fn cw20_spend(
+    storage: &dyn Storage,
+    spender: &Addr,
+    wasm_msg: &WasmMsg,
+) -> Result<Option<(Addr, Uint128)>, ContractError> {
+    let (token, msg) = match wasm_msg {
+        WasmMsg::Execute {
+            contract_addr, msg, ..
+        } => (Addr::unchecked(contract_addr), msg),
+        _ => return Ok(None),
+    };
+    if !CW20_ALLOWANCES.has(storage, (spender, &token)) {
+        return Ok(None);
+    }
+
+    let rejected = || ContractError::Cw20MessageRejected {
+        token: token.to_string(),
+    };
+    let amount = match from_binary(msg).map_err(|_| rejected())? {
+        Cw20ExecuteMsg::Transfer { amount, .. }
+        | Cw20ExecuteMsg::Send { amount, .. }
+        | Cw20ExecuteMsg::Burn { amount } => amount,
+        _ => return Err(rejected()),
+    };
+    Ok(Some((token, amount)))
+}
+
+fn deduct_cw20_allowance(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    spender: &Addr,
+    token: &Addr,
+    amount: Uint128,
+) -> Result<Cw20Allowance, ContractError> {
+    CW20_ALLOWANCES.update::<_, ContractError>(storage, (spender, token), |allow| {
+        let mut allowance = allow
+            .filter(|allow| !allow.expires.is_expired(block))
+            .ok_or(ContractError::NoAllowance {})?;
+        allowance.amount = allowance
+            .amount
+            .checked_sub(amount)
+            .map_err(StdError::overflow)?;
+        Ok(allowance)
+    })
+}
+
+/// Appends a spend to the subkey's history, dropping the oldest one beyond the limit
+fn record_spend(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    spender: &Addr,
+    recipient: &str,
+    amount: &[Coin],
+) -> StdResult<()> {
+    let limit = SPEND_HISTORY_LIMIT
+        .may_load(storage)?
+        .unwrap_or(DEFAULT_SPEND_HISTORY_LIMIT) as u64;
+    if limit == 0 {
+        return Ok(());
+    }
+
+    let id = SPEND_COUNT.may_load(storage, spender)?.unwrap_or_default();
+    let record = SpendRecord {
+        height: block.height,
+        time: block.time,
+        amount: amount.to_vec(),
+        recipient: recipient.to_owned(),
+    };
+    SPEND_HISTORY.save(storage, (spender, id), &record)?;
+    SPEND_COUNT.save(storage, spender, &(id + 1))?;
+    if id >= limit {
+        SPEND_HISTORY.remove(storage, (spender, id - limit));
+    }
+    Ok(())
+}
+
 pub fn check_staking_permissions(
     staking_msg: &StakingMsg,
-    permissions: Permissions,
+    permissions: &Permissions,
 ) -> Result<(), ContractError> {
     match staking_msg {
         StakingMsg::Delegate { .. } => {
@@ -151,7 +325,7 @@ pub fn check_staking_permissions(
 
 pub fn check_distribution_permissions(
     distribution_msg: &DistributionMsg,
-    permissions: Permissions,
+    permissions: &Permissions,
 ) -> Result<(), ContractError> {
     match distribution_msg {
         DistributionMsg::SetWithdrawAddress { .. } => {
@@ -165,12 +339,33 @@ pub fn check_distribution_permissions(
     Ok(())
 }
 
+pub fn check_wasm_permissions(
+    wasm_msg: &WasmMsg,
+    permissions: &Permissions,
+) -> Result<(), ContractError> {
+    match wasm_msg {
+        WasmMsg::Execute { contract_addr, .. } => {
+            ensure!(
+                permissions
+                    .allowed_wasm_contracts
+                    .iter()
+                    .any(|allowed| allowed == contract_addr),
+                ContractError::WasmExecutePerm {
+                    contract: contract_addr.clone()
+                }
+            );
+        }
+        _ => return Err(ContractError::UnsupportedMessage {}),
+    }
+    Ok(())
+}
+
 pub fn execute_increase_allowance<T>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     spender: String,
-    amount: Coin,
+    amount: Amount,
     expires: Option<Expiration>,
 ) -> Result<Response<T>, ContractError>
 where
@@ -186,6 +381,29 @@ where
         ContractError::CannotSetOwnAccount {}
     );
 
+    let amount = match amount {
+        Amount::Native(amount) => amount,
+        Amount::Cw20(amount) => {
+            let token = deps.api.addr_validate(&amount.address)?;
+            increase_cw20_allowance(
+                deps.storage,
+                &env.block,
+                &spender_addr,
+                &token,
+                amount.amount,
+                expires,
+            )?;
+            let res = Response::new()
+                .add_attribute("action", "increase_allowance")
+                .add_attribute("owner", info.sender)
+                .add_attribute("spender", spender)
+                .add_attribute("token", token)
+                .add_attribute("amount", amount.amount);
+            return Ok(res);
+        }
+    };
+
+    settle_accrual(deps.storage, &env.block, &spender_addr)?;
     ALLOWANCES.update::<_, ContractError>(deps.storage, &spender_addr, |allow| {
         let prev_expires = allow
             .as_ref()
@@ -224,7 +442,7 @@ pub fn execute_decrease_allowance<T>(
     env: Env,
     info: MessageInfo,
     spender: String,
-    amount: Coin,
+    amount: Amount,
     expires: Option<Expiration>,
 ) -> Result<Response<T>, ContractError>
 where
@@ -240,6 +458,29 @@ where
         ContractError::CannotSetOwnAccount {}
     );
 
+    let amount = match amount {
+        Amount::Native(amount) => amount,
+        Amount::Cw20(amount) => {
+            let token = deps.api.addr_validate(&amount.address)?;
+            decrease_cw20_allowance(
+                deps.storage,
+                &env.block,
+                &spender_addr,
+                &token,
+                amount.amount,
+                expires,
+            )?;
+            let res = Response::new()
+                .add_attribute("action", "decrease_allowance")
+                .add_attribute("owner", info.sender)
+                .add_attribute("spender", spender)
+                .add_attribute("token", token)
+                .add_attribute("amount", amount.amount);
+            return Ok(res);
+        }
+    };
+
+    settle_accrual(deps.storage, &env.block, &spender_addr)?;
     let allowance =
         ALLOWANCES.update::<_, ContractError>(deps.storage, &spender_addr, |allow| {
             // Fail fast
@@ -272,12 +513,73 @@ where
     Ok(res)
 }
 
+// same expiration rules as the native allowance, kept per token
+fn increase_cw20_allowance(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    spender: &Addr,
+    token: &Addr,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Cw20Allowance, ContractError> {
+    CW20_ALLOWANCES.update::<_, ContractError>(storage, (spender, token), |allow| {
+        let prev_expires = allow
+            .as_ref()
+            .map(|allow| allow.expires)
+            .unwrap_or_default();
+
+        let mut allowance = allow
+            .filter(|allow| !allow.expires.is_expired(block))
+            .unwrap_or_default();
+
+        if let Some(exp) = expires {
+            if exp.is_expired(block) {
+                return Err(ContractError::SettingExpiredAllowance(exp));
+            }
+
+            allowance.expires = exp;
+        } else if prev_expires.is_expired(block) {
+            return Err(ContractError::SettingExpiredAllowance(prev_expires));
+        }
+
+        allowance.amount += amount;
+        Ok(allowance)
+    })
+}
+
+// unlike native allowances, emptied cw20 allowances are kept, see CW20_ALLOWANCES
+fn decrease_cw20_allowance(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    spender: &Addr,
+    token: &Addr,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Cw20Allowance, ContractError> {
+    CW20_ALLOWANCES.update::<_, ContractError>(storage, (spender, token), |allow| {
+        let mut allowance = allow
+            .filter(|allow| !allow.expires.is_expired(block))
+            .ok_or(ContractError::NoAllowance {})?;
+
+        if let Some(exp) = expires {
+            if exp.is_expired(block) {
+                return Err(ContractError::SettingExpiredAllowance(exp));
+            }
+
+            allowance.expires = exp;
+        }
+
+        allowance.amount = allowance.amount.saturating_sub(amount);
+        Ok(allowance)
+    })
+}
+
 pub fn execute_set_permissions<T>(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     spender: String,
-    perm: Permissions,
+    mut perm: Permissions,
 ) -> Result<Response<T>, ContractError>
 where
     T: Clone + fmt::Debug + PartialEq + JsonSchema,
@@ -291,6 +593,11 @@ where
         spender_addr,
         ContractError::CannotSetOwnAccount {}
     );
+    perm.allowed_wasm_contracts = perm
+        .allowed_wasm_contracts
+        .iter()
+        .map(|contract| deps.api.addr_validate(contract.as_str()))
+        .collect::<StdResult<_>>()?;
     PERMISSIONS.save(deps.storage, &spender_addr, &perm)?;
 
     let res = Response::new()
@@ -301,11 +608,80 @@ where
     Ok(res)
 }
 
+pub fn execute_set_accrual<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    accrual: Option<AccrualConfig>,
+) -> Result<Response<T>, ContractError>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    ensure!(cfg.is_admin(&info.sender), ContractError::Unauthorized {});
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ensure_ne!(
+        info.sender,
+        spender_addr,
+        ContractError::CannotSetOwnAccount {}
+    );
+
+    // whatever accrued under the previous terms is kept
+    settle_accrual(deps.storage, &env.block, &spender_addr)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_accrual")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender);
+    let res = match accrual {
+        Some(AccrualConfig {
+            amount,
+            period,
+            max,
+        }) => {
+            let mut amount = NativeBalance(amount);
+            amount.normalize();
+            let zero_period = matches!(period, Duration::Height(0) | Duration::Time(0));
+            ensure!(
+                !amount.is_empty() && !zero_period,
+                ContractError::InvalidAccrual {}
+            );
+            let max = max.map(|max| {
+                let mut max = NativeBalance(max);
+                max.normalize();
+                max
+            });
+
+            let accrual = Accrual {
+                last_settled: Accrual::now(&period, &env.block),
+                amount,
+                period,
+                max,
+            };
+            ACCRUALS.save(deps.storage, &spender_addr, &accrual)?;
+            res.add_attribute("amount", accrual.amount.to_string())
+        }
+        None => {
+            ACCRUALS.remove(deps.storage, &spender_addr);
+            res.add_attribute("amount", "none")
+        }
+    };
+    Ok(res)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::AdminList {} => to_binary(&query_admin_list(deps)?),
         QueryMsg::Allowance { spender } => to_binary(&query_allowance(deps, env, spender)?),
+        QueryMsg::EffectiveAllowance { spender } => {
+            to_binary(&query_effective_allowance(deps, env, spender)?)
+        }
+        QueryMsg::Cw20Allowance { spender, token } => {
+            to_binary(&query_cw20_allowance(deps, env, spender, token)?)
+        }
         QueryMsg::Permissions { spender } => to_binary(&query_permissions(deps, spender)?),
         QueryMsg::CanExecute { sender, msg } => {
             to_binary(&query_can_execute(deps, env, sender, msg)?)
@@ -316,6 +692,11 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::AllPermissions { start_after, limit } => {
             to_binary(&query_all_permissions(deps, start_after, limit)?)
         }
+        QueryMsg::SpendHistory {
+            spender,
+            start_after,
+            limit,
+        } => to_binary(&query_spend_history(deps, spender, start_after, limit)?),
     }
 }
 
@@ -331,6 +712,43 @@ pub fn query_allowance(deps: Deps, env: Env, spender: String) -> StdResult<Allow
     Ok(allow)
 }
 
+// like query_allowance, with the accrual elapsed up to now added in `effective`
+pub fn query_effective_allowance(
+    deps: Deps,
+    env: Env,
+    spender: String,
+) -> StdResult<EffectiveAllowanceResponse> {
+    let stored = query_allowance(deps, env.clone(), spender.clone())?;
+    let spender = deps.api.addr_validate(&spender)?;
+    let (effective, accrual) = settled_allowance(deps.storage, &env.block, &spender)?;
+    let effective = effective
+        .filter(|allow| !allow.expires.is_expired(&env.block))
+        .unwrap_or_default();
+
+    Ok(EffectiveAllowanceResponse {
+        stored,
+        effective,
+        accrual,
+    })
+}
+
+// like query_allowance, for a single cw20 token
+pub fn query_cw20_allowance(
+    deps: Deps,
+    env: Env,
+    spender: String,
+    token: String,
+) -> StdResult<Cw20Allowance> {
+    let spender = deps.api.addr_validate(&spender)?;
+    let token = deps.api.addr_validate(&token)?;
+    let allow = CW20_ALLOWANCES
+        .may_load(deps.storage, (&spender, &token))?
+        .filter(|allow| !allow.expires.is_expired(&env.block))
+        .unwrap_or_default();
+
+    Ok(allow)
+}
+
 // if the subkey has no permissions, return an empty struct (not an error)
 pub fn query_permissions(deps: Deps, spender: String) -> StdResult<Permissions> {
     let spender = deps.api.addr_validate(&spender)?;
@@ -347,44 +765,60 @@ fn query_can_execute(
     msg: CosmosMsg,
 ) -> StdResult<CanExecuteResponse> {
     Ok(CanExecuteResponse {
-        can_execute: can_execute(deps, env, sender, msg)?,
+        can_execute: can_execute(deps, env, &sender, msg)?,
     })
 }
 
 // this can just return booleans and the query_can_execute wrapper creates the struct once, not on every path
-fn can_execute(deps: Deps, env: Env, sender: String, msg: CosmosMsg) -> StdResult<bool> {
-    let cfg = ADMIN_LIST.load(deps.storage)?;
-    if cfg.is_admin(&sender) {
+fn can_execute(deps: Deps, env: Env, sender: &str, msg: CosmosMsg) -> StdResult<bool> {
+    // admins may execute anything, just like in the whitelist
+    if whitelist_can_execute(deps, sender, &msg)? {
         return Ok(true);
     }
 
-    let sender = deps.api.addr_validate(&sender)?;
-    match msg {
-        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => {
-            // now we check if there is enough allowance for this message
-            let allowance = ALLOWANCES.may_load(deps.storage, &sender)?;
-            match allowance {
-                // if there is an allowance, we subtract the requested amount to ensure it is covered (error on underflow)
-                Some(allow) => {
-                    Ok(!allow.expires.is_expired(&env.block) && allow.balance.sub(amount).is_ok())
-                }
-                None => Ok(false),
-            }
-        }
-        CosmosMsg::Staking(staking_msg) => {
-            let perm_opt = PERMISSIONS.may_load(deps.storage, &sender)?;
-            match perm_opt {
-                Some(permission) => Ok(check_staking_permissions(&staking_msg, permission).is_ok()),
-                None => Ok(false),
+    let sender = deps.api.addr_validate(sender)?;
+    // now we check if there is enough allowance for this amount
+    let covered = |amount: Vec<Coin>| -> StdResult<bool> {
+        let (allowance, _) = settled_allowance(deps.storage, &env.block, &sender)?;
+        match allowance {
+            // if there is an allowance, we subtract the requested amount to ensure it is covered (error on underflow)
+            Some(allow) => {
+                Ok(!allow.expires.is_expired(&env.block) && allow.balance.sub(amount).is_ok())
             }
+            None => Ok(false),
         }
-        CosmosMsg::Distribution(distribution_msg) => {
-            let perm_opt = PERMISSIONS.may_load(deps.storage, &sender)?;
-            match perm_opt {
-                Some(permission) => {
-                    Ok(check_distribution_permissions(&distribution_msg, permission).is_ok())
+    };
+    let perm = PERMISSIONS.may_load(deps.storage, &sender)?;
+    match (msg, perm) {
+        (CosmosMsg::Bank(BankMsg::Send { amount, .. }), _) => covered(amount),
+        (CosmosMsg::Bank(BankMsg::Burn { .. }), Some(permission)) => Ok(permission.allow_bank_burn),
+        (CosmosMsg::Staking(staking_msg), Some(permission)) => {
+            Ok(check_staking_permissions(&staking_msg, &permission).is_ok())
+        }
+        (CosmosMsg::Distribution(distribution_msg), Some(permission)) => {
+            Ok(check_distribution_permissions(&distribution_msg, &permission).is_ok())
+        }
+        (CosmosMsg::Wasm(wasm_msg), perm) => {
+            match cw20_spend(deps.storage, &sender, &wasm_msg) {
+                Ok(Some((token, amount))) => {
+                    let allow = CW20_ALLOWANCES.load(deps.storage, (&sender, &token))?;
+                    if allow.expires.is_expired(&env.block) || allow.amount < amount {
+                        return Ok(false);
+                    }
+                }
+                Ok(None) => {
+                    let permitted = perm.map_or(false, |permission| {
+                        check_wasm_permissions(&wasm_msg, &permission).is_ok()
+                    });
+                    if !permitted {
+                        return Ok(false);
+                    }
                 }
-                None => Ok(false),
+                Err(_) => return Ok(false),
+            }
+            match wasm_msg {
+                WasmMsg::Execute { funds, .. } if !funds.is_empty() => covered(funds),
+                _ => Ok(true),
             }
         }
         _ => Ok(false),
@@ -406,8 +840,8 @@ pub fn query_all_allowances(
     limit: Option<u32>,
 ) -> StdResult<AllAllowancesResponse> {
     let limit = calc_limit(limit);
-    // we use raw addresses here....
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
 
     let allowances = ALLOWANCES
         .range(deps.storage, start, None, Order::Ascending)
@@ -437,7 +871,8 @@ pub fn query_all_permissions(
     limit: Option<u32>,
 ) -> StdResult<AllPermissionsResponse> {
     let limit = calc_limit(limit);
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
 
     let permissions = PERMISSIONS
         .range(deps.storage, start, None, Order::Ascending)
@@ -452,6 +887,34 @@ pub fn query_all_permissions(
     Ok(AllPermissionsResponse { permissions })
 }
 
+// return the spends of a subkey, newest first
+pub fn query_spend_history(
+    deps: Deps,
+    spender: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SpendHistoryResponse> {
+    let spender = deps.api.addr_validate(&spender)?;
+    let limit = calc_limit(limit);
+    let end = start_after.map(Bound::exclusive);
+
+    let spends = SPEND_HISTORY
+        .prefix(&spender)
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, spend)| SpendInfo {
+                id,
+                height: spend.height,
+                time: spend.time,
+                amount: spend.amount,
+                recipient: spend.recipient,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(SpendHistoryResponse { spends })
+}
+
 // Migrate contract if version is lower than current version
 #[entry_point]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: Empty) -> Result<Response, ContractError> {
@@ -473,7 +936,7 @@ mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
-    use cosmwasm_std::{coin, coins, OwnedDeps, StakingMsg, SubMsg, Timestamp};
+    use cosmwasm_std::{coin, coins, from_slice, OwnedDeps, StakingMsg, SubMsg, Timestamp};
 
     use cw1_whitelist::msg::AdminListResponse;
     use cw2::{get_contract_version, ContractVersion};
@@ -504,12 +967,16 @@ mod tests {
         redelegate: true,
         undelegate: true,
         withdraw: true,
+        allowed_wasm_contracts: Vec::new(),
+        allow_bank_burn: true,
     };
     const NO_PERMS: Permissions = Permissions {
         delegate: false,
         redelegate: false,
         undelegate: false,
         withdraw: false,
+        allowed_wasm_contracts: Vec::new(),
+        allow_bank_burn: false,
     };
 
     // Expiration constant working properly with default `mock_env`
@@ -603,6 +1070,7 @@ mod tests {
             let instantiate_msg = InstantiateMsg {
                 admins,
                 mutable: true,
+                spend_history_limit: None,
             };
             let owner = mock_info(OWNER, &[]);
 
@@ -624,7 +1092,7 @@ mod tests {
                 for amount in allowances {
                     let msg = ExecuteMsg::IncreaseAllowance {
                         spender: name.to_owned(),
-                        amount,
+                        amount: amount.into(),
                         expires,
                     };
 
@@ -677,6 +1145,7 @@ mod tests {
             ContractVersion {
                 contract: CONTRACT_NAME.to_string(),
                 version: CONTRACT_VERSION.to_string(),
+                commit_hash: None,
             },
             get_contract_version(&deps.storage).unwrap()
         )
@@ -1029,7 +1498,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(3, TOKEN1),
+                    amount: coin(3, TOKEN1).into(),
                     expires: None,
                 },
             )
@@ -1071,7 +1540,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(3, TOKEN1),
+                    amount: coin(3, TOKEN1).into(),
                     expires: Some(NON_EXPIRED_HEIGHT),
                 },
             )
@@ -1110,7 +1579,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(3, TOKEN2),
+                    amount: coin(3, TOKEN2).into(),
                     expires: None,
                 },
             )
@@ -1149,7 +1618,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER2.to_owned(),
-                    amount: coin(3, TOKEN1),
+                    amount: coin(3, TOKEN1).into(),
                     expires: None,
                 },
             )
@@ -1195,7 +1664,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER2.to_owned(),
-                    amount: coin(3, TOKEN1),
+                    amount: coin(3, TOKEN1).into(),
                     expires: Some(NON_EXPIRED_HEIGHT),
                 },
             )
@@ -1242,7 +1711,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(2, TOKEN2),
+                    amount: coin(2, TOKEN2).into(),
                     expires: Some(NON_EXPIRED_TIME),
                 },
             )
@@ -1282,7 +1751,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(2, TOKEN2),
+                    amount: coin(2, TOKEN2).into(),
                     expires: Some(EXPIRED_TIME),
                 },
             );
@@ -1321,7 +1790,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(2, TOKEN2),
+                    amount: coin(2, TOKEN2).into(),
                     expires: None,
                 },
             )
@@ -1357,7 +1826,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(4, TOKEN1),
+                    amount: coin(4, TOKEN1).into(),
                     expires: None,
                 },
             )
@@ -1398,7 +1867,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(10, TOKEN1),
+                    amount: coin(10, TOKEN1).into(),
                     expires: None,
                 },
             )
@@ -1439,7 +1908,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(15, TOKEN1),
+                    amount: coin(15, TOKEN1).into(),
                     expires: None,
                 },
             )
@@ -1479,7 +1948,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(10, TOKEN1),
+                    amount: coin(10, TOKEN1).into(),
                     expires: None,
                 },
             )
@@ -1512,7 +1981,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(4, TOKEN1),
+                    amount: coin(4, TOKEN1).into(),
                     expires: Some(NON_EXPIRED_TIME),
                 },
             )
@@ -1552,7 +2021,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(4, TOKEN2),
+                    amount: coin(4, TOKEN2).into(),
                     expires: None,
                 },
             )
@@ -1587,7 +2056,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER2.to_owned(),
-                    amount: coin(4, TOKEN1),
+                    amount: coin(4, TOKEN1).into(),
                     expires: None,
                 },
             )
@@ -1623,7 +2092,7 @@ mod tests {
                 owner,
                 ExecuteMsg::DecreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(2, TOKEN1),
+                    amount: coin(2, TOKEN1).into(),
                     expires: Some(NON_EXPIRED_TIME),
                 },
             )
@@ -1652,7 +2121,7 @@ mod tests {
                 owner,
                 ExecuteMsg::IncreaseAllowance {
                     spender: SPENDER1.to_owned(),
-                    amount: coin(2, TOKEN1),
+                    amount: coin(2, TOKEN1).into(),
                     expires: Some(EXPIRED_TIME),
                 },
             )
@@ -2064,6 +2533,496 @@ mod tests {
         }
     }
 
+    mod wasm_permission {
+        use super::*;
+
+        const CONTRACT1: &str = "contract1";
+        const CONTRACT2: &str = "contract2";
+
+        fn wasm_execute(contract: &str, funds: Vec<Coin>) -> CosmosMsg {
+            WasmMsg::Execute {
+                contract_addr: contract.to_owned(),
+                msg: to_binary(&Empty {}).unwrap(),
+                funds,
+            }
+            .into()
+        }
+
+        fn contract_perms(allow_bank_burn: bool) -> Permissions {
+            Permissions {
+                allowed_wasm_contracts: vec![Addr::unchecked(CONTRACT1)],
+                allow_bank_burn,
+                ..ALL_PERMS
+            }
+        }
+
+        #[test]
+        fn allowed() {
+            let Suite { mut deps, .. } = SuiteConfig::new()
+                .with_permissions(SPENDER1, contract_perms(true))
+                .with_allowance(SPENDER1, coin(100, TOKEN1))
+                .init();
+
+            let msgs = vec![
+                wasm_execute(CONTRACT1, vec![]),
+                wasm_execute(CONTRACT1, coins(30, TOKEN1)),
+                BankMsg::Burn {
+                    amount: coins(5, TOKEN),
+                }
+                .into(),
+            ];
+            let rsp = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute { msgs: msgs.clone() },
+            )
+            .unwrap();
+            assert_eq!(
+                rsp.messages,
+                msgs.into_iter().map(SubMsg::new).collect::<Vec<_>>()
+            );
+
+            // funds attached to contract calls come out of the allowance
+            let allowance =
+                query_allowance(deps.as_ref(), mock_env(), SPENDER1.to_owned()).unwrap();
+            assert_eq!(allowance.balance, NativeBalance(coins(70, TOKEN1)));
+
+            let perms = query_permissions(deps.as_ref(), SPENDER1.to_owned()).unwrap();
+            assert_eq!(perms, contract_perms(true));
+        }
+
+        #[test]
+        fn mixed_batch_rejected() {
+            let Suite { mut deps, .. } = SuiteConfig::new()
+                .with_permissions(SPENDER1, contract_perms(false))
+                .with_permissions(SPENDER2, ALL_PERMS)
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .init();
+
+            let delegate: CosmosMsg = StakingMsg::Delegate {
+                validator: "validator1".to_owned(),
+                amount: coin(10, TOKEN1),
+            }
+            .into();
+            let cases = vec![
+                (
+                    SPENDER1,
+                    vec![delegate.clone(), wasm_execute(CONTRACT2, vec![])],
+                    ContractError::WasmExecutePerm {
+                        contract: CONTRACT2.to_owned(),
+                    },
+                ),
+                (
+                    SPENDER1,
+                    vec![
+                        wasm_execute(CONTRACT1, vec![]),
+                        BankMsg::Burn {
+                            amount: coins(5, TOKEN),
+                        }
+                        .into(),
+                    ],
+                    ContractError::BurnPerm {},
+                ),
+                (
+                    SPENDER1,
+                    vec![
+                        wasm_execute(CONTRACT1, vec![]),
+                        WasmMsg::ClearAdmin {
+                            contract_addr: CONTRACT1.to_owned(),
+                        }
+                        .into(),
+                    ],
+                    ContractError::UnsupportedMessage {},
+                ),
+                // an empty contract list denies all contracts
+                (
+                    SPENDER2,
+                    vec![wasm_execute(CONTRACT1, vec![])],
+                    ContractError::WasmExecutePerm {
+                        contract: CONTRACT1.to_owned(),
+                    },
+                ),
+            ];
+
+            for (spender, msgs, expected) in cases {
+                let err = execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(spender, &[]),
+                    ExecuteMsg::Execute { msgs },
+                )
+                .unwrap_err();
+                assert_eq!(err, expected);
+            }
+
+            // funds beyond the allowance
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs: vec![delegate, wasm_execute(CONTRACT1, coins(11, TOKEN1))],
+                },
+            )
+            .unwrap_err();
+        }
+
+        #[test]
+        fn can_execute() {
+            let Suite { deps, .. } = SuiteConfig::new()
+                .with_permissions(SPENDER1, contract_perms(false))
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .init();
+
+            let cases = vec![
+                (wasm_execute(CONTRACT1, vec![]), true),
+                (wasm_execute(CONTRACT1, coins(10, TOKEN1)), true),
+                (wasm_execute(CONTRACT1, coins(11, TOKEN1)), false),
+                (wasm_execute(CONTRACT2, vec![]), false),
+                (
+                    BankMsg::Burn {
+                        amount: coins(5, TOKEN),
+                    }
+                    .into(),
+                    false,
+                ),
+            ];
+
+            for (msg, can_execute) in cases {
+                let resp =
+                    query_can_execute(deps.as_ref(), mock_env(), SPENDER1.to_owned(), msg.clone())
+                        .unwrap();
+                assert_eq!(
+                    resp,
+                    CanExecuteResponse { can_execute },
+                    "Original message: {:#?}",
+                    msg
+                );
+            }
+        }
+    }
+
+    mod cw20_allowance {
+        use super::*;
+
+        use cw20::Cw20Coin;
+
+        const CW20: &str = "cw20token";
+
+        fn cw20_execute(msg: &Cw20ExecuteMsg) -> CosmosMsg {
+            WasmMsg::Execute {
+                contract_addr: CW20.to_owned(),
+                msg: to_binary(msg).unwrap(),
+                funds: vec![],
+            }
+            .into()
+        }
+
+        fn transfer(amount: u128) -> CosmosMsg {
+            cw20_execute(&Cw20ExecuteMsg::Transfer {
+                recipient: "recipient".to_owned(),
+                amount: amount.into(),
+            })
+        }
+
+        fn suite_with_cw20_allowance(amount: u128) -> Suite {
+            let mut suite = Suite::init();
+            execute(
+                suite.deps.as_mut(),
+                mock_env(),
+                suite.owner.clone(),
+                ExecuteMsg::IncreaseAllowance {
+                    spender: SPENDER1.to_owned(),
+                    amount: Cw20Coin {
+                        address: CW20.to_owned(),
+                        amount: amount.into(),
+                    }
+                    .into(),
+                    expires: None,
+                },
+            )
+            .unwrap();
+            suite
+        }
+
+        fn can_execute(suite: &Suite, msg: CosmosMsg) -> bool {
+            query_can_execute(suite.deps.as_ref(), mock_env(), SPENDER1.to_owned(), msg)
+                .unwrap()
+                .can_execute
+        }
+
+        #[test]
+        fn spends_exactly_allowance() {
+            let mut suite = suite_with_cw20_allowance(100);
+            // without any wasm permissions
+            assert!(can_execute(&suite, transfer(100)));
+            assert!(!can_execute(&suite, transfer(101)));
+
+            let msgs = vec![
+                transfer(40),
+                cw20_execute(&Cw20ExecuteMsg::Send {
+                    contract: "contract".to_owned(),
+                    amount: 50u128.into(),
+                    msg: Binary::default(),
+                }),
+                cw20_execute(&Cw20ExecuteMsg::Burn {
+                    amount: 10u128.into(),
+                }),
+            ];
+            let rsp = execute(
+                suite.deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute { msgs: msgs.clone() },
+            )
+            .unwrap();
+            assert_eq!(
+                rsp.messages,
+                msgs.into_iter().map(SubMsg::new).collect::<Vec<_>>()
+            );
+
+            let allowance = query_cw20_allowance(
+                suite.deps.as_ref(),
+                mock_env(),
+                SPENDER1.to_owned(),
+                CW20.to_owned(),
+            )
+            .unwrap();
+            assert_eq!(allowance.amount, Uint128::zero());
+            assert!(!can_execute(&suite, transfer(1)));
+
+            execute(
+                suite.deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute {
+                    msgs: vec![transfer(1)],
+                },
+            )
+            .unwrap_err();
+        }
+
+        #[test]
+        fn disguised_transfers_rejected() {
+            let mut suite = suite_with_cw20_allowance(100);
+            // even with permission to call the contract, only metered messages pass
+            execute(
+                suite.deps.as_mut(),
+                mock_env(),
+                suite.owner.clone(),
+                ExecuteMsg::SetPermissions {
+                    spender: SPENDER1.to_owned(),
+                    permissions: Permissions {
+                        allowed_wasm_contracts: vec![Addr::unchecked(CW20)],
+                        ..NO_PERMS
+                    },
+                },
+            )
+            .unwrap();
+
+            let approve = cw20_execute(&Cw20ExecuteMsg::IncreaseAllowance {
+                spender: "accomplice".to_owned(),
+                amount: 1000u128.into(),
+                expires: None,
+            });
+            let unparseable: CosmosMsg = WasmMsg::Execute {
+                contract_addr: CW20.to_owned(),
+                msg: Binary::from(br#"{"transfer":{"recipient":"recipient"}}"#),
+                funds: vec![],
+            }
+            .into();
+
+            for msg in [approve, unparseable] {
+                assert!(!can_execute(&suite, msg.clone()));
+                let err = execute(
+                    suite.deps.as_mut(),
+                    mock_env(),
+                    mock_info(SPENDER1, &[]),
+                    ExecuteMsg::Execute { msgs: vec![msg] },
+                )
+                .unwrap_err();
+                assert_eq!(
+                    err,
+                    ContractError::Cw20MessageRejected {
+                        token: CW20.to_owned()
+                    }
+                );
+            }
+
+            let allowance = query_cw20_allowance(
+                suite.deps.as_ref(),
+                mock_env(),
+                SPENDER1.to_owned(),
+                CW20.to_owned(),
+            )
+            .unwrap();
+            assert_eq!(allowance.amount, Uint128::new(100));
+        }
+    }
+
+    mod accrual {
+        use super::*;
+
+        fn env_at(height: u64) -> Env {
+            let mut env = mock_env();
+            env.block.height = height;
+            env
+        }
+
+        fn effective(deps: Deps, height: u64) -> NativeBalance {
+            query_effective_allowance(deps, env_at(height), SPENDER1.to_owned())
+                .unwrap()
+                .effective
+                .balance
+        }
+
+        fn spend(deps: DepsMut, height: u64, amount: u128) -> Result<Response, ContractError> {
+            let msgs = vec![BankMsg::Send {
+                to_address: SPENDER2.to_owned(),
+                amount: coins(amount, TOKEN1),
+            }
+            .into()];
+            execute(
+                deps,
+                env_at(height),
+                mock_info(SPENDER1, &[]),
+                ExecuteMsg::Execute { msgs },
+            )
+        }
+
+        #[test]
+        fn accrues_every_period_up_to_cap() {
+            let Suite { mut deps, owner } = Suite::init();
+            let start = mock_env().block.height;
+
+            let msg = ExecuteMsg::SetAccrual {
+                spender: SPENDER1.to_owned(),
+                accrual: Some(AccrualConfig {
+                    amount: coins(5, TOKEN1),
+                    period: Duration::Height(10),
+                    max: Some(coins(12, TOKEN1)),
+                }),
+            };
+            execute(deps.as_mut(), env_at(start), owner.clone(), msg).unwrap();
+
+            // nothing before the first full period
+            assert_eq!(effective(deps.as_ref(), start + 9), NativeBalance(vec![]));
+            spend(deps.as_mut(), start + 9, 1).unwrap_err();
+            assert_eq!(
+                effective(deps.as_ref(), start + 10),
+                NativeBalance(coins(5, TOKEN1))
+            );
+            assert_eq!(
+                effective(deps.as_ref(), start + 29),
+                NativeBalance(coins(10, TOKEN1))
+            );
+            // saturates at the cap
+            assert_eq!(
+                effective(deps.as_ref(), start + 100),
+                NativeBalance(coins(12, TOKEN1))
+            );
+
+            // spending settles first, and accrual goes on from there
+            spend(deps.as_mut(), start + 25, 8).unwrap();
+            let res =
+                query_effective_allowance(deps.as_ref(), env_at(start + 25), SPENDER1.to_owned())
+                    .unwrap();
+            assert_eq!(res.stored.balance, NativeBalance(coins(2, TOKEN1)));
+            assert_eq!(res.effective.balance, NativeBalance(coins(2, TOKEN1)));
+            assert_eq!(res.accrual.unwrap().last_settled, start + 20);
+            assert_eq!(
+                effective(deps.as_ref(), start + 30),
+                NativeBalance(coins(7, TOKEN1))
+            );
+
+            // increase settles first too, and manual top-ups are not cut back by the cap
+            let msg = ExecuteMsg::IncreaseAllowance {
+                spender: SPENDER1.to_owned(),
+                amount: coin(20, TOKEN1).into(),
+                expires: None,
+            };
+            execute(deps.as_mut(), env_at(start + 30), owner.clone(), msg).unwrap();
+            let stored =
+                query_allowance(deps.as_ref(), env_at(start + 30), SPENDER1.to_owned()).unwrap();
+            assert_eq!(stored.balance, NativeBalance(coins(27, TOKEN1)));
+            assert_eq!(
+                effective(deps.as_ref(), start + 60),
+                NativeBalance(coins(27, TOKEN1))
+            );
+
+            // stopping the accrual keeps what was accrued so far
+            spend(deps.as_mut(), start + 30, 27).unwrap();
+            let msg = ExecuteMsg::SetAccrual {
+                spender: SPENDER1.to_owned(),
+                accrual: None,
+            };
+            execute(deps.as_mut(), env_at(start + 40), owner, msg).unwrap();
+            assert_eq!(
+                effective(deps.as_ref(), start + 100),
+                NativeBalance(coins(5, TOKEN1))
+            );
+        }
+
+        #[test]
+        fn time_based_and_validated() {
+            let Suite { mut deps, owner } = SuiteConfig::new()
+                .with_allowance(SPENDER1, coin(1, TOKEN2))
+                .init();
+
+            let accrual = |amount: Vec<Coin>, period: Duration| ExecuteMsg::SetAccrual {
+                spender: SPENDER1.to_owned(),
+                accrual: Some(AccrualConfig {
+                    amount,
+                    period,
+                    max: None,
+                }),
+            };
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                owner.clone(),
+                accrual(coins(5, TOKEN1), Duration::Time(0)),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::InvalidAccrual {});
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                owner.clone(),
+                accrual(coins(0, TOKEN1), Duration::Time(60)),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::InvalidAccrual {});
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(SPENDER1, &[]),
+                accrual(coins(5, TOKEN1), Duration::Time(60)),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                owner,
+                accrual(coins(5, TOKEN1), Duration::Time(60)),
+            )
+            .unwrap();
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(150);
+            let res = query_effective_allowance(deps.as_ref(), env, SPENDER1.to_owned()).unwrap();
+            assert_eq!(res.stored.balance, NativeBalance(coins(1, TOKEN2)));
+            let mut balance = res.effective.balance;
+            balance.normalize();
+            assert_eq!(
+                balance,
+                NativeBalance(vec![coin(10, TOKEN1), coin(1, TOKEN2)])
+            );
+        }
+    }
+
     mod can_execute {
         use super::*;
 
@@ -2174,6 +3133,45 @@ mod tests {
             }
         }
 
+        #[test]
+        fn query_checks_message_contents() {
+            let Suite { deps, .. } = SuiteConfig::new()
+                .with_permissions(SPENDER1, ALL_PERMS)
+                .with_allowance(SPENDER1, coin(10, TOKEN1))
+                .init();
+
+            let can_execute = |msg: CosmosMsg| -> bool {
+                let query_msg = QueryMsg::CanExecute {
+                    sender: SPENDER1.to_owned(),
+                    msg,
+                };
+                let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
+                from_slice::<CanExecuteResponse>(&res).unwrap().can_execute
+            };
+
+            // over the allowance
+            assert!(!can_execute(
+                BankMsg::Send {
+                    to_address: SPENDER2.to_owned(),
+                    amount: coins(11, TOKEN1),
+                }
+                .into()
+            ));
+            // permitted staking
+            assert!(can_execute(
+                StakingMsg::Delegate {
+                    validator: SPENDER2.to_owned(),
+                    amount: coin(8, TOKEN),
+                }
+                .into()
+            ));
+
+            // nothing was executed
+            let allowance =
+                query_allowance(deps.as_ref(), mock_env(), SPENDER1.to_owned()).unwrap();
+            assert_eq!(allowance.balance, NativeBalance(coins(10, TOKEN1)));
+        }
+
         #[test]
         fn custom() {
             let Suite { deps, .. } = SuiteConfig::new()
@@ -2226,6 +3224,75 @@ mod tests {
     }
 
     // tests permissions and allowances are independent features and does not affect each other
+    #[test]
+    fn spend_history_is_pruned_and_newest_first() {
+        let mut deps = mock_dependencies();
+        let owner = mock_info(OWNER, &[]);
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![OWNER.to_owned()],
+            mutable: true,
+            spend_history_limit: Some(3),
+        };
+        instantiate(deps.as_mut(), mock_env(), owner.clone(), instantiate_msg).unwrap();
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: SPENDER1.to_owned(),
+            amount: coin(100, TOKEN1).into(),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+
+        // admins spend without an allowance, so nothing is recorded for them
+        let send = |amount: u128| -> CosmosMsg {
+            BankMsg::Send {
+                to_address: SPENDER2.to_owned(),
+                amount: coins(amount, TOKEN1),
+            }
+            .into()
+        };
+        let msg = ExecuteMsg::Execute {
+            msgs: vec![send(50)],
+        };
+        execute(deps.as_mut(), mock_env(), owner, msg).unwrap();
+
+        for amount in 1..=5 {
+            let mut env = mock_env();
+            env.block.height += amount as u64;
+            let msg = ExecuteMsg::Execute {
+                msgs: vec![send(amount)],
+            };
+            execute(deps.as_mut(), env, mock_info(SPENDER1, &[]), msg).unwrap();
+        }
+
+        let history = query_spend_history(deps.as_ref(), SPENDER1.to_owned(), None, None).unwrap();
+        let amounts: Vec<_> = history
+            .spends
+            .iter()
+            .map(|spend| (spend.id, spend.amount.clone()))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                (4, coins(5, TOKEN1)),
+                (3, coins(4, TOKEN1)),
+                (2, coins(3, TOKEN1)),
+            ]
+        );
+        assert_eq!(history.spends[0].height, mock_env().block.height + 5);
+        assert_eq!(history.spends[0].recipient, SPENDER2);
+
+        // paginating goes further back in time
+        let history =
+            query_spend_history(deps.as_ref(), SPENDER1.to_owned(), Some(4), Some(1)).unwrap();
+        assert_eq!(history.spends.len(), 1);
+        assert_eq!(history.spends[0].id, 3);
+        let history =
+            query_spend_history(deps.as_ref(), SPENDER1.to_owned(), Some(2), None).unwrap();
+        assert_eq!(history.spends, vec![]);
+
+        let history = query_spend_history(deps.as_ref(), OWNER.to_owned(), None, None).unwrap();
+        assert_eq!(history.spends, vec![]);
+    }
+
     #[test]
     fn permissions_allowances_independent() {
         let mut deps = mock_dependencies();
@@ -2249,6 +3316,7 @@ mod tests {
             redelegate: false,
             undelegate: false,
             withdraw: true,
+            ..Permissions::default()
         };
 
         let info = mock_info(owner, &[]);
@@ -2256,19 +3324,20 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins,
             mutable: true,
+            spend_history_limit: None,
         };
         instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
 
         // setup permission and then allowance and check if changed
         let setup_perm_msg = ExecuteMsg::SetPermissions {
             spender: spender1.to_string(),
-            permissions: perm,
+            permissions: perm.clone(),
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_perm_msg).unwrap();
 
         let setup_allowance_msg = ExecuteMsg::IncreaseAllowance {
             spender: spender1.to_string(),
-            amount: coin.clone(),
+            amount: coin.clone().into(),
             expires: None,
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_allowance_msg).unwrap();
@@ -2281,14 +3350,14 @@ mod tests {
         // setup allowance and then permission and check if changed
         let setup_allowance_msg = ExecuteMsg::IncreaseAllowance {
             spender: spender2.to_string(),
-            amount: coin,
+            amount: coin.into(),
             expires: None,
         };
         execute(deps.as_mut(), mock_env(), info.clone(), setup_allowance_msg).unwrap();
 
         let setup_perm_msg = ExecuteMsg::SetPermissions {
             spender: spender2.to_string(),
-            permissions: perm,
+            permissions: perm.clone(),
         };
         execute(deps.as_mut(), mock_env(), info, setup_perm_msg).unwrap();
 
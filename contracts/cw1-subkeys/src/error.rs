@@ -37,6 +37,18 @@ pub enum ContractError {
     #[error("Set withdraw address is not allowed")]
     WithdrawAddrPerm {},
 
+    #[error("Executing contract {contract} is not allowed")]
+    WasmExecutePerm { contract: String },
+
+    #[error("Only Transfer, Send and Burn may be relayed to cw20 token {token}")]
+    Cw20MessageRejected { token: String },
+
+    #[error("Burn is not allowed")]
+    BurnPerm {},
+
+    #[error("Accrual needs a non-zero amount and period")]
+    InvalidAccrual {},
+
     #[error("Unsupported message")]
     UnsupportedMessage {},
 
@@ -52,6 +64,8 @@ impl From<cw1_whitelist::ContractError> for ContractError {
         match err {
             cw1_whitelist::ContractError::Std(error) => ContractError::Std(error),
             cw1_whitelist::ContractError::Unauthorized {} => ContractError::Unauthorized {},
+            // subkeys never set an admin change threshold
+            err => ContractError::Std(StdError::generic_err(err.to_string())),
         }
     }
 }
@@ -0,0 +1,140 @@
+#![cfg(test)]
+
+use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::{
+    coin, coins, Addr, BankMsg, CosmosMsg, Decimal, DistributionMsg, Empty, StakingMsg, Validator,
+};
+use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor, StakingInfo};
+
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::state::Permissions;
+
+const OWNER: &str = "owner";
+const SPENDER: &str = "spender";
+const VALIDATOR: &str = "validator";
+const DENOM: &str = "ustake";
+const YEAR: u64 = 60 * 60 * 24 * 365;
+
+fn contract_subkeys() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query);
+    Box::new(contract)
+}
+
+// Returns the app with one validator and the subkeys contract, holding 1000 DENOM
+fn setup() -> (App, Addr) {
+    let mut app = AppBuilder::new().build(|router, api, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(OWNER), coins(1000, DENOM))
+            .unwrap();
+        let info = StakingInfo {
+            bonded_denom: DENOM.into(),
+            unbonding_time: 60,
+            apr: Decimal::percent(10),
+        };
+        router.staking.setup(storage, info).unwrap();
+        let validator = Validator {
+            address: VALIDATOR.into(),
+            commission: Decimal::zero(),
+            max_commission: Decimal::percent(20),
+            max_change_rate: Decimal::percent(1),
+        };
+        router
+            .staking
+            .add_validator(api, storage, &mock_env().block, validator)
+            .unwrap();
+    });
+
+    let code_id = app.store_code(contract_subkeys());
+    let msg = InstantiateMsg {
+        admins: vec![OWNER.into()],
+        mutable: true,
+        spend_history_limit: None,
+    };
+    let contract = app
+        .instantiate_contract(code_id, Addr::unchecked(OWNER), &msg, &[], "subkeys", None)
+        .unwrap();
+    app.send_tokens(
+        Addr::unchecked(OWNER),
+        contract.clone(),
+        &coins(1000, DENOM),
+    )
+    .unwrap();
+    (app, contract)
+}
+
+fn relay(app: &mut App, contract: &Addr, msg: CosmosMsg) -> anyhow::Result<()> {
+    let msg = ExecuteMsg::Execute { msgs: vec![msg] };
+    app.execute_contract(Addr::unchecked(SPENDER), contract.clone(), &msg, &[])?;
+    Ok(())
+}
+
+#[test]
+fn subkey_delegates_and_withdraws() {
+    let (mut app, contract) = setup();
+    let delegate: CosmosMsg = StakingMsg::Delegate {
+        validator: VALIDATOR.into(),
+        amount: coin(600, DENOM),
+    }
+    .into();
+
+    // no permissions yet
+    relay(&mut app, &contract, delegate.clone()).unwrap_err();
+
+    let permissions = Permissions {
+        delegate: true,
+        redelegate: false,
+        undelegate: false,
+        withdraw: true,
+        allowed_wasm_contracts: vec![],
+        allow_bank_burn: false,
+    };
+    let msg = ExecuteMsg::<Empty>::SetPermissions {
+        spender: SPENDER.into(),
+        permissions,
+    };
+    app.execute_contract(Addr::unchecked(OWNER), contract.clone(), &msg, &[])
+        .unwrap();
+
+    // the contract delegates its own tokens
+    relay(&mut app, &contract, delegate).unwrap();
+    let delegation = app
+        .wrap()
+        .query_delegation(&contract, VALIDATOR)
+        .unwrap()
+        .unwrap();
+    assert_eq!(delegation.amount, coin(600, DENOM));
+    let balance = app.wrap().query_balance(&contract, DENOM).unwrap();
+    assert_eq!(balance, coin(400, DENOM));
+
+    // a year later the rewards go to the contract
+    app.update_block(|block| block.time = block.time.plus_seconds(YEAR));
+    let withdraw: CosmosMsg = DistributionMsg::WithdrawDelegatorReward {
+        validator: VALIDATOR.into(),
+    }
+    .into();
+    relay(&mut app, &contract, withdraw).unwrap();
+    let balance = app.wrap().query_balance(&contract, DENOM).unwrap();
+    assert_eq!(balance, coin(400 + 60, DENOM));
+
+    // undelegating was not permitted, neither is sending without allowance
+    let undelegate: CosmosMsg = StakingMsg::Undelegate {
+        validator: VALIDATOR.into(),
+        amount: coin(600, DENOM),
+    }
+    .into();
+    relay(&mut app, &contract, undelegate).unwrap_err();
+    let send: CosmosMsg = BankMsg::Send {
+        to_address: SPENDER.into(),
+        amount: coins(1, DENOM),
+    }
+    .into();
+    relay(&mut app, &contract, send).unwrap_err();
+    let delegation = app
+        .wrap()
+        .query_delegation(&contract, VALIDATOR)
+        .unwrap()
+        .unwrap();
+    assert_eq!(delegation.amount, coin(600, DENOM));
+}
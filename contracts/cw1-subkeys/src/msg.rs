@@ -3,10 +3,20 @@ use schemars::JsonSchema;
 use std::fmt;
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Coin, CosmosMsg, Empty};
-use cw_utils::{Expiration, NativeBalance};
+use cosmwasm_std::{Coin, CosmosMsg, Empty, Timestamp};
+use cw20::Amount;
+use cw_utils::{Duration, Expiration, NativeBalance};
 
-use crate::state::Permissions;
+use crate::state::{Accrual, Allowance, Permissions};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub admins: Vec<String>,
+    pub mutable: bool,
+    /// How many spends are kept per subkey for the SpendHistory query.
+    /// Defaults to DEFAULT_SPEND_HISTORY_LIMIT, 0 disables the history.
+    pub spend_history_limit: Option<u32>,
+}
 
 #[cw_serde]
 pub enum ExecuteMsg<T = Empty>
@@ -23,16 +33,16 @@ where
     /// and only works if the contract is mutable
     UpdateAdmins { admins: Vec<String> },
 
-    /// Add an allowance to a given subkey (subkey must not be admin)
+    /// Add an allowance of a native or cw20 token to a given subkey (subkey must not be admin)
     IncreaseAllowance {
         spender: String,
-        amount: Coin,
+        amount: Amount,
         expires: Option<Expiration>,
     },
-    /// Decreases an allowance for a given subkey (subkey must not be admin)
+    /// Decreases an allowance of a native or cw20 token for a given subkey (subkey must not be admin)
     DecreaseAllowance {
         spender: String,
-        amount: Coin,
+        amount: Amount,
         expires: Option<Expiration>,
     },
 
@@ -41,6 +51,21 @@ where
         spender: String,
         permissions: Permissions,
     },
+    /// Makes the allowance of a given subkey top up by itself every period, or stops it
+    /// with None (subkey must not be admin)
+    SetAccrual {
+        spender: String,
+        accrual: Option<AccrualConfig>,
+    },
+}
+
+#[cw_serde]
+pub struct AccrualConfig {
+    /// Added to the allowance once every period
+    pub amount: Vec<Coin>,
+    pub period: Duration,
+    /// The allowance of a denom does not accrue beyond this
+    pub max: Option<Vec<Coin>>,
 }
 
 #[cw_serde]
@@ -55,6 +80,13 @@ where
     /// Get the current allowance for the given subkey (how much it can spend)
     #[returns(crate::state::Allowance)]
     Allowance { spender: String },
+    /// Get the stored allowance for the given subkey, along with what it is worth
+    /// at the current block once its accrual is settled
+    #[returns(EffectiveAllowanceResponse)]
+    EffectiveAllowance { spender: String },
+    /// Get the current allowance of the given subkey for a cw20 token
+    #[returns(crate::state::Cw20Allowance)]
+    Cw20Allowance { spender: String, token: String },
     /// Get the current permissions for the given subkey (how much it can spend)
     #[returns(PermissionsInfo)]
     Permissions { spender: String },
@@ -75,6 +107,20 @@ where
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Gets the most recent spends of the given subkey against its allowance, newest first
+    #[returns(SpendHistoryResponse)]
+    SpendHistory {
+        spender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct EffectiveAllowanceResponse {
+    pub stored: Allowance,
+    pub effective: Allowance,
+    pub accrual: Option<Accrual>,
 }
 
 #[cw_serde]
@@ -188,3 +234,19 @@ impl PermissionsInfo {
 pub struct AllPermissionsResponse {
     pub permissions: Vec<PermissionsInfo>,
 }
+
+#[cw_serde]
+pub struct SpendInfo {
+    /// Sequence number of the spend for this subkey, to be used as `start_after`
+    pub id: u64,
+    pub height: u64,
+    pub time: Timestamp,
+    pub amount: Vec<Coin>,
+    /// The address receiving the funds
+    pub recipient: String,
+}
+
+#[cw_serde]
+pub struct SpendHistoryResponse {
+    pub spends: Vec<SpendInfo>,
+}
@@ -2,28 +2,45 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::Map;
-use cw_utils::{Expiration, NativeBalance};
+use cosmwasm_std::{coin, Addr, BlockInfo, Coin, Timestamp, Uint128};
+use cw_storage_plus::{Item, Map};
+use cw_utils::{Duration, Expiration, NativeBalance};
 
 // Permissions struct defines users message execution permissions.
 // Could have implemented permissions for each cosmos module(StakingPermissions, GovPermissions etc...)
 // But that meant a lot of code for each module. Keeping the permissions inside one struct is more
 // optimal. Define other modules permissions here.
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default, Copy)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
 pub struct Permissions {
     pub delegate: bool,
     pub redelegate: bool,
     pub undelegate: bool,
     pub withdraw: bool,
+    /// Contracts the subkey may call with `WasmMsg::Execute`. Empty denies all wasm messages.
+    /// Funds attached to such calls are taken from the subkey's allowance.
+    #[serde(default)]
+    pub allowed_wasm_contracts: Vec<Addr>,
+    /// Whether the subkey may relay `BankMsg::Burn`
+    #[serde(default)]
+    pub allow_bank_burn: bool,
 }
 
 impl fmt::Display for Permissions {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "staking: {{ delegate: {}, redelegate: {}, undelegate: {}, withdraw: {} }}",
-            self.delegate, self.redelegate, self.undelegate, self.withdraw
+            "staking: {{ delegate: {}, redelegate: {}, undelegate: {}, withdraw: {} }}, \
+            wasm: {{ contracts: [{}] }}, bank: {{ burn: {} }}",
+            self.delegate,
+            self.redelegate,
+            self.undelegate,
+            self.withdraw,
+            self.allowed_wasm_contracts
+                .iter()
+                .map(Addr::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.allow_bank_burn
         )
     }
 }
@@ -34,6 +51,64 @@ pub struct Allowance {
     pub expires: Expiration,
 }
 
+/// Periodic top-up of a subkey's allowance, settled lazily whenever the allowance is touched
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Accrual {
+    /// Added to the allowance once every period
+    pub amount: NativeBalance,
+    pub period: Duration,
+    /// The allowance of a denom does not accrue beyond this
+    pub max: Option<NativeBalance>,
+    /// Block height or time in seconds (matching `period`) up to which accrual was settled
+    pub last_settled: u64,
+}
+
+impl Accrual {
+    /// Current point in time, in the unit of `period`
+    pub fn now(period: &Duration, block: &BlockInfo) -> u64 {
+        match period {
+            Duration::Height(_) => block.height,
+            Duration::Time(_) => block.time.seconds(),
+        }
+    }
+
+    /// Adds all the periods fully elapsed since the last settlement to `balance`, capped at `max`
+    pub fn settle(&mut self, block: &BlockInfo, mut balance: NativeBalance) -> NativeBalance {
+        let period = match self.period {
+            Duration::Height(h) => h,
+            Duration::Time(t) => t,
+        };
+        let periods =
+            Self::now(&self.period, block).saturating_sub(self.last_settled) / period.max(1);
+        if periods == 0 {
+            return balance;
+        }
+        self.last_settled += periods * period;
+
+        for accrued in &self.amount.0 {
+            let current = amount_of(&balance, &accrued.denom);
+            let mut target = current.saturating_add(accrued.amount.saturating_mul(periods.into()));
+            if let Some(max) = &self.max {
+                // never cut an allowance that was raised above the cap by the admin
+                target = target.min(amount_of(max, &accrued.denom).max(current));
+            }
+            if target > current {
+                balance += coin((target - current).u128(), &accrued.denom);
+            }
+        }
+        balance
+    }
+}
+
+fn amount_of(balance: &NativeBalance, denom: &str) -> Uint128 {
+    balance
+        .0
+        .iter()
+        .find(|c| c.denom == denom)
+        .map(|c| c.amount)
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 impl Allowance {
     /// Utility function for converting message to its canonical form, so two messages with
@@ -70,3 +145,32 @@ impl Allowance {
 
 pub const PERMISSIONS: Map<&Addr, Permissions> = Map::new("permissions");
 pub const ALLOWANCES: Map<&Addr, Allowance> = Map::new("allowances");
+pub const ACCRUALS: Map<&Addr, Accrual> = Map::new("accruals");
+
+/// What a subkey may still spend of one cw20 token through `Transfer`, `Send` and `Burn`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Cw20Allowance {
+    pub amount: Uint128,
+    pub expires: Expiration,
+}
+
+/// Cw20 allowances by spender and token contract. Entries are never removed, so every call
+/// to a token a subkey once had an allowance for stays metered
+pub const CW20_ALLOWANCES: Map<(&Addr, &Addr), Cw20Allowance> = Map::new("cw20_allowances");
+
+/// One spend of a subkey against its allowance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SpendRecord {
+    pub height: u64,
+    pub time: Timestamp,
+    pub amount: Vec<Coin>,
+    pub recipient: String,
+}
+
+pub const DEFAULT_SPEND_HISTORY_LIMIT: u32 = 20;
+
+pub const SPEND_HISTORY_LIMIT: Item<u32> = Item::new("spend_history_limit");
+/// Number of spends ever recorded per subkey, the next record's sequence number
+pub const SPEND_COUNT: Map<&Addr, u64> = Map::new("spend_count");
+/// The last `SPEND_HISTORY_LIMIT` spends of every subkey, by sequence number
+pub const SPEND_HISTORY: Map<(&Addr, u64), SpendRecord> = Map::new("spend_history");
@@ -4,21 +4,29 @@ use std::fmt;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, Api, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response,
-    StdResult,
+    ensure, to_binary, Addr, Api, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo,
+    Response, StdError, StdResult, Storage,
 };
 
-use cw1::CanExecuteResponse;
+use cw1::{msg_type, CanExecuteResponse};
 use cw2::set_contract_version;
 
 use crate::error::ContractError;
-use crate::msg::{AdminListResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{AdminList, ADMIN_LIST};
+use crate::msg::{
+    AdminListResponse, ExecuteMsg, InstantiateMsg, PendingAdminChangeResponse, QueryMsg,
+};
+use crate::state::{
+    AdminChange, AdminList, PendingAdminChange, ADMIN_CHANGE_COUNT, ADMIN_CHANGE_THRESHOLD,
+    ADMIN_LIST, PENDING_ADMIN_CHANGE,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw1-whitelist";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Longest memo accepted by `Execute`, in bytes
+pub const MAX_MEMO_LENGTH: usize = 256;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -31,6 +39,14 @@ pub fn instantiate(
         admins: map_validate(deps.api, &msg.admins)?,
         mutable: msg.mutable,
     };
+    if let Some(threshold) = msg.admin_change_threshold {
+        if threshold == 0 || threshold as usize > distinct_admins(&cfg.admins) {
+            return Err(StdError::generic_err(
+                ContractError::InvalidThreshold {}.to_string(),
+            ));
+        }
+        ADMIN_CHANGE_THRESHOLD.save(deps.storage, &threshold)?;
+    }
     ADMIN_LIST.save(deps.storage, &cfg)?;
     Ok(Response::default())
 }
@@ -39,6 +55,14 @@ pub fn map_validate(api: &dyn Api, admins: &[String]) -> StdResult<Vec<Addr>> {
     admins.iter().map(|addr| api.addr_validate(addr)).collect()
 }
 
+/// Admins listed more than once can still approve only once
+fn distinct_admins(admins: &[Addr]) -> usize {
+    let mut admins: Vec<_> = admins.iter().collect();
+    admins.sort_unstable();
+    admins.dedup();
+    admins.len()
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -49,9 +73,11 @@ pub fn execute(
     msg: ExecuteMsg<Empty>,
 ) -> Result<Response<Empty>, ContractError> {
     match msg {
-        ExecuteMsg::Execute { msgs } => execute_execute(deps, env, info, msgs),
+        ExecuteMsg::Execute { msgs, memo } => execute_execute(deps, env, info, msgs, memo),
         ExecuteMsg::Freeze {} => execute_freeze(deps, env, info),
         ExecuteMsg::UpdateAdmins { admins } => execute_update_admins(deps, env, info, admins),
+        ExecuteMsg::ApproveAdminChange { id } => execute_approve_admin_change(deps, env, info, id),
+        ExecuteMsg::CancelAdminChange { id } => execute_cancel_admin_change(deps, env, info, id),
     }
 }
 
@@ -60,18 +86,37 @@ pub fn execute_execute<T>(
     _env: Env,
     info: MessageInfo,
     msgs: Vec<CosmosMsg<T>>,
+    memo: Option<String>,
 ) -> Result<Response<T>, ContractError>
 where
     T: Clone + fmt::Debug + PartialEq + JsonSchema,
 {
-    if !can_execute(deps.as_ref(), info.sender.as_ref())? {
-        Err(ContractError::Unauthorized {})
-    } else {
-        let res = Response::new()
-            .add_messages(msgs)
-            .add_attribute("action", "execute");
-        Ok(res)
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    if !cfg.is_admin(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(memo) = &memo {
+        ensure!(
+            memo.len() <= MAX_MEMO_LENGTH,
+            ContractError::MemoTooLong {
+                max: MAX_MEMO_LENGTH
+            }
+        );
+    }
+
+    // lets indexers attribute the effects of every message to its index in the batch
+    let msg_types = msgs
+        .iter()
+        .enumerate()
+        .map(|(idx, msg)| (format!("msg_{}_type", idx), msg_type(msg)));
+    let mut res = Response::new()
+        .add_attribute("action", "execute")
+        .add_attribute("msg_count", msgs.len().to_string())
+        .add_attributes(msg_types);
+    if let Some(memo) = memo {
+        res = res.add_attribute("memo", memo);
     }
+    Ok(res.add_messages(msgs))
 }
 
 pub fn execute_freeze(
@@ -79,39 +124,157 @@ pub fn execute_freeze(
     _env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let mut cfg = ADMIN_LIST.load(deps.storage)?;
+    propose_admin_change(deps, info, AdminChange::Freeze {})
+}
+
+pub fn execute_update_admins(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    admins: Vec<String>,
+) -> Result<Response, ContractError> {
+    let admins = map_validate(deps.api, &admins)?;
+    propose_admin_change(deps, info, AdminChange::UpdateAdmins { admins })
+}
+
+/// Applies the change right away, unless an admin change threshold asks for more approvals.
+/// In that case it becomes the pending change, which fails while another one is pending.
+fn propose_admin_change(
+    deps: DepsMut,
+    info: MessageInfo,
+    change: AdminChange,
+) -> Result<Response, ContractError> {
+    let cfg = ADMIN_LIST.load(deps.storage)?;
     if !cfg.can_modify(info.sender.as_ref()) {
-        Err(ContractError::Unauthorized {})
-    } else {
-        cfg.mutable = false;
-        ADMIN_LIST.save(deps.storage, &cfg)?;
+        return Err(ContractError::Unauthorized {});
+    }
 
-        let res = Response::new().add_attribute("action", "freeze");
-        Ok(res)
+    let threshold = ADMIN_CHANGE_THRESHOLD.may_load(deps.storage)?;
+    match (threshold, &change) {
+        // never leave fewer admins than approvals needed
+        (Some(threshold), AdminChange::UpdateAdmins { admins })
+            if (distinct_admins(admins) as u32) < threshold =>
+        {
+            Err(ContractError::InvalidThreshold {})
+        }
+        (Some(threshold), _) if threshold > 1 => {
+            if let Some(pending) = PENDING_ADMIN_CHANGE.may_load(deps.storage)? {
+                return Err(ContractError::AdminChangePending { id: pending.id });
+            }
+            let id = ADMIN_CHANGE_COUNT
+                .may_load(deps.storage)?
+                .unwrap_or_default()
+                + 1;
+            ADMIN_CHANGE_COUNT.save(deps.storage, &id)?;
+            let pending = PendingAdminChange {
+                id,
+                change,
+                approvals: vec![info.sender],
+            };
+            PENDING_ADMIN_CHANGE.save(deps.storage, &pending)?;
+
+            let res = Response::new()
+                .add_attribute("action", "propose_admin_change")
+                .add_attribute("id", id.to_string())
+                .add_attribute("approvals", "1");
+            Ok(res)
+        }
+        _ => apply_admin_change(deps.storage, cfg, change),
     }
 }
 
-pub fn execute_update_admins(
+fn apply_admin_change(
+    storage: &mut dyn Storage,
+    mut cfg: AdminList,
+    change: AdminChange,
+) -> Result<Response, ContractError> {
+    let action = match change {
+        AdminChange::UpdateAdmins { admins } => {
+            cfg.admins = admins;
+            "update_admins"
+        }
+        AdminChange::Freeze {} => {
+            cfg.mutable = false;
+            "freeze"
+        }
+    };
+    ADMIN_LIST.save(storage, &cfg)?;
+    PENDING_ADMIN_CHANGE.remove(storage);
+
+    let res = Response::new().add_attribute("action", action);
+    Ok(res)
+}
+
+/// Loads the pending admin change, if it is the one with the given id
+fn load_pending_admin_change(
+    storage: &dyn Storage,
+    id: u64,
+) -> Result<PendingAdminChange, ContractError> {
+    match PENDING_ADMIN_CHANGE.may_load(storage)? {
+        Some(pending) if pending.id == id => Ok(pending),
+        _ => Err(ContractError::NoPendingAdminChange { id }),
+    }
+}
+
+pub fn execute_approve_admin_change(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    admins: Vec<String>,
+    id: u64,
 ) -> Result<Response, ContractError> {
-    let mut cfg = ADMIN_LIST.load(deps.storage)?;
+    let cfg = ADMIN_LIST.load(deps.storage)?;
     if !cfg.can_modify(info.sender.as_ref()) {
-        Err(ContractError::Unauthorized {})
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pending = load_pending_admin_change(deps.storage, id)?;
+    if pending.approvals.contains(&info.sender) {
+        return Err(ContractError::AlreadyApproved {});
+    }
+    pending.approvals.push(info.sender);
+
+    let threshold = ADMIN_CHANGE_THRESHOLD.may_load(deps.storage)?.unwrap_or(1);
+    let approvals = pending.approvals.len();
+    if approvals as u32 >= threshold {
+        let res = apply_admin_change(deps.storage, cfg, pending.change)?;
+        Ok(res.add_attribute("approvals", approvals.to_string()))
     } else {
-        cfg.admins = map_validate(deps.api, &admins)?;
-        ADMIN_LIST.save(deps.storage, &cfg)?;
+        PENDING_ADMIN_CHANGE.save(deps.storage, &pending)?;
 
-        let res = Response::new().add_attribute("action", "update_admins");
+        let res = Response::new()
+            .add_attribute("action", "approve_admin_change")
+            .add_attribute("id", id.to_string())
+            .add_attribute("approvals", approvals.to_string());
         Ok(res)
     }
 }
 
-fn can_execute(deps: Deps, sender: &str) -> StdResult<bool> {
+pub fn execute_cancel_admin_change(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
     let cfg = ADMIN_LIST.load(deps.storage)?;
-    let can = cfg.is_admin(&sender);
+    if !cfg.can_modify(info.sender.as_ref()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    load_pending_admin_change(deps.storage, id)?;
+    PENDING_ADMIN_CHANGE.remove(deps.storage);
+
+    let res = Response::new()
+        .add_attribute("action", "cancel_admin_change")
+        .add_attribute("id", id.to_string());
+    Ok(res)
+}
+
+/// Base check behind the `CanExecute` query. The whitelist allows any message
+/// from an admin, so `msg` is ignored here, but contracts building on this one
+/// (like cw1-subkeys) call it first and then inspect the message themselves.
+pub fn can_execute<T>(deps: Deps, sender: &str, _msg: &CosmosMsg<T>) -> StdResult<bool> {
+    let cfg = ADMIN_LIST.load(deps.storage)?;
+    let can = cfg.is_admin(sender);
     Ok(can)
 }
 
@@ -120,9 +283,17 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::AdminList {} => to_binary(&query_admin_list(deps)?),
         QueryMsg::CanExecute { sender, msg } => to_binary(&query_can_execute(deps, sender, msg)?),
+        QueryMsg::PendingAdminChange {} => to_binary(&query_pending_admin_change(deps)?),
     }
 }
 
+pub fn query_pending_admin_change(deps: Deps) -> StdResult<PendingAdminChangeResponse> {
+    Ok(PendingAdminChangeResponse {
+        threshold: ADMIN_CHANGE_THRESHOLD.may_load(deps.storage)?,
+        pending: PENDING_ADMIN_CHANGE.may_load(deps.storage)?,
+    })
+}
+
 pub fn query_admin_list(deps: Deps) -> StdResult<AdminListResponse> {
     let cfg = ADMIN_LIST.load(deps.storage)?;
     Ok(AdminListResponse {
@@ -134,10 +305,10 @@ pub fn query_admin_list(deps: Deps) -> StdResult<AdminListResponse> {
 pub fn query_can_execute(
     deps: Deps,
     sender: String,
-    _msg: CosmosMsg,
+    msg: CosmosMsg,
 ) -> StdResult<CanExecuteResponse> {
     Ok(CanExecuteResponse {
-        can_execute: can_execute(deps, &sender)?,
+        can_execute: can_execute(deps, &sender, &msg)?,
     })
 }
 
@@ -161,6 +332,7 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), bob.to_string(), carl.to_string()],
             mutable: true,
+            admin_change_threshold: None,
         };
         let info = mock_info(anyone, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
@@ -217,6 +389,278 @@ mod tests {
         assert_eq!(err, ContractError::Unauthorized {});
     }
 
+    #[test]
+    fn duplicate_admins_do_not_count_towards_threshold() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+        let bob = "bob";
+        let anyone = "anyone";
+
+        // alice cannot approve three times, so this could never be changed
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string(); 3],
+            mutable: true,
+            admin_change_threshold: Some(3),
+        };
+        let info = mock_info(anyone, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap_err();
+
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string(), alice.to_string(), bob.to_string()],
+            mutable: true,
+            admin_change_threshold: Some(2),
+        };
+        let info = mock_info(anyone, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // same for proposed admins
+        let msg = ExecuteMsg::UpdateAdmins {
+            admins: vec![bob.to_string(), bob.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidThreshold {});
+    }
+
+    #[test]
+    fn admin_changes_need_threshold_approvals() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+        let bob = "bob";
+        let carl = "carl";
+        let anyone = "anyone";
+        let admins = vec![alice.to_string(), bob.to_string(), carl.to_string()];
+
+        // threshold must be reachable
+        for threshold in [0, 4] {
+            let instantiate_msg = InstantiateMsg {
+                admins: admins.clone(),
+                mutable: true,
+                admin_change_threshold: Some(threshold),
+            };
+            let info = mock_info(anyone, &[]);
+            instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap_err();
+        }
+        let instantiate_msg = InstantiateMsg {
+            admins: admins.clone(),
+            mutable: true,
+            admin_change_threshold: Some(2),
+        };
+        let info = mock_info(anyone, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // nothing to approve yet
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(bob, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingAdminChange { id: 1 });
+
+        // cannot propose fewer admins than the threshold
+        let msg = ExecuteMsg::UpdateAdmins {
+            admins: vec![alice.to_string()],
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidThreshold {});
+
+        // alice proposes to kick out carl, which is not applied yet
+        let msg = ExecuteMsg::UpdateAdmins {
+            admins: vec![alice.to_string(), bob.to_string()],
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            [
+                ("action", "propose_admin_change"),
+                ("id", "1"),
+                ("approvals", "1")
+            ]
+        );
+        assert_eq!(query_admin_list(deps.as_ref()).unwrap().admins, admins);
+        assert_eq!(
+            query_pending_admin_change(deps.as_ref()).unwrap(),
+            PendingAdminChangeResponse {
+                threshold: Some(2),
+                pending: Some(PendingAdminChange {
+                    id: 1,
+                    change: AdminChange::UpdateAdmins {
+                        admins: vec![Addr::unchecked(alice), Addr::unchecked(bob)],
+                    },
+                    approvals: vec![Addr::unchecked(alice)],
+                }),
+            }
+        );
+
+        // a non-admin cannot approve, and alice cannot approve twice
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(anyone, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyApproved {});
+
+        // bob's approval exactly meets the threshold
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(bob, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            [("action", "update_admins"), ("approvals", "2")]
+        );
+        assert_eq!(
+            query_admin_list(deps.as_ref()).unwrap().admins,
+            vec![alice.to_string(), bob.to_string()]
+        );
+        assert_eq!(
+            query_pending_admin_change(deps.as_ref()).unwrap().pending,
+            None
+        );
+
+        // freezing follows the same rule
+        let info = mock_info(bob, &[]);
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Freeze {}).unwrap();
+        assert!(query_admin_list(deps.as_ref()).unwrap().mutable);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(carl, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 2 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(alice, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 2 },
+        )
+        .unwrap();
+        assert!(!query_admin_list(deps.as_ref()).unwrap().mutable);
+    }
+
+    #[test]
+    fn approvals_are_bound_to_the_proposal() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+        let bob = "bob";
+        let carl = "carl";
+        let admins = vec![alice.to_string(), bob.to_string(), carl.to_string()];
+        let instantiate_msg = InstantiateMsg {
+            admins: admins.clone(),
+            mutable: true,
+            admin_change_threshold: Some(2),
+        };
+        let info = mock_info(alice, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
+
+        // alice proposes to kick out carl
+        let msg = ExecuteMsg::UpdateAdmins {
+            admins: vec![alice.to_string(), bob.to_string()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(alice, &[]), msg).unwrap();
+
+        // carl cannot replace it with his own proposal
+        let carls_change = ExecuteMsg::UpdateAdmins {
+            admins: vec![bob.to_string(), carl.to_string()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(carl, &[]),
+            carls_change.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AdminChangePending { id: 1 });
+
+        // only admins can cancel, and only the pending one
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CancelAdminChange { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(carl, &[]),
+            ExecuteMsg::CancelAdminChange { id: 2 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingAdminChange { id: 2 });
+
+        // carl cancels and proposes his own change instead
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(carl, &[]),
+            ExecuteMsg::CancelAdminChange { id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            [("action", "cancel_admin_change"), ("id", "1")]
+        );
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(carl, &[]),
+            carls_change,
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1], ("id", "2"));
+
+        // bob's approval of alice's proposal doesn't count for carl's
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(bob, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingAdminChange { id: 1 });
+        assert_eq!(query_admin_list(deps.as_ref()).unwrap().admins, admins);
+        let pending = query_pending_admin_change(deps.as_ref())
+            .unwrap()
+            .pending
+            .unwrap();
+        assert_eq!(pending.id, 2);
+        assert_eq!(pending.approvals, vec![Addr::unchecked(carl)]);
+
+        // it only passes when approved by id
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(bob, &[]),
+            ExecuteMsg::ApproveAdminChange { id: 2 },
+        )
+        .unwrap();
+        assert_eq!(
+            query_admin_list(deps.as_ref()).unwrap().admins,
+            vec![bob.to_string(), carl.to_string()]
+        );
+    }
+
     #[test]
     fn execute_messages_has_proper_permissions() {
         let mut deps = mock_dependencies();
@@ -229,6 +673,7 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), carl.to_string()],
             mutable: false,
+            admin_change_threshold: None,
         };
         let info = mock_info(bob, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
@@ -249,7 +694,10 @@ mod tests {
         ];
 
         // make some nice message
-        let execute_msg = ExecuteMsg::Execute { msgs: msgs.clone() };
+        let execute_msg = ExecuteMsg::Execute {
+            msgs: msgs.clone(),
+            memo: None,
+        };
 
         // bob cannot execute them
         let info = mock_info(bob, &[]);
@@ -263,7 +711,77 @@ mod tests {
             res.messages,
             msgs.into_iter().map(SubMsg::new).collect::<Vec<_>>()
         );
-        assert_eq!(res.attributes, [("action", "execute")]);
+        assert_eq!(
+            res.attributes,
+            [
+                ("action", "execute"),
+                ("msg_count", "2"),
+                ("msg_0_type", "bank/send"),
+                ("msg_1_type", "wasm/execute"),
+            ]
+        );
+    }
+
+    #[test]
+    fn execute_attributes_describe_batch() {
+        let mut deps = mock_dependencies();
+
+        let alice = "alice";
+
+        let instantiate_msg = InstantiateMsg {
+            admins: vec![alice.to_string()],
+            mutable: false,
+            admin_change_threshold: None,
+        };
+        let info = mock_info(alice, &[]);
+        instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        let msgs = vec![
+            WasmMsg::Execute {
+                contract_addr: "some contract".into(),
+                msg: to_binary(&Empty {}).unwrap(),
+                funds: vec![],
+            }
+            .into(),
+            StakingMsg::Delegate {
+                validator: "validator".to_string(),
+                amount: coin(70000, "ureef"),
+            }
+            .into(),
+            BankMsg::Send {
+                to_address: alice.to_string(),
+                amount: coins(10000, "DAI"),
+            }
+            .into(),
+        ];
+        let execute_msg = ExecuteMsg::Execute {
+            msgs,
+            memo: Some("payroll".to_string()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), execute_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            [
+                ("action", "execute"),
+                ("msg_count", "3"),
+                ("msg_0_type", "wasm/execute"),
+                ("msg_1_type", "staking/delegate"),
+                ("msg_2_type", "bank/send"),
+                ("memo", "payroll"),
+            ]
+        );
+
+        let execute_msg = ExecuteMsg::Execute {
+            msgs: vec![],
+            memo: Some("a".repeat(MAX_MEMO_LENGTH + 1)),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, execute_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MemoTooLong {
+                max: MAX_MEMO_LENGTH
+            }
+        );
     }
 
     #[test]
@@ -279,6 +797,7 @@ mod tests {
         let instantiate_msg = InstantiateMsg {
             admins: vec![alice.to_string(), bob.to_string()],
             mutable: false,
+            admin_change_threshold: None,
         };
         let info = mock_info(anyone, &[]);
         instantiate(deps.as_mut(), mock_env(), info, instantiate_msg).unwrap();
@@ -8,4 +8,19 @@ pub enum ContractError {
 
     #[error("Unauthorized")]
     Unauthorized {},
+
+    #[error("Admin change threshold must be between 1 and the number of admins")]
+    InvalidThreshold {},
+
+    #[error("Admin change {id} is not waiting for approval")]
+    NoPendingAdminChange { id: u64 },
+
+    #[error("Admin change {id} is still pending, it must be approved or cancelled first")]
+    AdminChangePending { id: u64 },
+
+    #[error("Admin change already approved by this admin")]
+    AlreadyApproved {},
+
+    #[error("Memo is longer than {max} bytes")]
+    MemoTooLong { max: usize },
 }
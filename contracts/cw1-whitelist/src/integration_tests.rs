@@ -47,7 +47,11 @@ impl Suite {
             .instantiate_contract(
                 self.cw1_id,
                 Addr::unchecked(self.owner.clone()),
-                &InstantiateMsg { admins, mutable },
+                &InstantiateMsg {
+                    admins,
+                    mutable,
+                    admin_change_threshold: None,
+                },
                 &[],
                 "Whitelist",
                 None,
@@ -71,6 +75,7 @@ impl Suite {
                 msg: to_binary(&msg)?,
                 funds: vec![],
             })],
+            memo: None,
         };
         self.app
             .execute_contract(
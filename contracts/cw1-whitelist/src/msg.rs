@@ -5,10 +5,15 @@ use std::fmt;
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{CosmosMsg, Empty};
 
+use crate::state::PendingAdminChange;
+
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admins: Vec<String>,
     pub mutable: bool,
+    /// If set, UpdateAdmins and Freeze only propose a change, which is applied once
+    /// this many admins (including the proposer) approved it
+    pub admin_change_threshold: Option<u32>,
 }
 
 #[cw_serde]
@@ -19,12 +24,23 @@ where
     /// Execute requests the contract to re-dispatch all these messages with the
     /// contract's address as sender. Every implementation has it's own logic to
     /// determine in
-    Execute { msgs: Vec<CosmosMsg<T>> },
+    Execute {
+        msgs: Vec<CosmosMsg<T>>,
+        /// Echoed as the `memo` attribute, at most MAX_MEMO_LENGTH bytes
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
+    },
     /// Freeze will make a mutable contract immutable, must be called by an admin
     Freeze {},
     /// UpdateAdmins will change the admin set of the contract, must be called by an existing admin,
     /// and only works if the contract is mutable
     UpdateAdmins { admins: Vec<String> },
+    /// With an admin change threshold, approves the pending UpdateAdmins or Freeze with this id.
+    /// Must be called by an existing admin
+    ApproveAdminChange { id: u64 },
+    /// Drops the pending admin change with this id, so a new one can be proposed.
+    /// Must be called by an existing admin
+    CancelAdminChange { id: u64 },
 }
 
 #[cw_serde]
@@ -41,6 +57,15 @@ where
     /// before any further state changes, should also succeed.
     #[returns(cw1::CanExecuteResponse)]
     CanExecute { sender: String, msg: CosmosMsg<T> },
+    /// Shows the admin change threshold and the change waiting for approvals, if any
+    #[returns(PendingAdminChangeResponse)]
+    PendingAdminChange {},
+}
+
+#[cw_serde]
+pub struct PendingAdminChangeResponse {
+    pub threshold: Option<u32>,
+    pub pending: Option<PendingAdminChange>,
 }
 
 #[cw_serde]
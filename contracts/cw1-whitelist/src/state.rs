@@ -23,7 +23,28 @@ impl AdminList {
     }
 }
 
+/// A change of the admin set waiting for enough admins to approve it
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
+pub struct PendingAdminChange {
+    /// approvals and cancellations must name this id, so they never hit a different proposal
+    pub id: u64,
+    pub change: AdminChange,
+    pub approvals: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminChange {
+    UpdateAdmins { admins: Vec<Addr> },
+    Freeze {},
+}
+
 pub const ADMIN_LIST: Item<AdminList> = Item::new("admin_list");
+/// When set, admin changes need this many approving admins instead of a single one
+pub const ADMIN_CHANGE_THRESHOLD: Item<u32> = Item::new("admin_change_threshold");
+pub const PENDING_ADMIN_CHANGE: Item<PendingAdminChange> = Item::new("pending_admin_change");
+/// Number of admin changes proposed so far, the last one has this id
+pub const ADMIN_CHANGE_COUNT: Item<u64> = Item::new("admin_change_count");
 
 #[cfg(test)]
 mod tests {
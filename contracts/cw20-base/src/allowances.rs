@@ -1,10 +1,13 @@
 use cosmwasm_std::{
-    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
-    Storage, Uint128,
+    attr, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+    Uint128,
 };
-use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
+use cw20::{AllowanceResponse, Cw20Coin, Cw20ReceiveMsg, Expiration};
 
+use crate::contract::validate_batch;
 use crate::error::ContractError;
+use crate::hooks::receive_hook;
+use crate::msg::AllowanceStatusResponse;
 use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, TOKEN_INFO};
 
 pub fn execute_increase_allowance(
@@ -20,19 +23,24 @@ pub fn execute_increase_allowance(
         return Err(ContractError::CannotSetOwnAccount {});
     }
 
-    let update_fn = |allow: Option<AllowanceResponse>| -> Result<_, _> {
-        let mut val = allow.unwrap_or_default();
+    assert_not_expired(&env, expires)?;
+
+    let update_fn = |allow: Option<AllowanceResponse>| -> Result<_, ContractError> {
+        // a new expiration always replaces the old one, and an expired allowance
+        // starts over instead of adding to the stale amount
+        let mut val = allow
+            .filter(|allow| is_allowance_usable(&env, allow))
+            .unwrap_or_default();
         if let Some(exp) = expires {
-            if exp.is_expired(&env.block) {
-                return Err(ContractError::InvalidExpiration {});
-            }
             val.expires = exp;
         }
         val.allowance += amount;
         Ok(val)
     };
-    ALLOWANCES.update(deps.storage, (&info.sender, &spender_addr), update_fn)?;
-    ALLOWANCES_SPENDER.update(deps.storage, (&spender_addr, &info.sender), update_fn)?;
+    let key = (info.sender.clone(), spender_addr.clone());
+    ALLOWANCES.update(deps.storage, key, update_fn)?;
+    let reverse = (spender_addr, info.sender.clone());
+    ALLOWANCES_SPENDER.update(deps.storage, reverse, update_fn)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "increase_allowance"),
@@ -56,31 +64,30 @@ pub fn execute_decrease_allowance(
         return Err(ContractError::CannotSetOwnAccount {});
     }
 
-    let key = (&info.sender, &spender_addr);
+    let key = (info.sender.clone(), spender_addr.clone());
+    let reverse = (spender_addr, info.sender.clone());
 
-    fn reverse<'a>(t: (&'a Addr, &'a Addr)) -> (&'a Addr, &'a Addr) {
-        (t.1, t.0)
-    }
+    assert_not_expired(&env, expires)?;
 
-    // load value and delete if it hits 0, or update otherwise
-    let mut allowance = ALLOWANCES.load(deps.storage, key)?;
-    if amount < allowance.allowance {
+    // load value and delete if it hits 0 (an expired allowance counts as 0), or update otherwise
+    let mut allowance = ALLOWANCES
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NoAllowance {})?;
+    if is_allowance_usable(&env, &allowance) && amount < allowance.allowance {
         // update the new amount
         allowance.allowance = allowance
             .allowance
             .checked_sub(amount)
             .map_err(StdError::overflow)?;
         if let Some(exp) = expires {
-            if exp.is_expired(&env.block) {
-                return Err(ContractError::InvalidExpiration {});
-            }
             allowance.expires = exp;
         }
         ALLOWANCES.save(deps.storage, key, &allowance)?;
-        ALLOWANCES_SPENDER.save(deps.storage, reverse(key), &allowance)?;
+        ALLOWANCES_SPENDER.save(deps.storage, reverse, &allowance)?;
     } else {
+        allowance.allowance = Uint128::zero();
         ALLOWANCES.remove(deps.storage, key);
-        ALLOWANCES_SPENDER.remove(deps.storage, reverse(key));
+        ALLOWANCES_SPENDER.remove(deps.storage, reverse);
     }
 
     let res = Response::new().add_attributes(vec![
@@ -88,22 +95,38 @@ pub fn execute_decrease_allowance(
         attr("owner", info.sender),
         attr("spender", spender),
         attr("amount", amount),
+        attr("allowance", allowance.allowance),
     ]);
     Ok(res)
 }
 
+/// Decides if an allowance can be spent in the current block. An allowance expiring
+/// at height H (or time T) is usable in blocks before H and expired from H on.
+/// Spending and the Allowance query both go through this, so they always agree.
+pub fn is_allowance_usable(env: &Env, allowance: &AllowanceResponse) -> bool {
+    !allowance.expires.is_expired(&env.block)
+}
+
+/// A newly set expiration must still be in the future
+fn assert_not_expired(env: &Env, expires: Option<Expiration>) -> Result<(), ContractError> {
+    match expires {
+        Some(exp) if exp.is_expired(&env.block) => Err(ContractError::AlreadyExpired {}),
+        _ => Ok(()),
+    }
+}
+
 // this can be used to update a lower allowance - call bucket.update with proper keys
 pub fn deduct_allowance(
     storage: &mut dyn Storage,
     owner: &Addr,
     spender: &Addr,
-    block: &BlockInfo,
+    env: &Env,
     amount: Uint128,
 ) -> Result<AllowanceResponse, ContractError> {
     let update_fn = |current: Option<AllowanceResponse>| -> _ {
         match current {
             Some(mut a) => {
-                if a.expires.is_expired(block) {
+                if !is_allowance_usable(env, &a) {
                     Err(ContractError::Expired {})
                 } else {
                     // deduct the allowance if enough
@@ -117,8 +140,8 @@ pub fn deduct_allowance(
             None => Err(ContractError::NoAllowance {}),
         }
     };
-    ALLOWANCES.update(storage, (owner, spender), update_fn)?;
-    ALLOWANCES_SPENDER.update(storage, (spender, owner), update_fn)
+    ALLOWANCES.update(storage, (owner.clone(), spender.clone()), update_fn)?;
+    ALLOWANCES_SPENDER.update(storage, (spender.clone(), owner.clone()), update_fn)
 }
 
 pub fn execute_transfer_from(
@@ -133,7 +156,7 @@ pub fn execute_transfer_from(
     let owner_addr = deps.api.addr_validate(&owner)?;
 
     // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env, amount)?;
 
     BALANCES.update(
         deps.storage,
@@ -148,13 +171,16 @@ pub fn execute_transfer_from(
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
-    let res = Response::new().add_attributes(vec![
-        attr("action", "transfer_from"),
-        attr("from", owner),
-        attr("to", recipient),
-        attr("by", info.sender),
-        attr("amount", amount),
-    ]);
+    let hook = receive_hook(deps.storage, &info.sender, &rcpt_addr, amount)?;
+    let res = Response::new()
+        .add_attributes(vec![
+            attr("action", "transfer_from"),
+            attr("from", owner),
+            attr("to", recipient),
+            attr("by", info.sender),
+            attr("amount", amount),
+        ])
+        .add_submessages(hook);
     Ok(res)
 }
 
@@ -169,7 +195,7 @@ pub fn execute_burn_from(
     let owner_addr = deps.api.addr_validate(&owner)?;
 
     // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env, amount)?;
 
     // lower balance
     BALANCES.update(
@@ -194,6 +220,38 @@ pub fn execute_burn_from(
     Ok(res)
 }
 
+pub fn execute_burn_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owners: Vec<Cw20Coin>,
+) -> Result<Response, ContractError> {
+    let (owners, total) = validate_batch(deps.as_ref(), &owners)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "burn_batch")
+        .add_attribute("by", &info.sender);
+    for (owner_addr, amount) in owners {
+        deduct_allowance(deps.storage, &owner_addr, &info.sender, &env, amount)?;
+        BALANCES.update(
+            deps.storage,
+            &owner_addr,
+            |balance: Option<Uint128>| -> StdResult<_> {
+                Ok(balance.unwrap_or_default().checked_sub(amount)?)
+            },
+        )?;
+        res = res.add_attribute("from", format!("{}: {}", owner_addr, amount));
+    }
+
+    // reduce total_supply
+    TOKEN_INFO.update(deps.storage, |mut meta| -> StdResult<_> {
+        meta.total_supply = meta.total_supply.checked_sub(total)?;
+        Ok(meta)
+    })?;
+
+    Ok(res.add_attribute("total_amount", total))
+}
+
 pub fn execute_send_from(
     deps: DepsMut,
     env: Env,
@@ -207,7 +265,7 @@ pub fn execute_send_from(
     let owner_addr = deps.api.addr_validate(&owner)?;
 
     // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env, amount)?;
 
     // move the tokens to the contract
     BALANCES.update(
@@ -247,11 +305,25 @@ pub fn query_allowance(deps: Deps, owner: String, spender: String) -> StdResult<
     let owner_addr = deps.api.addr_validate(&owner)?;
     let spender_addr = deps.api.addr_validate(&spender)?;
     let allowance = ALLOWANCES
-        .may_load(deps.storage, (&owner_addr, &spender_addr))?
+        .may_load(deps.storage, (owner_addr, spender_addr))?
         .unwrap_or_default();
     Ok(allowance)
 }
 
+pub fn query_allowance_status(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    spender: String,
+) -> StdResult<AllowanceStatusResponse> {
+    let allowance = query_allowance(deps, owner, spender)?;
+    Ok(AllowanceStatusResponse {
+        expired: !is_allowance_usable(&env, &allowance),
+        allowance: allowance.allowance,
+        expires: allowance.expires,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -283,6 +355,7 @@ mod tests {
             }],
             mint: None,
             marketing: None,
+            import_from: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -320,7 +393,7 @@ mod tests {
             allowance,
             AllowanceResponse {
                 allowance: allow1,
-                expires
+                expires,
             }
         );
 
@@ -338,7 +411,7 @@ mod tests {
             allowance,
             AllowanceResponse {
                 allowance: allow2,
-                expires
+                expires,
             }
         );
 
@@ -357,7 +430,7 @@ mod tests {
             allowance,
             AllowanceResponse {
                 allowance: allow3,
-                expires: new_expire
+                expires: new_expire,
             }
         );
 
@@ -655,6 +728,72 @@ mod tests {
         assert_eq!(err, ContractError::Expired {});
     }
 
+    #[test]
+    fn burn_batch_respects_limits() {
+        let mut deps = mock_dependencies_with_balance(&[]);
+        let owner1 = String::from("addr0001");
+        let owner2 = String::from("addr0002");
+        let spender = String::from("addr0003");
+
+        do_instantiate(deps.as_mut(), &owner1, Uint128::new(1000));
+        let msg = ExecuteMsg::Transfer {
+            recipient: owner2.clone(),
+            amount: Uint128::new(400),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(&owner1, &[]), msg).unwrap();
+        for owner in [&owner1, &owner2] {
+            let msg = ExecuteMsg::IncreaseAllowance {
+                spender: spender.clone(),
+                amount: Uint128::new(100),
+                expires: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+        }
+        let burns = |first: u128, second: u128| ExecuteMsg::BurnBatch {
+            owners: vec![
+                Cw20Coin {
+                    address: owner1.clone(),
+                    amount: Uint128::new(first),
+                },
+                Cw20Coin {
+                    address: owner2.clone(),
+                    amount: Uint128::new(second),
+                },
+            ],
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+
+        // exceeding any allowance fails the whole batch
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), burns(101, 40)).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        let res = execute(deps.as_mut(), mock_env(), info, burns(60, 40)).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "burn_batch"),
+                attr("by", &spender),
+                attr("from", "addr0001: 60"),
+                attr("from", "addr0002: 40"),
+                attr("total_amount", "100"),
+            ]
+        );
+        assert_eq!(
+            get_balance(deps.as_ref(), owner1.clone()),
+            Uint128::new(540)
+        );
+        assert_eq!(
+            get_balance(deps.as_ref(), owner2.clone()),
+            Uint128::new(360)
+        );
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(900)
+        );
+        let allowance = query_allowance(deps.as_ref(), owner2, spender).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(60));
+    }
+
     #[test]
     fn send_from_respects_limits() {
         let mut deps = mock_dependencies_with_balance(&[]);
@@ -780,7 +919,7 @@ mod tests {
 
         // ensure it is rejected
         assert_eq!(
-            Err(ContractError::InvalidExpiration {}),
+            Err(ContractError::AlreadyExpired {}),
             execute(deps.as_mut(), env.clone(), info.clone(), msg)
         );
 
@@ -794,7 +933,7 @@ mod tests {
 
         // ensure it is rejected
         assert_eq!(
-            Err(ContractError::InvalidExpiration {}),
+            Err(ContractError::AlreadyExpired {}),
             execute(deps.as_mut(), env.clone(), info.clone(), msg)
         );
 
@@ -815,7 +954,7 @@ mod tests {
             allowance,
             AllowanceResponse {
                 allowance: allow,
-                expires
+                expires,
             }
         );
 
@@ -836,7 +975,7 @@ mod tests {
             allowance,
             AllowanceResponse {
                 allowance: allow + allow, // we increased twice
-                expires
+                expires,
             }
         );
 
@@ -851,7 +990,7 @@ mod tests {
 
         // ensure it is rejected
         assert_eq!(
-            Err(ContractError::InvalidExpiration {}),
+            Err(ContractError::AlreadyExpired {}),
             execute(deps.as_mut(), env.clone(), info.clone(), msg)
         );
 
@@ -872,8 +1011,259 @@ mod tests {
             allowance,
             AllowanceResponse {
                 allowance: allow,
-                expires
+                expires,
+            }
+        );
+    }
+
+    #[test]
+    fn allowance_usable_until_exact_expiration() {
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let start = mock_env();
+        let at_height = Expiration::AtHeight(start.block.height + 10);
+        let at_time = Expiration::AtTime(start.block.time.plus_seconds(100));
+
+        // env one unit before (-1), exactly at (0) and one unit after (1) the expiration
+        let env_at = |expires: &Expiration, offset: i64| {
+            let mut env = mock_env();
+            match expires {
+                Expiration::AtHeight(h) => env.block.height = (*h as i64 + offset) as u64,
+                Expiration::AtTime(t) => {
+                    env.block.time = Timestamp::from_nanos((t.nanos() as i64 + offset) as u64)
+                }
+                Expiration::Never {} => unreachable!(),
+            }
+            env
+        };
+        let spend_msgs = [
+            ExecuteMsg::TransferFrom {
+                owner: owner.clone(),
+                recipient: String::from("addr0003"),
+                amount: Uint128::new(1),
+            },
+            ExecuteMsg::SendFrom {
+                owner: owner.clone(),
+                contract: String::from("contract0004"),
+                amount: Uint128::new(1),
+                msg: Binary::default(),
+            },
+            ExecuteMsg::BurnFrom {
+                owner: owner.clone(),
+                amount: Uint128::new(1),
+            },
+        ];
+
+        for expires in [at_height, at_time] {
+            for offset in [-1, 0, 1] {
+                let mut deps = mock_dependencies_with_balance(&[]);
+                do_instantiate(deps.as_mut(), &owner, Uint128::new(1000));
+                let msg = ExecuteMsg::IncreaseAllowance {
+                    spender: spender.clone(),
+                    amount: Uint128::new(100),
+                    expires: Some(expires),
+                };
+                execute(deps.as_mut(), start.clone(), mock_info(&owner, &[]), msg).unwrap();
+
+                let env = env_at(&expires, offset);
+                let usable = offset < 0;
+                let status = query_allowance_status(
+                    deps.as_ref(),
+                    env.clone(),
+                    owner.clone(),
+                    spender.clone(),
+                )
+                .unwrap();
+                assert_eq!(status.expired, !usable, "{:?} at {}", expires, offset);
+                assert_eq!(status.expires, expires);
+
+                for msg in spend_msgs.iter().cloned() {
+                    let res = execute(deps.as_mut(), env.clone(), mock_info(&spender, &[]), msg);
+                    if usable {
+                        res.unwrap();
+                    } else {
+                        assert_eq!(res.unwrap_err(), ContractError::Expired {});
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn allowance_update_semantics() {
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let env = mock_env();
+        let h = env.block.height;
+        let in_10 = Expiration::AtHeight(h + 10);
+        let future = Some(in_10);
+        let past = Some(Expiration::AtHeight(h));
+
+        let never = Expiration::Never {};
+        let later = Expiration::AtHeight(h + 100);
+
+        enum Existing {
+            Nothing,
+            // 100 tokens until `later`
+            Active,
+            // 100 tokens, expired at h - 100
+            Stale,
+        }
+        enum Op {
+            Increase,
+            Decrease,
+        }
+        use Existing::*;
+        use Op::*;
+        type Expected = Result<Option<(u128, Expiration)>, ContractError>;
+        let passed = || Err(ContractError::AlreadyExpired {});
+
+        // (existing, op, amount, expires, expected allowance and expiration after the update)
+        let cases: Vec<(Existing, Op, u128, Option<Expiration>, Expected)> = vec![
+            (Nothing, Increase, 50, None, Ok(Some((50, never)))),
+            (Nothing, Increase, 50, future, Ok(Some((50, in_10)))),
+            (Nothing, Increase, 50, past, passed()),
+            (Active, Increase, 50, None, Ok(Some((150, later)))),
+            (Active, Increase, 50, future, Ok(Some((150, in_10)))),
+            (Active, Increase, 50, past, passed()),
+            (Stale, Increase, 50, None, Ok(Some((50, never)))),
+            (Stale, Increase, 50, future, Ok(Some((50, in_10)))),
+            (
+                Nothing,
+                Decrease,
+                50,
+                None,
+                Err(ContractError::NoAllowance {}),
+            ),
+            (Active, Decrease, 30, None, Ok(Some((70, later)))),
+            (Active, Decrease, 30, future, Ok(Some((70, in_10)))),
+            (Active, Decrease, 30, past, passed()),
+            (Active, Decrease, 100, None, Ok(None)),
+            (Active, Decrease, 500, future, Ok(None)),
+            (Stale, Decrease, 30, None, Ok(None)),
+        ];
+
+        for (i, (existing, op, amount, expires, expected)) in cases.into_iter().enumerate() {
+            let mut deps = mock_dependencies_with_balance(&[]);
+            do_instantiate(deps.as_mut(), &owner, Uint128::new(1000));
+            let info = mock_info(&owner, &[]);
+
+            let mut earlier = mock_env();
+            earlier.block.height = h - 200;
+            let existing = match existing {
+                Nothing => None,
+                Active => Some(later),
+                Stale => Some(Expiration::AtHeight(h - 100)),
+            };
+            if let Some(exp) = existing {
+                let msg = ExecuteMsg::IncreaseAllowance {
+                    spender: spender.clone(),
+                    amount: Uint128::new(100),
+                    expires: Some(exp),
+                };
+                execute(deps.as_mut(), earlier, info.clone(), msg).unwrap();
             }
+
+            let amount = Uint128::new(amount);
+            let msg = match op {
+                Increase => ExecuteMsg::IncreaseAllowance {
+                    spender: spender.clone(),
+                    amount,
+                    expires,
+                },
+                Decrease => ExecuteMsg::DecreaseAllowance {
+                    spender: spender.clone(),
+                    amount,
+                    expires,
+                },
+            };
+            let res = execute(deps.as_mut(), env.clone(), info, msg);
+
+            let expected = match (res, expected) {
+                (Err(err), Err(expected)) => {
+                    assert_eq!(err, expected, "case {}", i);
+                    continue;
+                }
+                (Ok(res), Ok(expected)) => {
+                    if let Decrease = op {
+                        let left = expected.map_or(0, |(allowance, _)| allowance);
+                        assert_eq!(
+                            res.attributes[4],
+                            attr("allowance", left.to_string()),
+                            "case {}",
+                            i
+                        );
+                    }
+                    expected
+                }
+                (res, expected) => panic!("case {}: got {:?}, expected {:?}", i, res, expected),
+            };
+
+            let stored = ALLOWANCES
+                .may_load(
+                    &deps.storage,
+                    (Addr::unchecked(&owner), Addr::unchecked(&spender)),
+                )
+                .unwrap();
+            let by_spender = ALLOWANCES_SPENDER
+                .may_load(
+                    &deps.storage,
+                    (Addr::unchecked(&spender), Addr::unchecked(&owner)),
+                )
+                .unwrap();
+            assert_eq!(stored, by_spender, "case {}", i);
+            assert_eq!(
+                stored.map(|a| (a.allowance.u128(), a.expires)),
+                expected,
+                "case {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn owned_allowance_keys_match_borrowed() {
+        use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER};
+        use cw_storage_plus::Map;
+
+        // the previous layout of ALLOWANCES and ALLOWANCES_SPENDER, keyed by borrowed addresses
+        const BORROWED: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
+        const BORROWED_SPENDER: Map<(&Addr, &Addr), AllowanceResponse> =
+            Map::new("allowance_spender");
+
+        let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
+        let owner = Addr::unchecked("addr0001");
+        let spender = Addr::unchecked("addr0002");
+        do_instantiate(deps.as_mut(), owner.as_str(), Uint128::new(12340000));
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.to_string(),
+            amount: Uint128::new(7777),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the encoded keys are byte-identical, so stored allowances need no migration
+        let owned = ALLOWANCES.key((owner.clone(), spender.clone()));
+        let borrowed = BORROWED.key((&owner, &spender));
+        assert_eq!(owned.to_vec(), borrowed.to_vec());
+        assert_eq!(
+            BORROWED.load(&deps.storage, (&owner, &spender)).unwrap(),
+            ALLOWANCES
+                .load(&deps.storage, (owner.clone(), spender.clone()))
+                .unwrap()
+        );
+        let owned = ALLOWANCES_SPENDER.key((spender.clone(), owner.clone()));
+        let borrowed = BORROWED_SPENDER.key((&spender, &owner));
+        assert_eq!(owned.to_vec(), borrowed.to_vec());
+        assert_eq!(
+            BORROWED_SPENDER
+                .load(&deps.storage, (&spender, &owner))
+                .unwrap(),
+            ALLOWANCES_SPENDER
+                .load(&deps.storage, (spender, owner))
+                .unwrap()
         );
     }
 }
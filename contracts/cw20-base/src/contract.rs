@@ -2,32 +2,42 @@
 use cosmwasm_std::entry_point;
 use cosmwasm_std::Order::Ascending;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Uint128,
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult,
+    Storage, SubMsg, SubMsgResult, Uint128,
 };
 
-use cw2::set_contract_version;
+use cw2::{ensure_from_older_version, set_contract_version};
 use cw20::{
     BalanceResponse, Cw20Coin, Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo,
     MarketingInfoResponse, MinterResponse, TokenInfoResponse,
 };
-use cw_utils::ensure_from_older_version;
 
 use crate::allowances::{
-    execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
-    execute_transfer_from, query_allowance,
+    execute_burn_batch, execute_burn_from, execute_decrease_allowance, execute_increase_allowance,
+    execute_send_from, execute_transfer_from, query_allowance, query_allowance_status,
 };
 use crate::enumerable::{query_all_accounts, query_owner_allowances, query_spender_allowances};
 use crate::error::ContractError;
+use crate::hooks::{
+    execute_deregister_receive_hook, execute_register_receive_hook, query_receive_hooks,
+    receive_hook, RECEIVE_HOOK_ID,
+};
+use crate::import::{assert_import_complete, execute_continue_import};
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::state::{
-    MinterData, TokenInfo, ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, LOGO, MARKETING_INFO,
-    TOKEN_INFO,
+    ImportState, MinterData, PendingSend, TokenInfo, ALLOWANCES, ALLOWANCES_SPENDER, BALANCES,
+    IMPORT, LOGO, MARKETING_INFO, PENDING_SEND, TOKEN_INFO,
 };
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-base";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const TRY_SEND_ID: u64 = 0x7e5d;
+
+/// Upper bound on the entries of TransferBatch and BurnBatch, to keep them within block gas limits
+pub const MAX_BATCH_SIZE: usize = 200;
+
 const LOGO_SIZE_CAP: usize = 5 * 1024;
 
 /// Checks if data starts with XML preamble
@@ -95,12 +105,29 @@ fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
 pub fn instantiate(
     mut deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     // check valid token info
     msg.validate()?;
+    if let Some(import) = &msg.import_from {
+        // the total supply must equal the sum of imported balances
+        if !msg.initial_balances.is_empty() {
+            return Err(ContractError::ImportWithInitialBalances {});
+        }
+        if import.limit == 0 {
+            return Err(ContractError::InvalidImportLimit {});
+        }
+        let state = ImportState {
+            contract: deps.api.addr_validate(&import.contract)?,
+            start_after: import.start_after.clone(),
+            limit: import.limit,
+            importer: info.sender,
+            complete: false,
+        };
+        IMPORT.save(deps.storage, &state)?;
+    }
     // create initial accounts
     let total_supply = create_accounts(&mut deps, &msg.initial_balances)?;
 
@@ -184,6 +211,37 @@ pub fn validate_accounts(accounts: &[Cw20Coin]) -> Result<(), ContractError> {
     }
 }
 
+/// Validates all the entries of a batch before anything is applied.
+/// Returns the validated addresses along with the total amount.
+pub fn validate_batch(
+    deps: Deps,
+    entries: &[Cw20Coin],
+) -> Result<(Vec<(Addr, Uint128)>, Uint128), ContractError> {
+    if entries.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    if entries.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::BatchTooLarge {
+            max: MAX_BATCH_SIZE,
+        });
+    }
+
+    let mut total = Uint128::zero();
+    let validated = entries
+        .iter()
+        .map(|entry| {
+            if entry.amount.is_zero() {
+                return Err(ContractError::InvalidZeroAmount {});
+            }
+            total = total
+                .checked_add(entry.amount)
+                .map_err(StdError::overflow)?;
+            Ok((deps.api.addr_validate(&entry.address)?, entry.amount))
+        })
+        .collect::<Result<_, ContractError>>()?;
+    Ok((validated, total))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -191,16 +249,41 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // only allowances and metadata can be changed while balances are imported
+    let allowed_during_import = matches!(
+        msg,
+        ExecuteMsg::ContinueImport { .. }
+            | ExecuteMsg::IncreaseAllowance { .. }
+            | ExecuteMsg::DecreaseAllowance { .. }
+            | ExecuteMsg::UpdateMarketing { .. }
+            | ExecuteMsg::UploadLogo(_)
+            | ExecuteMsg::UpdateMinter { .. }
+            | ExecuteMsg::UpdateCap { .. }
+            | ExecuteMsg::RegisterReceiveHook {}
+            | ExecuteMsg::DeregisterReceiveHook {}
+    );
+    if !allowed_during_import {
+        assert_import_complete(deps.storage)?;
+    }
+
     match msg {
         ExecuteMsg::Transfer { recipient, amount } => {
             execute_transfer(deps, env, info, recipient, amount)
         }
         ExecuteMsg::Burn { amount } => execute_burn(deps, env, info, amount),
+        ExecuteMsg::TransferBatch { recipients } => {
+            execute_transfer_batch(deps, env, info, recipients)
+        }
         ExecuteMsg::Send {
             contract,
             amount,
             msg,
         } => execute_send(deps, env, info, contract, amount, msg),
+        ExecuteMsg::TrySend {
+            contract,
+            amount,
+            msg,
+        } => execute_try_send(deps, env, info, contract, amount, msg),
         ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
         ExecuteMsg::IncreaseAllowance {
             spender,
@@ -218,6 +301,7 @@ pub fn execute(
             amount,
         } => execute_transfer_from(deps, env, info, owner, recipient, amount),
         ExecuteMsg::BurnFrom { owner, amount } => execute_burn_from(deps, env, info, owner, amount),
+        ExecuteMsg::BurnBatch { owners } => execute_burn_batch(deps, env, info, owners),
         ExecuteMsg::SendFrom {
             owner,
             contract,
@@ -233,6 +317,10 @@ pub fn execute(
         ExecuteMsg::UpdateMinter { new_minter } => {
             execute_update_minter(deps, env, info, new_minter)
         }
+        ExecuteMsg::UpdateCap { cap } => execute_update_cap(deps, env, info, cap),
+        ExecuteMsg::ContinueImport { limit } => execute_continue_import(deps, env, info, limit),
+        ExecuteMsg::RegisterReceiveHook {} => execute_register_receive_hook(deps, env, info),
+        ExecuteMsg::DeregisterReceiveHook {} => execute_deregister_receive_hook(deps, env, info),
     }
 }
 
@@ -262,14 +350,50 @@ pub fn execute_transfer(
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
+    let hook = receive_hook(deps.storage, &info.sender, &rcpt_addr, amount)?;
     let res = Response::new()
         .add_attribute("action", "transfer")
         .add_attribute("from", info.sender)
         .add_attribute("to", recipient)
-        .add_attribute("amount", amount);
+        .add_attribute("amount", amount)
+        .add_submessages(hook);
     Ok(res)
 }
 
+pub fn execute_transfer_batch(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    recipients: Vec<Cw20Coin>,
+) -> Result<Response, ContractError> {
+    let (recipients, total) = validate_batch(deps.as_ref(), &recipients)?;
+
+    // take the total at once, so an insufficient balance fails before any recipient is credited
+    BALANCES.update(
+        deps.storage,
+        &info.sender,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(total)?)
+        },
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "transfer_batch")
+        .add_attribute("from", &info.sender);
+    for (rcpt_addr, amount) in recipients {
+        BALANCES.update(
+            deps.storage,
+            &rcpt_addr,
+            |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+        )?;
+        res = res.add_attribute("to", format!("{}: {}", rcpt_addr, amount));
+        if let Some(hook) = receive_hook(deps.storage, &info.sender, &rcpt_addr, amount)? {
+            res = res.add_submessage(hook);
+        }
+    }
+    Ok(res.add_attribute("total_amount", total))
+}
+
 pub fn execute_burn(
     deps: DepsMut,
     _env: Env,
@@ -394,6 +518,98 @@ pub fn execute_send(
     Ok(res)
 }
 
+pub fn execute_try_send(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let rcpt_addr = deps.api.addr_validate(&contract)?;
+    move_balance(deps.storage, &info.sender, &rcpt_addr, amount)?;
+
+    // If the receiver fails, all of its state changes (including any nested TrySend
+    // overwriting this) are reverted before the reply runs, so the singleton is safe here
+    PENDING_SEND.save(
+        deps.storage,
+        &PendingSend {
+            sender: info.sender.clone(),
+            contract: rcpt_addr,
+            amount,
+        },
+    )?;
+
+    let receive = Cw20ReceiveMsg {
+        sender: info.sender.to_string(),
+        amount,
+        msg,
+    }
+    .into_cosmos_msg(&contract)?;
+
+    let res = Response::new()
+        .add_attribute("action", "try_send")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", contract)
+        .add_attribute("amount", amount)
+        .add_submessage(SubMsg::reply_on_error(receive, TRY_SEND_ID));
+    Ok(res)
+}
+
+fn move_balance(
+    storage: &mut dyn Storage,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    BALANCES.update(storage, from, |balance: Option<Uint128>| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    BALANCES.update(storage, to, |balance: Option<Uint128>| -> StdResult<_> {
+        Ok(balance.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
+    match reply.id {
+        // a failing receive hook must not revert the transfer
+        RECEIVE_HOOK_ID => match reply.result {
+            SubMsgResult::Ok(_) => Ok(Response::new()),
+            SubMsgResult::Err(err) => Ok(Response::new()
+                .add_attribute("action", "receive_hook_failed")
+                .add_attribute("error", err)),
+        },
+        TRY_SEND_ID => match reply.result {
+            SubMsgResult::Ok(_) => Ok(Response::new()),
+            SubMsgResult::Err(err) => {
+                // refund the tokens the receiver could not take
+                let pending = PENDING_SEND.load(deps.storage)?;
+                PENDING_SEND.remove(deps.storage);
+                move_balance(
+                    deps.storage,
+                    &pending.contract,
+                    &pending.sender,
+                    pending.amount,
+                )?;
+
+                Ok(Response::new()
+                    .add_attribute("action", "send_failed")
+                    .add_attribute("from", pending.sender)
+                    .add_attribute("to", pending.contract)
+                    .add_attribute("amount", pending.amount)
+                    .add_attribute("error", err))
+            }
+        },
+        _ => Err(ContractError::UnknownReplyId { id: reply.id }),
+    }
+}
+
 pub fn execute_update_minter(
     deps: DepsMut,
     _env: Env,
@@ -432,6 +648,37 @@ pub fn execute_update_minter(
         ))
 }
 
+pub fn execute_update_cap(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    cap: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut config = TOKEN_INFO
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    let total_supply = config.total_supply;
+    let mint = config.mint.as_mut().ok_or(ContractError::Unauthorized {})?;
+    if mint.minter != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if matches!(cap, Some(cap) if cap < total_supply) {
+        return Err(ContractError::CapBelowSupply {});
+    }
+    mint.cap = cap;
+
+    TOKEN_INFO.save(deps.storage, &config)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "update_cap")
+        .add_attribute(
+            "cap",
+            cap.map(|cap| cap.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        ))
+}
+
 pub fn execute_update_marketing(
     deps: DepsMut,
     _env: Env,
@@ -521,7 +768,7 @@ pub fn execute_upload_logo(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
         QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
@@ -529,6 +776,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
+        QueryMsg::AllowanceStatus { owner, spender } => {
+            to_binary(&query_allowance_status(deps, env, owner, spender)?)
+        }
         QueryMsg::AllAllowances {
             owner,
             start_after,
@@ -549,6 +799,9 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         }
         QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
         QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(deps)?),
+        QueryMsg::AllReceiveHooks { start_after, limit } => {
+            to_binary(&query_receive_hooks(deps, start_after, limit)?)
+        }
     }
 }
 
@@ -613,7 +866,7 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
             .range(deps.storage, None, None, Ascending)
             .collect::<StdResult<Vec<_>>>()?;
         for ((owner, spender), allowance) in data {
-            ALLOWANCES_SPENDER.save(deps.storage, (&spender, &owner), &allowance)?;
+            ALLOWANCES_SPENDER.save(deps.storage, (spender, owner), &allowance)?;
         }
     }
     Ok(Response::default())
@@ -624,7 +877,7 @@ mod tests {
     use cosmwasm_std::testing::{
         mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
     };
-    use cosmwasm_std::{coins, from_binary, Addr, CosmosMsg, StdError, SubMsg, WasmMsg};
+    use cosmwasm_std::{attr, coins, from_binary, Addr, CosmosMsg, StdError, SubMsg, WasmMsg};
 
     use super::*;
     use crate::msg::InstantiateMarketingInfo;
@@ -674,6 +927,7 @@ mod tests {
             }],
             mint: mint.clone(),
             marketing: None,
+            import_from: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -714,6 +968,7 @@ mod tests {
                 }],
                 mint: None,
                 marketing: None,
+                import_from: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -754,6 +1009,7 @@ mod tests {
                     cap: Some(limit),
                 }),
                 marketing: None,
+                import_from: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -801,6 +1057,7 @@ mod tests {
                     cap: Some(limit),
                 }),
                 marketing: None,
+                import_from: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -829,6 +1086,7 @@ mod tests {
                         marketing: Some("marketing".to_owned()),
                         logo: Some(Logo::Url("url".to_owned())),
                     }),
+                    import_from: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -869,6 +1127,7 @@ mod tests {
                         marketing: Some("m".to_owned()),
                         logo: Some(Logo::Url("url".to_owned())),
                     }),
+                    import_from: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -1043,6 +1302,92 @@ mod tests {
         assert_eq!(err, ContractError::Unauthorized {});
     }
 
+    #[test]
+    fn minter_can_update_cap() {
+        let mut deps = mock_dependencies();
+        let minter = String::from("minter");
+        do_instantiate_with_minter(
+            deps.as_mut(),
+            "genesis",
+            Uint128::new(1234),
+            &minter,
+            Some(Uint128::new(3000)),
+        );
+        let update_cap = |cap: Option<u128>| ExecuteMsg::UpdateCap {
+            cap: cap.map(Uint128::new),
+        };
+        let query_cap = |deps: Deps| query_minter(deps).unwrap().unwrap().cap;
+
+        // others cannot change the cap
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("genesis", &[]),
+            update_cap(Some(5000)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // the cap can not go below the supply, but down to it
+        let info = mock_info(&minter, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            update_cap(Some(1233)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CapBelowSupply {});
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            update_cap(Some(1234)),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1], ("cap", "1234"));
+        assert_eq!(query_cap(deps.as_ref()), Some(Uint128::new(1234)));
+        let msg = ExecuteMsg::Mint {
+            recipient: String::from("lucky"),
+            amount: Uint128::new(1),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+
+        // removing the cap allows minting again
+        execute(deps.as_mut(), mock_env(), info.clone(), update_cap(None)).unwrap();
+        assert_eq!(query_cap(deps.as_ref()), None);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(1235)
+        );
+
+        // after a handoff, only the new minter controls the cap
+        let msg = ExecuteMsg::UpdateMinter {
+            new_minter: Some("new_minter".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), info, update_cap(Some(2000))).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let new_info = mock_info("new_minter", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            new_info.clone(),
+            update_cap(Some(2000)),
+        )
+        .unwrap();
+        assert_eq!(query_cap(deps.as_ref()), Some(Uint128::new(2000)));
+
+        // once minting is renounced, the cap is fixed as well
+        let msg = ExecuteMsg::UpdateMinter { new_minter: None };
+        execute(deps.as_mut(), mock_env(), new_info.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), new_info, update_cap(Some(3000))).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        assert_eq!(query_minter(deps.as_ref()).unwrap(), None);
+    }
+
     #[test]
     fn no_one_mints_if_minter_unset() {
         let mut deps = mock_dependencies();
@@ -1085,6 +1430,7 @@ mod tests {
             ],
             mint: None,
             marketing: None,
+            import_from: None,
         };
         let err =
             instantiate(deps.as_mut(), env.clone(), info.clone(), instantiate_msg).unwrap_err();
@@ -1107,6 +1453,7 @@ mod tests {
             ],
             mint: None,
             marketing: None,
+            import_from: None,
         };
         let res = instantiate(deps.as_mut(), env, info, instantiate_msg).unwrap();
         assert_eq!(0, res.messages.len());
@@ -1220,6 +1567,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transfer_batch() {
+        let mut deps = mock_dependencies();
+        let sender = String::from("addr0001");
+        let start = Uint128::new(1000);
+        do_instantiate(deps.as_mut(), &sender, start);
+        let coin = |address: &str, amount: u128| Cw20Coin {
+            address: address.to_string(),
+            amount: Uint128::new(amount),
+        };
+        let info = mock_info(sender.as_ref(), &[]);
+
+        // empty, oversized and zero-amount batches are rejected
+        let msg = ExecuteMsg::TransferBatch { recipients: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::EmptyBatch {});
+        let msg = ExecuteMsg::TransferBatch {
+            recipients: vec![coin("addr0002", 1); MAX_BATCH_SIZE + 1],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::BatchTooLarge {
+                max: MAX_BATCH_SIZE
+            }
+        );
+        let msg = ExecuteMsg::TransferBatch {
+            recipients: vec![coin("addr0002", 10), coin("addr0003", 0)],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        // the total must be covered, or nobody gets anything
+        let msg = ExecuteMsg::TransferBatch {
+            recipients: vec![coin("addr0002", 600), coin("addr0003", 401)],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+        assert_eq!(get_balance(deps.as_ref(), "addr0002"), Uint128::zero());
+        assert_eq!(get_balance(deps.as_ref(), sender.clone()), start);
+
+        // a batch ends up with the same balances as the single transfers
+        let recipients = vec![
+            coin("addr0002", 100),
+            coin("addr0003", 250),
+            coin("addr0002", 50),
+        ];
+        let mut singles = mock_dependencies();
+        do_instantiate(singles.as_mut(), &sender, start);
+        for rcpt in &recipients {
+            let msg = ExecuteMsg::Transfer {
+                recipient: rcpt.address.clone(),
+                amount: rcpt.amount,
+            };
+            execute(singles.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+
+        let msg = ExecuteMsg::TransferBatch { recipients };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "transfer_batch"),
+                attr("from", &sender),
+                attr("to", "addr0002: 100"),
+                attr("to", "addr0003: 250"),
+                attr("to", "addr0002: 50"),
+                attr("total_amount", "400"),
+            ]
+        );
+        for addr in [sender.as_str(), "addr0002", "addr0003"] {
+            assert_eq!(
+                get_balance(deps.as_ref(), addr),
+                get_balance(singles.as_ref(), addr)
+            );
+        }
+        assert_eq!(get_balance(deps.as_ref(), "addr0002"), Uint128::new(150));
+        assert_eq!(query_token_info(deps.as_ref()).unwrap().total_supply, start);
+    }
+
     #[test]
     fn burn() {
         let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
@@ -1343,6 +1770,162 @@ mod tests {
         );
     }
 
+    mod try_send {
+        use super::*;
+
+        use cosmwasm_schema::cw_serde;
+        use cosmwasm_std::Empty;
+        use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+        #[cw_serde]
+        enum ReceiverExecuteMsg {
+            Receive(Cw20ReceiveMsg),
+        }
+
+        fn cw20_contract() -> Box<dyn Contract<Empty>> {
+            let contract = ContractWrapper::new(
+                crate::contract::execute,
+                crate::contract::instantiate,
+                crate::contract::query,
+            )
+            .with_reply(crate::contract::reply);
+            Box::new(contract)
+        }
+
+        /// Accepts every transfer, unless the attached message is "fail"
+        fn receiver_contract() -> Box<dyn Contract<Empty>> {
+            let contract = ContractWrapper::new(
+                |_, _, _, ReceiverExecuteMsg::Receive(msg)| -> StdResult<Response> {
+                    if msg.msg.as_slice() == b"fail" {
+                        return Err(StdError::generic_err("receiver refused"));
+                    }
+                    Ok(Response::new())
+                },
+                |_, _, _, _: Empty| -> StdResult<Response> { Ok(Response::new()) },
+                |_, _, _: Empty| -> StdResult<Binary> { to_binary(&Empty {}) },
+            );
+            Box::new(contract)
+        }
+
+        fn balance(app: &App, token: &Addr, address: &Addr) -> Uint128 {
+            let res: BalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    token,
+                    &QueryMsg::Balance {
+                        address: address.to_string(),
+                    },
+                )
+                .unwrap();
+            res.balance
+        }
+
+        #[test]
+        fn refunds_when_receiver_fails() {
+            let mut app = App::default();
+            let owner = Addr::unchecked("owner");
+
+            let cw20_id = app.store_code(cw20_contract());
+            let token = app
+                .instantiate_contract(
+                    cw20_id,
+                    owner.clone(),
+                    &InstantiateMsg {
+                        name: "Token".to_string(),
+                        symbol: "TOKEN".to_string(),
+                        decimals: 6,
+                        initial_balances: vec![Cw20Coin {
+                            address: owner.to_string(),
+                            amount: Uint128::new(1000),
+                        }],
+                        mint: None,
+                        marketing: None,
+                        import_from: None,
+                    },
+                    &[],
+                    "TOKEN",
+                    None,
+                )
+                .unwrap();
+            let receiver_id = app.store_code(receiver_contract());
+            let receiver = app
+                .instantiate_contract(receiver_id, owner.clone(), &Empty {}, &[], "RCPT", None)
+                .unwrap();
+
+            // a successful delivery behaves like Send
+            app.execute_contract(
+                owner.clone(),
+                token.clone(),
+                &ExecuteMsg::TrySend {
+                    contract: receiver.to_string(),
+                    amount: Uint128::new(300),
+                    msg: Binary::from(b"ok"),
+                },
+                &[],
+            )
+            .unwrap();
+            assert_eq!(balance(&app, &token, &owner), Uint128::new(700));
+            assert_eq!(balance(&app, &token, &receiver), Uint128::new(300));
+
+            // a failing receiver leaves the transaction intact and refunds the sender
+            let res = app
+                .execute_contract(
+                    owner.clone(),
+                    token.clone(),
+                    &ExecuteMsg::TrySend {
+                        contract: receiver.to_string(),
+                        amount: Uint128::new(200),
+                        msg: Binary::from(b"fail"),
+                    },
+                    &[],
+                )
+                .unwrap();
+            assert_eq!(balance(&app, &token, &owner), Uint128::new(700));
+            assert_eq!(balance(&app, &token, &receiver), Uint128::new(300));
+
+            let failed = res
+                .events
+                .iter()
+                .find(|ev| {
+                    ev.attributes
+                        .iter()
+                        .any(|attr| attr.key == "action" && attr.value == "send_failed")
+                })
+                .expect("send_failed attributes missing");
+            let amount = failed.attributes.iter().find(|attr| attr.key == "amount");
+            assert_eq!(amount.unwrap().value, "200");
+            let to = failed.attributes.iter().find(|attr| attr.key == "to");
+            assert_eq!(to.unwrap().value, receiver.as_str());
+
+            // plain Send still aborts on failure
+            app.execute_contract(
+                owner.clone(),
+                token.clone(),
+                &ExecuteMsg::Send {
+                    contract: receiver.to_string(),
+                    amount: Uint128::new(200),
+                    msg: Binary::from(b"fail"),
+                },
+                &[],
+            )
+            .unwrap_err();
+            assert_eq!(balance(&app, &token, &owner), Uint128::new(700));
+
+            // cannot try to send more than the balance
+            app.execute_contract(
+                owner.clone(),
+                token.clone(),
+                &ExecuteMsg::TrySend {
+                    contract: receiver.to_string(),
+                    amount: Uint128::new(701),
+                    msg: Binary::from(b"ok"),
+                },
+                &[],
+            )
+            .unwrap_err();
+        }
+    }
+
     mod migration {
         use super::*;
 
@@ -1361,6 +1944,40 @@ mod tests {
             Box::new(contract)
         }
 
+        #[test]
+        fn migrate_checks_contract_version() {
+            let mut deps = mock_dependencies();
+            do_instantiate(deps.as_mut(), "sender", Uint128::new(100));
+
+            // a newer stored version cannot be downgraded
+            set_contract_version(&mut deps.storage, CONTRACT_NAME, "999.0.0").unwrap();
+            let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::Std(StdError::generic_err(format!(
+                    "Cannot migrate from newer version (999.0.0) to older ({})",
+                    CONTRACT_VERSION
+                )))
+            );
+
+            // nor can another contract be migrated into a cw20-base
+            set_contract_version(&mut deps.storage, "crates.io:cw4-group", "0.1.0").unwrap();
+            let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::Std(StdError::generic_err(format!(
+                    "Cannot migrate from crates.io:cw4-group to {}",
+                    CONTRACT_NAME
+                )))
+            );
+
+            // an older cw20-base is upgraded to the current version
+            set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.13.0").unwrap();
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+            let version = cw2::get_contract_version(&deps.storage).unwrap();
+            assert_eq!(version.version, CONTRACT_VERSION);
+        }
+
         #[test]
         fn test_migrate() {
             let mut app = App::default();
@@ -1380,6 +1997,7 @@ mod tests {
                         }],
                         mint: None,
                         marketing: None,
+                        import_from: None,
                     },
                     &[],
                     "TOKEN",
@@ -1481,6 +2099,7 @@ mod tests {
                     marketing: Some("marketing".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1535,6 +2154,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1588,6 +2208,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1641,6 +2262,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1694,6 +2316,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1747,6 +2370,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1756,7 +2380,7 @@ mod tests {
             let res = execute(
                 deps.as_mut(),
                 mock_env(),
-                info,
+                info.clone(),
                 ExecuteMsg::UpdateMarketing {
                     project: None,
                     description: None,
@@ -1777,6 +2401,16 @@ mod tests {
                 }
             );
 
+            // the previous marketing admin lost its rights
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                ExecuteMsg::UploadLogo(Logo::Url("new_url".to_owned())),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+
             let err = query_download_logo(deps.as_ref()).unwrap_err();
             assert!(
                 matches!(err, StdError::NotFound { .. }),
@@ -1800,6 +2434,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1857,6 +2492,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1910,6 +2546,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -1959,6 +2596,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2009,6 +2647,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2060,6 +2699,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2110,6 +2750,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2167,6 +2808,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -2217,6 +2859,7 @@ mod tests {
                     marketing: Some("creator".to_owned()),
                     logo: Some(Logo::Url("url".to_owned())),
                 }),
+                import_from: None,
             };
 
             let info = mock_info("creator", &[]);
@@ -6,6 +6,7 @@ use cw20::{
 
 use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES};
 use cw_storage_plus::Bound;
+use cw_utils::maybe_addr;
 
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
@@ -19,10 +20,11 @@ pub fn query_owner_allowances(
 ) -> StdResult<AllAllowancesResponse> {
     let owner_addr = deps.api.addr_validate(&owner)?;
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.map(Bound::exclusive);
 
     let allowances = ALLOWANCES
-        .prefix(&owner_addr)
+        .prefix(owner_addr)
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -44,10 +46,11 @@ pub fn query_spender_allowances(
 ) -> StdResult<AllSpenderAllowancesResponse> {
     let spender_addr = deps.api.addr_validate(&spender)?;
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.map(Bound::exclusive);
 
     let allowances = ALLOWANCES_SPENDER
-        .prefix(&spender_addr)
+        .prefix(spender_addr)
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -67,7 +70,8 @@ pub fn query_all_accounts(
     limit: Option<u32>,
 ) -> StdResult<AllAccountsResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
 
     let accounts = BALANCES
         .keys(deps.storage, start, None, Order::Ascending)
@@ -101,6 +105,7 @@ mod tests {
             }],
             mint: None,
             marketing: None,
+            import_from: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -315,5 +320,9 @@ mod tests {
             query_all_accounts(deps.as_ref(), Some(accounts.accounts[0].clone()), Some(777))
                 .unwrap();
         assert_eq!(accounts.accounts, expected_order[3..].to_vec());
+
+        // start_after is a human address and must be given in its normalized form
+        let start = expected_order[1].to_uppercase();
+        query_all_accounts(deps.as_ref(), Some(start), None).unwrap_err();
     }
 }
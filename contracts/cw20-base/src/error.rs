@@ -24,6 +24,18 @@ pub enum ContractError {
     #[error("Minting cannot exceed the cap")]
     CannotExceedCap {},
 
+    #[error("Cap cannot be below the current total supply")]
+    CapBelowSupply {},
+
+    #[error("Batch must not be empty")]
+    EmptyBatch {},
+
+    #[error("Batch cannot have more than {max} entries")]
+    BatchTooLarge { max: usize },
+
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+
     #[error("Logo binary data exceeds 5KB limit")]
     LogoTooBig {},
 
@@ -33,9 +45,27 @@ pub enum ContractError {
     #[error("Invalid png header")]
     InvalidPngHeader {},
 
-    #[error("Invalid expiration value")]
-    InvalidExpiration {},
+    #[error("Cannot set an expiration that already passed")]
+    AlreadyExpired {},
 
     #[error("Duplicate initial balance addresses")]
     DuplicateInitialBalanceAddresses {},
+
+    #[error("Initial balances cannot be combined with an import")]
+    ImportWithInitialBalances {},
+
+    #[error("Import limit must be greater than zero")]
+    InvalidImportLimit {},
+
+    #[error("Balances are frozen until the import is complete")]
+    ImportRunning {},
+
+    #[error("No import is running")]
+    NoImport {},
+
+    #[error("Only contracts can register a receive hook")]
+    NotAContract {},
+
+    #[error("No receive hook is registered for this account")]
+    NoReceiveHook {},
 }
@@ -0,0 +1,436 @@
+use cosmwasm_std::{
+    Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult, Storage,
+    SubMsg, Uint128,
+};
+use cw20::{AllAccountsResponse, Cw20ReceiveMsg};
+use cw_storage_plus::Bound;
+use cw_utils::maybe_addr;
+
+use crate::error::ContractError;
+use crate::state::RECEIVE_HOOKS;
+
+/// Reply id of transfer notifications, whose failures are ignored
+pub const RECEIVE_HOOK_ID: u64 = 0x7e5e;
+
+/// Gas a transfer notification may use. Without a limit, a receiver running out of gas
+/// would abort the whole transfer, as such a failure cannot be caught in the reply.
+pub const RECEIVE_HOOK_GAS_LIMIT: u64 = 200_000;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+/// Makes every plain transfer to the calling contract notify it with a `Receive`
+pub fn execute_register_receive_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    deps.querier
+        .query_wasm_contract_info(&info.sender)
+        .map_err(|_| ContractError::NotAContract {})?;
+    RECEIVE_HOOKS.save(deps.storage, &info.sender, &Empty {})?;
+
+    let res = Response::new()
+        .add_attribute("action", "register_receive_hook")
+        .add_attribute("account", info.sender);
+    Ok(res)
+}
+
+pub fn execute_deregister_receive_hook(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if !RECEIVE_HOOKS.has(deps.storage, &info.sender) {
+        return Err(ContractError::NoReceiveHook {});
+    }
+    RECEIVE_HOOKS.remove(deps.storage, &info.sender);
+
+    let res = Response::new()
+        .add_attribute("action", "deregister_receive_hook")
+        .add_attribute("account", info.sender);
+    Ok(res)
+}
+
+/// The notification of a transfer to `recipient`, if it registered a receive hook.
+/// It must be added after all the state writes of the transfer, and the transfer
+/// stands even if the notification fails.
+pub fn receive_hook(
+    storage: &dyn Storage,
+    sender: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<Option<SubMsg>> {
+    if !RECEIVE_HOOKS.has(storage, recipient) {
+        return Ok(None);
+    }
+    let notify = Cw20ReceiveMsg {
+        sender: sender.to_string(),
+        amount,
+        msg: Binary::default(),
+    }
+    .into_cosmos_msg(recipient)?;
+    let mut submsg = SubMsg::reply_on_error(notify, RECEIVE_HOOK_ID);
+    submsg.gas_limit = Some(RECEIVE_HOOK_GAS_LIMIT);
+    Ok(Some(submsg))
+}
+
+pub fn query_receive_hooks(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    let accounts = RECEIVE_HOOKS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Into::into))
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllAccountsResponse { accounts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{to_binary, StdError, WasmMsg};
+    use cw20::{BalanceResponse, Cw20Coin};
+    use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+    use cw_storage_plus::Item;
+
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    #[cw_serde]
+    struct ReceiverInstantiateMsg {
+        fail: bool,
+        #[serde(default)]
+        burn_gas: bool,
+    }
+
+    #[cw_serde]
+    enum ReceiverExecuteMsg {
+        Receive(Cw20ReceiveMsg),
+        /// (De)registers the receive hook on the given token
+        Hook {
+            token: String,
+            register: bool,
+        },
+    }
+
+    const FAIL: Item<bool> = Item::new("fail");
+    const BURN_GAS: Item<bool> = Item::new("burn_gas");
+    const NOTIFICATIONS: Item<u32> = Item::new("notifications");
+
+    fn cw20_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        )
+        .with_reply(crate::contract::reply);
+        Box::new(contract)
+    }
+
+    /// Counts the notifications it gets, or fails on every one of them
+    fn receiver_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            |deps, _, _, msg: ReceiverExecuteMsg| -> StdResult<Response> {
+                match msg {
+                    ReceiverExecuteMsg::Receive(_) => {
+                        // counted before failing, to show the count is reverted
+                        NOTIFICATIONS.update(deps.storage, |n| -> StdResult<_> { Ok(n + 1) })?;
+                        if FAIL.load(deps.storage)? {
+                            return Err(StdError::generic_err("hook refused"));
+                        }
+                        if BURN_GAS.load(deps.storage)? {
+                            // multi-test does not meter gas, so this fails like the chain does
+                            // once the receiver used up the gas limit of the notification
+                            for n in 0..1000u32 {
+                                NOTIFICATIONS.save(deps.storage, &n)?;
+                            }
+                            return Err(StdError::generic_err("out of gas"));
+                        }
+                        Ok(Response::new())
+                    }
+                    ReceiverExecuteMsg::Hook { token, register } => {
+                        let msg = if register {
+                            ExecuteMsg::RegisterReceiveHook {}
+                        } else {
+                            ExecuteMsg::DeregisterReceiveHook {}
+                        };
+                        Ok(Response::new().add_message(WasmMsg::Execute {
+                            contract_addr: token,
+                            msg: to_binary(&msg)?,
+                            funds: vec![],
+                        }))
+                    }
+                }
+            },
+            |deps, _, _, msg: ReceiverInstantiateMsg| -> StdResult<Response> {
+                FAIL.save(deps.storage, &msg.fail)?;
+                BURN_GAS.save(deps.storage, &msg.burn_gas)?;
+                NOTIFICATIONS.save(deps.storage, &0)?;
+                Ok(Response::new())
+            },
+            |deps, _, _: Empty| -> StdResult<Binary> {
+                to_binary(&NOTIFICATIONS.load(deps.storage)?)
+            },
+        );
+        Box::new(contract)
+    }
+
+    fn notifications(app: &App, receiver: &Addr) -> u32 {
+        app.wrap().query_wasm_smart(receiver, &Empty {}).unwrap()
+    }
+
+    fn balance(app: &App, token: &Addr, address: &Addr) -> Uint128 {
+        let res: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                token,
+                &QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        res.balance
+    }
+
+    fn hook_failed(res: &AppResponse) -> bool {
+        res.events.iter().any(|ev| {
+            ev.attributes
+                .iter()
+                .any(|attr| attr.key == "action" && attr.value == "receive_hook_failed")
+        })
+    }
+
+    #[test]
+    fn transfers_notify_hooked_accounts() {
+        let mut app = App::default();
+        let owner = Addr::unchecked("owner");
+
+        let cw20_id = app.store_code(cw20_contract());
+        let token = app
+            .instantiate_contract(
+                cw20_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    name: "Token".to_string(),
+                    symbol: "TOKEN".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![Cw20Coin {
+                        address: owner.to_string(),
+                        amount: Uint128::new(1000),
+                    }],
+                    mint: None,
+                    marketing: None,
+                    import_from: None,
+                },
+                &[],
+                "TOKEN",
+                None,
+            )
+            .unwrap();
+        let receiver_id = app.store_code(receiver_contract());
+        let mut receiver = |fail: bool| {
+            let receiver = app
+                .instantiate_contract(
+                    receiver_id,
+                    owner.clone(),
+                    &ReceiverInstantiateMsg {
+                        fail,
+                        burn_gas: false,
+                    },
+                    &[],
+                    "RCPT",
+                    None,
+                )
+                .unwrap();
+            app.execute_contract(
+                owner.clone(),
+                receiver.clone(),
+                &ReceiverExecuteMsg::Hook {
+                    token: token.to_string(),
+                    register: true,
+                },
+                &[],
+            )
+            .unwrap();
+            receiver
+        };
+        let counter = receiver(false);
+        let failing = receiver(true);
+
+        // only contracts can register
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                token.clone(),
+                &ExecuteMsg::RegisterReceiveHook {},
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::NotAContract {}, err.downcast().unwrap());
+
+        let hooks: AllAccountsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &token,
+                &QueryMsg::AllReceiveHooks {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            hooks.accounts,
+            vec![counter.to_string(), failing.to_string()]
+        );
+
+        let transfer = |recipient: &Addr, amount: u128| ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount: Uint128::new(amount),
+        };
+        let res = app
+            .execute_contract(owner.clone(), token.clone(), &transfer(&counter, 100), &[])
+            .unwrap();
+        assert!(!hook_failed(&res));
+        assert_eq!(notifications(&app, &counter), 1);
+
+        // a failing hook doesn't stop the transfer
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                token.clone(),
+                &ExecuteMsg::TransferBatch {
+                    recipients: vec![
+                        Cw20Coin {
+                            address: counter.to_string(),
+                            amount: Uint128::new(10),
+                        },
+                        Cw20Coin {
+                            address: failing.to_string(),
+                            amount: Uint128::new(20),
+                        },
+                    ],
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(hook_failed(&res));
+        assert_eq!(notifications(&app, &counter), 2);
+        assert_eq!(notifications(&app, &failing), 0);
+        assert_eq!(balance(&app, &token, &counter), Uint128::new(110));
+        assert_eq!(balance(&app, &token, &failing), Uint128::new(20));
+        assert_eq!(balance(&app, &token, &owner), Uint128::new(870));
+
+        // no more notifications once deregistered
+        app.execute_contract(
+            owner.clone(),
+            counter.clone(),
+            &ReceiverExecuteMsg::Hook {
+                token: token.to_string(),
+                register: false,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(owner.clone(), token.clone(), &transfer(&counter, 5), &[])
+            .unwrap();
+        assert_eq!(notifications(&app, &counter), 2);
+        assert_eq!(balance(&app, &token, &counter), Uint128::new(115));
+
+        let err = app
+            .execute_contract(
+                counter.clone(),
+                token,
+                &ExecuteMsg::DeregisterReceiveHook {},
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::NoReceiveHook {}, err.downcast().unwrap());
+    }
+
+    #[test]
+    fn gas_hungry_hooks_do_not_block_transfers() {
+        // the notification can only use a bounded amount of gas
+        let mut storage = cosmwasm_std::testing::MockStorage::new();
+        let (owner, rcpt) = (Addr::unchecked("owner"), Addr::unchecked("rcpt"));
+        RECEIVE_HOOKS.save(&mut storage, &rcpt, &Empty {}).unwrap();
+        let hook = receive_hook(&storage, &owner, &rcpt, Uint128::new(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(hook.gas_limit, Some(RECEIVE_HOOK_GAS_LIMIT));
+
+        let mut app = App::default();
+        let cw20_id = app.store_code(cw20_contract());
+        let token = app
+            .instantiate_contract(
+                cw20_id,
+                owner.clone(),
+                &InstantiateMsg {
+                    name: "Token".to_string(),
+                    symbol: "TOKEN".to_string(),
+                    decimals: 6,
+                    initial_balances: vec![Cw20Coin {
+                        address: owner.to_string(),
+                        amount: Uint128::new(1000),
+                    }],
+                    mint: None,
+                    marketing: None,
+                    import_from: None,
+                },
+                &[],
+                "TOKEN",
+                None,
+            )
+            .unwrap();
+        let receiver_id = app.store_code(receiver_contract());
+        let receiver = app
+            .instantiate_contract(
+                receiver_id,
+                owner.clone(),
+                &ReceiverInstantiateMsg {
+                    fail: false,
+                    burn_gas: true,
+                },
+                &[],
+                "RCPT",
+                None,
+            )
+            .unwrap();
+        app.execute_contract(
+            owner.clone(),
+            receiver.clone(),
+            &ReceiverExecuteMsg::Hook {
+                token: token.to_string(),
+                register: true,
+            },
+            &[],
+        )
+        .unwrap();
+
+        // the receiver runs out of gas, the transfer goes through all the same
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                token.clone(),
+                &ExecuteMsg::Transfer {
+                    recipient: receiver.to_string(),
+                    amount: Uint128::new(100),
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(hook_failed(&res));
+        assert_eq!(notifications(&app, &receiver), 0);
+        assert_eq!(balance(&app, &token, &receiver), Uint128::new(100));
+        assert_eq!(balance(&app, &token, &owner), Uint128::new(900));
+    }
+}
@@ -0,0 +1,255 @@
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128};
+use cw20::{AllAccountsResponse, Cw20Contract, Cw20QueryMsg};
+
+use crate::error::ContractError;
+use crate::state::{BALANCES, IMPORT, TOKEN_INFO};
+
+/// Fails while balances are still being imported from another contract
+pub fn assert_import_complete(storage: &dyn Storage) -> Result<(), ContractError> {
+    match IMPORT.may_load(storage)? {
+        Some(import) if !import.complete => Err(ContractError::ImportRunning {}),
+        _ => Ok(()),
+    }
+}
+
+/// Credits the balances of the next page of accounts of the source contract.
+/// The import completes once the source returns no more accounts.
+pub fn execute_continue_import(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut import = IMPORT
+        .may_load(deps.storage)?
+        .filter(|import| !import.complete)
+        .ok_or(ContractError::NoImport {})?;
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    let is_minter = matches!(&config.mint, Some(mint) if mint.minter == info.sender);
+    if !is_minter && import.importer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let limit = limit.unwrap_or(import.limit);
+    if limit == 0 {
+        return Err(ContractError::InvalidImportLimit {});
+    }
+
+    let source = Cw20Contract(import.contract.clone());
+    let page: AllAccountsResponse = deps.querier.query_wasm_smart(
+        &import.contract,
+        &Cw20QueryMsg::AllAccounts {
+            start_after: import.start_after.clone(),
+            limit: Some(limit),
+        },
+    )?;
+    let mut imported = Uint128::zero();
+    for account in &page.accounts {
+        let balance = source.balance(&deps.querier, account)?;
+        if balance.is_zero() {
+            continue;
+        }
+        let address = deps.api.addr_validate(account)?;
+        BALANCES.update(deps.storage, &address, |current| -> StdResult<_> {
+            Ok(current.unwrap_or_default().checked_add(balance)?)
+        })?;
+        imported = imported.checked_add(balance).map_err(StdError::overflow)?;
+    }
+
+    config.total_supply = config
+        .total_supply
+        .checked_add(imported)
+        .map_err(StdError::overflow)?;
+    if let Some(limit) = config.get_cap() {
+        if config.total_supply > limit {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    TOKEN_INFO.save(deps.storage, &config)?;
+
+    // the source may cap the page size, so only an empty page ends the import
+    match page.accounts.last() {
+        Some(last) => import.start_after = Some(last.clone()),
+        None => import.complete = true,
+    }
+    IMPORT.save(deps.storage, &import)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "continue_import")
+        .add_attribute("accounts", page.accounts.len().to_string())
+        .add_attribute("amount", imported)
+        .add_attribute("complete", import.complete.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::{Addr, Empty};
+    use cw20::{BalanceResponse, Cw20Coin, MinterResponse, TokenInfoResponse};
+    use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+    use crate::msg::{ExecuteMsg, ImportFrom, InstantiateMsg, QueryMsg};
+
+    fn cw20_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    fn token_msg(
+        initial_balances: Vec<Cw20Coin>,
+        import_from: Option<ImportFrom>,
+    ) -> InstantiateMsg {
+        InstantiateMsg {
+            name: "Token".to_string(),
+            symbol: "TOKEN".to_string(),
+            decimals: 6,
+            initial_balances,
+            mint: Some(MinterResponse {
+                minter: "minter".to_string(),
+                cap: None,
+            }),
+            marketing: None,
+            import_from,
+        }
+    }
+
+    fn balance(app: &App, token: &Addr, address: &str) -> Uint128 {
+        let res: BalanceResponse = app
+            .wrap()
+            .query_wasm_smart(
+                token,
+                &QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        res.balance
+    }
+
+    #[test]
+    fn imports_balances_in_pages() {
+        let mut app = App::default();
+        let owner = Addr::unchecked("owner");
+        let cw20_id = app.store_code(cw20_contract());
+
+        // source token with 7 accounts, one of them without balance
+        let balances: Vec<_> = (1..=7u128)
+            .map(|i| Cw20Coin {
+                address: format!("holder{}", i),
+                amount: Uint128::new(if i == 4 { 0 } else { i * 100 }),
+            })
+            .collect();
+        let source = app
+            .instantiate_contract(
+                cw20_id,
+                owner.clone(),
+                &token_msg(balances.clone(), None),
+                &[],
+                "SOURCE",
+                None,
+            )
+            .unwrap();
+
+        let import_from = ImportFrom {
+            contract: source.to_string(),
+            start_after: None,
+            limit: 3,
+        };
+        // imported balances cannot be mixed with initial ones
+        let err = app
+            .instantiate_contract(
+                cw20_id,
+                owner.clone(),
+                &token_msg(balances[..1].to_vec(), Some(import_from.clone())),
+                &[],
+                "TOKEN",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(
+            ContractError::ImportWithInitialBalances {},
+            err.downcast().unwrap()
+        );
+        let token = app
+            .instantiate_contract(
+                cw20_id,
+                owner.clone(),
+                &token_msg(vec![], Some(import_from)),
+                &[],
+                "TOKEN",
+                None,
+            )
+            .unwrap();
+
+        let continue_import = |app: &mut App, sender: &str| {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                token.clone(),
+                &ExecuteMsg::ContinueImport { limit: None },
+                &[],
+            )
+        };
+        let transfer = ExecuteMsg::Transfer {
+            recipient: "holder1".to_string(),
+            amount: Uint128::new(1),
+        };
+
+        // only the instantiator or the minter can import
+        let err = continue_import(&mut app, "holder1").unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // pages of 3, 3 and 1 accounts, then an empty one completes the import
+        let mut pages = vec![];
+        for sender in ["owner", "minter", "owner", "minter"] {
+            let err = app
+                .execute_contract(Addr::unchecked("holder1"), token.clone(), &transfer, &[])
+                .unwrap_err();
+            assert_eq!(ContractError::ImportRunning {}, err.downcast().unwrap());
+
+            let res = continue_import(&mut app, sender).unwrap();
+            let attrs = res.custom_attrs(1);
+            pages.push((
+                attrs[1].value.clone(),
+                attrs[2].value.clone(),
+                attrs[3].value.clone(),
+            ));
+        }
+        let page = |accounts: &str, amount: &str, complete: &str| {
+            (
+                accounts.to_string(),
+                amount.to_string(),
+                complete.to_string(),
+            )
+        };
+        assert_eq!(
+            pages,
+            vec![
+                page("3", "600", "false"),
+                page("3", "1100", "false"),
+                page("1", "700", "false"),
+                page("0", "0", "true"),
+            ]
+        );
+        let err = continue_import(&mut app, "owner").unwrap_err();
+        assert_eq!(ContractError::NoImport {}, err.downcast().unwrap());
+
+        // balances and total supply match the source
+        for coin in &balances {
+            assert_eq!(balance(&app, &token, &coin.address), coin.amount);
+        }
+        let info: TokenInfoResponse = app
+            .wrap()
+            .query_wasm_smart(&token, &QueryMsg::TokenInfo {})
+            .unwrap();
+        assert_eq!(info.total_supply, Uint128::new(2400));
+
+        // and tokens can be moved again
+        app.execute_contract(Addr::unchecked("holder2"), token.clone(), &transfer, &[])
+            .unwrap();
+        assert_eq!(balance(&app, &token, "holder1"), Uint128::new(101));
+    }
+}
@@ -18,6 +18,8 @@ pub mod allowances;
 pub mod contract;
 pub mod enumerable;
 mod error;
+pub mod hooks;
+pub mod import;
 pub mod msg;
 pub mod state;
 
@@ -1,11 +1,9 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{StdError, StdResult, Uint128};
-use cw20::{Cw20Coin, Logo, MinterResponse};
+use cosmwasm_std::{Binary, StdError, StdResult, Uint128};
+use cw20::{Cw20Coin, Expiration, Logo, MinterResponse};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-pub use cw20::Cw20ExecuteMsg as ExecuteMsg;
-
 #[cw_serde]
 pub struct InstantiateMarketingInfo {
     pub project: Option<String>,
@@ -14,6 +12,16 @@ pub struct InstantiateMarketingInfo {
     pub logo: Option<Logo>,
 }
 
+/// Copies the balances of an existing cw20 contract, page by page with `ContinueImport`
+#[cw_serde]
+pub struct ImportFrom {
+    pub contract: String,
+    /// only accounts after this one are imported
+    pub start_after: Option<String>,
+    /// number of accounts imported per `ContinueImport`, unless it sets its own
+    pub limit: u32,
+}
+
 #[cw_serde]
 #[cfg_attr(test, derive(Default))]
 pub struct InstantiateMsg {
@@ -23,6 +31,9 @@ pub struct InstantiateMsg {
     pub initial_balances: Vec<Cw20Coin>,
     pub mint: Option<MinterResponse>,
     pub marketing: Option<InstantiateMarketingInfo>,
+    /// Balances to copy from another cw20 contract instead of `initial_balances`.
+    /// Balances are frozen until the import is complete.
+    pub import_from: Option<ImportFrom>,
 }
 
 impl InstantiateMsg {
@@ -70,6 +81,99 @@ impl InstantiateMsg {
     }
 }
 
+/// The cw20 spec messages (see `cw20::Cw20ExecuteMsg`), plus the ones only this contract supports
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Transfer is a base message to move tokens to another account without triggering actions
+    Transfer { recipient: String, amount: Uint128 },
+    /// Burn is a base message to destroy tokens forever
+    Burn { amount: Uint128 },
+    /// TransferBatch moves tokens to many accounts at once. Either all transfers
+    /// succeed or none does.
+    TransferBatch { recipients: Vec<Cw20Coin> },
+    /// Send is a base message to transfer tokens to a contract and trigger an action
+    /// on the receiving contract.
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// TrySend works like Send, but if the receiving contract fails, the tokens are
+    /// returned to the sender instead of aborting the whole transaction.
+    TrySend {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Allows spender to access an additional amount tokens
+    /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
+    /// expiration with this one.
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Lowers the spender's access of tokens
+    /// from the owner's (env.sender) account by amount. If expires is Some(), overwrites current
+    /// allowance expiration with this one.
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Transfers amount tokens from owner -> recipient
+    /// if `env.sender` has sufficient pre-approval.
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Only with "approval" extension. Sends amount tokens from owner -> contract
+    /// if `env.sender` has sufficient pre-approval.
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Destroys tokens forever
+    BurnFrom { owner: String, amount: Uint128 },
+    /// Only with "approval" extension. Like BurnFrom, for many owners at once.
+    /// Either all burns succeed or none does.
+    BurnBatch { owners: Vec<Cw20Coin> },
+    /// Only with the "mintable" extension. If authorized, creates amount new tokens
+    /// and adds to the recipient balance.
+    Mint { recipient: String, amount: Uint128 },
+    /// Only with the "mintable" extension. The current minter may set
+    /// a new minter. Setting the minter to None will remove the
+    /// token's minter forever.
+    UpdateMinter { new_minter: Option<String> },
+    /// Only with the "mintable" extension. The current minter may change
+    /// the cap, but not below the current total supply. None removes the cap.
+    UpdateCap { cap: Option<Uint128> },
+    /// Only with the "marketing" extension. If authorized, updates marketing metadata.
+    /// Setting None/null for any of these will leave it unchanged.
+    /// Setting Some("") will clear this field on the contract storage
+    UpdateMarketing {
+        /// A URL pointing to the project behind this token.
+        project: Option<String>,
+        /// A longer description of the token and it's utility. Designed for tooltips or such
+        description: Option<String>,
+        /// The address (if any) who can update this data structure
+        marketing: Option<String>,
+    },
+    /// If set as the "marketing" role on the contract, upload a new URL, SVG, or PNG for the token
+    UploadLogo(Logo),
+    /// Only for tokens importing balances from another contract. Copies the balances of
+    /// the next `limit` accounts of the source contract.
+    ContinueImport { limit: Option<u32> },
+    /// Only for contracts. Every Transfer, TransferBatch or TransferFrom to the caller
+    /// also sends it a `Receive` with an empty `msg`. Failures of that call are ignored.
+    RegisterReceiveHook {},
+    /// Stops the notifications set up with `RegisterReceiveHook`
+    DeregisterReceiveHook {},
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -87,6 +191,10 @@ pub enum QueryMsg {
     /// Returns how much spender can use from owner account, 0 if unset.
     #[returns(cw20::AllowanceResponse)]
     Allowance { owner: String, spender: String },
+    /// Only with "allowance" extension.
+    /// Like Allowance, but also tells if the allowance expired at the current block.
+    #[returns(AllowanceStatusResponse)]
+    AllowanceStatus { owner: String, spender: String },
     /// Only with "enumerable" extension (and "allowances")
     /// Returns all allowances this owner has approved. Supports pagination.
     #[returns(cw20::AllAllowancesResponse)]
@@ -120,6 +228,20 @@ pub enum QueryMsg {
     /// contract.
     #[returns(cw20::DownloadLogoResponse)]
     DownloadLogo {},
+    /// Returns all accounts notified on incoming transfers. Supports pagination.
+    #[returns(cw20::AllAccountsResponse)]
+    AllReceiveHooks {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct AllowanceStatusResponse {
+    pub allowance: Uint128,
+    pub expires: Expiration,
+    /// true if the allowance can no longer be spent at the queried block
+    pub expired: bool,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema)]
@@ -129,6 +251,42 @@ pub struct MigrateMsg {}
 mod tests {
     use super::*;
 
+    #[test]
+    fn execute_msg_extends_spec() {
+        use cosmwasm_std::{from_slice, to_vec};
+
+        // spec messages keep their format
+        let spec = cw20::Cw20ExecuteMsg::Transfer {
+            recipient: "rcpt".into(),
+            amount: Uint128::new(5),
+        };
+        let msg: ExecuteMsg = from_slice(&to_vec(&spec).unwrap()).unwrap();
+        assert_eq!(
+            msg,
+            ExecuteMsg::Transfer {
+                recipient: "rcpt".into(),
+                amount: Uint128::new(5),
+            }
+        );
+
+        // while the extensions stay local
+        let ext = to_vec(&ExecuteMsg::ContinueImport { limit: None }).unwrap();
+        from_slice::<cw20::Cw20ExecuteMsg>(&ext).unwrap_err();
+        let ext = to_vec(&ExecuteMsg::UpdateCap { cap: None }).unwrap();
+        from_slice::<cw20::Cw20ExecuteMsg>(&ext).unwrap_err();
+        let ext = to_vec(&ExecuteMsg::TrySend {
+            contract: "rcpt".into(),
+            amount: Uint128::new(5),
+            msg: Binary::default(),
+        })
+        .unwrap();
+        from_slice::<cw20::Cw20ExecuteMsg>(&ext).unwrap_err();
+        let ext = to_vec(&ExecuteMsg::TransferBatch { recipients: vec![] }).unwrap();
+        from_slice::<cw20::Cw20ExecuteMsg>(&ext).unwrap_err();
+        let ext = to_vec(&ExecuteMsg::BurnBatch { owners: vec![] }).unwrap();
+        from_slice::<cw20::Cw20ExecuteMsg>(&ext).unwrap_err();
+    }
+
     #[test]
     fn validate_instantiatemsg_name() {
         // Too short
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Empty, Uint128};
 use cw_storage_plus::{Item, Map};
 
 use cw20::{AllowanceResponse, Logo, MarketingInfoResponse};
@@ -26,11 +26,36 @@ impl TokenInfo {
     }
 }
 
+/// A `TrySend` whose receive submessage has not settled yet
+#[cw_serde]
+pub struct PendingSend {
+    pub sender: Addr,
+    pub contract: Addr,
+    pub amount: Uint128,
+}
+
+/// Progress of the import requested with `InstantiateMsg::import_from`
+#[cw_serde]
+pub struct ImportState {
+    /// cw20 contract the balances are copied from
+    pub contract: Addr,
+    /// last imported account, the next page starts after it
+    pub start_after: Option<String>,
+    /// default number of accounts imported per `ContinueImport`
+    pub limit: u32,
+    /// instantiator of this contract, allowed to continue the import besides the minter
+    pub importer: Addr,
+    pub complete: bool,
+}
+
 pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
 pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
 pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
-pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
+pub const PENDING_SEND: Item<PendingSend> = Item::new("pending_send");
+pub const IMPORT: Item<ImportState> = Item::new("import");
+/// Accounts notified on incoming transfers
+pub const RECEIVE_HOOKS: Map<&Addr, Empty> = Map::new("receive_hooks");
+pub const ALLOWANCES: Map<(Addr, Addr), AllowanceResponse> = Map::new("allowance");
 // TODO: After https://github.com/CosmWasm/cw-plus/issues/670 is implemented, replace this with a `MultiIndex` over `ALLOWANCES`
-pub const ALLOWANCES_SPENDER: Map<(&Addr, &Addr), AllowanceResponse> =
-    Map::new("allowance_spender");
+pub const ALLOWANCES_SPENDER: Map<(Addr, Addr), AllowanceResponse> = Map::new("allowance_spender");
@@ -4,6 +4,9 @@ use cosmwasm_std::{Coin, Uint128};
 use cw20::Cw20Coin;
 use std::convert::TryInto;
 
+/// cw20 tokens are represented as "cw20:{contract address}" in channel state and ics20 packets
+const CW20_DENOM_PREFIX: &str = "cw20:";
+
 #[cw_serde]
 pub enum Amount {
     Native(Coin),
@@ -12,13 +15,14 @@ pub enum Amount {
 }
 
 impl Amount {
-    // TODO: write test for this
+    /// This is the inverse of `denom()`, it parses the denom used in channel state and packets
     pub fn from_parts(denom: String, amount: Uint128) -> Self {
-        if denom.starts_with("cw20:") {
-            let address = denom.get(5..).unwrap().into();
-            Amount::Cw20(Cw20Coin { address, amount })
-        } else {
-            Amount::Native(Coin { denom, amount })
+        match denom.strip_prefix(CW20_DENOM_PREFIX) {
+            Some(address) => Amount::Cw20(Cw20Coin {
+                address: address.into(),
+                amount,
+            }),
+            None => Amount::Native(Coin { denom, amount }),
         }
     }
 
@@ -41,7 +45,7 @@ impl Amount {
     pub fn denom(&self) -> String {
         match self {
             Amount::Native(c) => c.denom.clone(),
-            Amount::Cw20(c) => format!("cw20:{}", c.address.as_str()),
+            Amount::Cw20(c) => format!("{}{}", CW20_DENOM_PREFIX, c.address.as_str()),
         }
     }
 
@@ -64,3 +68,26 @@ impl Amount {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_amount() {
+        // native denom
+        let res = Amount::from_parts("ucosm".to_string(), 1234u128.into());
+        assert_eq!("ucosm", res.denom());
+        assert_eq!(Amount::native(1234, "ucosm"), res);
+
+        // cw20 token
+        let res = Amount::from_parts("cw20:my-token".to_string(), 1234u128.into());
+        assert_eq!("cw20:my-token", res.denom());
+        assert_eq!(Amount::cw20(1234, "my-token"), res);
+
+        // denom round trips for both
+        for amount in [Amount::native(55, "uatom"), Amount::cw20(66, "token-addr")] {
+            assert_eq!(Amount::from_parts(amount.denom(), amount.amount()), amount);
+        }
+    }
+}
@@ -1,33 +1,37 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, IbcMsg, IbcQuery, MessageInfo, Order,
-    PortIdResponse, Response, StdError, StdResult,
+    to_binary, Addr, Binary, Coin, Deps, DepsMut, Env, IbcMsg, IbcTimeout, MessageInfo, Order,
+    Response, StdError, StdResult, Uint128, WasmMsg,
 };
 use semver::Version;
 
 use cw2::{get_contract_version, set_contract_version};
-use cw20::{Cw20Coin, Cw20ReceiveMsg};
+use cw20::{Cw20Coin, Cw20Contract, Cw20ExecuteMsg, Cw20ReceiveMsg};
 use cw_storage_plus::Bound;
 
 use crate::amount::Amount;
 use crate::error::ContractError;
-use crate::ibc::Ics20Packet;
-use crate::migrations::{v1, v2};
+use crate::ibc::{send_amount, voucher_denom, Ics20Packet};
+use crate::migrations::{v1, v2, v3};
 use crate::msg::{
     AllowMsg, AllowedInfo, AllowedResponse, ChannelResponse, ConfigResponse, ExecuteMsg, InitMsg,
     ListAllowedResponse, ListChannelsResponse, MigrateMsg, PortResponse, QueryMsg, TransferMsg,
 };
 use crate::state::{
-    increase_channel_balance, AllowInfo, Config, ADMIN, ALLOW_LIST, CHANNEL_INFO, CHANNEL_STATE,
-    CONFIG,
+    add_in_flight, increase_channel_balance, reduce_channel_balance, AllowInfo, Config,
+    VoucherInfo, ADMIN, ALLOW_LIST, CHANNEL_INFO, CHANNEL_STATE, CONFIG, IN_FLIGHT, VOUCHERS,
+    VOUCHER_INFO,
 };
-use cw_utils::{maybe_addr, nonpayable, one_coin};
+use cw_utils::{maybe_addr, nonpayable};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw20-ics20";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Longest memo we put into an outgoing packet (32KiB)
+pub const MAX_MEMO_LENGTH: usize = 32 * 1024;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
@@ -39,6 +43,8 @@ pub fn instantiate(
     let cfg = Config {
         default_timeout: msg.default_timeout,
         default_gas_limit: msg.default_gas_limit,
+        counterparty_port: msg.counterparty_port,
+        paused: false,
     };
     CONFIG.save(deps.storage, &cfg)?;
 
@@ -66,7 +72,7 @@ pub fn execute(
     match msg {
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
         ExecuteMsg::Transfer(msg) => {
-            let coin = one_coin(&info)?;
+            let coin = one_native_coin(&info)?;
             execute_transfer(deps, env, msg, Amount::Native(coin), info.sender)
         }
         ExecuteMsg::Allow(allow) => execute_allow(deps, env, info, allow),
@@ -74,6 +80,28 @@ pub fn execute(
             let admin = deps.api.addr_validate(&admin)?;
             Ok(ADMIN.execute_update_admin(deps, info, Some(admin))?)
         }
+        ExecuteMsg::Pause {} => execute_set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => execute_set_paused(deps, info, false),
+        ExecuteMsg::RegisterVoucher {
+            channel,
+            remote_denom,
+            contract,
+        } => execute_register_voucher(deps, env, info, channel, remote_denom, contract),
+        ExecuteMsg::RecoverLockedFunds {
+            channel,
+            denom,
+            recipient,
+        } => execute_recover_locked_funds(deps, info, channel, denom, recipient),
+    }
+}
+
+/// Ensures exactly one native coin with a non-zero amount was sent along with the transfer
+pub fn one_native_coin(info: &MessageInfo) -> Result<Coin, ContractError> {
+    match info.funds.as_slice() {
+        [] => Err(ContractError::NoFunds {}),
+        [coin] if coin.amount.is_zero() => Err(ContractError::NoFunds {}),
+        [coin] => Ok(coin.clone()),
+        _ => Err(ContractError::OnlyOneCoinAllowed {}),
     }
 }
 
@@ -85,13 +113,14 @@ pub fn execute_receive(
 ) -> Result<Response, ContractError> {
     nonpayable(&info)?;
 
-    let msg: TransferMsg = from_binary(&wrapper.msg)?;
+    // any cw20 token can be sent, execute_transfer checks it against the allow list
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
     let amount = Amount::Cw20(Cw20Coin {
         address: info.sender.to_string(),
         amount: wrapper.amount,
     });
-    let api = deps.api;
-    execute_transfer(deps, env, msg, amount, api.addr_validate(&wrapper.sender)?)
+    let msg: TransferMsg = wrapper.into_inner()?;
+    execute_transfer(deps, env, msg, amount, sender)
 }
 
 pub fn execute_transfer(
@@ -101,66 +130,149 @@ pub fn execute_transfer(
     amount: Amount,
     sender: Addr,
 ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused {
+        return Err(ContractError::Paused {});
+    }
     if amount.is_empty() {
         return Err(ContractError::NoFunds {});
     }
-    // ensure the requested channel is registered
-    if !CHANNEL_INFO.has(deps.storage, &msg.channel) {
-        return Err(ContractError::NoSuchChannel { id: msg.channel });
+    // ensure the requested channel is registered and still open
+    let channel = CHANNEL_INFO
+        .may_load(deps.storage, &msg.channel)?
+        .ok_or_else(|| ContractError::NoSuchChannel {
+            id: msg.channel.clone(),
+        })?;
+    if channel.closed {
+        return Err(ContractError::ChannelClosed { id: msg.channel });
     }
-    let config = CONFIG.load(deps.storage)?;
 
-    // if cw20 token, validate and ensure it is whitelisted, or we set default gas limit
-    if let Amount::Cw20(coin) = &amount {
-        let addr = deps.api.addr_validate(&coin.address)?;
-        // if limit is set, then we always allow cw20
-        if config.default_gas_limit.is_none() {
-            ALLOW_LIST
-                .may_load(deps.storage, &addr)?
-                .ok_or(ContractError::NotOnAllowList)?;
+    // vouchers we minted for remote tokens are burned, the remote chain will release the originals
+    let voucher = match &amount {
+        Amount::Cw20(coin) => {
+            let addr = deps.api.addr_validate(&coin.address)?;
+            VOUCHER_INFO.may_load(deps.storage, &addr)?
         }
+        Amount::Native(_) => None,
     };
-
-    // delta from user is in seconds
-    let timeout_delta = match msg.timeout {
-        Some(t) => t,
-        None => config.default_timeout,
+    let is_voucher = voucher.is_some();
+    let denom = match voucher {
+        Some(voucher) => {
+            if voucher.channel != msg.channel {
+                return Err(ContractError::WrongVoucherChannel {
+                    channel: voucher.channel,
+                });
+            }
+            voucher_denom(&channel.port_id, &channel.id, &voucher.remote_denom)
+        }
+        None => {
+            // if cw20 token, validate and ensure it is whitelisted, or we set default gas limit
+            if let Amount::Cw20(coin) = &amount {
+                let addr = deps.api.addr_validate(&coin.address)?;
+                // if limit is set, then we always allow cw20
+                if config.default_gas_limit.is_none() {
+                    ALLOW_LIST
+                        .may_load(deps.storage, &addr)?
+                        .ok_or(ContractError::NotOnAllowList)?;
+                }
+            };
+            amount.denom()
+        }
     };
-    // timeout is in nanoseconds
-    let timeout = env.block.time.plus_seconds(timeout_delta);
+
+    let timeout = packet_timeout(&env, &msg, config.default_timeout)?;
+    if let Some(memo) = &msg.memo {
+        if memo.len() > MAX_MEMO_LENGTH {
+            return Err(ContractError::MemoTooLong {
+                max: MAX_MEMO_LENGTH,
+            });
+        }
+    }
 
     // build ics20 packet
     let packet = Ics20Packet::new(
         amount.amount(),
-        amount.denom(),
+        &denom,
         sender.as_ref(),
         &msg.remote_address,
-    );
+    )
+    .with_memo(msg.memo.clone());
     packet.validate()?;
 
-    // Update the balance now (optimistically) like ibctransfer modules.
-    // In on_packet_failure (ack with error message or a timeout), we reduce the balance appropriately.
-    // This means the channel works fine if success acks are not relayed.
-    increase_channel_balance(deps.storage, &msg.channel, &amount.denom(), amount.amount())?;
+    let mut res = Response::new();
+    if is_voucher {
+        // we can never burn more vouchers than we minted on this channel.
+        // In on_packet_failure, we mint them again and restore the balance.
+        reduce_channel_balance(deps.storage, &msg.channel, &denom, amount.amount())?;
+        if let Amount::Cw20(coin) = amount {
+            let burn = Cw20ExecuteMsg::Burn {
+                amount: coin.amount,
+            };
+            res = res.add_message(WasmMsg::Execute {
+                contract_addr: coin.address,
+                msg: to_binary(&burn)?,
+                funds: vec![],
+            });
+        }
+    } else {
+        // Update the balance now (optimistically) like ibctransfer modules.
+        // In on_packet_failure (ack with error message or a timeout), we reduce the balance appropriately.
+        // This means the channel works fine if success acks are not relayed.
+        increase_channel_balance(deps.storage, &msg.channel, &denom, amount.amount())?;
+        add_in_flight(deps.storage, &msg.channel, &denom, amount.amount())?;
+    }
 
     // prepare ibc message
     let msg = IbcMsg::SendPacket {
         channel_id: msg.channel,
         data: to_binary(&packet)?,
-        timeout: timeout.into(),
+        timeout,
     };
 
     // send response
-    let res = Response::new()
+    let res = res
         .add_message(msg)
         .add_attribute("action", "transfer")
         .add_attribute("sender", &packet.sender)
         .add_attribute("receiver", &packet.receiver)
         .add_attribute("denom", &packet.denom)
-        .add_attribute("amount", &packet.amount.to_string());
+        .add_attribute("amount", packet.amount.to_string());
     Ok(res)
 }
 
+/// Absolute timeouts set by the user are used as given, otherwise the relative timeout
+/// (or default_timeout if unset) is added to the current block time.
+fn packet_timeout(
+    env: &Env,
+    msg: &TransferMsg,
+    default_timeout: u64,
+) -> Result<IbcTimeout, ContractError> {
+    let timeout = match (msg.timeout_height, msg.timeout_timestamp) {
+        (None, None) => {
+            // delta from user is in seconds
+            let timeout_delta = msg.timeout.unwrap_or(default_timeout);
+            // timeout is in nanoseconds
+            IbcTimeout::with_timestamp(env.block.time.plus_seconds(timeout_delta))
+        }
+        (Some(block), None) => IbcTimeout::with_block(block),
+        (None, Some(timestamp)) => IbcTimeout::with_timestamp(timestamp),
+        (Some(block), Some(timestamp)) => IbcTimeout::with_both(block, timestamp),
+    };
+
+    // the height refers to the remote chain, so we can only ensure it is set at all
+    if let Some(block) = timeout.block() {
+        if block.height == 0 {
+            return Err(ContractError::InvalidTimeout {});
+        }
+    }
+    if let Some(timestamp) = timeout.timestamp() {
+        if timestamp <= env.block.time {
+            return Err(ContractError::InvalidTimeout {});
+        }
+    }
+    Ok(timeout)
+}
+
 /// The gov contract can allow new contracts, or increase the gas limit on existing contracts.
 /// It cannot block or reduce the limit to avoid forcible sticking tokens in the channel.
 pub fn execute_allow(
@@ -202,10 +314,139 @@ pub fn execute_allow(
     Ok(res)
 }
 
+/// The admin can stop new transfers in an emergency. Acks, timeouts and incoming packets are
+/// still processed while paused, so refunds keep flowing.
+pub fn execute_set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    CONFIG.update(deps.storage, |mut cfg| -> StdResult<_> {
+        cfg.paused = paused;
+        Ok(cfg)
+    })?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    let res = Response::new()
+        .add_attribute("action", action)
+        .add_attribute("sender", info.sender);
+    Ok(res)
+}
+
+/// The gov contract can register a cw20 contract as voucher for a token native to the remote
+/// chain of one channel. We must be the minter of that contract, as we mint vouchers on receive
+/// and burn them when they are sent back. A registration cannot be changed afterwards.
+pub fn execute_register_voucher(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    remote_denom: String,
+    contract: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    if !CHANNEL_INFO.has(deps.storage, &channel) {
+        return Err(ContractError::NoSuchChannel { id: channel });
+    }
+    let addr = deps.api.addr_validate(&contract)?;
+    let minter = Cw20Contract(addr.clone()).minter(&deps.querier)?;
+    if minter.map(|m| m.minter) != Some(env.contract.address.into()) {
+        return Err(ContractError::NotVoucherMinter { contract });
+    }
+
+    if VOUCHERS.has(deps.storage, (&channel, &remote_denom))
+        || VOUCHER_INFO.has(deps.storage, &addr)
+    {
+        return Err(ContractError::VoucherAlreadyRegistered {
+            denom: remote_denom,
+        });
+    }
+    VOUCHERS.save(deps.storage, (&channel, &remote_denom), &addr)?;
+    let voucher = VoucherInfo {
+        channel: channel.clone(),
+        remote_denom: remote_denom.clone(),
+    };
+    VOUCHER_INFO.save(deps.storage, &addr, &voucher)?;
+
+    let res = Response::new()
+        .add_attribute("action", "register_voucher")
+        .add_attribute("channel", channel)
+        .add_attribute("remote_denom", remote_denom)
+        .add_attribute("contract", contract);
+    Ok(res)
+}
+
+/// The admin can drain the outstanding balance of one escrowed denom (a cw20 or native token
+/// sent from this chain, never a voucher) from a closed channel.
+/// Amounts of packets still in flight are kept, so they can be refunded on timeout.
+pub fn execute_recover_locked_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    denom: String,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let chan = CHANNEL_INFO
+        .may_load(deps.storage, &channel)?
+        .ok_or_else(|| ContractError::NoSuchChannel {
+            id: channel.clone(),
+        })?;
+    if !chan.closed {
+        return Err(ContractError::ChannelNotClosed { id: channel });
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    // Only escrowed tokens are held by this contract. The balance of a voucher just counts
+    // what is in circulation, there is nothing locked to recover for it.
+    let escrowed = match Amount::from_parts(denom.clone(), Uint128::zero()) {
+        Amount::Cw20(coin) => !VOUCHER_INFO.has(deps.storage, &Addr::unchecked(coin.address)),
+        Amount::Native(coin) => {
+            !coin
+                .denom
+                .starts_with(&voucher_denom(&chan.port_id, &channel, ""))
+        }
+    };
+    if !escrowed {
+        return Err(ContractError::NotEscrowed { denom });
+    }
+
+    let outstanding = CHANNEL_STATE
+        .may_load(deps.storage, (&channel, &denom))?
+        .unwrap_or_default()
+        .outstanding;
+    let in_flight = IN_FLIGHT
+        .may_load(deps.storage, (&channel, &denom))?
+        .unwrap_or_default();
+    let recovered = outstanding.saturating_sub(in_flight);
+    if recovered.is_zero() {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    reduce_channel_balance(deps.storage, &channel, &denom, recovered)?;
+
+    let amount = Amount::from_parts(denom.clone(), recovered);
+    let res = Response::new()
+        .add_message(send_amount(amount, recipient.to_string()))
+        .add_attribute("action", "recover_locked_funds")
+        .add_attribute("channel", channel)
+        .add_attribute("denom", denom)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", recovered);
+    Ok(res)
+}
+
 const MIGRATE_MIN_VERSION: &str = "0.11.1";
 const MIGRATE_VERSION_2: &str = "0.12.0-alpha1";
 // the new functionality starts in 0.13.1, this is the last release that needs to be migrated to v3
 const MIGRATE_VERSION_3: &str = "0.13.0";
+// ChannelInfo got our port_id and the closed flag in this release, everything before needs migrating
+const MIGRATE_VERSION_4: &str = "1.1.0";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
@@ -240,6 +481,8 @@ pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response,
         let config = Config {
             default_timeout: old_config.default_timeout,
             default_gas_limit: None,
+            counterparty_port: None,
+            paused: false,
         };
         CONFIG.save(deps.storage, &config)?;
     }
@@ -247,6 +490,11 @@ pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response,
     if storage_version <= MIGRATE_VERSION_3.parse().map_err(from_semver)? {
         v2::update_balances(deps.branch(), &env)?;
     }
+    // run the v3->v4 conversion if we are v3 style
+    if storage_version < MIGRATE_VERSION_4.parse().map_err(from_semver)? {
+        v3::update_channels(deps.branch())?;
+        v3::set_settled_below(deps.branch(), &msg.settled_below)?;
+    }
     // otherwise no migration (yet) - add them here
 
     // always allow setting the default gas limit via MigrateMsg, even if same version
@@ -257,6 +505,13 @@ pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response,
             Ok(old)
         })?;
     }
+    // same for the expected counterparty port
+    if msg.counterparty_port.is_some() {
+        CONFIG.update(deps.storage, |mut old| -> StdResult<_> {
+            old.counterparty_port = msg.counterparty_port;
+            Ok(old)
+        })?;
+    }
 
     // we don't need to save anything if migrating from the same version
     if storage_version < version {
@@ -275,7 +530,11 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Port {} => to_binary(&query_port(deps)?),
         QueryMsg::ListChannels {} => to_binary(&query_list(deps)?),
-        QueryMsg::Channel { id } => to_binary(&query_channel(deps, id)?),
+        QueryMsg::Channel {
+            id,
+            start_after,
+            limit,
+        } => to_binary(&query_channel(deps, id, start_after, limit)?),
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::Allowed { contract } => to_binary(&query_allowed(deps, contract)?),
         QueryMsg::ListAllowed { start_after, limit } => {
@@ -285,10 +544,16 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
+// we store our port on every channel connect, so any channel will tell us
 fn query_port(deps: Deps) -> StdResult<PortResponse> {
-    let query = IbcQuery::PortId {}.into();
-    let PortIdResponse { port_id } = deps.querier.query(&query)?;
-    Ok(PortResponse { port_id })
+    let channel = CHANNEL_INFO
+        .range(deps.storage, None, None, Order::Ascending)
+        .next()
+        .transpose()?
+        .ok_or_else(|| StdError::generic_err("No channel connected yet"))?;
+    Ok(PortResponse {
+        port_id: channel.1.port_id,
+    })
 }
 
 fn query_list(deps: Deps) -> StdResult<ListChannelsResponse> {
@@ -300,12 +565,20 @@ fn query_list(deps: Deps) -> StdResult<ListChannelsResponse> {
 }
 
 // make public for ibc tests
-pub fn query_channel(deps: Deps, id: String) -> StdResult<ChannelResponse> {
+pub fn query_channel(
+    deps: Deps,
+    id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ChannelResponse> {
     let info = CHANNEL_INFO.load(deps.storage, &id)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
     // this returns Vec<(outstanding, total)>
     let state = CHANNEL_STATE
         .prefix(&id)
-        .range(deps.storage, None, None, Order::Ascending)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
         .map(|r| {
             r.map(|(denom, v)| {
                 let outstanding = Amount::from_parts(denom.clone(), v.outstanding);
@@ -324,13 +597,15 @@ pub fn query_channel(deps: Deps, id: String) -> StdResult<ChannelResponse> {
     })
 }
 
-fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     let admin = ADMIN.get(deps)?.unwrap_or_else(|| Addr::unchecked(""));
     let res = ConfigResponse {
         default_timeout: cfg.default_timeout,
         default_gas_limit: cfg.default_gas_limit,
+        counterparty_port: cfg.counterparty_port,
         gov_contract: admin.into(),
+        paused: cfg.paused,
     };
     Ok(res)
 }
@@ -382,14 +657,30 @@ mod test {
     use super::*;
     use crate::test_helpers::*;
 
-    use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::{coin, coins, CosmosMsg, IbcMsg, StdError, Uint128};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{
+        coin, coins, from_binary, BankMsg, ContractResult, CosmosMsg, IbcMsg, IbcTimeoutBlock,
+        StdError, SubMsg, SystemResult, Uint128, WasmMsg,
+    };
 
-    use crate::state::ChannelState;
+    use crate::msg::ChannelSequence;
+    use crate::state::{
+        mark_packet_handled, settle_in_flight, ChannelState, HANDLED_PACKETS, SETTLED_BELOW,
+    };
+    use cw20::{Cw20ExecuteMsg, MinterResponse};
+    use cw_controllers::AdminError;
     use cw_utils::PaymentError;
 
     #[test]
     fn setup_and_query() {
+        let mut deps = mock_dependencies();
+        let err = query(deps.as_ref(), mock_env(), QueryMsg::Port {}).unwrap_err();
+        assert_eq!(err, StdError::generic_err("No channel connected yet"));
+        add_channel(deps.as_mut(), "channel-3");
+        let raw_port = query(deps.as_ref(), mock_env(), QueryMsg::Port {}).unwrap();
+        let port_res: PortResponse = from_binary(&raw_port).unwrap();
+        assert_eq!(port_res.port_id, CONTRACT_PORT);
+
         let deps = setup(&["channel-3", "channel-7"], &[]);
 
         let raw_list = query(deps.as_ref(), mock_env(), QueryMsg::ListChannels {}).unwrap();
@@ -403,6 +694,8 @@ mod test {
             mock_env(),
             QueryMsg::Channel {
                 id: "channel-3".to_string(),
+                start_after: None,
+                limit: None,
             },
         )
         .unwrap();
@@ -416,6 +709,8 @@ mod test {
             mock_env(),
             QueryMsg::Channel {
                 id: "channel-10".to_string(),
+                start_after: None,
+                limit: None,
             },
         )
         .unwrap_err();
@@ -431,6 +726,9 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "foreign-address".to_string(),
             timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
         };
 
         // works with proper funds
@@ -461,13 +759,19 @@ mod test {
         let msg = ExecuteMsg::Transfer(transfer.clone());
         let info = mock_info("foobar", &[]);
         let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
-        assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
+        assert_eq!(err, ContractError::NoFunds {});
+
+        // reject with zero amount
+        let msg = ExecuteMsg::Transfer(transfer.clone());
+        let info = mock_info("foobar", &coins(0, "ucosm"));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::NoFunds {});
 
         // reject with multiple tokens funds
         let msg = ExecuteMsg::Transfer(transfer.clone());
         let info = mock_info("foobar", &[coin(1234567, "ucosm"), coin(54321, "uatom")]);
         let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
-        assert_eq!(err, ContractError::Payment(PaymentError::MultipleDenoms {}));
+        assert_eq!(err, ContractError::OnlyOneCoinAllowed {});
 
         // reject with bad channel id
         transfer.channel = "channel-45".to_string();
@@ -492,6 +796,9 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "foreign-address".to_string(),
             timeout: Some(7777),
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
         };
         let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
             sender: "my-account".into(),
@@ -526,6 +833,114 @@ mod test {
         let info = mock_info("foobar", &coins(1234567, "ucosm"));
         let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
         assert_eq!(err, ContractError::Payment(PaymentError::NonPayable {}));
+
+        // reject zero amount
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "my-account".into(),
+            amount: Uint128::zero(),
+            msg: to_binary(&transfer).unwrap(),
+        });
+        let info = mock_info(cw20_addr, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::NoFunds {});
+
+        // reject a malformed transfer message
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "my-account".into(),
+            amount: Uint128::new(888777666),
+            msg: Binary::from(br#"{"channel":"channel-15"}"#),
+        });
+        let info = mock_info(cw20_addr, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::ParseErr { .. })));
+    }
+
+    #[test]
+    fn transfer_timeouts() {
+        let send_channel = "channel-5";
+        let mut deps = setup(&[send_channel], &[]);
+        let env = mock_env();
+
+        let transfer = TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "foreign-address".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        };
+        let mut sent_timeout = |transfer: TransferMsg| -> Result<IbcTimeout, ContractError> {
+            let info = mock_info("foobar", &coins(1234567, "ucosm"));
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                info,
+                ExecuteMsg::Transfer(transfer),
+            )?;
+            match &res.messages[0].msg {
+                CosmosMsg::Ibc(IbcMsg::SendPacket { timeout, .. }) => Ok(timeout.clone()),
+                msg => panic!("Unexpected message: {:?}", msg),
+            }
+        };
+
+        // falls back to the default
+        let timeout = sent_timeout(transfer.clone()).unwrap();
+        let expected = IbcTimeout::with_timestamp(env.block.time.plus_seconds(DEFAULT_TIMEOUT));
+        assert_eq!(timeout, expected);
+
+        // explicit values override the default
+        let block = IbcTimeoutBlock {
+            revision: 2,
+            height: 12345,
+        };
+        let timestamp = env.block.time.plus_seconds(60);
+        let timeout = sent_timeout(TransferMsg {
+            timeout_height: Some(block),
+            ..transfer.clone()
+        })
+        .unwrap();
+        assert_eq!(timeout, IbcTimeout::with_block(block));
+        let timeout = sent_timeout(TransferMsg {
+            timeout: Some(7777),
+            timeout_timestamp: Some(timestamp),
+            ..transfer.clone()
+        })
+        .unwrap();
+        assert_eq!(timeout, IbcTimeout::with_timestamp(timestamp));
+        let timeout = sent_timeout(TransferMsg {
+            timeout_height: Some(block),
+            timeout_timestamp: Some(timestamp),
+            ..transfer.clone()
+        })
+        .unwrap();
+        assert_eq!(timeout, IbcTimeout::with_both(block, timestamp));
+
+        // zero or past timeouts are rejected
+        let invalid = [
+            TransferMsg {
+                timeout: Some(0),
+                ..transfer.clone()
+            },
+            TransferMsg {
+                timeout_timestamp: Some(env.block.time),
+                ..transfer.clone()
+            },
+            TransferMsg {
+                timeout_timestamp: Some(env.block.time.minus_seconds(1)),
+                ..transfer.clone()
+            },
+            TransferMsg {
+                timeout_height: Some(IbcTimeoutBlock {
+                    revision: 2,
+                    height: 0,
+                }),
+                ..transfer
+            },
+        ];
+        for msg in invalid {
+            let err = sent_timeout(msg).unwrap_err();
+            assert_eq!(err, ContractError::InvalidTimeout {});
+        }
     }
 
     #[test]
@@ -538,6 +953,9 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "foreign-address".to_string(),
             timeout: Some(7777),
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
         };
         let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
             sender: "my-account".into(),
@@ -556,6 +974,8 @@ mod test {
             mock_env(),
             MigrateMsg {
                 default_gas_limit: Some(123456),
+                counterparty_port: None,
+                settled_below: vec![],
             },
         )
         .unwrap();
@@ -564,6 +984,511 @@ mod test {
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     }
 
+    #[test]
+    fn query_channel_balances_paginated() {
+        let send_channel = "channel-5";
+        let mut deps = setup(&[send_channel], &[]);
+
+        let transfer = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "foreign-address".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        });
+        for (amount, denom) in [(100, "ucosm"), (200, "uatom"), (300, "ujuno")] {
+            let info = mock_info("foobar", &coins(amount, denom));
+            execute(deps.as_mut(), mock_env(), info, transfer.clone()).unwrap();
+        }
+
+        let page = query_channel(deps.as_ref(), send_channel.into(), None, Some(2)).unwrap();
+        assert_eq!(page.info, mock_channel_info(send_channel));
+        assert_eq!(
+            page.balances,
+            vec![Amount::native(200, "uatom"), Amount::native(100, "ucosm")]
+        );
+        assert_eq!(page.total_sent, page.balances);
+
+        let page = query_channel(
+            deps.as_ref(),
+            send_channel.into(),
+            Some("ucosm".to_string()),
+            Some(2),
+        )
+        .unwrap();
+        assert_eq!(page.balances, vec![Amount::native(300, "ujuno")]);
+
+        let page = query_channel(
+            deps.as_ref(),
+            send_channel.into(),
+            Some("ujuno".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(page.balances, vec![]);
+    }
+
+    #[test]
+    fn allow_list_and_config_queries() {
+        let mut deps = setup(&["channel-3"], &[("token-b", 2000), ("token-a", 1000)]);
+
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(
+            config,
+            ConfigResponse {
+                default_timeout: DEFAULT_TIMEOUT,
+                default_gas_limit: None,
+                counterparty_port: None,
+                gov_contract: "gov".to_string(),
+                paused: false,
+            }
+        );
+
+        // only the gov contract can allow new tokens
+        let allow = AllowMsg {
+            contract: "token-c".to_string(),
+            gas_limit: Some(3000),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("foobar", &[]),
+            ExecuteMsg::Allow(allow.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::Allow(allow),
+        )
+        .unwrap();
+
+        // gas limit cannot be lowered, only raised
+        let lower = AllowMsg {
+            contract: "token-b".to_string(),
+            gas_limit: Some(1500),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::Allow(lower),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CannotLowerGas);
+        let raise = AllowMsg {
+            contract: "token-b".to_string(),
+            gas_limit: Some(2500),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::Allow(raise),
+        )
+        .unwrap();
+
+        let allowed = query_allowed(deps.as_ref(), "token-b".to_string()).unwrap();
+        assert_eq!(
+            allowed,
+            AllowedResponse {
+                is_allowed: true,
+                gas_limit: Some(2500),
+            }
+        );
+        let allowed = query_allowed(deps.as_ref(), "token-z".to_string()).unwrap();
+        assert!(!allowed.is_allowed);
+
+        // list them paginated
+        let page = list_allowed(deps.as_ref(), None, Some(2)).unwrap();
+        let contracts: Vec<_> = page.allow.iter().map(|a| a.contract.as_str()).collect();
+        assert_eq!(contracts, vec!["token-a", "token-b"]);
+        let page = list_allowed(deps.as_ref(), Some("token-b".to_string()), Some(2)).unwrap();
+        assert_eq!(
+            page.allow,
+            vec![AllowedInfo {
+                contract: "token-c".to_string(),
+                gas_limit: Some(3000),
+            }]
+        );
+    }
+
+    #[test]
+    fn register_voucher_checks() {
+        let channel = "channel-3";
+        let mut deps = setup(&[channel], &[]);
+
+        let register =
+            |channel: &str, remote_denom: &str, contract: &str| ExecuteMsg::RegisterVoucher {
+                channel: channel.to_string(),
+                remote_denom: remote_denom.to_string(),
+                contract: contract.to_string(),
+            };
+
+        // only gov can register
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("foobar", &[]),
+            register(channel, "ujuno", "voucher"),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+
+        // must be a known channel
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            register("channel-99", "ujuno", "voucher"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NoSuchChannel {
+                id: "channel-99".to_string()
+            }
+        );
+
+        // we must be the minter
+        deps.querier.update_wasm(|_| {
+            let minter = Some(MinterResponse {
+                minter: "someone-else".to_string(),
+                cap: None,
+            });
+            SystemResult::Ok(ContractResult::Ok(to_binary(&minter).unwrap()))
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            register(channel, "ujuno", "voucher"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NotVoucherMinter {
+                contract: "voucher".to_string()
+            }
+        );
+
+        // registrations cannot be changed, neither denom nor contract can be reused
+        register_voucher(&mut deps, channel, "ujuno", "voucher");
+        for msg in [
+            register(channel, "ujuno", "other-voucher"),
+            register(channel, "uosmo", "voucher"),
+        ] {
+            let err = execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), msg).unwrap_err();
+            assert!(matches!(
+                err,
+                ContractError::VoucherAlreadyRegistered { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn recover_locked_funds_after_close() {
+        let send_channel = "channel-5";
+        let cw20_addr = "my-token";
+        let mut deps = setup(&[send_channel], &[(cw20_addr, 123456)]);
+
+        // lock some native and cw20 tokens on the channel
+        let transfer = TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "foreign-address".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        };
+        let info = mock_info("foobar", &coins(1234567, "ucosm"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Transfer(transfer.clone()),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "my-account".into(),
+            amount: Uint128::new(888777666),
+            msg: to_binary(&transfer).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info(cw20_addr, &[]), msg).unwrap();
+
+        let recover = |denom: &str| ExecuteMsg::RecoverLockedFunds {
+            channel: send_channel.to_string(),
+            denom: denom.to_string(),
+            recipient: "rescue".to_string(),
+        };
+        let cw20_denom = format!("cw20:{}", cw20_addr);
+
+        // both packets are acknowledged, see the ibc tests for packets still in flight
+        for (denom, amount) in [("ucosm", 1234567), (cw20_denom.as_str(), 888777666)] {
+            settle_in_flight(
+                deps.as_mut().storage,
+                send_channel,
+                denom,
+                Uint128::new(amount),
+            )
+            .unwrap();
+        }
+
+        // cannot recover while the channel is open
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            recover("ucosm"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ChannelNotClosed {
+                id: send_channel.to_string()
+            }
+        );
+
+        close_channel(deps.as_mut(), send_channel);
+
+        // only the admin can recover
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("foobar", &[]),
+            recover("ucosm"),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+
+        // admin drains the native balance
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            recover("ucosm"),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "rescue".into(),
+                amount: coins(1234567, "ucosm"),
+            })]
+        );
+
+        // and the cw20 balance
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            recover(&cw20_denom),
+        )
+        .unwrap();
+        let transfer = Cw20ExecuteMsg::Transfer {
+            recipient: "rescue".into(),
+            amount: Uint128::new(888777666),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(WasmMsg::Execute {
+                contract_addr: cw20_addr.into(),
+                msg: to_binary(&transfer).unwrap(),
+                funds: vec![],
+            })]
+        );
+
+        // nothing left to recover
+        let chan = query_channel(deps.as_ref(), send_channel.into(), None, None).unwrap();
+        assert_eq!(
+            chan.balances,
+            vec![Amount::cw20(0, cw20_addr), Amount::native(0, "ucosm")]
+        );
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            recover("ucosm"),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFunds {});
+
+        // vouchers are minted, not escrowed, so their balance cannot be drained
+        let port = CHANNEL_INFO
+            .load(&deps.storage, send_channel)
+            .unwrap()
+            .port_id;
+        let voucher = voucher_denom(&port, send_channel, "uatom");
+        increase_channel_balance(
+            deps.as_mut().storage,
+            send_channel,
+            &voucher,
+            Uint128::new(500),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            recover(&voucher),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NotEscrowed { denom: voucher });
+    }
+
+    #[test]
+    fn v4_migration_skipped_on_current_version() {
+        let mut deps = setup(&["channel-3"], &[]);
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+
+        // an entry without port is left alone, as the store is already up to date
+        let mut info = CHANNEL_INFO
+            .load(deps.as_ref().storage, "channel-3")
+            .unwrap();
+        info.port_id = String::new();
+        CHANNEL_INFO
+            .save(deps.as_mut().storage, "channel-3", &info)
+            .unwrap();
+
+        // no port query happens, the default mock querier would reject it
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                default_gas_limit: None,
+                counterparty_port: None,
+                settled_below: vec![],
+            },
+        )
+        .unwrap();
+        let list = query_list(deps.as_ref()).unwrap();
+        assert_eq!(list.channels, vec![info]);
+    }
+
+    #[test]
+    fn v4_migration_works() {
+        let mut deps = setup(&[], &[]);
+        // pretend this is an old contract - the last release with the old channel layout
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "1.0.0").unwrap();
+
+        // old style channels, without port and closed flag
+        for id in ["channel-3", "channel-7"] {
+            let old = format!(
+                r#"{{"id":"{}","counterparty_endpoint":{{"port_id":"{}","channel_id":"{}5"}},"connection_id":"{}"}}"#,
+                id, REMOTE_PORT, id, CONNECTION_ID
+            );
+            deps.as_mut()
+                .storage
+                .set(&CHANNEL_INFO.key(id), old.as_bytes());
+        }
+        // they cannot be read any more
+        CHANNEL_INFO
+            .load(deps.as_ref().storage, "channel-3")
+            .unwrap_err();
+
+        // the lowest sequence still in flight must be given for every channel
+        let mut deps = with_ibc_querier(deps);
+        let settled_below = |channels: &[(&str, u64)]| {
+            channels
+                .iter()
+                .map(|(channel, sequence)| ChannelSequence {
+                    channel: channel.to_string(),
+                    sequence: *sequence,
+                })
+                .collect::<Vec<_>>()
+        };
+        let migrate_msg = |channels: &[(&str, u64)]| MigrateMsg {
+            default_gas_limit: None,
+            counterparty_port: None,
+            settled_below: settled_below(channels),
+        };
+        let err = migrate(deps.as_mut(), mock_env(), migrate_msg(&[("channel-3", 4)])).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MissingSettledBelow {
+                id: "channel-7".to_string()
+            }
+        );
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            migrate_msg(&[("channel-3", 4), ("channel-7", 1), ("channel-9", 1)]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NoSuchChannel {
+                id: "channel-9".to_string()
+            }
+        );
+
+        // run migration, which queries our port
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            migrate_msg(&[("channel-3", 4), ("channel-7", 1)]),
+        )
+        .unwrap();
+
+        // packets settled before the upgrade are not recorded, the watermark moves on from
+        // the given sequence
+        assert_eq!(SETTLED_BELOW.load(&deps.storage, "channel-3").unwrap(), 4);
+        assert!(!mark_packet_handled(deps.as_mut().storage, "channel-3", 3).unwrap());
+        assert!(mark_packet_handled(deps.as_mut().storage, "channel-3", 4).unwrap());
+        assert_eq!(SETTLED_BELOW.load(&deps.storage, "channel-3").unwrap(), 5);
+        assert!(HANDLED_PACKETS.is_empty(&deps.storage));
+
+        // read back in the new format
+        let list = query_list(deps.as_ref()).unwrap();
+        assert_eq!(
+            list.channels,
+            vec![
+                mock_channel_info("channel-3"),
+                mock_channel_info("channel-7")
+            ]
+        );
+        let version = get_contract_version(deps.as_ref().storage).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // running it again on the same version changes nothing
+        close_channel(deps.as_mut(), "channel-7");
+        let before = query_list(deps.as_ref()).unwrap();
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                default_gas_limit: None,
+                counterparty_port: None,
+                settled_below: vec![],
+            },
+        )
+        .unwrap();
+        assert_eq!(query_list(deps.as_ref()).unwrap(), before);
+        assert!(before.channels[1].closed);
+
+        // refuses to migrate from another contract
+        set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.16.0").unwrap();
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                default_gas_limit: None,
+                counterparty_port: None,
+                settled_below: vec![],
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotMigrate {
+                previous_contract: "crates.io:cw20-base".to_string()
+            }
+        );
+    }
+
     #[test]
     fn v3_migration_works() {
         // basic state with one channel
@@ -595,12 +1520,17 @@ mod test {
             mock_env(),
             MigrateMsg {
                 default_gas_limit: Some(123456),
+                counterparty_port: None,
+                settled_below: vec![ChannelSequence {
+                    channel: send_channel.to_string(),
+                    sequence: 1,
+                }],
             },
         )
         .unwrap();
 
         // check new channel state
-        let chan = query_channel(deps.as_ref(), send_channel.into()).unwrap();
+        let chan = query_channel(deps.as_ref(), send_channel.into(), None, None).unwrap();
         assert_eq!(chan.balances, vec![Amount::native(50000, native)]);
         assert_eq!(chan.total_sent, vec![Amount::native(114000, native)]);
 
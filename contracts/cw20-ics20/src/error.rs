@@ -21,12 +21,33 @@ pub enum ContractError {
     #[error("{0}")]
     Admin(#[from] AdminError),
 
+    #[error("Transfers are paused by the admin")]
+    Paused {},
+
     #[error("Channel doesn't exist: {id}")]
     NoSuchChannel { id: String },
 
+    #[error("Migration needs the lowest sequence that may still be in flight on channel {id}")]
+    MissingSettledBelow { id: String },
+
+    #[error("Channel is closed: {id}")]
+    ChannelClosed { id: String },
+
+    #[error("Channel is still open, cannot recover funds: {id}")]
+    ChannelNotClosed { id: String },
+
     #[error("Didn't send any funds")]
     NoFunds {},
 
+    #[error("Only one native coin can be sent per transfer")]
+    OnlyOneCoinAllowed {},
+
+    #[error("Packet timeout must be set and lie in the future")]
+    InvalidTimeout {},
+
+    #[error("Memo must not be longer than {max} bytes")]
+    MemoTooLong { max: usize },
+
     #[error("Amount larger than 2**64, not supported by ics20 packets")]
     AmountOverflow {},
 
@@ -34,19 +55,28 @@ pub enum ContractError {
     InvalidIbcVersion { version: String },
 
     #[error("Only supports unordered channel")]
-    OnlyOrderedChannel {},
+    OnlyUnorderedChannel {},
+
+    #[error("Only connects to the transfer port or the configured counterparty port, got {port}")]
+    InvalidCounterpartyPort { port: String },
 
     #[error("Insufficient funds to redeem voucher on channel")]
     InsufficientFunds {},
 
-    #[error("Only accepts tokens that originate on this chain, not native tokens of remote chain")]
+    #[error("Only escrowed cw20 or native tokens can be recovered, not {denom}")]
+    NotEscrowed { denom: String },
+
+    #[error("Only accepts tokens that originate on this chain, or have a voucher registered by governance")]
     NoForeignTokens {},
 
-    #[error("Parsed port from denom ({port}) doesn't match packet")]
-    FromOtherPort { port: String },
+    #[error("Voucher already registered for this token or contract: {denom}")]
+    VoucherAlreadyRegistered { denom: String },
+
+    #[error("This contract must be the minter of the voucher contract {contract}")]
+    NotVoucherMinter { contract: String },
 
-    #[error("Parsed channel from denom ({channel}) doesn't match packet")]
-    FromOtherChannel { channel: String },
+    #[error("Voucher can only be sent back over channel {channel}")]
+    WrongVoucherChannel { channel: String },
 
     #[error("Cannot migrate from different contract type: {previous_contract}")]
     CannotMigrate { previous_contract: String },
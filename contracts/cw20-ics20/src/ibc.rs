@@ -3,22 +3,29 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    attr, entry_point, from_binary, to_binary, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Env,
-    IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
-    IbcEndpoint, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg,
-    IbcReceiveResponse, Reply, Response, SubMsg, SubMsgResult, Uint128, WasmMsg,
+    attr, entry_point, from_binary, to_binary, Addr, BankMsg, Binary, CosmosMsg, Deps, DepsMut,
+    Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
+    IbcChannelOpenResponse, IbcEndpoint, IbcOrder, IbcPacket, IbcPacketAckMsg, IbcPacketReceiveMsg,
+    IbcPacketTimeoutMsg, IbcReceiveResponse, Reply, Response, SubMsg, SubMsgResult, Uint128,
+    WasmMsg,
 };
 
+#[cfg(feature = "ibc3")]
+use cosmwasm_std::Ibc3ChannelOpenResponse;
+
 use crate::amount::Amount;
 use crate::error::{ContractError, Never};
 use crate::state::{
-    reduce_channel_balance, undo_reduce_channel_balance, ChannelInfo, ReplyArgs, ALLOW_LIST,
-    CHANNEL_INFO, CONFIG, REPLY_ARGS,
+    increase_channel_balance, mark_packet_handled, reduce_channel_balance, settle_in_flight,
+    undo_increase_channel_balance, undo_reduce_channel_balance, ChannelInfo, ReplyArgs, ALLOW_LIST,
+    CHANNEL_INFO, CONFIG, REPLY_ARGS, VOUCHERS,
 };
-use cw20::Cw20ExecuteMsg;
+use cw20::{Cw20Contract, Cw20ExecuteMsg};
 
 pub const ICS20_VERSION: &str = "ics20-1";
 pub const ICS20_ORDERING: IbcOrder = IbcOrder::Unordered;
+/// The port of the ibctransfer module on the counterparty chain
+pub const ICS20_PORT: &str = "transfer";
 
 /// The format for sending an ics20 packet.
 /// Proto defined here: https://github.com/cosmos/cosmos-sdk/blob/v0.42.0/proto/ibc/applications/transfer/v1/transfer.proto#L11-L20
@@ -33,6 +40,9 @@ pub struct Ics20Packet {
     pub receiver: String,
     /// the sender address
     pub sender: String,
+    /// optional memo, only serialized if set so counterparties without memo support can parse it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
 }
 
 impl Ics20Packet {
@@ -42,9 +52,15 @@ impl Ics20Packet {
             amount,
             sender: sender.to_string(),
             receiver: receiver.to_string(),
+            memo: None,
         }
     }
 
+    pub fn with_memo(mut self, memo: Option<String>) -> Self {
+        self.memo = memo;
+        self
+    }
+
     pub fn validate(&self) -> Result<(), ContractError> {
         if self.amount.u128() > (u64::MAX as u128) {
             Err(ContractError::AmountOverflow {})
@@ -97,14 +113,30 @@ pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, Contrac
                 // reentrancy on these functions (cannot be called by another contract). This pattern
                 // should not be used for ExecuteMsg handlers
                 let reply_args = REPLY_ARGS.load(deps.storage)?;
-                undo_reduce_channel_balance(
-                    deps.storage,
-                    &reply_args.channel,
-                    &reply_args.denom,
-                    reply_args.amount,
-                )?;
-
-                Ok(Response::new().set_data(ack_fail(err)))
+                if reply_args.voucher {
+                    undo_increase_channel_balance(
+                        deps.storage,
+                        &reply_args.channel,
+                        &reply_args.denom,
+                        reply_args.amount,
+                    )?;
+                } else {
+                    undo_reduce_channel_balance(
+                        deps.storage,
+                        &reply_args.channel,
+                        &reply_args.denom,
+                        reply_args.amount,
+                    )?;
+                }
+
+                // this includes a forward running out of the gas limit of the token
+                Ok(Response::new()
+                    .set_data(ack_fail(err.clone()))
+                    .add_attribute("action", "forward_failed")
+                    .add_attribute("channel", reply_args.channel)
+                    .add_attribute("denom", reply_args.denom)
+                    .add_attribute("amount", reply_args.amount)
+                    .add_attribute("error", err))
             }
         },
         ACK_FAILURE_ID => match reply.result {
@@ -116,13 +148,21 @@ pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, Contrac
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-/// enforces ordering and versioning constraints
+/// enforces ordering, versioning and counterparty port constraints
 pub fn ibc_channel_open(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
     msg: IbcChannelOpenMsg,
-) -> Result<(), ContractError> {
+) -> Result<IbcChannelOpenResponse, ContractError> {
     enforce_order_and_version(msg.channel(), msg.counterparty_version())?;
+    enforce_counterparty_port(deps.as_ref(), msg.channel())?;
+
+    // with ibc3 we can tell the counterparty which version we agreed on
+    #[cfg(feature = "ibc3")]
+    return Ok(Some(Ibc3ChannelOpenResponse {
+        version: ICS20_VERSION.to_string(),
+    }));
+    #[cfg(not(feature = "ibc3"))]
     Ok(())
 }
 
@@ -135,12 +175,15 @@ pub fn ibc_channel_connect(
 ) -> Result<IbcBasicResponse, ContractError> {
     // we need to check the counter party version in try and ack (sometimes here)
     enforce_order_and_version(msg.channel(), msg.counterparty_version())?;
+    enforce_counterparty_port(deps.as_ref(), msg.channel())?;
 
     let channel: IbcChannel = msg.into();
     let info = ChannelInfo {
         id: channel.endpoint.channel_id,
+        port_id: channel.endpoint.port_id,
         counterparty_endpoint: channel.counterparty_endpoint,
         connection_id: channel.connection_id,
+        closed: false,
     };
     CHANNEL_INFO.save(deps.storage, &info.id, &info)?;
 
@@ -164,20 +207,45 @@ fn enforce_order_and_version(
         }
     }
     if channel.order != ICS20_ORDERING {
-        return Err(ContractError::OnlyOrderedChannel {});
+        return Err(ContractError::OnlyUnorderedChannel {});
+    }
+    Ok(())
+}
+
+// we only talk to the ibctransfer module, or another ics20 contract if configured
+fn enforce_counterparty_port(deps: Deps, channel: &IbcChannel) -> Result<(), ContractError> {
+    let port = &channel.counterparty_endpoint.port_id;
+    if port == ICS20_PORT {
+        return Ok(());
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg.counterparty_port.as_ref() != Some(port) {
+        return Err(ContractError::InvalidCounterpartyPort { port: port.clone() });
     }
     Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
+/// mark the channel as closed in CHANNEL_INFO, so no new transfers are sent over it.
+/// Packets still in flight are settled as usual by their ack or timeout, and whatever
+/// remains outstanding afterwards can be recovered by the admin.
 pub fn ibc_channel_close(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
-    _channel: IbcChannelCloseMsg,
+    msg: IbcChannelCloseMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    // TODO: what to do here?
-    // we will have locked funds that need to be returned somehow
-    unimplemented!();
+    let id = msg.channel().endpoint.channel_id.as_str();
+    CHANNEL_INFO.update(deps.storage, id, |info| match info {
+        Some(mut info) => {
+            info.closed = true;
+            Ok(info)
+        }
+        None => Err(ContractError::NoSuchChannel { id: id.to_string() }),
+    })?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "close")
+        .add_attribute("channel", id))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -191,39 +259,41 @@ pub fn ibc_packet_receive(
     let packet = msg.packet;
 
     do_ibc_packet_receive(deps, &packet).or_else(|err| {
-        Ok(IbcReceiveResponse::new()
+        let mut res = IbcReceiveResponse::new()
             .set_ack(ack_fail(err.to_string()))
-            .add_attributes(vec![
-                attr("action", "receive"),
-                attr("success", "false"),
-                attr("error", err.to_string()),
-            ]))
+            .add_attribute("action", "receive");
+        // report the packet content as well if it can be parsed at all
+        if let Ok(msg) = from_binary::<Ics20Packet>(&packet.data) {
+            res = res.add_attributes(vec![
+                attr("sender", msg.sender),
+                attr("receiver", msg.receiver),
+                attr("denom", msg.denom),
+                attr("amount", msg.amount),
+            ]);
+        }
+        Ok(res
+            .add_attribute("success", "false")
+            .add_attribute("error", err.to_string()))
     })
 }
 
-// Returns local denom if the denom is an encoded voucher from the expected endpoint
-// Otherwise, error
+// Returns local denom if the denom is an encoded voucher from the expected endpoint,
+// that is one of our tokens returning. Returns None for tokens native to the remote chain.
 fn parse_voucher_denom<'a>(
     voucher_denom: &'a str,
     remote_endpoint: &IbcEndpoint,
-) -> Result<&'a str, ContractError> {
-    let split_denom: Vec<&str> = voucher_denom.splitn(3, '/').collect();
-    if split_denom.len() != 3 {
-        return Err(ContractError::NoForeignTokens {});
-    }
-    // a few more sanity checks
-    if split_denom[0] != remote_endpoint.port_id {
-        return Err(ContractError::FromOtherPort {
-            port: split_denom[0].into(),
-        });
-    }
-    if split_denom[1] != remote_endpoint.channel_id {
-        return Err(ContractError::FromOtherChannel {
-            channel: split_denom[1].into(),
-        });
-    }
+) -> Option<&'a str> {
+    let prefix = format!(
+        "{}/{}/",
+        remote_endpoint.port_id, remote_endpoint.channel_id
+    );
+    voucher_denom.strip_prefix(&prefix)
+}
 
-    Ok(split_denom[2])
+/// The denom we use for the voucher of a remote token, both in CHANNEL_STATE and in the
+/// packets sending it back. The remote chain will strip our prefix when it arrives there.
+pub fn voucher_denom(port_id: &str, channel_id: &str, remote_denom: &str) -> String {
+    format!("{}/{}/{}", port_id, channel_id, remote_denom)
 }
 
 // this does the work of ibc_packet_receive, we wrap it to turn errors into acknowledgements
@@ -232,25 +302,42 @@ fn do_ibc_packet_receive(
     packet: &IbcPacket,
 ) -> Result<IbcReceiveResponse, ContractError> {
     let msg: Ics20Packet = from_binary(&packet.data)?;
-    let channel = packet.dest.channel_id.clone();
+    msg.validate()?;
 
     // If the token originated on the remote chain, it looks like "ucosm".
     // If it originated on our chain, it looks like "port/channel/ucosm".
-    let denom = parse_voucher_denom(&msg.denom, &packet.src)?;
+    match parse_voucher_denom(&msg.denom, &packet.src) {
+        Some(denom) => receive_returning_tokens(deps, packet, denom.to_string(), msg),
+        None => receive_remote_tokens(deps, packet, msg),
+    }
+}
+
+// we are the source of these tokens, so we release what we have locked on the channel
+fn receive_returning_tokens(
+    deps: DepsMut,
+    packet: &IbcPacket,
+    denom: String,
+    msg: Ics20Packet,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let channel = packet.dest.channel_id.clone();
+
+    // Any error we return is turned into an error ack without reverting state,
+    // so all checks must happen before we update the balance
+    let to_send = Amount::from_parts(denom.clone(), msg.amount);
+    let gas_limit = check_gas_limit(deps.as_ref(), &to_send)?;
 
     // make sure we have enough balance for this
-    reduce_channel_balance(deps.storage, &channel, denom, msg.amount)?;
+    reduce_channel_balance(deps.storage, &channel, &denom, msg.amount)?;
 
     // we need to save the data to update the balances in reply
     let reply_args = ReplyArgs {
         channel,
-        denom: denom.to_string(),
+        denom: denom.clone(),
         amount: msg.amount,
+        voucher: false,
     };
     REPLY_ARGS.save(deps.storage, &reply_args)?;
 
-    let to_send = Amount::from_parts(denom.to_string(), msg.amount);
-    let gas_limit = check_gas_limit(deps.as_ref(), &to_send)?;
     let send = send_amount(to_send, msg.receiver.clone());
     let mut submsg = SubMsg::reply_on_error(send, RECEIVE_ID);
     submsg.gas_limit = gas_limit;
@@ -265,7 +352,55 @@ fn do_ibc_packet_receive(
         .add_attribute("amount", msg.amount)
         .add_attribute("success", "true");
 
-    Ok(res)
+    Ok(add_memo_attribute(res, msg.memo))
+}
+
+// expose the memo to indexers, if the sender added one
+fn add_memo_attribute(res: IbcReceiveResponse, memo: Option<String>) -> IbcReceiveResponse {
+    match memo {
+        Some(memo) => res.add_attribute("memo", memo),
+        None => res,
+    }
+}
+
+// we are the sink of these tokens, so we mint vouchers if governance registered a cw20 for them
+fn receive_remote_tokens(
+    deps: DepsMut,
+    packet: &IbcPacket,
+    msg: Ics20Packet,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let channel = packet.dest.channel_id.clone();
+    let voucher = VOUCHERS
+        .may_load(deps.storage, (&channel, &msg.denom))?
+        .ok_or(ContractError::NoForeignTokens {})?;
+    let denom = voucher_denom(&packet.dest.port_id, &channel, &msg.denom);
+
+    // track the vouchers in circulation, so we never burn more than we minted
+    increase_channel_balance(deps.storage, &channel, &denom, msg.amount)?;
+
+    // we need to save the data to update the balances in reply
+    let reply_args = ReplyArgs {
+        channel,
+        denom: denom.clone(),
+        amount: msg.amount,
+        voucher: true,
+    };
+    REPLY_ARGS.save(deps.storage, &reply_args)?;
+
+    let mint = mint_voucher(voucher, msg.receiver.clone(), msg.amount);
+    let submsg = SubMsg::reply_on_error(mint, RECEIVE_ID);
+
+    let res = IbcReceiveResponse::new()
+        .set_ack(ack_success())
+        .add_submessage(submsg)
+        .add_attribute("action", "receive")
+        .add_attribute("sender", msg.sender)
+        .add_attribute("receiver", msg.receiver)
+        .add_attribute("denom", denom)
+        .add_attribute("amount", msg.amount)
+        .add_attribute("success", "true");
+
+    Ok(add_memo_attribute(res, msg.memo))
 }
 
 fn check_gas_limit(deps: Deps, amount: &Amount) -> Result<Option<u64>, ContractError> {
@@ -296,10 +431,14 @@ pub fn ibc_packet_ack(
     // Design decision: should we trap error like in receive?
     // TODO: unsure... as it is now a failed ack handling would revert the tx and would be
     // retried again and again. is that good?
+    let packet = &msg.original_packet;
+    if !mark_packet_handled(deps.storage, &packet.src.channel_id, packet.sequence)? {
+        return Ok(duplicate_response("acknowledge", packet));
+    }
     let ics20msg: Ics20Ack = from_binary(&msg.acknowledgement.data)?;
     match ics20msg {
         Ics20Ack::Result(_) => on_packet_success(deps, msg.original_packet),
-        Ics20Ack::Error(err) => on_packet_failure(deps, msg.original_packet, err),
+        Ics20Ack::Error(err) => on_packet_failure(deps, msg.original_packet, "acknowledge", err),
     }
 }
 
@@ -312,13 +451,30 @@ pub fn ibc_packet_timeout(
 ) -> Result<IbcBasicResponse, ContractError> {
     // TODO: trap error like in receive? (same question as ack above)
     let packet = msg.packet;
-    on_packet_failure(deps, packet, "timeout".to_string())
+    if !mark_packet_handled(deps.storage, &packet.src.channel_id, packet.sequence)? {
+        return Ok(duplicate_response("timeout", &packet));
+    }
+    on_packet_failure(deps, packet, "timeout", "timeout".to_string())
+}
+
+// the packet was already settled by an earlier ack or timeout, so we leave the state untouched
+fn duplicate_response(action: &str, packet: &IbcPacket) -> IbcBasicResponse {
+    IbcBasicResponse::new()
+        .add_attribute("action", action)
+        .add_attribute("channel", &packet.src.channel_id)
+        .add_attribute("sequence", packet.sequence.to_string())
+        .add_attribute("duplicate", "true")
 }
 
 // update the balance stored on this (channel, denom) index
-fn on_packet_success(_deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
+fn on_packet_success(deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicResponse, ContractError> {
     let msg: Ics20Packet = from_binary(&packet.data)?;
 
+    // escrowed tokens stay locked, but are no longer in flight
+    if parse_voucher_denom(&msg.denom, &packet.src).is_none() {
+        settle_in_flight(deps.storage, &packet.src.channel_id, &msg.denom, msg.amount)?;
+    }
+
     // similar event messages like ibctransfer module
     let attributes = vec![
         attr("action", "acknowledge"),
@@ -332,27 +488,42 @@ fn on_packet_success(_deps: DepsMut, packet: IbcPacket) -> Result<IbcBasicRespon
     Ok(IbcBasicResponse::new().add_attributes(attributes))
 }
 
-// return the tokens to sender
+// return the tokens to sender, action is either "acknowledge" (error ack) or "timeout"
 fn on_packet_failure(
     deps: DepsMut,
     packet: IbcPacket,
+    action: &str,
     err: String,
 ) -> Result<IbcBasicResponse, ContractError> {
     let msg: Ics20Packet = from_binary(&packet.data)?;
-
-    // undo the balance update on failure (as we pre-emptively added it on send)
-    reduce_channel_balance(deps.storage, &packet.src.channel_id, &msg.denom, msg.amount)?;
-
-    let to_send = Amount::from_parts(msg.denom.clone(), msg.amount);
-    let gas_limit = check_gas_limit(deps.as_ref(), &to_send)?;
-    let send = send_amount(to_send, msg.sender.clone());
-    let mut submsg = SubMsg::reply_on_error(send, ACK_FAILURE_ID);
-    submsg.gas_limit = gas_limit;
+    let channel = &packet.src.channel_id;
+
+    let submsg = match parse_voucher_denom(&msg.denom, &packet.src) {
+        // we burned vouchers on send, re-mint them to the sender
+        Some(remote_denom) => {
+            let voucher = VOUCHERS.load(deps.storage, (channel, remote_denom))?;
+            undo_reduce_channel_balance(deps.storage, channel, &msg.denom, msg.amount)?;
+            let mint = mint_voucher(voucher, msg.sender.clone(), msg.amount);
+            SubMsg::reply_on_error(mint, ACK_FAILURE_ID)
+        }
+        None => {
+            // undo the balance update on failure (as we pre-emptively added it on send)
+            reduce_channel_balance(deps.storage, channel, &msg.denom, msg.amount)?;
+            settle_in_flight(deps.storage, channel, &msg.denom, msg.amount)?;
+
+            let to_send = Amount::from_parts(msg.denom.clone(), msg.amount);
+            let gas_limit = check_gas_limit(deps.as_ref(), &to_send)?;
+            let send = send_amount(to_send, msg.sender.clone());
+            let mut submsg = SubMsg::reply_on_error(send, ACK_FAILURE_ID);
+            submsg.gas_limit = gas_limit;
+            submsg
+        }
+    };
 
     // similar event messages like ibctransfer module
     let res = IbcBasicResponse::new()
         .add_submessage(submsg)
-        .add_attribute("action", "acknowledge")
+        .add_attribute("action", action)
         .add_attribute("sender", msg.sender)
         .add_attribute("receiver", msg.receiver)
         .add_attribute("denom", msg.denom)
@@ -363,25 +534,26 @@ fn on_packet_failure(
     Ok(res)
 }
 
-fn send_amount(amount: Amount, recipient: String) -> CosmosMsg {
+fn mint_voucher(voucher: Addr, recipient: String, amount: Uint128) -> CosmosMsg {
+    let msg = Cw20ExecuteMsg::Mint { recipient, amount };
+    WasmMsg::Execute {
+        contract_addr: voucher.into(),
+        msg: to_binary(&msg).unwrap(),
+        funds: vec![],
+    }
+    .into()
+}
+
+pub(crate) fn send_amount(amount: Amount, recipient: String) -> CosmosMsg {
     match amount {
         Amount::Native(coin) => BankMsg::Send {
             to_address: recipient,
             amount: vec![coin],
         }
         .into(),
-        Amount::Cw20(coin) => {
-            let msg = Cw20ExecuteMsg::Transfer {
-                recipient,
-                amount: coin.amount,
-            };
-            WasmMsg::Execute {
-                contract_addr: coin.address,
-                msg: to_binary(&msg).unwrap(),
-                funds: vec![],
-            }
-            .into()
-        }
+        Amount::Cw20(coin) => Cw20Contract(Addr::unchecked(coin.address))
+            .transfer(recipient, coin.amount)
+            .unwrap(),
     }
 }
 
@@ -390,11 +562,82 @@ mod test {
     use super::*;
     use crate::test_helpers::*;
 
-    use crate::contract::{execute, migrate, query_channel};
+    use crate::contract::{execute, migrate, query_channel, query_config, MAX_MEMO_LENGTH};
     use crate::msg::{ExecuteMsg, MigrateMsg, TransferMsg};
     use cosmwasm_std::testing::{mock_env, mock_info};
-    use cosmwasm_std::{coins, to_vec, IbcEndpoint, IbcMsg, IbcTimeout, Timestamp};
+    use cosmwasm_std::{
+        attr, coins, from_slice, to_vec, IbcAcknowledgement, IbcEndpoint, IbcMsg, IbcTimeout,
+        Order, OwnedDeps, StdResult, Timestamp,
+    };
     use cw20::Cw20ReceiveMsg;
+    use cw_controllers::AdminError;
+
+    #[test]
+    fn channel_open_checks() {
+        let mut deps = setup(&[], &[]);
+
+        // wrong version
+        let mut channel = mock_channel("channel-1");
+        channel.version = "ics20-2".to_string();
+        let msg = IbcChannelOpenMsg::new_init(channel);
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidIbcVersion {
+                version: "ics20-2".to_string()
+            }
+        );
+
+        // wrong counterparty version
+        let msg = IbcChannelOpenMsg::new_try(mock_channel("channel-1"), "ics20-7");
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidIbcVersion {
+                version: "ics20-7".to_string()
+            }
+        );
+
+        // wrong ordering
+        let mut channel = mock_channel("channel-1");
+        channel.order = IbcOrder::Ordered;
+        let msg = IbcChannelOpenMsg::new_init(channel);
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert_eq!(err, ContractError::OnlyUnorderedChannel {});
+
+        // unknown counterparty port, also on connect
+        let remote_port = "wasm.remote-ics20";
+        let mut channel = mock_channel("channel-1");
+        channel.counterparty_endpoint.port_id = remote_port.to_string();
+        let msg = IbcChannelOpenMsg::new_init(channel.clone());
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), msg.clone()).unwrap_err();
+        let expected = ContractError::InvalidCounterpartyPort {
+            port: remote_port.to_string(),
+        };
+        assert_eq!(err, expected);
+        let connect = IbcChannelConnectMsg::new_ack(channel.clone(), ICS20_VERSION);
+        let err = ibc_channel_connect(deps.as_mut(), mock_env(), connect.clone()).unwrap_err();
+        assert_eq!(err, expected);
+
+        // works once it is configured
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg {
+                default_gas_limit: None,
+                counterparty_port: Some(remote_port.to_string()),
+                settled_below: vec![],
+            },
+        )
+        .unwrap();
+        ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap();
+        ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap();
+
+        // and the transfer port is still accepted
+        add_channel(deps.as_mut(), "channel-2");
+        assert!(CHANNEL_INFO.has(&deps.storage, "channel-1"));
+        assert!(CHANNEL_INFO.has(&deps.storage, "channel-2"));
+    }
 
     #[test]
     fn check_ack_json() {
@@ -421,6 +664,72 @@ mod test {
 
         let encdoded = String::from_utf8(to_vec(&packet).unwrap()).unwrap();
         assert_eq!(expected, encdoded.as_str());
+
+        // and we can parse it back
+        let decoded: Ics20Packet = from_slice(expected.as_bytes()).unwrap();
+        assert_eq!(decoded, packet);
+
+        // the memo is only added when set
+        let packet = packet.with_memo(Some("hello".to_string()));
+        let expected = r#"{"amount":"12345","denom":"ucosm","receiver":"wasm1fucynrfkrt684pm8jrt8la5h2csvs5cnldcgqc","sender":"cosmos1zedxv25ah8fksmg2lzrndrpkvsjqgk4zt5ff7n","memo":"hello"}"#;
+        let encdoded = String::from_utf8(to_vec(&packet).unwrap()).unwrap();
+        assert_eq!(expected, encdoded.as_str());
+        let decoded: Ics20Packet = from_slice(expected.as_bytes()).unwrap();
+        assert_eq!(decoded, packet);
+    }
+
+    #[test]
+    fn parse_sdk_packet_with_memo() {
+        // Packet data from a chain with memo support (ibc-go v5+)
+        let raw = r#"{"amount":"1000000","denom":"uatom","memo":"{\"wasm\":{}}","receiver":"wasm1fucynrfkrt684pm8jrt8la5h2csvs5cnldcgqc","sender":"cosmos1zedxv25ah8fksmg2lzrndrpkvsjqgk4zt5ff7n"}"#;
+        let packet: Ics20Packet = from_slice(raw.as_bytes()).unwrap();
+        assert_eq!(packet.denom, "uatom");
+        assert_eq!(packet.memo, Some(r#"{"wasm":{}}"#.to_string()));
+
+        // an empty memo is the same as none on the sdk side, but we keep it as is
+        let raw =
+            r#"{"amount":"1000000","denom":"uatom","memo":"","receiver":"foo","sender":"bar"}"#;
+        let packet: Ics20Packet = from_slice(raw.as_bytes()).unwrap();
+        assert_eq!(packet.memo, Some("".to_string()));
+    }
+
+    #[test]
+    fn parse_sdk_packet_json() {
+        // Packet data relayed from a gaia chain, returning a token we sent before
+        let raw = r#"{"amount":"1000000","denom":"transfer/channel-0/cw20:wasm1zedxv25ah8fksmg2lzrndrpkvsjqgk4zt5ff7n","receiver":"wasm1fucynrfkrt684pm8jrt8la5h2csvs5cnldcgqc","sender":"cosmos1zedxv25ah8fksmg2lzrndrpkvsjqgk4zt5ff7n"}"#;
+        let packet: Ics20Packet = from_slice(raw.as_bytes()).unwrap();
+        assert_eq!(packet.amount, Uint128::new(1000000));
+        assert_eq!(
+            packet.denom,
+            "transfer/channel-0/cw20:wasm1zedxv25ah8fksmg2lzrndrpkvsjqgk4zt5ff7n"
+        );
+        packet.validate().unwrap();
+
+        // re-encoding leads to the same bytes
+        let encoded = String::from_utf8(to_vec(&packet).unwrap()).unwrap();
+        assert_eq!(raw, encoded.as_str());
+
+        // amount must be a string, not a number
+        let numeric = r#"{"amount":1000000,"denom":"ucosm","receiver":"foo","sender":"bar"}"#;
+        from_slice::<Ics20Packet>(numeric.as_bytes()).unwrap_err();
+    }
+
+    #[test]
+    fn packet_amount_limited_to_u64() {
+        let max = Ics20Packet::new(Uint128::new(u64::MAX as u128), "ucosm", "foo", "bar");
+        max.validate().unwrap();
+
+        let raw =
+            r#"{"amount":"18446744073709551616","denom":"ucosm","receiver":"foo","sender":"bar"}"#;
+        let too_big: Ics20Packet = from_slice(raw.as_bytes()).unwrap();
+        assert_eq!(
+            too_big.validate().unwrap_err(),
+            ContractError::AmountOverflow {}
+        );
+
+        // beyond u128 it cannot even be parsed
+        let raw = r#"{"amount":"340282366920938463463374607431768211456","denom":"ucosm","receiver":"foo","sender":"bar"}"#;
+        from_slice::<Ics20Packet>(raw.as_bytes()).unwrap_err();
     }
 
     fn cw20_payment(
@@ -458,13 +767,25 @@ mod test {
         amount: u128,
         denom: &str,
         receiver: &str,
+    ) -> IbcPacket {
+        // this is returning a foreign (our) token, thus denom is <port>/<channel>/<denom>
+        let denom = format!("{}/{}/{}", REMOTE_PORT, "channel-1234", denom);
+        mock_remote_packet(my_channel, amount, &denom, receiver)
+    }
+
+    // the denom is sent as is, without any prefix this is a token native to the remote chain
+    fn mock_remote_packet(
+        my_channel: &str,
+        amount: u128,
+        denom: &str,
+        receiver: &str,
     ) -> IbcPacket {
         let data = Ics20Packet {
-            // this is returning a foreign (our) token, thus denom is <port>/<channel>/<denom>
-            denom: format!("{}/{}/{}", REMOTE_PORT, "channel-1234", denom),
+            denom: denom.to_string(),
             amount: amount.into(),
             sender: "remote-sender".to_string(),
             receiver: receiver.to_string(),
+            memo: None,
         };
         print!("Packet denom: {}", &data.denom);
         IbcPacket::new(
@@ -482,6 +803,757 @@ mod test {
         )
     }
 
+    fn mock_sent_packet(my_channel: &str, amount: u128, denom: &str, sender: &str) -> IbcPacket {
+        let data = Ics20Packet {
+            denom: denom.into(),
+            amount: amount.into(),
+            sender: sender.to_string(),
+            receiver: "remote-rcpt".to_string(),
+            memo: None,
+        };
+        IbcPacket::new(
+            to_binary(&data).unwrap(),
+            IbcEndpoint {
+                port_id: CONTRACT_PORT.to_string(),
+                channel_id: my_channel.to_string(),
+            },
+            IbcEndpoint {
+                port_id: REMOTE_PORT.to_string(),
+                channel_id: "channel-1234".to_string(),
+            },
+            2,
+            Timestamp::from_seconds(1665321069).into(),
+        )
+    }
+
+    #[test]
+    fn in_flight_packets_settle_on_closed_channel() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&["channel-1", send_channel], &[]);
+        let denom = "uatom";
+
+        // send two transfers, both still in flight when the channel closes
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        });
+        let info = mock_info("local-sender", &coins(987654321, denom));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        close_channel(deps.as_mut(), send_channel);
+
+        // closed channel is marked in the channel info
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert!(state.info.closed);
+        assert_eq!(state.balances, vec![Amount::native(1975308642, denom)]);
+
+        // no more transfers allowed, other channels are unaffected
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ChannelClosed {
+                id: send_channel.to_string()
+            }
+        );
+        let other = ExecuteMsg::Transfer(TransferMsg {
+            channel: "channel-1".to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        });
+        execute(deps.as_mut(), mock_env(), info, other).unwrap();
+
+        // the first one is acknowledged
+        let sent_packet = mock_sent_packet(send_channel, 987654321, denom, "local-sender");
+        let msg = IbcPacketAckMsg::new(IbcAcknowledgement::new(ack_success()), sent_packet.clone());
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        // the second one times out and is properly refunded
+        let mut sent_packet = sent_packet;
+        sent_packet.sequence += 1;
+        let msg = IbcPacketTimeoutMsg::new(sent_packet);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        let refund = SubMsg::reply_on_error(
+            BankMsg::Send {
+                to_address: "local-sender".into(),
+                amount: coins(987654321, denom),
+            },
+            ACK_FAILURE_ID,
+        );
+        assert_eq!(refund, res.messages[0]);
+
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(987654321, denom)]);
+        assert_eq!(state.total_sent, vec![Amount::native(1975308642, denom)]);
+    }
+
+    #[test]
+    fn recovery_keeps_in_flight_funds() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+        let denom = "uatom";
+
+        // two transfers, the first one is acknowledged after the channel closes
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        });
+        let info = mock_info("local-sender", &coins(987654321, denom));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        close_channel(deps.as_mut(), send_channel);
+        let sent_packet = mock_sent_packet(send_channel, 987654321, denom, "local-sender");
+        let msg = IbcPacketAckMsg::new(IbcAcknowledgement::new(ack_success()), sent_packet.clone());
+        ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+
+        // the admin can only recover the acknowledged one
+        let recover = ExecuteMsg::RecoverLockedFunds {
+            channel: send_channel.to_string(),
+            denom: denom.to_string(),
+            recipient: "rescue".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            recover.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "rescue".into(),
+                amount: coins(987654321, denom),
+            })]
+        );
+        let err = execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), recover).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFunds {});
+
+        // the second one still times out and is refunded
+        let mut sent_packet = sent_packet;
+        sent_packet.sequence += 1;
+        let msg = IbcPacketTimeoutMsg::new(sent_packet);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        let refund = SubMsg::reply_on_error(
+            BankMsg::Send {
+                to_address: "local-sender".into(),
+                amount: coins(987654321, denom),
+            },
+            ACK_FAILURE_ID,
+        );
+        assert_eq!(res.messages, vec![refund]);
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(0, denom)]);
+    }
+
+    #[test]
+    fn refunds_settle_while_paused() {
+        let send_channel = "channel-9";
+        let cw20_addr = "token-addr";
+        let mut deps = setup(&[send_channel], &[(cw20_addr, 1234567)]);
+        let denom = "uatom";
+
+        // one transfer is in flight when the admin pauses
+        let transfer = TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        };
+        let msg = ExecuteMsg::Transfer(transfer.clone());
+        let info = mock_info("local-sender", &coins(987654321, denom));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+
+        // only the admin can pause
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("local-sender", &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "pause"));
+        assert!(query_config(deps.as_ref()).unwrap().paused);
+
+        // no new native or cw20 transfers
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+        let receive = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "local-sender".to_string(),
+            amount: Uint128::new(1000),
+            msg: to_binary(&transfer).unwrap(),
+        });
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(cw20_addr, &[]),
+            receive,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        // the in-flight packet times out and is still refunded
+        let sent_packet = mock_sent_packet(send_channel, 987654321, denom, "local-sender");
+        let msg = IbcPacketTimeoutMsg::new(sent_packet);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        let refund = SubMsg::reply_on_error(
+            BankMsg::Send {
+                to_address: "local-sender".into(),
+                amount: coins(987654321, denom),
+            },
+            ACK_FAILURE_ID,
+        );
+        assert_eq!(res.messages, vec![refund]);
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(0, denom)]);
+
+        // after unpausing, transfers work again
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("gov", &[]),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "unpause"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Transfer(transfer),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn receive_errors_return_error_acks() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+        let denom = "uatom";
+
+        // lock some tokens on the channel
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        });
+        let info = mock_info("local-sender", &coins(1000, denom));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let assert_error_ack = |res: IbcReceiveResponse, err: &str| {
+            assert!(res.messages.is_empty());
+            let ack: Ics20Ack = from_binary(&res.acknowledgement).unwrap();
+            assert_eq!(ack, Ics20Ack::Error(err.to_string()));
+            assert!(res.attributes.contains(&attr("success", "false")));
+            assert!(res.attributes.contains(&attr("error", err)));
+        };
+
+        // malformed packet body
+        let mut packet = mock_receive_packet(send_channel, 500, denom, "local-rcpt");
+        packet.data = b"{not an ics20 packet".into();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        let ack: Ics20Ack = from_binary(&res.acknowledgement).unwrap();
+        assert!(matches!(ack, Ics20Ack::Error(_)));
+        assert!(res.messages.is_empty());
+
+        // token native to the remote chain
+        let mut packet = mock_receive_packet(send_channel, 500, denom, "local-rcpt");
+        let mut data: Ics20Packet = from_binary(&packet.data).unwrap();
+        data.denom = "ucosm".to_string();
+        packet.data = to_binary(&data).unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        assert_error_ack(res, &ContractError::NoForeignTokens {}.to_string());
+
+        // a denom we never sent
+        let packet = mock_receive_packet(send_channel, 500, "ujuno", "local-rcpt");
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        assert_error_ack(res, &ContractError::InsufficientFunds {}.to_string());
+
+        // outstanding balance too small
+        let packet = mock_receive_packet(send_channel, 1001, denom, "local-rcpt");
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        assert_error_ack(res, &ContractError::InsufficientFunds {}.to_string());
+
+        // none of these touched the balance
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(1000, denom)]);
+    }
+
+    #[test]
+    fn packet_events_match_ibctransfer() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+        let denom = "uatom";
+
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        });
+        let info = mock_info("local-sender", &coins(1000, denom));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // successful receive
+        let packet = mock_receive_packet(send_channel, 300, denom, "local-rcpt");
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        let remote_denom = format!("{}/{}/{}", REMOTE_PORT, "channel-1234", denom);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "receive"),
+                attr("sender", "remote-sender"),
+                attr("receiver", "local-rcpt"),
+                attr("denom", denom),
+                attr("amount", "300"),
+                attr("success", "true"),
+            ]
+        );
+
+        // failed receive
+        let packet = mock_receive_packet(send_channel, 5000, denom, "local-rcpt");
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "receive"),
+                attr("sender", "remote-sender"),
+                attr("receiver", "local-rcpt"),
+                attr("denom", remote_denom),
+                attr("amount", "5000"),
+                attr("success", "false"),
+                attr("error", ContractError::InsufficientFunds {}.to_string()),
+            ]
+        );
+
+        // successful ack
+        let sent = mock_sent_packet(send_channel, 500, denom, "local-sender");
+        let success = IbcAcknowledgement::new(ack_success());
+        let msg = IbcPacketAckMsg::new(success, sent.clone());
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "acknowledge"),
+                attr("sender", "local-sender"),
+                attr("receiver", "remote-rcpt"),
+                attr("denom", denom),
+                attr("amount", "500"),
+                attr("success", "true"),
+            ]
+        );
+
+        // error ack refunds
+        let mut sent = sent;
+        sent.sequence += 1;
+        let failure = IbcAcknowledgement::new(ack_fail("bad coin".to_string()));
+        let msg = IbcPacketAckMsg::new(failure, sent.clone());
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "acknowledge"),
+                attr("sender", "local-sender"),
+                attr("receiver", "remote-rcpt"),
+                attr("denom", denom),
+                attr("amount", "500"),
+                attr("success", "false"),
+                attr("error", "bad coin"),
+            ]
+        );
+
+        // timeout refunds
+        sent.sequence += 1;
+        let msg = IbcPacketTimeoutMsg::new(sent);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "timeout"),
+                attr("sender", "local-sender"),
+                attr("receiver", "remote-rcpt"),
+                attr("denom", denom),
+                attr("amount", "500"),
+                attr("success", "false"),
+                attr("error", "timeout"),
+            ]
+        );
+    }
+
+    fn voucher_mint(amount: u128, voucher: &str, recipient: &str, reply_id: u64) -> SubMsg {
+        let msg = Cw20ExecuteMsg::Mint {
+            recipient: recipient.into(),
+            amount: Uint128::new(amount),
+        };
+        let exec = WasmMsg::Execute {
+            contract_addr: voucher.into(),
+            msg: to_binary(&msg).unwrap(),
+            funds: vec![],
+        };
+        SubMsg::reply_on_error(exec, reply_id)
+    }
+
+    #[test]
+    fn receive_and_return_remote_tokens() {
+        let channel = "channel-9";
+        let voucher = "juno-voucher";
+        let mut deps = setup(&["channel-1", channel], &[]);
+        register_voucher(&mut deps, channel, "ujuno", voucher);
+        let local_denom = format!("{}/{}/ujuno", CONTRACT_PORT, channel);
+
+        // the remote chain sends its native token without prefix, we mint vouchers
+        let packet = mock_remote_packet(channel, 5000, "ujuno", "local-rcpt");
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        let ack: Ics20Ack = from_binary(&res.acknowledgement).unwrap();
+        assert!(matches!(ack, Ics20Ack::Result(_)));
+        assert_eq!(
+            res.messages,
+            vec![voucher_mint(5000, voucher, "local-rcpt", RECEIVE_ID)]
+        );
+        assert!(res.attributes.contains(&attr("denom", &local_denom)));
+        let state = query_channel(deps.as_ref(), channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(5000, &local_denom)]);
+
+        // the same token over another channel has no voucher
+        let packet = mock_remote_packet("channel-1", 5000, "ujuno", "local-rcpt");
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet))
+            .unwrap();
+        assert!(res.messages.is_empty());
+        let ack: Ics20Ack = from_binary(&res.acknowledgement).unwrap();
+        let no_voucher = Ics20Ack::Error(ContractError::NoForeignTokens {}.to_string());
+        assert_eq!(ack, no_voucher);
+
+        // a failed mint is reverted in the reply
+        let packet = mock_remote_packet(channel, 1000, "ujuno", "bad-rcpt");
+        ibc_packet_receive(deps.as_mut(), mock_env(), IbcPacketReceiveMsg::new(packet)).unwrap();
+        let reply_msg = Reply {
+            id: RECEIVE_ID,
+            result: SubMsgResult::Err("invalid address".to_string()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        assert_eq!(res.data, Some(ack_fail("invalid address".to_string())));
+        let state = query_channel(deps.as_ref(), channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(5000, &local_denom)]);
+        assert_eq!(state.total_sent, vec![Amount::native(5000, &local_denom)]);
+
+        // vouchers can only be sent back over their own channel
+        let send_back = |channel: &str, amount: u128| {
+            let transfer = TransferMsg {
+                channel: channel.to_string(),
+                remote_address: "remote-rcpt".to_string(),
+                timeout: None,
+                timeout_height: None,
+                timeout_timestamp: None,
+                memo: None,
+            };
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "local-sender".to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&transfer).unwrap(),
+            })
+        };
+        let info = mock_info(voucher, &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            send_back("channel-1", 3000),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WrongVoucherChannel {
+                channel: channel.to_string()
+            }
+        );
+
+        // sending back burns them, and carries our prefix for the remote chain to strip
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            send_back(channel, 3000),
+        )
+        .unwrap();
+        let burn = Cw20ExecuteMsg::Burn {
+            amount: Uint128::new(3000),
+        };
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[0],
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: voucher.into(),
+                msg: to_binary(&burn).unwrap(),
+                funds: vec![],
+            })
+        );
+        let expected = Ics20Packet::new(
+            Uint128::new(3000),
+            local_denom.clone(),
+            "local-sender",
+            "remote-rcpt",
+        );
+        let timeout = mock_env().block.time.plus_seconds(DEFAULT_TIMEOUT);
+        assert_eq!(
+            res.messages[1],
+            SubMsg::new(IbcMsg::SendPacket {
+                channel_id: channel.to_string(),
+                data: to_binary(&expected).unwrap(),
+                timeout: IbcTimeout::with_timestamp(timeout),
+            })
+        );
+        let state = query_channel(deps.as_ref(), channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(2000, &local_denom)]);
+
+        // we never burn more than we minted
+        let err = execute(deps.as_mut(), mock_env(), info, send_back(channel, 3000)).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFunds {});
+
+        // on timeout, vouchers are minted again for the sender
+        let sent_packet = mock_sent_packet(channel, 3000, &local_denom, "local-sender");
+        let msg = IbcPacketTimeoutMsg::new(sent_packet);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![voucher_mint(3000, voucher, "local-sender", ACK_FAILURE_ID)]
+        );
+        let state = query_channel(deps.as_ref(), channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(5000, &local_denom)]);
+    }
+
+    #[test]
+    fn timeout_refunds_cw20_to_sender() {
+        let send_channel = "channel-9";
+        let cw20_addr = "token-addr";
+        let gas_limit = 1234567;
+        let mut deps = setup(&[send_channel], &[(cw20_addr, gas_limit)]);
+
+        // we send some cw20 tokens over
+        let transfer = TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "local-sender".to_string(),
+            amount: Uint128::new(987654321),
+            msg: to_binary(&transfer).unwrap(),
+        });
+        let info = mock_info(cw20_addr, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let sent_packet = match &res.messages[0].msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => {
+                let mut packet =
+                    mock_sent_packet(send_channel, 987654321, "cw20:token-addr", "local-sender");
+                packet.data = data.clone();
+                packet
+            }
+            msg => panic!("Unexpected message: {:?}", msg),
+        };
+
+        // it times out, so the cw20 contract transfers them back to the sender
+        let msg = IbcPacketTimeoutMsg::new(sent_packet);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        let mut refund = cw20_payment(987654321, cw20_addr, "local-sender", Some(gas_limit));
+        refund.id = ACK_FAILURE_ID;
+        assert_eq!(res.messages, vec![refund]);
+
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::cw20(0, cw20_addr)]);
+    }
+
+    #[test]
+    fn memo_passthrough() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+        let denom = "uatom";
+
+        // the memo is part of the sent packet
+        let mut transfer = TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: Some("for the relayer".to_string()),
+        };
+        let info = mock_info("local-sender", &coins(5000, denom));
+        let msg = ExecuteMsg::Transfer(transfer.clone());
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let packet: Ics20Packet = match &res.messages[0].msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => from_binary(data).unwrap(),
+            msg => panic!("Unexpected message: {:?}", msg),
+        };
+        assert_eq!(packet.memo, transfer.memo);
+
+        // but not too long
+        transfer.memo = Some("x".repeat(MAX_MEMO_LENGTH + 1));
+        let msg = ExecuteMsg::Transfer(transfer);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MemoTooLong {
+                max: MAX_MEMO_LENGTH
+            }
+        );
+
+        // incoming memos show up in the attributes
+        let mut recv_packet = mock_receive_packet(send_channel, 1000, denom, "local-rcpt");
+        let mut data: Ics20Packet = from_binary(&recv_packet.data).unwrap();
+        data.memo = Some("from remote".to_string());
+        recv_packet.data = to_binary(&data).unwrap();
+        let msg = IbcPacketReceiveMsg::new(recv_packet);
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert!(res.attributes.contains(&attr("memo", "from remote")));
+    }
+
+    #[test]
+    fn duplicate_acks_are_ignored() {
+        let send_channel = "channel-9";
+        let mut deps = setup(&[send_channel], &[]);
+        let denom = "uatom";
+
+        let msg = ExecuteMsg::Transfer(TransferMsg {
+            channel: send_channel.to_string(),
+            remote_address: "remote-rcpt".to_string(),
+            timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
+        });
+        let info = mock_info("local-sender", &coins(500, denom));
+        execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the first packet fails, the error ack is delivered twice
+        let sent = mock_sent_packet(send_channel, 500, denom, "local-sender");
+        let failure = IbcAcknowledgement::new(ack_fail("bad coin".to_string()));
+        let msg = IbcPacketAckMsg::new(failure, sent.clone());
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "acknowledge"),
+                attr("channel", send_channel),
+                attr("sequence", sent.sequence.to_string()),
+                attr("duplicate", "true"),
+            ]
+        );
+        // neither a timeout for the same packet
+        let msg = IbcPacketTimeoutMsg::new(sent.clone());
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(res.attributes[3], attr("duplicate", "true"));
+
+        // only refunded once
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(500, denom)]);
+
+        // the second packet times out, also delivered twice
+        let mut sent = sent;
+        sent.sequence += 1;
+        let msg = IbcPacketTimeoutMsg::new(sent);
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+        assert_eq!(1, res.messages.len());
+        let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::native(0, denom)]);
+        assert_eq!(state.total_sent, vec![Amount::native(1000, denom)]);
+    }
+
+    #[test]
+    fn settled_packets_are_pruned() {
+        use crate::state::{HANDLED_PACKETS, MAX_PRUNE, SETTLED_BELOW};
+
+        let channel = "channel-9";
+        let mut deps = setup(&[channel], &[]);
+        let handled = |deps: &OwnedDeps<_, _, _>| {
+            HANDLED_PACKETS
+                .prefix(channel)
+                .keys(&deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap()
+        };
+
+        // settled out of order, so we have to remember them
+        assert!(mark_packet_handled(deps.as_mut().storage, channel, 3).unwrap());
+        assert!(mark_packet_handled(deps.as_mut().storage, channel, 2).unwrap());
+        assert_eq!(handled(&deps), vec![2, 3]);
+
+        // once the gap is closed, they are dropped
+        assert!(mark_packet_handled(deps.as_mut().storage, channel, 1).unwrap());
+        assert_eq!(handled(&deps), Vec::<u64>::new());
+        assert_eq!(SETTLED_BELOW.load(&deps.storage, channel).unwrap(), 4);
+        // but still count as handled
+        for seq in 1..4 {
+            assert!(!mark_packet_handled(deps.as_mut().storage, channel, seq).unwrap());
+        }
+
+        // a long run is pruned in steps
+        let last = 5 + MAX_PRUNE + 2;
+        for seq in 5..=last {
+            assert!(mark_packet_handled(deps.as_mut().storage, channel, seq).unwrap());
+        }
+        assert!(mark_packet_handled(deps.as_mut().storage, channel, 4).unwrap());
+        assert_eq!(
+            SETTLED_BELOW.load(&deps.storage, channel).unwrap(),
+            4 + MAX_PRUNE
+        );
+        assert_eq!(handled(&deps), ((4 + MAX_PRUNE)..=last).collect::<Vec<_>>());
+        assert!(mark_packet_handled(deps.as_mut().storage, channel, last + 1).unwrap());
+        assert_eq!(handled(&deps), Vec::<u64>::new());
+        assert!(!mark_packet_handled(deps.as_mut().storage, channel, last).unwrap());
+
+        // other channels are tracked on their own
+        assert!(mark_packet_handled(deps.as_mut().storage, "channel-1", 1).unwrap());
+    }
+
     #[test]
     fn send_receive_cw20() {
         let send_channel = "channel-9";
@@ -511,6 +1583,9 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "remote-rcpt".to_string(),
             timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
         };
         let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
             sender: "local-sender".to_string(),
@@ -525,6 +1600,7 @@ mod test {
             amount: Uint128::new(987654321),
             sender: "local-sender".to_string(),
             receiver: "remote-rcpt".to_string(),
+            memo: None,
         };
         let timeout = mock_env().block.time.plus_seconds(DEFAULT_TIMEOUT);
         assert_eq!(
@@ -537,7 +1613,7 @@ mod test {
         );
 
         // query channel state|_|
-        let state = query_channel(deps.as_ref(), send_channel.to_string()).unwrap();
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
         assert_eq!(state.balances, vec![Amount::cw20(987654321, cw20_addr)]);
         assert_eq!(state.total_sent, vec![Amount::cw20(987654321, cw20_addr)]);
 
@@ -559,12 +1635,32 @@ mod test {
         let ack: Ics20Ack = from_binary(&res.acknowledgement).unwrap();
         assert!(matches!(ack, Ics20Ack::Result(_)));
 
-        // TODO: we need to call the reply block
-
         // query channel state
-        let state = query_channel(deps.as_ref(), send_channel.to_string()).unwrap();
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
         assert_eq!(state.balances, vec![Amount::cw20(111111111, cw20_addr)]);
         assert_eq!(state.total_sent, vec![Amount::cw20(987654321, cw20_addr)]);
+
+        // a receiver hook burning all the gas makes the forward fail within its gas limit,
+        // which turns into an error ack and restores the balance
+        let reply_msg = Reply {
+            id: RECEIVE_ID,
+            result: SubMsgResult::Err("out of gas".to_string()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+        assert_eq!(res.data, Some(ack_fail("out of gas".to_string())));
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "forward_failed"),
+                attr("channel", send_channel),
+                attr("denom", cw20_denom),
+                attr("amount", "876543210"),
+                attr("error", "out of gas"),
+            ]
+        );
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
+        assert_eq!(state.balances, vec![Amount::cw20(987654321, cw20_addr)]);
+        assert_eq!(state.total_sent, vec![Amount::cw20(987654321, cw20_addr)]);
     }
 
     #[test]
@@ -591,12 +1687,15 @@ mod test {
             channel: send_channel.to_string(),
             remote_address: "my-remote-address".to_string(),
             timeout: None,
+            timeout_height: None,
+            timeout_timestamp: None,
+            memo: None,
         });
         let info = mock_info("local-sender", &coins(987654321, denom));
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         // query channel state|_|
-        let state = query_channel(deps.as_ref(), send_channel.to_string()).unwrap();
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
         assert_eq!(state.balances, vec![Amount::native(987654321, denom)]);
         assert_eq!(state.total_sent, vec![Amount::native(987654321, denom)]);
 
@@ -621,7 +1720,7 @@ mod test {
         // only need to call reply block on error case
 
         // query channel state
-        let state = query_channel(deps.as_ref(), send_channel.to_string()).unwrap();
+        let state = query_channel(deps.as_ref(), send_channel.to_string(), None, None).unwrap();
         assert_eq!(state.balances, vec![Amount::native(111111111, denom)]);
         assert_eq!(state.total_sent, vec![Amount::native(987654321, denom)]);
     }
@@ -648,6 +1747,8 @@ mod test {
             mock_env(),
             MigrateMsg {
                 default_gas_limit: Some(def_limit),
+                counterparty_port: None,
+                settled_below: vec![],
             },
         )
         .unwrap();
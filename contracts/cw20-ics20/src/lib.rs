@@ -3,9 +3,9 @@ This is an *IBC Enabled* contract that allows us to send CW20 tokens from one ch
 protocol to the bank module of another chain. In short, it lets us send our custom CW20 tokens with IBC and use
 them just like native tokens on other chains.
 
-It is only designed to send tokens and redeem previously sent tokens. It will not mint tokens belonging
-to assets originating on the foreign chain. This is different than the Golang `ibctransfer` module, but
-we properly implement ICS20 and respond with an error message... let's hope the Go side handles this correctly.
+It is mainly designed to send tokens and redeem previously sent tokens. Tokens originating on the foreign
+chain are only accepted if governance registered a cw20 contract as voucher for them, which this contract
+mints on receive and burns when sent back. Any other foreign token is refused with an error acknowledgement.
 
 For more information on this contract, please check out the
 [README](https://github.com/CosmWasm/cw-plus/blob/main/contracts/cw20-ics20/README.md).
@@ -84,3 +84,88 @@ pub mod v2 {
         Ok(())
     }
 }
+
+// v3 format is anything up to 1.0.0, when ChannelInfo didn't store our port or the closed flag
+pub mod v3 {
+    use cosmwasm_std::{DepsMut, IbcEndpoint, IbcQuery, Order, PortIdResponse, StdResult};
+    use cw_storage_plus::Map;
+    use serde::{Deserialize, Serialize};
+
+    use crate::msg::ChannelSequence;
+    use crate::state;
+    use crate::ContractError;
+
+    /// This parses both the old and the new format, so the migration is safe to run again
+    #[derive(Serialize, Deserialize)]
+    pub struct ChannelInfo {
+        pub id: String,
+        pub counterparty_endpoint: IbcEndpoint,
+        pub connection_id: String,
+        #[serde(default)]
+        pub port_id: String,
+        #[serde(default)]
+        pub closed: bool,
+    }
+
+    pub const CHANNEL_INFO: Map<&str, ChannelInfo> = Map::new("channel_info");
+
+    pub fn update_channels(deps: DepsMut) -> StdResult<()> {
+        // only old style entries are missing the port
+        let channels = CHANNEL_INFO
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|r| match r {
+                Ok((_, info)) => info.port_id.is_empty(),
+                Err(_) => true,
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        if channels.is_empty() {
+            return Ok(());
+        }
+
+        // all channels are bound to the one port of this contract
+        let PortIdResponse { port_id } = deps.querier.query(&IbcQuery::PortId {}.into())?;
+        for (id, old) in channels {
+            let info = state::ChannelInfo {
+                id: old.id,
+                port_id: port_id.clone(),
+                counterparty_endpoint: old.counterparty_endpoint,
+                connection_id: old.connection_id,
+                closed: old.closed,
+            };
+            state::CHANNEL_INFO.save(deps.storage, &id, &info)?;
+        }
+        Ok(())
+    }
+
+    /// Packets settled before the upgrade were not recorded, so the settled watermark of every
+    /// channel has to be given. Otherwise it would stay at 1 and nothing would ever be pruned.
+    pub fn set_settled_below(
+        deps: DepsMut,
+        settled_below: &[ChannelSequence],
+    ) -> Result<(), ContractError> {
+        if let Some(unknown) = settled_below
+            .iter()
+            .find(|entry| !state::CHANNEL_INFO.has(deps.storage, &entry.channel))
+        {
+            return Err(ContractError::NoSuchChannel {
+                id: unknown.channel.clone(),
+            });
+        }
+
+        let channels = state::CHANNEL_INFO
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for channel in channels {
+            let sequence = settled_below
+                .iter()
+                .find(|entry| entry.channel == channel)
+                .map(|entry| entry.sequence)
+                .ok_or_else(|| ContractError::MissingSettledBelow {
+                    id: channel.clone(),
+                })?;
+            // ibc sequences start at 1
+            state::SETTLED_BELOW.save(deps.storage, &channel, &sequence.max(1))?;
+        }
+        Ok(())
+    }
+}
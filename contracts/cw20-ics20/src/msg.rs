@@ -1,4 +1,5 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{IbcTimeoutBlock, Timestamp};
 use cw20::Cw20ReceiveMsg;
 
 use crate::amount::Amount;
@@ -15,6 +16,8 @@ pub struct InitMsg {
     /// If set, contracts off the allowlist will run with this gas limit.
     /// If unset, will refuse to accept any contract off the allow list.
     pub default_gas_limit: Option<u64>,
+    /// If set, channels may also connect to this counterparty port, in addition to "transfer".
+    pub counterparty_port: Option<String>,
 }
 
 #[cw_serde]
@@ -26,6 +29,18 @@ pub struct AllowMsg {
 #[cw_serde]
 pub struct MigrateMsg {
     pub default_gas_limit: Option<u64>,
+    pub counterparty_port: Option<String>,
+    /// Required when migrating from before 1.1.0, for every existing channel
+    #[serde(default)]
+    pub settled_below: Vec<ChannelSequence>,
+}
+
+#[cw_serde]
+pub struct ChannelSequence {
+    pub channel: String,
+    /// lowest sequence of a sent packet that may still be acknowledged or timed out,
+    /// or the next sequence to be sent if none is in flight
+    pub sequence: u64,
 }
 
 #[cw_serde]
@@ -38,6 +53,25 @@ pub enum ExecuteMsg {
     Allow(AllowMsg),
     /// Change the admin (must be called by current admin)
     UpdateAdmin { admin: String },
+    /// Stops all new transfers, while packets in flight still settle (must be called by current admin)
+    Pause {},
+    /// Accepts new transfers again (must be called by current admin)
+    Unpause {},
+    /// This must be called by gov_contract, will register a cw20 contract (minted by us)
+    /// as voucher for a token native to the remote chain of the channel
+    RegisterVoucher {
+        channel: String,
+        remote_denom: String,
+        contract: String,
+    },
+    /// Once a channel is closed, this sends all outstanding tokens of the given escrowed
+    /// (cw20 or native, not voucher) denom on that channel to the recipient
+    /// (must be called by current admin)
+    RecoverLockedFunds {
+        channel: String,
+        denom: String,
+        recipient: String,
+    },
 }
 
 /// This is the message we accept via Receive
@@ -51,20 +85,33 @@ pub struct TransferMsg {
     pub remote_address: String,
     /// How long the packet lives in seconds. If not specified, use default_timeout
     pub timeout: Option<u64>,
+    /// Absolute timeout as height of the remote chain. If this or timeout_timestamp is set,
+    /// they are used instead of the relative timeout.
+    pub timeout_height: Option<IbcTimeoutBlock>,
+    /// Absolute timeout as timestamp, must be in the future.
+    pub timeout_timestamp: Option<Timestamp>,
+    /// An optional memo passed along in the ics20 packet, at most 32KiB
+    pub memo: Option<String>,
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    /// Return the port ID bound by this contract.
+    /// Return the port ID bound by this contract, as stored on channel connect.
+    /// Errors if no channel has connected yet.
     #[returns(PortResponse)]
     Port {},
     /// Show all channels we have connected to.
     #[returns(ListChannelsResponse)]
     ListChannels {},
     /// Returns the details of the name channel, error if not created.
+    /// The balances are paginated by denom.
     #[returns(ChannelResponse)]
-    Channel { id: String },
+    Channel {
+        id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Show the Config.
     #[returns(ConfigResponse)]
     Config {},
@@ -106,7 +153,10 @@ pub struct PortResponse {
 pub struct ConfigResponse {
     pub default_timeout: u64,
     pub default_gas_limit: Option<u64>,
+    pub counterparty_port: Option<String>,
     pub gov_contract: String,
+    /// if true, no new transfers are accepted
+    pub paused: bool,
 }
 
 #[cw_serde]
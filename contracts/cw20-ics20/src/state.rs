@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, IbcEndpoint, StdResult, Storage, Uint128};
+use cosmwasm_std::{Addr, Empty, IbcEndpoint, StdResult, Storage, Uint128};
 use cw_controllers::Admin;
 use cw_storage_plus::{Item, Map};
 
@@ -18,9 +18,31 @@ pub const CHANNEL_INFO: Map<&str, ChannelInfo> = Map::new("channel_info");
 /// indexed by (channel_id, denom) maintaining the balance of the channel in that currency
 pub const CHANNEL_STATE: Map<(&str, &str), ChannelState> = Map::new("channel_state");
 
+/// indexed by (channel_id, denom), the escrowed amount of sent packets that were neither
+/// acknowledged nor timed out yet. This part of the outstanding balance cannot be recovered.
+pub const IN_FLIGHT: Map<(&str, &str), Uint128> = Map::new("in_flight");
+
 /// Every cw20 contract we allow to be sent is stored here, possibly with a gas_limit
 pub const ALLOW_LIST: Map<&Addr, AllowInfo> = Map::new("allow_list");
 
+/// cw20 contracts we mint as vouchers for tokens native to the remote chain,
+/// indexed by (channel_id, remote denom). We must be the minter of those contracts.
+pub const VOUCHERS: Map<(&str, &str), Addr> = Map::new("vouchers");
+
+/// reverse lookup of VOUCHERS, so we know which cw20 contracts to burn when sent back
+pub const VOUCHER_INFO: Map<&Addr, VoucherInfo> = Map::new("voucher_info");
+
+/// indexed by (channel_id, sequence) of every packet we sent that was already acknowledged or timed out,
+/// so a duplicate delivery doesn't settle the balance twice
+pub const HANDLED_PACKETS: Map<(&str, u64), Empty> = Map::new("handled_packets");
+
+/// indexed by channel_id, every packet we sent below this sequence is settled.
+/// Those are dropped from HANDLED_PACKETS, so it only holds the ones settled out of order.
+pub const SETTLED_BELOW: Map<&str, u64> = Map::new("settled_below");
+
+/// how many entries of HANDLED_PACKETS a single ack or timeout prunes at most
+pub const MAX_PRUNE: u64 = 32;
+
 #[cw_serde]
 #[derive(Default)]
 pub struct ChannelState {
@@ -32,16 +54,25 @@ pub struct ChannelState {
 pub struct Config {
     pub default_timeout: u64,
     pub default_gas_limit: Option<u64>,
+    /// counterparty port we accept besides "transfer"
+    pub counterparty_port: Option<String>,
+    /// set by the admin to stop new outgoing transfers, in-flight packets still settle
+    #[serde(default)]
+    pub paused: bool,
 }
 
 #[cw_serde]
 pub struct ChannelInfo {
     /// id of this channel
     pub id: String,
+    /// our own port this channel is bound to
+    pub port_id: String,
     /// the remote channel/port we connect to
     pub counterparty_endpoint: IbcEndpoint,
     /// the connection this exists on (you can use to query client/consensus info)
     pub connection_id: String,
+    /// set when the channel is closed, no new transfers are accepted afterwards
+    pub closed: bool,
 }
 
 #[cw_serde]
@@ -49,11 +80,46 @@ pub struct AllowInfo {
     pub gas_limit: Option<u64>,
 }
 
+#[cw_serde]
+pub struct VoucherInfo {
+    /// the channel the remote token arrives on
+    pub channel: String,
+    /// the denom of the token as sent by the remote chain
+    pub remote_denom: String,
+}
+
 #[cw_serde]
 pub struct ReplyArgs {
     pub channel: String,
     pub denom: String,
     pub amount: Uint128,
+    /// true if we minted vouchers, rather than releasing our own tokens
+    pub voucher: bool,
+}
+
+/// Marks the packet as settled. Returns false if it was already handled before.
+pub fn mark_packet_handled(
+    storage: &mut dyn Storage,
+    channel: &str,
+    sequence: u64,
+) -> StdResult<bool> {
+    // ibc sequences start at 1
+    let below = SETTLED_BELOW.may_load(storage, channel)?.unwrap_or(1);
+    if sequence < below || HANDLED_PACKETS.has(storage, (channel, sequence)) {
+        return Ok(false);
+    }
+    HANDLED_PACKETS.save(storage, (channel, sequence), &Empty {})?;
+
+    // move the watermark over the settled packets right above it, a bounded number each time
+    let mut next = below;
+    while next < below + MAX_PRUNE && HANDLED_PACKETS.has(storage, (channel, next)) {
+        HANDLED_PACKETS.remove(storage, (channel, next));
+        next += 1;
+    }
+    if next != below {
+        SETTLED_BELOW.save(storage, channel, &next)?;
+    }
+    Ok(true)
 }
 
 pub fn increase_channel_balance(
@@ -93,6 +159,37 @@ pub fn reduce_channel_balance(
     Ok(())
 }
 
+pub fn add_in_flight(
+    storage: &mut dyn Storage,
+    channel: &str,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    IN_FLIGHT.update(storage, (channel, denom), |orig| -> StdResult<_> {
+        Ok(orig.unwrap_or_default() + amount)
+    })?;
+    Ok(())
+}
+
+// packets sent before in-flight amounts were tracked are not counted, hence the saturation
+pub fn settle_in_flight(
+    storage: &mut dyn Storage,
+    channel: &str,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let left = IN_FLIGHT
+        .may_load(storage, (channel, denom))?
+        .unwrap_or_default()
+        .saturating_sub(amount);
+    if left.is_zero() {
+        IN_FLIGHT.remove(storage, (channel, denom));
+    } else {
+        IN_FLIGHT.save(storage, (channel, denom), &left)?;
+    }
+    Ok(())
+}
+
 // this is like increase, but it only "un-subtracts" (= adds) outstanding, not total_sent
 // calling `reduce_channel_balance` and then `undo_reduce_channel_balance` should leave state unchanged.
 pub fn undo_reduce_channel_balance(
@@ -108,3 +205,30 @@ pub fn undo_reduce_channel_balance(
     })?;
     Ok(())
 }
+
+// this is like reduce, but it also "un-adds" total_sent
+// calling `increase_channel_balance` and then `undo_increase_channel_balance` should leave state unchanged.
+pub fn undo_increase_channel_balance(
+    storage: &mut dyn Storage,
+    channel: &str,
+    denom: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    CHANNEL_STATE.update(
+        storage,
+        (channel, denom),
+        |orig| -> Result<_, ContractError> {
+            let mut cur = orig.ok_or(ContractError::InsufficientFunds {})?;
+            cur.outstanding = cur
+                .outstanding
+                .checked_sub(amount)
+                .or(Err(ContractError::InsufficientFunds {}))?;
+            cur.total_sent = cur
+                .total_sent
+                .checked_sub(amount)
+                .or(Err(ContractError::InsufficientFunds {}))?;
+            Ok(cur)
+        },
+    )?;
+    Ok(())
+}
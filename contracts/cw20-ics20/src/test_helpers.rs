@@ -1,17 +1,23 @@
 #![cfg(test)]
 
-use crate::contract::instantiate;
-use crate::ibc::{ibc_channel_connect, ibc_channel_open, ICS20_ORDERING, ICS20_VERSION};
+use crate::contract::{execute, instantiate};
+use crate::ibc::{
+    ibc_channel_close, ibc_channel_connect, ibc_channel_open, ICS20_ORDERING, ICS20_VERSION,
+};
 use crate::state::ChannelInfo;
 
 use cosmwasm_std::testing::{
-    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
 };
 use cosmwasm_std::{
-    DepsMut, IbcChannel, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, OwnedDeps,
+    from_slice, to_binary, ContractResult, DepsMut, Empty, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcEndpoint, IbcQuery, OwnedDeps, PortIdResponse,
+    Querier, QuerierResult, QueryRequest, SystemResult,
 };
+use cw20::MinterResponse;
+use std::marker::PhantomData;
 
-use crate::msg::{AllowMsg, InitMsg};
+use crate::msg::{AllowMsg, ExecuteMsg, InitMsg};
 
 pub const DEFAULT_TIMEOUT: u64 = 3600; // 1 hour,
 pub const CONTRACT_PORT: &str = "ibc:wasm1234567890abcdef";
@@ -37,11 +43,13 @@ pub fn mock_channel(channel_id: &str) -> IbcChannel {
 pub fn mock_channel_info(channel_id: &str) -> ChannelInfo {
     ChannelInfo {
         id: channel_id.to_string(),
+        port_id: CONTRACT_PORT.into(),
         counterparty_endpoint: IbcEndpoint {
             port_id: REMOTE_PORT.into(),
             channel_id: format!("{}5", channel_id),
         },
         connection_id: CONNECTION_ID.into(),
+        closed: false,
     }
 }
 
@@ -54,6 +62,12 @@ pub fn add_channel(mut deps: DepsMut, channel_id: &str) {
     ibc_channel_connect(deps.branch(), mock_env(), connect_msg).unwrap();
 }
 
+// we simulate the counterparty closing the channel here
+pub fn close_channel(deps: DepsMut, channel_id: &str) {
+    let close_msg = IbcChannelCloseMsg::new_confirm(mock_channel(channel_id));
+    ibc_channel_close(deps, mock_env(), close_msg).unwrap();
+}
+
 pub fn setup(
     channels: &[&str],
     allow: &[(&str, u64)],
@@ -71,6 +85,7 @@ pub fn setup(
     // instantiate an empty contract
     let instantiate_msg = InitMsg {
         default_gas_limit: None,
+        counterparty_port: None,
         default_timeout: DEFAULT_TIMEOUT,
         gov_contract: "gov".to_string(),
         allowlist,
@@ -84,3 +99,55 @@ pub fn setup(
     }
     deps
 }
+
+// we mock all cw20 contracts to be minted by us, and let gov register one as voucher
+pub fn register_voucher(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    channel: &str,
+    remote_denom: &str,
+    contract: &str,
+) {
+    deps.querier.update_wasm(|_| {
+        let minter = Some(MinterResponse {
+            minter: MOCK_CONTRACT_ADDR.to_string(),
+            cap: None,
+        });
+        SystemResult::Ok(ContractResult::Ok(to_binary(&minter).unwrap()))
+    });
+    let msg = ExecuteMsg::RegisterVoucher {
+        channel: channel.to_string(),
+        remote_denom: remote_denom.to_string(),
+        contract: contract.to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("gov", &[]), msg).unwrap();
+}
+
+/// MockQuerier doesn't support ibc queries, this answers the PortId query on top of it
+pub struct IbcPortQuerier {
+    pub base: MockQuerier,
+}
+
+impl Querier for IbcPortQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        match from_slice(bin_request) {
+            Ok(QueryRequest::<Empty>::Ibc(IbcQuery::PortId {})) => {
+                let res = PortIdResponse {
+                    port_id: CONTRACT_PORT.to_string(),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+            }
+            _ => self.base.raw_query(bin_request),
+        }
+    }
+}
+
+pub fn with_ibc_querier(
+    deps: OwnedDeps<MockStorage, MockApi, MockQuerier>,
+) -> OwnedDeps<MockStorage, MockApi, IbcPortQuerier> {
+    OwnedDeps {
+        storage: deps.storage,
+        api: deps.api,
+        querier: IbcPortQuerier { base: deps.querier },
+        custom_query_type: PhantomData,
+    }
+}
@@ -3,9 +3,10 @@ use std::cmp::Ordering;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
-    Response, StdResult,
+    to_binary, Binary, BlockInfo, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env, MessageInfo,
+    Order, Response, StdResult,
 };
+use serde::de::DeserializeOwned;
 
 use cw2::set_contract_version;
 use cw3::{
@@ -13,11 +14,14 @@ use cw3::{
     VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse, Votes,
 };
 use cw_storage_plus::Bound;
-use cw_utils::{Expiration, ThresholdResponse};
+use cw_utils::{maybe_addr, Expiration, ThresholdResponse};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{next_id, Config, BALLOTS, CONFIG, PROPOSALS, VOTERS};
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, TotalWeightResponse};
+use crate::state::{
+    next_id, proposals, save_ballot, Config, BALLOTS, BALLOTS_BY_VOTER, CLOSED_PROPOSALS, CONFIG,
+    VOTERS,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw3-fixed-multisig";
@@ -26,7 +30,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -37,19 +41,28 @@ pub fn instantiate(
 
     msg.threshold.validate(total_weight)?;
 
+    let proposal_deposit = msg
+        .proposal_deposit
+        .map(|deposit| deposit.into_checked(deps.as_ref()))
+        .transpose()?;
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     let cfg = Config {
         threshold: msg.threshold,
         total_weight,
         max_voting_period: msg.max_voting_period,
+        default_voting_period: msg.default_voting_period,
+        executor: msg.executor,
+        proposal_deposit,
     };
+    cfg.validate_default_voting_period()?;
     CONFIG.save(deps.storage, &cfg)?;
 
     // add all voters
     for voter in msg.voters.iter() {
         let key = deps.api.addr_validate(&voter.addr)?;
-        VOTERS.save(deps.storage, &key, &voter.weight)?;
+        VOTERS.save(deps.storage, &key, &voter.weight, env.block.height)?;
     }
     Ok(Response::default())
 }
@@ -61,6 +74,19 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<Empty>, ContractError> {
+    execute_custom(deps, env, info, msg)
+}
+
+/// Same as `execute`, for chains where proposals may carry custom messages of type T
+pub fn execute_custom<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg<T>,
+) -> Result<Response<T>, ContractError>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     match msg {
         ExecuteMsg::Propose {
             title,
@@ -69,21 +95,27 @@ pub fn execute(
             latest,
         } => execute_propose(deps, env, info, title, description, msgs, latest),
         ExecuteMsg::Vote { proposal_id, vote } => execute_vote(deps, env, info, proposal_id, vote),
+        ExecuteMsg::UpdateVote { proposal_id, vote } => {
+            execute_update_vote(deps, env, info, proposal_id, vote)
+        }
         ExecuteMsg::Execute { proposal_id } => execute_execute(deps, env, info, proposal_id),
         ExecuteMsg::Close { proposal_id } => execute_close(deps, env, info, proposal_id),
     }
 }
 
-pub fn execute_propose(
+pub fn execute_propose<T>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     title: String,
     description: String,
-    msgs: Vec<CosmosMsg>,
+    msgs: Vec<CosmosMsg<T>>,
     // we ignore earliest
     latest: Option<Expiration>,
-) -> Result<Response<Empty>, ContractError> {
+) -> Result<Response<T>, ContractError>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     // only members of the multisig can create a proposal
     let vote_power = VOTERS
         .may_load(deps.storage, &info.sender)?
@@ -91,16 +123,33 @@ pub fn execute_propose(
 
     let cfg = CONFIG.load(deps.storage)?;
 
-    // max expires also used as default
-    let max_expires = cfg.max_voting_period.after(&env.block);
-    let mut expires = latest.unwrap_or(max_expires);
-    let comp = expires.partial_cmp(&max_expires);
-    if let Some(Ordering::Greater) = comp {
-        expires = max_expires;
-    } else if comp.is_none() {
-        return Err(ContractError::WrongExpiration {});
+    // Check that the native deposit was paid (as needed).
+    if let Some(deposit) = cfg.proposal_deposit.as_ref() {
+        deposit.check_native_deposit_paid(&info)?;
     }
 
+    // a requested expiration must use the unit of the max voting period, and lie within it
+    let expires = match latest {
+        Some(latest) => match latest.partial_cmp(&cfg.max_voting_period.after(&env.block)) {
+            Some(Ordering::Greater) => return Err(ContractError::ExpirationTooLate {}),
+            Some(_) => latest,
+            None => return Err(ContractError::WrongExpiration {}),
+        },
+        None => cfg
+            .default_voting_period
+            .unwrap_or(cfg.max_voting_period)
+            .after(&env.block),
+    };
+
+    // Take the cw20 token deposit, if required. We do this before
+    // creating the proposal struct below so that we can avoid a clone
+    // and move the loaded deposit info into it.
+    let take_deposit_msg = if let Some(deposit_info) = cfg.proposal_deposit.as_ref() {
+        deposit_info.get_take_deposit_messages(&info.sender, &env.contract.address)?
+    } else {
+        vec![]
+    };
+
     // create a proposal
     let mut prop = Proposal {
         title,
@@ -113,44 +162,54 @@ pub fn execute_propose(
         threshold: cfg.threshold,
         total_weight: cfg.total_weight,
         proposer: info.sender.clone(),
-        deposit: None,
+        deposit: cfg.proposal_deposit,
     };
     prop.update_status(&env.block);
     let id = next_id(deps.storage)?;
-    PROPOSALS.save(deps.storage, id, &prop)?;
+    proposals().save(deps.storage, id, &prop)?;
 
     // add the first yes vote from voter
     let ballot = Ballot {
         weight: vote_power,
         vote: Vote::Yes,
     };
-    BALLOTS.save(deps.storage, (id, &info.sender), &ballot)?;
+    save_ballot(deps.storage, id, &info.sender, &ballot)?;
 
     Ok(Response::new()
+        .add_messages(take_deposit_msg)
         .add_attribute("action", "propose")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", id.to_string())
         .add_attribute("status", format!("{:?}", prop.status)))
 }
 
-pub fn execute_vote(
+pub fn execute_vote<T>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
     vote: Vote,
-) -> Result<Response<Empty>, ContractError> {
+) -> Result<Response<T>, ContractError>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    // ensure proposal exists
+    let mut prop = proposals::<T>().load(deps.storage, proposal_id)?;
+
     // only members of the multisig with weight >= 1 can vote
-    let voter_power = VOTERS.may_load(deps.storage, &info.sender)?;
+    // use the weights from the end of the block the proposal was created in
+    let snapshot_height = prop.start_height + 1;
+    let voter_power = VOTERS.may_load_at_height(deps.storage, &info.sender, snapshot_height)?;
     let vote_power = match voter_power {
         Some(power) if power >= 1 => power,
         _ => return Err(ContractError::Unauthorized {}),
     };
 
-    // ensure proposal exists and can be voted on
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    // ensure proposal can be voted on
     // Allow voting on Passed and Rejected proposals too,
-    if ![Status::Open, Status::Passed, Status::Rejected].contains(&prop.status) {
+    if ![Status::Open, Status::Passed, Status::Rejected].contains(&prop.status)
+        || CLOSED_PROPOSALS.has(deps.storage, proposal_id)
+    {
         return Err(ContractError::NotOpen {});
     }
     // if they are not expired
@@ -159,18 +218,20 @@ pub fn execute_vote(
     }
 
     // cast vote if no vote previously cast
-    BALLOTS.update(deps.storage, (proposal_id, &info.sender), |bal| match bal {
-        Some(_) => Err(ContractError::AlreadyVoted {}),
-        None => Ok(Ballot {
-            weight: vote_power,
-            vote,
-        }),
-    })?;
+    let key = (proposal_id, &info.sender);
+    if BALLOTS.has(deps.storage, key) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    let ballot = Ballot {
+        weight: vote_power,
+        vote,
+    };
+    save_ballot(deps.storage, proposal_id, &info.sender, &ballot)?;
 
     // update vote tally
     prop.votes.add_vote(vote, vote_power);
     prop.update_status(&env.block);
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    proposals().save(deps.storage, proposal_id, &prop)?;
 
     Ok(Response::new()
         .add_attribute("action", "vote")
@@ -179,15 +240,60 @@ pub fn execute_vote(
         .add_attribute("status", format!("{:?}", prop.status)))
 }
 
-pub fn execute_execute(
+pub fn execute_update_vote<T>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
-) -> Result<Response, ContractError> {
-    // anyone can trigger this if the vote passed
+    vote: Vote,
+) -> Result<Response<T>, ContractError>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    let mut prop = proposals::<T>().load(deps.storage, proposal_id)?;
+    // a passed proposal is final, but a rejected one may still flip until it expires
+    if ![Status::Open, Status::Rejected].contains(&prop.status)
+        || CLOSED_PROPOSALS.has(deps.storage, proposal_id)
+    {
+        return Err(ContractError::NotOpen {});
+    }
+    if prop.expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    let key = (proposal_id, &info.sender);
+    let mut ballot = BALLOTS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NotVoted {})?;
+
+    // move the ballot's weight over to the new option
+    prop.votes.remove_vote(ballot.vote, ballot.weight);
+    prop.votes.add_vote(vote, ballot.weight);
+    ballot.vote = vote;
+    save_ballot(deps.storage, proposal_id, &info.sender, &ballot)?;
+
+    // re-evaluate from scratch, the new tally may lift an early rejection
+    prop.status = Status::Open;
+    prop.update_status(&env.block);
+    proposals().save(deps.storage, proposal_id, &prop)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_vote")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("status", format!("{:?}", prop.status)))
+}
 
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+pub fn execute_execute<T>(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response<T>, ContractError>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    let mut prop = proposals::<T>().load(deps.storage, proposal_id)?;
     // we allow execution even after the proposal "expiration" as long as all vote come in before
     // that point. If it was approved on time, it can be executed any time.
     prop.update_status(&env.block);
@@ -195,70 +301,116 @@ pub fn execute_execute(
         return Err(ContractError::WrongExecuteStatus {});
     }
 
+    let cfg = CONFIG.load(deps.storage)?;
+    cfg.authorize(deps.storage, &info.sender)?;
+
     // set it to executed
     prop.status = Status::Executed;
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    proposals().save(deps.storage, proposal_id, &prop)?;
+
+    // Unconditionally refund here.
+    let response = match prop.deposit {
+        Some(deposit) => {
+            Response::new().add_message(deposit.get_return_deposit_message(&prop.proposer)?)
+        }
+        None => Response::new(),
+    };
 
     // dispatch all proposed messages
-    Ok(Response::new()
+    Ok(response
         .add_messages(prop.msgs)
         .add_attribute("action", "execute")
         .add_attribute("sender", info.sender)
         .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
-pub fn execute_close(
+pub fn execute_close<T>(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
-) -> Result<Response<Empty>, ContractError> {
-    // anyone can trigger this if the vote passed
-
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
-    if [Status::Executed, Status::Rejected, Status::Passed].contains(&prop.status) {
-        return Err(ContractError::WrongCloseStatus {});
-    }
-    // Avoid closing of Passed due to expiration proposals
-    if prop.current_status(&env.block) == Status::Passed {
+) -> Result<Response<T>, ContractError>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    // anyone can trigger this if the vote failed
+
+    let mut prop = proposals::<T>().load(deps.storage, proposal_id)?;
+    if CLOSED_PROPOSALS.has(deps.storage, proposal_id) {
         return Err(ContractError::WrongCloseStatus {});
     }
-    if !prop.expires.is_expired(&env.block) {
-        return Err(ContractError::NotExpired {});
+    // the stored status may be stale, e.g. for proposals passed on expiration
+    prop.update_status(&env.block);
+    match prop.status {
+        // rejected early by the votes, or expired without passing
+        Status::Rejected => {}
+        Status::Open => return Err(ContractError::NotExpired {}),
+        _ => return Err(ContractError::WrongCloseStatus {}),
     }
+    let reason = if prop.is_rejected(&env.block) {
+        "rejected"
+    } else {
+        "expired"
+    };
 
-    // set it to failed
-    prop.status = Status::Rejected;
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    proposals().save(deps.storage, proposal_id, &prop)?;
+    CLOSED_PROPOSALS.save(deps.storage, proposal_id, &Empty {})?;
 
-    Ok(Response::new()
+    // Refund the deposit if we have been configured to do so.
+    let mut response = Response::new();
+    if let Some(deposit) = prop.deposit {
+        if deposit.refund_failed_proposals {
+            response = response.add_message(deposit.get_return_deposit_message(&prop.proposer)?)
+        }
+    }
+
+    Ok(response
         .add_attribute("action", "close")
-        .add_attribute("sender", info.sender)
-        .add_attribute("proposal_id", proposal_id.to_string()))
+        .add_attribute("sender", info.sender.clone())
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("closed_by", info.sender)
+        .add_attribute("reason", reason))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    query_custom::<Empty>(deps, env, msg)
+}
+
+/// Same as `query`, for chains where proposals may carry custom messages of type T
+pub fn query_custom<T>(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     match msg {
         QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
-        QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
+        QueryMsg::Proposal { proposal_id } => {
+            to_binary(&query_proposal::<T>(deps, env, proposal_id)?)
+        }
         QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
         QueryMsg::ListProposals { start_after, limit } => {
-            to_binary(&list_proposals(deps, env, start_after, limit)?)
+            to_binary(&list_proposals::<T>(deps, env, start_after, limit)?)
         }
         QueryMsg::ReverseProposals {
             start_before,
             limit,
-        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
+        } => to_binary(&reverse_proposals::<T>(deps, env, start_before, limit)?),
         QueryMsg::ListVotes {
             proposal_id,
             start_after,
             limit,
         } => to_binary(&list_votes(deps, proposal_id, start_after, limit)?),
+        QueryMsg::ListVotesByVoter {
+            voter,
+            start_after,
+            limit,
+        } => to_binary(&list_votes_by_voter(deps, voter, start_after, limit)?),
         QueryMsg::Voter { address } => to_binary(&query_voter(deps, address)?),
         QueryMsg::ListVoters { start_after, limit } => {
             to_binary(&list_voters(deps, start_after, limit)?)
         }
+        QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
     }
 }
 
@@ -267,8 +419,15 @@ fn query_threshold(deps: Deps) -> StdResult<ThresholdResponse> {
     Ok(cfg.threshold.to_response(cfg.total_weight))
 }
 
-fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse> {
-    let prop = PROPOSALS.load(deps.storage, id)?;
+fn query_config(deps: Deps) -> StdResult<Config> {
+    CONFIG.load(deps.storage)
+}
+
+fn query_proposal<T>(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse<T>>
+where
+    T: CustomMsg + DeserializeOwned,
+{
+    let prop = proposals::<T>().load(deps.storage, id)?;
     let status = prop.current_status(&env.block);
     let threshold = prop.threshold.to_response(prop.total_weight);
     Ok(ProposalResponse {
@@ -288,15 +447,18 @@ fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse>
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 
-fn list_proposals(
+fn list_proposals<T>(
     deps: Deps,
     env: Env,
     start_after: Option<u64>,
     limit: Option<u32>,
-) -> StdResult<ProposalListResponse> {
+) -> StdResult<ProposalListResponse<T>>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
-    let proposals = PROPOSALS
+    let proposals = proposals::<T>()
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|p| map_proposal(&env.block, p))
@@ -305,15 +467,18 @@ fn list_proposals(
     Ok(ProposalListResponse { proposals })
 }
 
-fn reverse_proposals(
+fn reverse_proposals<T>(
     deps: Deps,
     env: Env,
     start_before: Option<u64>,
     limit: Option<u32>,
-) -> StdResult<ProposalListResponse> {
+) -> StdResult<ProposalListResponse<T>>
+where
+    T: CustomMsg + DeserializeOwned,
+{
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let end = start_before.map(Bound::exclusive);
-    let props: StdResult<Vec<_>> = PROPOSALS
+    let props: StdResult<Vec<_>> = proposals::<T>()
         .range(deps.storage, None, end, Order::Descending)
         .take(limit)
         .map(|p| map_proposal(&env.block, p))
@@ -322,10 +487,10 @@ fn reverse_proposals(
     Ok(ProposalListResponse { proposals: props? })
 }
 
-fn map_proposal(
+fn map_proposal<T>(
     block: &BlockInfo,
-    item: StdResult<(u64, Proposal)>,
-) -> StdResult<ProposalResponse> {
+    item: StdResult<(u64, Proposal<T>)>,
+) -> StdResult<ProposalResponse<T>> {
     item.map(|(id, prop)| {
         let status = prop.current_status(block);
         let threshold = prop.threshold.to_response(prop.total_weight);
@@ -362,7 +527,8 @@ fn list_votes(
     limit: Option<u32>,
 ) -> StdResult<VoteListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
 
     let votes = BALLOTS
         .prefix(proposal_id)
@@ -381,19 +547,54 @@ fn list_votes(
     Ok(VoteListResponse { votes })
 }
 
+fn list_votes_by_voter(
+    deps: Deps,
+    voter: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<VoteListResponse> {
+    let voter = deps.api.addr_validate(&voter)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let votes = BALLOTS_BY_VOTER
+        .prefix(&voter)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(proposal_id, ballot)| VoteInfo {
+                proposal_id,
+                voter: voter.to_string(),
+                vote: ballot.vote,
+                weight: ballot.weight,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(VoteListResponse { votes })
+}
+
 fn query_voter(deps: Deps, voter: String) -> StdResult<VoterResponse> {
     let voter = deps.api.addr_validate(&voter)?;
     let weight = VOTERS.may_load(deps.storage, &voter)?;
     Ok(VoterResponse { weight })
 }
 
+fn query_total_weight(deps: Deps) -> StdResult<TotalWeightResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(TotalWeightResponse {
+        weight: cfg.total_weight,
+    })
+}
+
 fn list_voters(
     deps: Deps,
     start_after: Option<String>,
     limit: Option<u32>,
 ) -> StdResult<VoterListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
 
     let voters = VOTERS
         .range(deps.storage, start, None, Order::Ascending)
@@ -411,13 +612,17 @@ fn list_voters(
 
 #[cfg(test)]
 mod tests {
+    use cosmwasm_schema::cw_serde;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, from_binary, BankMsg, Decimal};
+    use cosmwasm_std::{
+        coin, from_binary, Addr, BankMsg, Decimal, IbcMsg, IbcTimeout, Storage, Timestamp,
+    };
 
     use cw2::{get_contract_version, ContractVersion};
     use cw_utils::{Duration, Threshold};
 
     use crate::msg::Voter;
+    use crate::state::{Config, Executor, PROPOSALS, VOTERS};
 
     use super::*;
 
@@ -457,6 +662,17 @@ mod tests {
         info: MessageInfo,
         threshold: Threshold,
         max_voting_period: Duration,
+    ) -> Result<Response<Empty>, ContractError> {
+        setup_test_case_with_executor(deps, info, threshold, max_voting_period, None)
+    }
+
+    #[track_caller]
+    fn setup_test_case_with_executor(
+        deps: DepsMut,
+        info: MessageInfo,
+        threshold: Threshold,
+        max_voting_period: Duration,
+        executor: Option<Executor>,
     ) -> Result<Response<Empty>, ContractError> {
         // Instantiate a contract with voters
         let voters = vec![
@@ -474,6 +690,9 @@ mod tests {
             voters,
             threshold,
             max_voting_period,
+            default_voting_period: None,
+            executor,
+            proposal_deposit: None,
         };
         instantiate(deps, mock_env(), info, instantiate_msg)
     }
@@ -511,6 +730,9 @@ mod tests {
                 quorum: Decimal::percent(1),
             },
             max_voting_period,
+            default_voting_period: None,
+            executor: None,
+            proposal_deposit: None,
         };
         let err = instantiate(
             deps.as_mut(),
@@ -551,6 +773,7 @@ mod tests {
             ContractVersion {
                 contract: CONTRACT_NAME.to_string(),
                 version: CONTRACT_VERSION.to_string(),
+                commit_hash: None,
             },
             get_contract_version(&deps.storage).unwrap()
         )
@@ -665,6 +888,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn propose_expiration_bounds() {
+        let mut deps = mock_dependencies();
+        let info = mock_info(OWNER, &[]);
+        let max_voting_period = Duration::Time(2000000);
+
+        // the default must use the unit of the max and not exceed it
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![voter(OWNER, 1)],
+            threshold: Threshold::AbsoluteCount { weight: 1 },
+            max_voting_period,
+            default_voting_period: Some(Duration::Height(100)),
+            executor: None,
+            proposal_deposit: None,
+        };
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::WrongVotingPeriod {});
+        let instantiate_msg = InstantiateMsg {
+            default_voting_period: Some(Duration::Time(2000001)),
+            ..instantiate_msg
+        };
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            instantiate_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::VotingPeriodTooLong {});
+        let instantiate_msg = InstantiateMsg {
+            default_voting_period: Some(Duration::Time(1000)),
+            ..instantiate_msg
+        };
+        instantiate(deps.as_mut(), mock_env(), info.clone(), instantiate_msg).unwrap();
+
+        // config shows both periods
+        let config: Config =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.max_voting_period, max_voting_period);
+        assert_eq!(config.default_voting_period, Some(Duration::Time(1000)));
+
+        let propose = |latest| ExecuteMsg::Propose {
+            title: "Text".to_string(),
+            description: "Nothing to execute".to_string(),
+            msgs: vec![],
+            latest,
+        };
+        let expires = |deps: Deps, id| {
+            let prop: ProposalResponse = from_binary(
+                &query(deps, mock_env(), QueryMsg::Proposal { proposal_id: id }).unwrap(),
+            )
+            .unwrap();
+            prop.expires
+        };
+        let env = mock_env();
+
+        // no expiration uses the default, not the max
+        execute(deps.as_mut(), env.clone(), info.clone(), propose(None)).unwrap();
+        assert_eq!(
+            expires(deps.as_ref(), 1),
+            Expiration::AtTime(env.block.time.plus_seconds(1000))
+        );
+
+        // the max itself is fine, a later or never-ending expiration is rejected
+        let max = Expiration::AtTime(env.block.time.plus_seconds(2000000));
+        execute(deps.as_mut(), env.clone(), info.clone(), propose(Some(max))).unwrap();
+        assert_eq!(expires(deps.as_ref(), 2), max);
+        let too_late = Expiration::AtTime(env.block.time.plus_seconds(2000001));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            propose(Some(too_late)),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ExpirationTooLate {});
+        let never = Some(Expiration::Never {});
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), propose(never)).unwrap_err();
+        assert_eq!(err, ContractError::ExpirationTooLate {});
+
+        // and the unit must match
+        let height = Some(Expiration::AtHeight(env.block.height + 10));
+        let err = execute(deps.as_mut(), env, info, propose(height)).unwrap_err();
+        assert_eq!(err, ContractError::WrongExpiration {});
+    }
+
     #[test]
     fn test_vote_works() {
         let mut deps = mock_dependencies();
@@ -890,7 +1205,7 @@ mod tests {
     }
 
     #[test]
-    fn test_execute_works() {
+    fn already_voted_check_does_not_parse_ballot() {
         let mut deps = mock_dependencies();
 
         let threshold = Threshold::AbsoluteCount { weight: 3 };
@@ -899,141 +1214,659 @@ mod tests {
         let info = mock_info(OWNER, &[]);
         setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
 
-        // Propose
-        let bank_msg = BankMsg::Send {
-            to_address: SOMEBODY.into(),
-            amount: vec![coin(1, "BTC")],
-        };
-        let msgs = vec![CosmosMsg::Bank(bank_msg)];
         let proposal = ExecuteMsg::Propose {
             title: "Pay somebody".to_string(),
             description: "Do I pay her?".to_string(),
-            msgs: msgs.clone(),
+            msgs: vec![],
             latest: None,
         };
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
-
-        // Get the proposal id from the logs
+        let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
         let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
 
-        // Only Passed can be executed
-        let execution = ExecuteMsg::Execute { proposal_id };
-        let err = execute(deps.as_mut(), mock_env(), info, execution.clone()).unwrap_err();
-        assert_eq!(err, ContractError::WrongExecuteStatus {});
+        // store a ballot that cannot be deserialized
+        let voter = Addr::unchecked(VOTER1);
+        let key = BALLOTS.key((proposal_id, &voter));
+        deps.storage.set(&key, b"{not json");
+        assert!(BALLOTS.has(&deps.storage, (proposal_id, &voter)));
 
-        // Vote it, so it passes
-        let vote = ExecuteMsg::Vote {
+        // the existence check alone rejects the vote, without a parse error
+        let yes_vote = ExecuteMsg::Vote {
             proposal_id,
             vote: Vote::Yes,
         };
-        let info = mock_info(VOTER3, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), vote).unwrap();
-
-        // Verify
-        assert_eq!(
-            res,
-            Response::new()
-                .add_attribute("action", "vote")
-                .add_attribute("sender", VOTER3)
-                .add_attribute("proposal_id", proposal_id.to_string())
-                .add_attribute("status", "Passed")
-        );
-
-        // In passing: Try to close Passed fails
-        let closing = ExecuteMsg::Close { proposal_id };
-        let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
-        assert_eq!(err, ContractError::WrongCloseStatus {});
-
-        // Execute works. Anybody can execute Passed proposals
-        let info = mock_info(SOMEBODY, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info.clone(), execution).unwrap();
-
-        // Verify
-        assert_eq!(
-            res,
-            Response::new()
-                .add_messages(msgs)
-                .add_attribute("action", "execute")
-                .add_attribute("sender", SOMEBODY)
-                .add_attribute("proposal_id", proposal_id.to_string())
-        );
-
-        // In passing: Try to close Executed fails
-        let closing = ExecuteMsg::Close { proposal_id };
-        let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
-        assert_eq!(err, ContractError::WrongCloseStatus {});
+        let info = mock_info(VOTER1, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, yes_vote).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
     }
 
     #[test]
-    fn proposal_pass_on_expiration() {
+    fn update_vote_works() {
         let mut deps = mock_dependencies();
 
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
-        };
+        // total weight is 17, so 8 No votes reject early
+        let threshold = Threshold::AbsoluteCount { weight: 10 };
         let voting_period = Duration::Time(2000000);
 
         let info = mock_info(OWNER, &[]);
         setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
 
-        // Propose
-        let bank_msg = BankMsg::Send {
-            to_address: SOMEBODY.into(),
-            amount: vec![coin(1, "BTC")],
-        };
-        let msgs = vec![CosmosMsg::Bank(bank_msg)];
         let proposal = ExecuteMsg::Propose {
             title: "Pay somebody".to_string(),
             description: "Do I pay her?".to_string(),
-            msgs,
+            msgs: vec![],
             latest: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
-
-        // Get the proposal id from the logs
         let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
 
-        // Vote it, so it passes after voting period is over
-        let vote = ExecuteMsg::Vote {
-            proposal_id,
-            vote: Vote::Yes,
-        };
-        let info = mock_info(VOTER3, &[]);
-        let res = execute(deps.as_mut(), mock_env(), info, vote).unwrap();
+        let vote = |vote| ExecuteMsg::Vote { proposal_id, vote };
+        let update = |vote| ExecuteMsg::UpdateVote { proposal_id, vote };
+
+        // cannot update without voting first
+        let info = mock_info(VOTER4, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, update(Vote::Yes)).unwrap_err();
+        assert_eq!(err, ContractError::NotVoted {});
+
+        // No votes of 5 + 4 reject the proposal early
+        let info = mock_info(VOTER5, &[]);
+        execute(deps.as_mut(), mock_env(), info, vote(Vote::No)).unwrap();
+        let info = mock_info(VOTER4, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), vote(Vote::No)).unwrap();
+        assert_eq!(res.attributes[3].value, "Rejected");
+
+        // flipping a No re-opens it, repeating the update doesn't count twice
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), update(Vote::Yes)).unwrap();
         assert_eq!(
             res,
             Response::new()
-                .add_attribute("action", "vote")
-                .add_attribute("sender", VOTER3)
+                .add_attribute("action", "update_vote")
+                .add_attribute("sender", VOTER4)
                 .add_attribute("proposal_id", proposal_id.to_string())
                 .add_attribute("status", "Open")
         );
+        execute(deps.as_mut(), mock_env(), info, update(Vote::Yes)).unwrap();
+        let prop = PROPOSALS.load(&deps.storage, proposal_id).unwrap();
+        assert_eq!(prop.votes.yes, 5);
+        assert_eq!(prop.votes.no, 5);
+        assert_eq!(get_tally(deps.as_ref(), proposal_id), 5);
 
-        // Wait until the voting period is over
-        let env = match voting_period {
-            Duration::Time(duration) => mock_env_time(duration + 1),
-            Duration::Height(duration) => mock_env_height(duration + 1),
-        };
+        // the second flip passes it
+        let info = mock_info(VOTER5, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), update(Vote::Yes)).unwrap();
+        assert_eq!(res.attributes[3].value, "Passed");
+        let prop = PROPOSALS.load(&deps.storage, proposal_id).unwrap();
+        assert_eq!(prop.votes.total(), 10);
 
-        // Proposal should now be passed
-        let prop: ProposalResponse = from_binary(
-            &query(
-                deps.as_ref(),
-                env.clone(),
-                QueryMsg::Proposal { proposal_id },
-            )
-            .unwrap(),
-        )
-        .unwrap();
-        assert_eq!(prop.status, Status::Passed);
+        // a passed proposal cannot be changed any more
+        let err = execute(deps.as_mut(), mock_env(), info, update(Vote::No)).unwrap_err();
+        assert_eq!(err, ContractError::NotOpen {});
 
-        // Closing should NOT be possible
-        let info = mock_info(SOMEBODY, &[]);
-        let err = execute(
-            deps.as_mut(),
-            env.clone(),
-            info.clone(),
+        // nor can an expired one
+        let env = mock_env_time(2000001);
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+        };
+        let info = mock_info(OWNER, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        let update = ExecuteMsg::UpdateVote {
+            proposal_id,
+            vote: Vote::No,
+        };
+        let err = execute(deps.as_mut(), env, info, update).unwrap_err();
+        assert_eq!(err, ContractError::Expired {});
+    }
+
+    #[test]
+    fn query_voters_and_weights() {
+        let mut deps = mock_dependencies();
+
+        let instantiate_msg = InstantiateMsg {
+            voters: vec![
+                voter(VOTER5, 5),
+                voter(VOTER1, 1),
+                voter(VOTER3, 3),
+                voter(VOTER2, 2),
+                voter(VOTER4, 4),
+            ],
+            threshold: Threshold::AbsoluteCount { weight: 3 },
+            max_voting_period: Duration::Time(1000),
+            default_voting_period: None,
+            executor: None,
+            proposal_deposit: None,
+        };
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(OWNER, &[]),
+            instantiate_msg,
+        )
+        .unwrap();
+
+        let total: TotalWeightResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::TotalWeight {}).unwrap())
+                .unwrap();
+        assert_eq!(total, TotalWeightResponse { weight: 15 });
+
+        let weight = |addr: &str| query_voter(deps.as_ref(), addr.to_owned()).unwrap().weight;
+        assert_eq!(weight(VOTER3), Some(3));
+        assert_eq!(weight(OWNER), None);
+
+        // pages are ordered by address
+        let list = |start_after: Option<&str>| {
+            list_voters(deps.as_ref(), start_after.map(String::from), Some(2))
+                .unwrap()
+                .voters
+                .into_iter()
+                .map(|v| (v.addr, v.weight))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            list(None),
+            vec![(VOTER1.to_owned(), 1), (VOTER2.to_owned(), 2)]
+        );
+        assert_eq!(
+            list(Some(VOTER2)),
+            vec![(VOTER3.to_owned(), 3), (VOTER4.to_owned(), 4)]
+        );
+        assert_eq!(list(Some(VOTER4)), vec![(VOTER5.to_owned(), 5)]);
+        assert_eq!(list(Some(VOTER5)), vec![]);
+    }
+
+    #[test]
+    fn list_votes_pagination() {
+        let mut deps = mock_dependencies();
+
+        // needs all votes, so everything stays open
+        let threshold = Threshold::AbsoluteCount { weight: 17 };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        for _ in 0..4 {
+            let proposal = ExecuteMsg::Propose {
+                title: "Pay somebody".to_string(),
+                description: "Do I pay her?".to_string(),
+                msgs: vec![],
+                latest: None,
+            };
+            execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
+        }
+        let vote = |deps: DepsMut, voter, proposal_id, vote| {
+            let msg = ExecuteMsg::Vote { proposal_id, vote };
+            execute(deps, mock_env(), mock_info(voter, &[]), msg).unwrap();
+        };
+        vote(deps.as_mut(), VOTER1, 1, Vote::Yes);
+        vote(deps.as_mut(), VOTER1, 2, Vote::No);
+        vote(deps.as_mut(), VOTER1, 4, Vote::Abstain);
+        vote(deps.as_mut(), VOTER2, 1, Vote::No);
+        vote(deps.as_mut(), VOTER3, 1, Vote::Veto);
+        let update = ExecuteMsg::UpdateVote {
+            proposal_id: 2,
+            vote: Vote::Yes,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER1, &[]), update).unwrap();
+
+        let by_voter = |deps: Deps, voter: &str, start_after, limit| {
+            let msg = QueryMsg::ListVotesByVoter {
+                voter: voter.to_string(),
+                start_after,
+                limit,
+            };
+            let res: VoteListResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.votes
+                .into_iter()
+                .map(|v| (v.proposal_id, v.vote, v.weight))
+                .collect::<Vec<_>>()
+        };
+
+        // ordered by proposal id, changed votes are reflected
+        assert_eq!(
+            by_voter(deps.as_ref(), VOTER1, None, Some(2)),
+            vec![(1, Vote::Yes, 1), (2, Vote::Yes, 1)]
+        );
+        assert_eq!(
+            by_voter(deps.as_ref(), VOTER1, Some(2), Some(2)),
+            vec![(4, Vote::Abstain, 1)]
+        );
+        assert_eq!(by_voter(deps.as_ref(), VOTER1, Some(4), None), vec![]);
+        // the proposer voted on all of them
+        assert_eq!(by_voter(deps.as_ref(), OWNER, None, None).len(), 4);
+        assert_eq!(by_voter(deps.as_ref(), VOTER4, None, None), vec![]);
+
+        // votes on one proposal are paginated by voter address
+        let list_votes = |deps: Deps, start_after: Option<&str>, limit| {
+            let msg = QueryMsg::ListVotes {
+                proposal_id: 1,
+                start_after: start_after.map(String::from),
+                limit,
+            };
+            let res: VoteListResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.votes.into_iter().map(|v| v.voter).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            list_votes(deps.as_ref(), None, Some(2)),
+            vec![OWNER, VOTER1]
+        );
+        assert_eq!(
+            list_votes(deps.as_ref(), Some(VOTER1), Some(2)),
+            vec![VOTER2, VOTER3]
+        );
+        assert_eq!(
+            list_votes(deps.as_ref(), Some(VOTER3), Some(2)),
+            Vec::<String>::new()
+        );
+    }
+
+    #[cw_serde]
+    enum ChainMsg {
+        Mint { amount: u64 },
+    }
+
+    impl CustomMsg for ChainMsg {}
+
+    #[test]
+    fn custom_messages_round_trip() {
+        let mut deps = mock_dependencies();
+
+        let threshold = Threshold::AbsoluteCount { weight: 3 };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        let msgs = vec![CosmosMsg::Custom(ChainMsg::Mint { amount: 7 })];
+        let proposal = ExecuteMsg::Propose {
+            title: "Mint".to_string(),
+            description: "Chain specific".to_string(),
+            msgs: msgs.clone(),
+            latest: None,
+        };
+        let res = execute_custom(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        let query_msg = QueryMsg::Proposal { proposal_id };
+        let bin = query_custom::<ChainMsg>(deps.as_ref(), mock_env(), query_msg).unwrap();
+        let prop: ProposalResponse<ChainMsg> = from_binary(&bin).unwrap();
+        assert_eq!(prop.msgs, msgs);
+
+        let vote: ExecuteMsg<ChainMsg> = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let info = mock_info(VOTER3, &[]);
+        execute_custom(deps.as_mut(), mock_env(), info, vote).unwrap();
+
+        let execution: ExecuteMsg<ChainMsg> = ExecuteMsg::Execute { proposal_id };
+        let info = mock_info(SOMEBODY, &[]);
+        let res = execute_custom(deps.as_mut(), mock_env(), info, execution).unwrap();
+        assert_eq!(
+            res,
+            Response::new()
+                .add_messages(msgs)
+                .add_attribute("action", "execute")
+                .add_attribute("sender", SOMEBODY)
+                .add_attribute("proposal_id", proposal_id.to_string())
+        );
+    }
+
+    #[test]
+    fn ibc_messages_round_trip() {
+        let mut deps = mock_dependencies();
+
+        let threshold = Threshold::AbsoluteCount { weight: 3 };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        let transfer = IbcMsg::Transfer {
+            channel_id: "channel-3".to_string(),
+            to_address: "cosmos1receiver".to_string(),
+            amount: coin(1, "BTC"),
+            timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(1_700_000_000)),
+        };
+        let msgs = vec![CosmosMsg::Ibc(transfer)];
+        let proposal = ExecuteMsg::Propose {
+            title: "Send over IBC".to_string(),
+            description: "Pay the other chain".to_string(),
+            msgs: msgs.clone(),
+            latest: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        let query_msg = QueryMsg::Proposal { proposal_id };
+        let prop: ProposalResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap();
+        assert_eq!(prop.msgs, msgs);
+
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(VOTER3, &[]), vote).unwrap();
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            execution,
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, msgs[0]);
+    }
+
+    #[test]
+    fn vote_uses_weight_at_proposal_start() {
+        let mut deps = mock_dependencies();
+
+        let threshold = Threshold::AbsoluteCount { weight: 17 };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal.clone()).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        // voters change after the proposal was created
+        let height = mock_env().block.height;
+        let voter3 = Addr::unchecked(VOTER3);
+        VOTERS
+            .save(&mut deps.storage, &voter3, &10, height + 1)
+            .unwrap();
+        let somebody = Addr::unchecked(SOMEBODY);
+        VOTERS
+            .save(&mut deps.storage, &somebody, &5, height + 1)
+            .unwrap();
+        let voter4 = Addr::unchecked(VOTER4);
+        VOTERS
+            .remove(&mut deps.storage, &voter4, height + 1)
+            .unwrap();
+
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let env = mock_env_height(2);
+        let vote_weight = |deps: Deps, proposal_id, voter: &str| {
+            let msg = QueryMsg::Vote {
+                proposal_id,
+                voter: voter.to_string(),
+            };
+            let res: VoteResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.vote.unwrap().weight
+        };
+
+        // the original weights are used
+        let info = mock_info(VOTER3, &[]);
+        execute(deps.as_mut(), env.clone(), info, yes_vote.clone()).unwrap();
+        assert_eq!(vote_weight(deps.as_ref(), proposal_id, VOTER3), 3);
+        let info = mock_info(VOTER4, &[]);
+        execute(deps.as_mut(), env.clone(), info, yes_vote.clone()).unwrap();
+        assert_eq!(vote_weight(deps.as_ref(), proposal_id, VOTER4), 4);
+        let info = mock_info(SOMEBODY, &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, yes_vote).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        assert_eq!(get_tally(deps.as_ref(), proposal_id), 8);
+
+        // while new proposals see the new weights
+        let res = execute(deps.as_mut(), env.clone(), mock_info(OWNER, &[]), proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(VOTER3, &[]),
+            yes_vote.clone(),
+        )
+        .unwrap();
+        assert_eq!(vote_weight(deps.as_ref(), proposal_id, VOTER3), 10);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(SOMEBODY, &[]),
+            yes_vote.clone(),
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env, mock_info(VOTER4, &[]), yes_vote).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_execute_works() {
+        let mut deps = mock_dependencies();
+
+        let threshold = Threshold::AbsoluteCount { weight: 3 };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        // Propose
+        let bank_msg = BankMsg::Send {
+            to_address: SOMEBODY.into(),
+            amount: vec![coin(1, "BTC")],
+        };
+        let msgs = vec![CosmosMsg::Bank(bank_msg)];
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: msgs.clone(),
+            latest: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal).unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        // Only Passed can be executed
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let err = execute(deps.as_mut(), mock_env(), info, execution.clone()).unwrap_err();
+        assert_eq!(err, ContractError::WrongExecuteStatus {});
+
+        // Vote it, so it passes
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let info = mock_info(VOTER3, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), vote).unwrap();
+
+        // Verify
+        assert_eq!(
+            res,
+            Response::new()
+                .add_attribute("action", "vote")
+                .add_attribute("sender", VOTER3)
+                .add_attribute("proposal_id", proposal_id.to_string())
+                .add_attribute("status", "Passed")
+        );
+
+        // In passing: Try to close Passed fails
+        let closing = ExecuteMsg::Close { proposal_id };
+        let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+
+        // Execute works. Anybody can execute Passed proposals
+        let info = mock_info(SOMEBODY, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), execution).unwrap();
+
+        // Verify
+        assert_eq!(
+            res,
+            Response::new()
+                .add_messages(msgs)
+                .add_attribute("action", "execute")
+                .add_attribute("sender", SOMEBODY)
+                .add_attribute("proposal_id", proposal_id.to_string())
+        );
+
+        // In passing: Try to close Executed fails
+        let closing = ExecuteMsg::Close { proposal_id };
+        let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+    }
+
+    // instantiates with the given executor and returns the id of a passed proposal
+    fn setup_passed_proposal(deps: &mut DepsMut, executor: Option<Executor>) -> u64 {
+        let threshold = Threshold::AbsoluteCount { weight: 3 };
+        let voting_period = Duration::Time(2000000);
+        let info = mock_info(OWNER, &[]);
+        setup_test_case_with_executor(
+            deps.branch(),
+            info.clone(),
+            threshold,
+            voting_period,
+            executor,
+        )
+        .unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+        };
+        let res = execute(deps.branch(), mock_env(), info, proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let info = mock_info(VOTER3, &[]);
+        execute(deps.branch(), mock_env(), info, vote).unwrap();
+        proposal_id
+    }
+
+    #[test]
+    fn execute_with_executor() {
+        // no executor, anyone can execute
+        let mut deps = mock_dependencies();
+        let proposal_id = setup_passed_proposal(&mut deps.as_mut(), None);
+        let config: Config =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.executor, None);
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let info = mock_info(SOMEBODY, &[]);
+        execute(deps.as_mut(), mock_env(), info, execution).unwrap();
+
+        // only voters, even without weight, can execute
+        let mut deps = mock_dependencies();
+        let proposal_id = setup_passed_proposal(&mut deps.as_mut(), Some(Executor::Member));
+        let config: Config =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.executor, Some(Executor::Member));
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let info = mock_info(SOMEBODY, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, execution.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(NOWEIGHT_VOTER, &[]);
+        execute(deps.as_mut(), mock_env(), info, execution).unwrap();
+
+        // a designated executor need not be a voter, and voters cannot execute
+        let mut deps = mock_dependencies();
+        let executor = Executor::Only(Addr::unchecked(SOMEBODY));
+        let proposal_id = setup_passed_proposal(&mut deps.as_mut(), Some(executor.clone()));
+        let config: Config =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.executor, Some(executor));
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let info = mock_info(VOTER3, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, execution.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info(SOMEBODY, &[]);
+        execute(deps.as_mut(), mock_env(), info, execution).unwrap();
+    }
+
+    #[test]
+    fn proposal_pass_on_expiration() {
+        let mut deps = mock_dependencies();
+
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        // Propose
+        let bank_msg = BankMsg::Send {
+            to_address: SOMEBODY.into(),
+            amount: vec![coin(1, "BTC")],
+        };
+        let msgs = vec![CosmosMsg::Bank(bank_msg)];
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs,
+            latest: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        // Vote it, so it passes after voting period is over
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let info = mock_info(VOTER3, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, vote).unwrap();
+        assert_eq!(
+            res,
+            Response::new()
+                .add_attribute("action", "vote")
+                .add_attribute("sender", VOTER3)
+                .add_attribute("proposal_id", proposal_id.to_string())
+                .add_attribute("status", "Open")
+        );
+
+        // Wait until the voting period is over
+        let env = match voting_period {
+            Duration::Time(duration) => mock_env_time(duration + 1),
+            Duration::Height(duration) => mock_env_height(duration + 1),
+        };
+
+        // Proposal should now be passed
+        let prop: ProposalResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Proposal { proposal_id },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+
+        // Closing should NOT be possible
+        let info = mock_info(SOMEBODY, &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
             ExecuteMsg::Close { proposal_id },
         )
         .unwrap_err();
@@ -1057,6 +1890,110 @@ mod tests {
         )
     }
 
+    #[test]
+    fn proposal_pass_early_with_percentage() {
+        let mut deps = mock_dependencies();
+
+        // 50% of the total weight of 17 needs 9 yes votes
+        let threshold = Threshold::AbsolutePercentage {
+            percentage: Decimal::percent(50),
+        };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        // the threshold is reported against the total weight
+        let res: ThresholdResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Threshold {}).unwrap())
+                .unwrap();
+        assert_eq!(
+            res,
+            ThresholdResponse::AbsolutePercentage {
+                percentage: Decimal::percent(50),
+                total_weight: 17,
+            }
+        );
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let info = mock_info(VOTER5, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, yes_vote.clone()).unwrap();
+        assert_eq!(res.attributes[3].value, "Open");
+
+        // passes before expiration as soon as 9 of 17 are reached
+        let info = mock_info(VOTER3, &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, yes_vote).unwrap();
+        assert_eq!(res.attributes[3].value, "Passed");
+    }
+
+    #[test]
+    fn proposal_rejected_without_quorum() {
+        let mut deps = mock_dependencies();
+
+        // quorum needs 11 of 17 to take part
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(50),
+            quorum: Decimal::percent(60),
+        };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        // a clear majority of the 7 that voted, but no quorum
+        let info = mock_info(VOTER5, &[]);
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        execute(deps.as_mut(), mock_env(), info, vote).unwrap();
+        let info = mock_info(VOTER1, &[]);
+        let vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::No,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, vote).unwrap();
+        assert_eq!(res.attributes[3].value, "Open");
+
+        // after expiration it is rejected and cannot be executed
+        let env = mock_env_time(2000001);
+        let prop: ProposalResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Proposal { proposal_id },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(prop.status, Status::Rejected);
+        let info = mock_info(SOMEBODY, &[]);
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let err = execute(deps.as_mut(), env, info, execution).unwrap_err();
+        assert_eq!(err, ContractError::WrongExecuteStatus {});
+    }
+
     #[test]
     fn test_close_works() {
         let mut deps = mock_dependencies();
@@ -1126,10 +2063,104 @@ mod tests {
                 .add_attribute("action", "close")
                 .add_attribute("sender", SOMEBODY)
                 .add_attribute("proposal_id", proposal_id.to_string())
+                .add_attribute("closed_by", SOMEBODY)
+                .add_attribute("reason", "expired")
         );
 
         // Trying to close it again fails
         let err = execute(deps.as_mut(), mock_env(), info, closing).unwrap_err();
         assert_eq!(err, ContractError::WrongCloseStatus {});
     }
+
+    #[test]
+    fn close_rejected_before_expiration() {
+        let mut deps = mock_dependencies();
+
+        // total weight is 17, so 8 No votes reject early
+        let threshold = Threshold::AbsoluteCount { weight: 10 };
+        let voting_period = Duration::Time(2000000);
+
+        let info = mock_info(OWNER, &[]);
+        setup_test_case(deps.as_mut(), info.clone(), threshold, voting_period).unwrap();
+
+        let proposal = ExecuteMsg::Propose {
+            title: "Pay somebody".to_string(),
+            description: "Do I pay her?".to_string(),
+            msgs: vec![],
+            latest: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), proposal.clone()).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+
+        let no_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::No,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER5, &[]),
+            no_vote.clone(),
+        )
+        .unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info(VOTER4, &[]), no_vote).unwrap();
+        assert_eq!(res.attributes[3].value, "Rejected");
+
+        // no need to wait for the expiration
+        let closing = ExecuteMsg::Close { proposal_id };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(SOMEBODY, &[]),
+            closing.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            Response::new()
+                .add_attribute("action", "close")
+                .add_attribute("sender", SOMEBODY)
+                .add_attribute("proposal_id", proposal_id.to_string())
+                .add_attribute("closed_by", SOMEBODY)
+                .add_attribute("reason", "rejected")
+        );
+
+        // a closed proposal is final
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info(SOMEBODY, &[]), closing).unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(VOTER3, &[]), yes_vote).unwrap_err();
+        assert_eq!(err, ContractError::NotOpen {});
+        let update = ExecuteMsg::UpdateVote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(VOTER4, &[]), update).unwrap_err();
+        assert_eq!(err, ContractError::NotOpen {});
+
+        // passed proposals cannot be closed
+        let res = execute(deps.as_mut(), mock_env(), info, proposal).unwrap();
+        let proposal_id: u64 = res.attributes[2].value.parse().unwrap();
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(VOTER5, &[]),
+            yes_vote.clone(),
+        )
+        .unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info(VOTER4, &[]), yes_vote).unwrap();
+        assert_eq!(res.attributes[3].value, "Passed");
+        let closing = ExecuteMsg::Close { proposal_id };
+        let env = mock_env_time(2000001);
+        let err = execute(deps.as_mut(), env, mock_info(SOMEBODY, &[]), closing).unwrap_err();
+        assert_eq!(err, ContractError::WrongCloseStatus {});
+    }
 }
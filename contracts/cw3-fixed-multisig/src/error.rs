@@ -1,4 +1,5 @@
 use cosmwasm_std::StdError;
+use cw3::DepositError;
 use cw_utils::ThresholdError;
 
 use thiserror::Error;
@@ -11,6 +12,9 @@ pub enum ContractError {
     #[error("{0}")]
     Threshold(#[from] ThresholdError),
 
+    #[error("{0}")]
+    Deposit(#[from] DepositError),
+
     #[error("Required weight cannot be zero")]
     ZeroWeight {},
 
@@ -35,9 +39,21 @@ pub enum ContractError {
     #[error("Wrong expiration option")]
     WrongExpiration {},
 
+    #[error("Proposal cannot expire later than the maximum voting period allows")]
+    ExpirationTooLate {},
+
+    #[error("Default voting period must use the same unit as the maximum")]
+    WrongVotingPeriod {},
+
+    #[error("Default voting period is longer than the maximum")]
+    VotingPeriodTooLong {},
+
     #[error("Already voted on this proposal")]
     AlreadyVoted {},
 
+    #[error("No vote cast on this proposal yet")]
+    NotVoted {},
+
     #[error("Proposal must have passed and not yet been executed")]
     WrongExecuteStatus {},
 
@@ -1,13 +1,14 @@
 #![cfg(test)]
 
-use cosmwasm_std::{to_binary, Addr, Empty, Uint128, WasmMsg};
-use cw20::{BalanceResponse, MinterResponse};
+use cosmwasm_std::{coins, to_binary, Addr, Empty, Uint128, WasmMsg};
+use cw20::{BalanceResponse, MinterResponse, UncheckedDenom};
 use cw20_base::msg::QueryMsg;
-use cw3::Vote;
-use cw_multi_test::{App, Contract, ContractWrapper, Executor};
-use cw_utils::{Duration, Threshold};
+use cw3::{DepositError, UncheckedDepositInfo, Vote};
+use cw_multi_test::{App, BankSudo, Contract, ContractWrapper, Executor, SudoMsg};
+use cw_utils::{Duration, PaymentError, Threshold};
 
 use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
 use crate::msg::{ExecuteMsg, InstantiateMsg, Voter};
 
 fn mock_app() -> App {
@@ -56,6 +57,9 @@ fn cw3_controls_cw20() {
         ],
         threshold: Threshold::AbsoluteCount { weight: 2 },
         max_voting_period: Duration::Height(3),
+        default_voting_period: None,
+        executor: None,
+        proposal_deposit: None,
     };
 
     let multisig_addr = router
@@ -82,6 +86,7 @@ fn cw3_controls_cw20() {
             cap: None,
         }),
         marketing: None,
+        import_from: None,
     };
     let cw20_addr = router
         .instantiate_contract(
@@ -107,7 +112,7 @@ fn cw3_controls_cw20() {
         msg: to_binary(&cw20_mint_msg).unwrap(),
         funds: vec![],
     };
-    let propose_msg = ExecuteMsg::Propose {
+    let propose_msg: ExecuteMsg = ExecuteMsg::Propose {
         title: "Mint tokens".to_string(),
         description: "Need to mint tokens".to_string(),
         msgs: vec![execute_mint_msg.into()],
@@ -119,7 +124,7 @@ fn cw3_controls_cw20() {
         .unwrap();
 
     // second votes
-    let vote2_msg = ExecuteMsg::Vote {
+    let vote2_msg: ExecuteMsg = ExecuteMsg::Vote {
         proposal_id: 1,
         vote: Vote::Yes,
     };
@@ -128,7 +133,7 @@ fn cw3_controls_cw20() {
         .unwrap();
 
     // only 1 vote and msg mint fails
-    let execute_proposal_msg = ExecuteMsg::Execute { proposal_id: 1 };
+    let execute_proposal_msg: ExecuteMsg = ExecuteMsg::Execute { proposal_id: 1 };
     // execute mint
     router
         .execute_contract(addr1, multisig_addr, &execute_proposal_msg, &[])
@@ -146,3 +151,154 @@ fn cw3_controls_cw20() {
     // compare minted amount
     assert_eq!(balance.balance, mint_amount);
 }
+
+// sets up a 2 of 3 multisig requiring a deposit of 10 TOKEN, each voter owns 20
+fn setup_with_deposit(app: &mut App, refund_failed_proposals: bool) -> Addr {
+    let voters = ["addr1", "addr2", "addr3"];
+    for voter in voters {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: voter.to_string(),
+            amount: coins(20, "TOKEN"),
+        }))
+        .unwrap();
+    }
+
+    let cw3_id = app.store_code(contract_cw3_fixed_multisig());
+    let msg = InstantiateMsg {
+        voters: voters
+            .iter()
+            .map(|addr| Voter {
+                addr: addr.to_string(),
+                weight: 1,
+            })
+            .collect(),
+        threshold: Threshold::AbsoluteCount { weight: 2 },
+        max_voting_period: Duration::Height(3),
+        default_voting_period: None,
+        executor: None,
+        proposal_deposit: Some(UncheckedDepositInfo {
+            amount: Uint128::new(10),
+            denom: UncheckedDenom::Native("TOKEN".to_string()),
+            refund_failed_proposals,
+        }),
+    };
+    app.instantiate_contract(cw3_id, Addr::unchecked("addr1"), &msg, &[], "Deposit", None)
+        .unwrap()
+}
+
+fn text_proposal() -> ExecuteMsg {
+    ExecuteMsg::Propose {
+        title: "Text".to_string(),
+        description: "Just agree".to_string(),
+        msgs: vec![],
+        latest: None,
+    }
+}
+
+fn token_balance(app: &App, addr: impl Into<String>) -> u128 {
+    app.wrap()
+        .query_balance(addr, "TOKEN")
+        .unwrap()
+        .amount
+        .u128()
+}
+
+#[test]
+fn proposal_deposit_refunded_on_execute() {
+    let mut app = mock_app();
+    let multisig = setup_with_deposit(&mut app, false);
+    let proposer = Addr::unchecked("addr1");
+
+    // the deposit must be paid exactly
+    let err = app
+        .execute_contract(proposer.clone(), multisig.clone(), &text_proposal(), &[])
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Deposit(DepositError::Payment(PaymentError::NoFunds {})),
+        err.downcast().unwrap()
+    );
+    let err = app
+        .execute_contract(
+            proposer.clone(),
+            multisig.clone(),
+            &text_proposal(),
+            &coins(5, "TOKEN"),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Deposit(DepositError::InvalidDeposit {}),
+        err.downcast().unwrap()
+    );
+
+    app.execute_contract(
+        proposer.clone(),
+        multisig.clone(),
+        &text_proposal(),
+        &coins(10, "TOKEN"),
+    )
+    .unwrap();
+    assert_eq!(token_balance(&app, &proposer), 10);
+    assert_eq!(token_balance(&app, &multisig), 10);
+
+    let vote: ExecuteMsg = ExecuteMsg::Vote {
+        proposal_id: 1,
+        vote: Vote::Yes,
+    };
+    app.execute_contract(Addr::unchecked("addr2"), multisig.clone(), &vote, &[])
+        .unwrap();
+
+    // executing (by anyone) returns the deposit to the proposer
+    let execution: ExecuteMsg = ExecuteMsg::Execute { proposal_id: 1 };
+    app.execute_contract(Addr::unchecked("addr3"), multisig.clone(), &execution, &[])
+        .unwrap();
+    assert_eq!(token_balance(&app, &proposer), 20);
+    assert_eq!(token_balance(&app, "addr3"), 20);
+    assert_eq!(token_balance(&app, &multisig), 0);
+
+    // and only once
+    app.execute_contract(Addr::unchecked("addr3"), multisig.clone(), &execution, &[])
+        .unwrap_err();
+    let close: ExecuteMsg = ExecuteMsg::Close { proposal_id: 1 };
+    app.execute_contract(Addr::unchecked("addr3"), multisig, &close, &[])
+        .unwrap_err();
+    assert_eq!(token_balance(&app, &proposer), 20);
+}
+
+#[test]
+fn proposal_deposit_on_close() {
+    for refund_failed_proposals in [true, false] {
+        let mut app = mock_app();
+        let multisig = setup_with_deposit(&mut app, refund_failed_proposals);
+        let proposer = Addr::unchecked("addr1");
+
+        app.execute_contract(
+            proposer.clone(),
+            multisig.clone(),
+            &text_proposal(),
+            &coins(10, "TOKEN"),
+        )
+        .unwrap();
+        assert_eq!(token_balance(&app, &proposer), 10);
+
+        // let it expire without passing
+        app.update_block(|b| b.height += 4);
+        let close: ExecuteMsg = ExecuteMsg::Close { proposal_id: 1 };
+        app.execute_contract(Addr::unchecked("addr2"), multisig.clone(), &close, &[])
+            .unwrap();
+
+        let (proposer_balance, multisig_balance) = match refund_failed_proposals {
+            true => (20, 0),
+            false => (10, 10),
+        };
+        assert_eq!(token_balance(&app, &proposer), proposer_balance);
+        assert_eq!(token_balance(&app, &multisig), multisig_balance);
+
+        // closing twice doesn't refund twice
+        let err = app
+            .execute_contract(Addr::unchecked("addr2"), multisig.clone(), &close, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+        assert_eq!(token_balance(&app, &proposer), proposer_balance);
+        assert_eq!(token_balance(&app, &multisig), multisig_balance);
+    }
+}
@@ -1,13 +1,27 @@
+use schemars::JsonSchema;
+
+use std::fmt;
+
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{CosmosMsg, Empty};
-use cw3::Vote;
+use cw3::{UncheckedDepositInfo, Vote};
 use cw_utils::{Duration, Expiration, Threshold};
 
+use crate::state::Executor;
+
 #[cw_serde]
 pub struct InstantiateMsg {
     pub voters: Vec<Voter>,
     pub threshold: Threshold,
     pub max_voting_period: Duration,
+    /// Voting period of proposals that don't set `latest`, defaults to `max_voting_period`.
+    /// Must use the same unit (height or time) and must not be longer.
+    pub default_voting_period: Option<Duration>,
+    // who is able to execute passed proposals
+    // None means that anyone can execute
+    pub executor: Option<Executor>,
+    /// The cost of creating a proposal (if any).
+    pub proposal_deposit: Option<UncheckedDepositInfo>,
 }
 
 #[cw_serde]
@@ -16,13 +30,16 @@ pub struct Voter {
     pub weight: u64,
 }
 
-// TODO: add some T variants? Maybe good enough as fixed Empty for now
+/// T is the type of custom messages proposals may contain, see `execute_custom`
 #[cw_serde]
-pub enum ExecuteMsg {
+pub enum ExecuteMsg<T = Empty>
+where
+    T: Clone + fmt::Debug + PartialEq + JsonSchema,
+{
     Propose {
         title: String,
         description: String,
-        msgs: Vec<CosmosMsg<Empty>>,
+        msgs: Vec<CosmosMsg<T>>,
         // note: we ignore API-spec'd earliest if passed, always opens immediately
         latest: Option<Expiration>,
     },
@@ -30,6 +47,11 @@ pub enum ExecuteMsg {
         proposal_id: u64,
         vote: Vote,
     },
+    /// Replaces the sender's earlier vote while the proposal can still be voted on
+    UpdateVote {
+        proposal_id: u64,
+        vote: Vote,
+    },
     Execute {
         proposal_id: u64,
     },
@@ -58,17 +80,41 @@ pub enum QueryMsg {
     },
     #[returns(cw3::VoteResponse)]
     Vote { proposal_id: u64, voter: String },
+    /// Lists the votes on a proposal by voter address.
+    /// limit defaults to 10 and is capped at 30.
     #[returns(cw3::VoteListResponse)]
     ListVotes {
         proposal_id: u64,
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Lists the votes of one voter by proposal id.
+    /// limit defaults to 10 and is capped at 30.
+    #[returns(cw3::VoteListResponse)]
+    ListVotesByVoter {
+        voter: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Weight of the given address, None if it is not a voter
     #[returns(cw3::VoterResponse)]
     Voter { address: String },
+    /// Lists the voters with their weights, ordered by address.
+    /// limit defaults to 10 and is capped at 30.
     #[returns(cw3::VoterListResponse)]
     ListVoters {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Total weight of all voters, like the cw4 `TotalWeight` query
+    #[returns(TotalWeightResponse)]
+    TotalWeight {},
+    /// Gets the current configuration.
+    #[returns(crate::state::Config)]
+    Config {},
+}
+
+#[cw_serde]
+pub struct TotalWeightResponse {
+    pub weight: u64,
 }
@@ -1,15 +1,74 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, StdResult, Storage};
+use cosmwasm_std::{Addr, Empty, StdResult, Storage};
 
-use cw3::{Ballot, Proposal};
-use cw_storage_plus::{Item, Map};
+use cw3::{Ballot, DepositInfo, Proposal};
+use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
 use cw_utils::{Duration, Threshold};
 
+use crate::error::ContractError;
+
+/// Defines who is able to execute proposals once passed
+#[cw_serde]
+pub enum Executor {
+    /// Any voter of the multisig, even with 0 weight
+    Member,
+    /// Only the given address
+    Only(Addr),
+}
+
 #[cw_serde]
 pub struct Config {
     pub threshold: Threshold,
     pub total_weight: u64,
     pub max_voting_period: Duration,
+    /// Voting period of proposals that don't set `latest`, `max_voting_period` if unset
+    #[serde(default)]
+    pub default_voting_period: Option<Duration>,
+    // who is able to execute passed proposals
+    // None means that anyone can execute
+    pub executor: Option<Executor>,
+    /// The price, if any, of creating a new proposal.
+    pub proposal_deposit: Option<DepositInfo>,
+}
+
+impl Config {
+    /// Checks that the default voting period uses the same unit as the max and is not longer.
+    pub fn validate_default_voting_period(&self) -> Result<(), ContractError> {
+        match (self.max_voting_period, self.default_voting_period) {
+            (_, None) => Ok(()),
+            (Duration::Height(max), Some(Duration::Height(default)))
+            | (Duration::Time(max), Some(Duration::Time(default))) => {
+                if default > max {
+                    Err(ContractError::VotingPeriodTooLong {})
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Err(ContractError::WrongVotingPeriod {}),
+        }
+    }
+
+    // Executor can be set in 3 ways:
+    // - Member: any voter of the multisig is authorized
+    // - Only: only passed address is authorized
+    // - None: Everyone are authorized
+    pub fn authorize(&self, storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+        if let Some(executor) = &self.executor {
+            match executor {
+                Executor::Member => {
+                    if VOTERS.may_load(storage, sender)?.is_none() {
+                        return Err(ContractError::Unauthorized {});
+                    }
+                }
+                Executor::Only(addr) => {
+                    if addr != sender {
+                        return Err(ContractError::Unauthorized {});
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 // unique items
@@ -18,13 +77,39 @@ pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
 
 // multiple-item map
 pub const BALLOTS: Map<(u64, &Addr), Ballot> = Map::new("votes");
-pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+// the same ballots keyed by voter first, to list the votes of one address
+pub const BALLOTS_BY_VOTER: Map<(&Addr, u64), Ballot> = Map::new("votes_by_voter");
+pub const PROPOSALS: Map<u64, Proposal> = proposals();
+
+// same storage as PROPOSALS, for proposals with custom messages of type T
+pub const fn proposals<'a, T>() -> Map<'a, u64, Proposal<T>> {
+    Map::new("proposals")
+}
+// proposals that were closed, so they cannot be closed or voted on again
+pub const CLOSED_PROPOSALS: Map<u64, Empty> = Map::new("closed_proposals");
 
 // multiple-item maps
-pub const VOTERS: Map<&Addr, u64> = Map::new("voters");
+// snapshotted, so votes can use the weights from the start of a proposal
+pub const VOTERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
+    "voters",
+    "voters__checkpoints",
+    "voters__changelog",
+    Strategy::EveryBlock,
+);
 
 pub fn next_id(store: &mut dyn Storage) -> StdResult<u64> {
     let id: u64 = PROPOSAL_COUNT.may_load(store)?.unwrap_or_default() + 1;
     PROPOSAL_COUNT.save(store, &id)?;
     Ok(id)
 }
+
+/// Stores the ballot under both BALLOTS and BALLOTS_BY_VOTER
+pub fn save_ballot(
+    store: &mut dyn Storage,
+    proposal_id: u64,
+    voter: &Addr,
+    ballot: &Ballot,
+) -> StdResult<()> {
+    BALLOTS.save(store, (proposal_id, voter), ballot)?;
+    BALLOTS_BY_VOTER.save(store, (voter, proposal_id), ballot)
+}
@@ -3,8 +3,8 @@ use std::cmp::Ordering;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
-    Response, StdResult,
+    to_binary, Addr, Binary, BlockInfo, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order,
+    QuerierWrapper, Response, StdResult, Storage,
 };
 
 use cw2::set_contract_version;
@@ -13,14 +13,17 @@ use cw3::{
     Ballot, Proposal, ProposalListResponse, ProposalResponse, Status, Vote, VoteInfo,
     VoteListResponse, VoteResponse, VoterDetail, VoterListResponse, VoterResponse, Votes,
 };
-use cw3_fixed_multisig::state::{next_id, BALLOTS, PROPOSALS};
+use cw3_fixed_multisig::state::{next_id, BALLOTS};
 use cw4::{Cw4Contract, MemberChangedHookMsg, MemberDiff};
 use cw_storage_plus::Bound;
-use cw_utils::{maybe_addr, Expiration, ThresholdResponse};
+use cw_utils::{maybe_addr, Duration, Expiration, ThresholdResponse};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{Config, CONFIG};
+use crate::msg::{DelegateResponse, ExecutableAtResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::state::{
+    proposals, Config, BALLOT_DELEGATES, CONFIG, DELEGATED_BALLOTS, DELEGATES, DELEGATORS,
+    PASSED_AT, REMOVED_VOTERS, VOTER_PROPOSALS,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw3-flex-multisig";
@@ -46,15 +49,31 @@ pub fn instantiate(
         .map(|deposit| deposit.into_checked(deps.as_ref()))
         .transpose()?;
 
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-
     let cfg = Config {
         threshold: msg.threshold,
         max_voting_period: msg.max_voting_period,
+        min_voting_period: msg.min_voting_period,
         group_addr,
         executor: msg.executor,
         proposal_deposit,
+        timelock: msg.timelock,
     };
+    // the minimum itself must fit within the maximum
+    if let Some(min) = cfg.min_voting_period {
+        cfg.validate_voting_period(min)?;
+    }
+    if let Some(timelock) = &cfg.timelock {
+        match (timelock.duration, cfg.max_voting_period) {
+            (Duration::Height(_), Duration::Height(_)) | (Duration::Time(_), Duration::Time(_)) => {
+            }
+            _ => return Err(ContractError::WrongTimelock {}),
+        }
+        for vetoer in &timelock.vetoers {
+            deps.api.addr_validate(vetoer.as_str())?;
+        }
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     CONFIG.save(deps.storage, &cfg)?;
 
     Ok(Response::default())
@@ -73,16 +92,30 @@ pub fn execute(
             description,
             msgs,
             latest,
-        } => execute_propose(deps, env, info, title, description, msgs, latest),
+            voting_period,
+        } => execute_propose(
+            deps,
+            env,
+            info,
+            title,
+            description,
+            msgs,
+            latest,
+            voting_period,
+        ),
         ExecuteMsg::Vote { proposal_id, vote } => execute_vote(deps, env, info, proposal_id, vote),
         ExecuteMsg::Execute { proposal_id } => execute_execute(deps, env, info, proposal_id),
         ExecuteMsg::Close { proposal_id } => execute_close(deps, env, info, proposal_id),
-        ExecuteMsg::MemberChangedHook(MemberChangedHookMsg { diffs }) => {
-            execute_membership_hook(deps, env, info, diffs)
+        ExecuteMsg::Veto { proposal_id } => execute_veto(deps, env, info, proposal_id),
+        ExecuteMsg::Finalize { proposal_id } => execute_finalize(deps, env, info, proposal_id),
+        ExecuteMsg::SetDelegate { delegate } => execute_set_delegate(deps, info, delegate),
+        ExecuteMsg::MemberChangedHook(MemberChangedHookMsg { diffs, height }) => {
+            execute_membership_hook(deps, env, info, diffs, height)
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_propose(
     deps: DepsMut,
     env: Env,
@@ -92,6 +125,7 @@ pub fn execute_propose(
     msgs: Vec<CosmosMsg>,
     // we ignore earliest
     latest: Option<Expiration>,
+    voting_period: Option<Duration>,
 ) -> Result<Response<Empty>, ContractError> {
     // only members of the multisig can create a proposal
     let cfg = CONFIG.load(deps.storage)?;
@@ -111,8 +145,17 @@ pub fn execute_propose(
         .is_member(&deps.querier, &info.sender, None)?
         .ok_or(ContractError::Unauthorized {})?;
 
+    // requested voting period must lie within the configured bounds
+    let voting_period = match voting_period {
+        Some(period) => {
+            cfg.validate_voting_period(period)?;
+            period
+        }
+        None => cfg.max_voting_period,
+    };
+
     // max expires also used as default
-    let max_expires = cfg.max_voting_period.after(&env.block);
+    let max_expires = voting_period.after(&env.block);
     let mut expires = latest.unwrap_or(max_expires);
     let comp = expires.partial_cmp(&max_expires);
     if let Some(Ordering::Greater) = comp {
@@ -144,9 +187,7 @@ pub fn execute_propose(
         proposer: info.sender.clone(),
         deposit: cfg.proposal_deposit,
     };
-    prop.update_status(&env.block);
     let id = next_id(deps.storage)?;
-    PROPOSALS.save(deps.storage, id, &prop)?;
 
     // add the first yes vote from voter
     let ballot = Ballot {
@@ -154,6 +195,26 @@ pub fn execute_propose(
         vote: Vote::Yes,
     };
     BALLOTS.save(deps.storage, (id, &info.sender), &ballot)?;
+    VOTER_PROPOSALS.save(deps.storage, (&info.sender, id), &Empty {})?;
+
+    // the proposer's delegators vote along with them
+    cast_delegated_votes(
+        deps.storage,
+        &deps.querier,
+        &cfg.group_addr,
+        id,
+        &mut prop,
+        &info.sender,
+        Vote::Yes,
+    )?;
+    prop.update_status(&env.block);
+    proposals().save(deps.storage, id, &prop)?;
+    if prop.status != Status::Open {
+        forget_voters(deps.storage, id)?;
+    }
+    if prop.status == Status::Passed {
+        PASSED_AT.save(deps.storage, id, &env.block)?;
+    }
 
     Ok(Response::new()
         .add_messages(take_deposit_msg)
@@ -174,11 +235,12 @@ pub fn execute_vote(
     let cfg = CONFIG.load(deps.storage)?;
 
     // ensure proposal exists and can be voted on
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let mut prop = proposals().load(deps.storage, proposal_id)?;
     // Allow voting on Passed and Rejected proposals too,
     if ![Status::Open, Status::Passed, Status::Rejected].contains(&prop.status) {
         return Err(ContractError::NotOpen {});
     }
+    let was_open = prop.status == Status::Open;
     // if they are not expired
     if prop.expires.is_expired(&env.block) {
         return Err(ContractError::Expired {});
@@ -191,20 +253,56 @@ pub fn execute_vote(
         .group_addr
         .is_voting_member(&deps.querier, &info.sender, prop.start_height)?
         .ok_or(ContractError::Unauthorized {})?;
+    // members removed from the group after the proposal started can no longer vote on it
+    if let Some(removed_at) = REMOVED_VOTERS.may_load(deps.storage, &info.sender)? {
+        if removed_at >= prop.start_height {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
 
-    // cast vote if no vote previously cast
-    BALLOTS.update(deps.storage, (proposal_id, &info.sender), |bal| match bal {
-        Some(_) => Err(ContractError::AlreadyVoted {}),
-        None => Ok(Ballot {
-            weight: vote_power,
-            vote,
-        }),
-    })?;
+    // cast vote if no vote previously cast. A ballot cast by a delegate
+    // is replaced by the voter's own vote, as long as the proposal is still open.
+    // Once it passed or got rejected, taking back a delegated vote cannot change the outcome.
+    let key = (proposal_id, &info.sender);
+    if let Some(previous) = BALLOTS.may_load(deps.storage, key)? {
+        let delegate = BALLOT_DELEGATES
+            .may_load(deps.storage, key)?
+            .ok_or(ContractError::AlreadyVoted {})?;
+        if !was_open {
+            return Err(ContractError::NotOpen {});
+        }
+        prop.votes.remove_vote(previous.vote, previous.weight);
+        BALLOT_DELEGATES.remove(deps.storage, key);
+        DELEGATED_BALLOTS.remove(deps.storage, (&delegate, proposal_id, &info.sender));
+    }
+    let ballot = Ballot {
+        weight: vote_power,
+        vote,
+    };
+    BALLOTS.save(deps.storage, key, &ballot)?;
+    if was_open {
+        VOTER_PROPOSALS.save(deps.storage, (&info.sender, proposal_id), &Empty {})?;
+    }
 
     // update vote tally
     prop.votes.add_vote(vote, vote_power);
+    cast_delegated_votes(
+        deps.storage,
+        &deps.querier,
+        &cfg.group_addr,
+        proposal_id,
+        &mut prop,
+        &info.sender,
+        vote,
+    )?;
     prop.update_status(&env.block);
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    proposals().save(deps.storage, proposal_id, &prop)?;
+    if was_open && prop.status != Status::Open {
+        forget_voters(deps.storage, proposal_id)?;
+    }
+    if prop.status == Status::Passed && !PASSED_AT.has(deps.storage, proposal_id) {
+        PASSED_AT.save(deps.storage, proposal_id, &env.block)?;
+    }
 
     Ok(Response::new()
         .add_attribute("action", "vote")
@@ -213,13 +311,129 @@ pub fn execute_vote(
         .add_attribute("status", format!("{:?}", prop.status)))
 }
 
+/// Casts `vote` on behalf of every delegator of `delegate` who has not voted on the
+/// proposal yet, with their weight at the start of the proposal.
+fn cast_delegated_votes(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    group: &Cw4Contract,
+    proposal_id: u64,
+    prop: &mut Proposal,
+    delegate: &Addr,
+    vote: Vote,
+) -> Result<(), ContractError> {
+    let delegators = DELEGATORS
+        .prefix(delegate)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for delegator in delegators {
+        let key = (proposal_id, &delegator);
+        if BALLOTS.has(storage, key) {
+            continue;
+        }
+        if let Some(removed_at) = REMOVED_VOTERS.may_load(storage, &delegator)? {
+            if removed_at >= prop.start_height {
+                continue;
+            }
+        }
+        let weight = match group.is_voting_member(querier, &delegator, prop.start_height)? {
+            Some(weight) if weight > 0 => weight,
+            _ => continue,
+        };
+
+        let ballot = Ballot { weight, vote };
+        BALLOTS.save(storage, key, &ballot)?;
+        BALLOT_DELEGATES.save(storage, key, delegate)?;
+        // only open proposals are adjusted on membership changes
+        if prop.status == Status::Open {
+            VOTER_PROPOSALS.save(storage, (&delegator, proposal_id), &Empty {})?;
+            DELEGATED_BALLOTS.save(storage, (delegate, proposal_id, &delegator), &Empty {})?;
+        }
+        prop.votes.add_vote(vote, weight);
+    }
+    Ok(())
+}
+
+/// Drops the VOTER_PROPOSALS and DELEGATED_BALLOTS entries of a proposal that is no longer
+/// open. Membership changes only adjust open proposals, so they are not needed anymore.
+fn forget_voters(storage: &mut dyn Storage, proposal_id: u64) -> StdResult<()> {
+    let voters = BALLOTS
+        .prefix(proposal_id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in voters {
+        VOTER_PROPOSALS.remove(storage, (&voter, proposal_id));
+    }
+    let delegated = BALLOT_DELEGATES
+        .prefix(proposal_id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (voter, delegate) in delegated {
+        DELEGATED_BALLOTS.remove(storage, (&delegate, proposal_id, &voter));
+    }
+    Ok(())
+}
+
+/// Sets the standing delegate of the sender. Delegation is only one level deep:
+/// delegates cannot delegate themselves, nor can delegators be delegated to.
+pub fn execute_set_delegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    delegate: Option<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    cfg.group_addr
+        .is_member(&deps.querier, &info.sender, None)?
+        .ok_or(ContractError::Unauthorized {})?;
+
+    let delegate = delegate
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    if let Some(delegate) = &delegate {
+        if delegate == &info.sender {
+            return Err(ContractError::SelfDelegation {});
+        }
+        cfg.group_addr
+            .is_member(&deps.querier, delegate, None)?
+            .ok_or(ContractError::DelegateNotMember {})?;
+        let has_delegators = DELEGATORS
+            .prefix(&info.sender)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        if DELEGATES.has(deps.storage, delegate) || has_delegators {
+            return Err(ContractError::DelegationChain {});
+        }
+    }
+
+    if let Some(previous) = DELEGATES.may_load(deps.storage, &info.sender)? {
+        DELEGATORS.remove(deps.storage, (&previous, &info.sender));
+    }
+    match &delegate {
+        Some(delegate) => {
+            DELEGATES.save(deps.storage, &info.sender, delegate)?;
+            DELEGATORS.save(deps.storage, (delegate, &info.sender), &Empty {})?;
+        }
+        None => DELEGATES.remove(deps.storage, &info.sender),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_delegate")
+        .add_attribute("sender", info.sender)
+        .add_attribute(
+            "delegate",
+            delegate.map_or_else(|| "none".to_string(), |d| d.to_string()),
+        ))
+}
+
 pub fn execute_execute(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     proposal_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
+    let mut prop = proposals().load(deps.storage, proposal_id)?;
+    let was_open = prop.status == Status::Open;
     // we allow execution even after the proposal "expiration" as long as all vote come in before
     // that point. If it was approved on time, it can be executed any time.
     prop.update_status(&env.block);
@@ -230,9 +444,23 @@ pub fn execute_execute(
     let cfg = CONFIG.load(deps.storage)?;
     cfg.authorize(&deps.querier, &info.sender)?;
 
-    // set it to executed
-    prop.status = Status::Executed;
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    // passed proposals must wait for the timelock (if any) to elapse
+    if let Some(executable_at) = cfg.executable_at(deps.storage, proposal_id, &prop)? {
+        if !executable_at.is_expired(&env.block) {
+            return Err(ContractError::TimelockNotElapsed {});
+        }
+    }
+
+    // set it to executed, or completed if there is nothing to execute
+    prop.status = if prop.msgs.is_empty() {
+        Status::Completed
+    } else {
+        Status::Executed
+    };
+    proposals().save(deps.storage, proposal_id, &prop)?;
+    if was_open {
+        forget_voters(deps.storage, proposal_id)?;
+    }
 
     // Unconditionally refund here.
     let response = match prop.deposit {
@@ -250,6 +478,50 @@ pub fn execute_execute(
         .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
+/// Completes a passed signaling proposal (one without messages). Unlike execute, anyone
+/// can call this, as no messages are dispatched on behalf of the multisig.
+pub fn execute_finalize(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut prop = proposals().load(deps.storage, proposal_id)?;
+    if !prop.msgs.is_empty() {
+        return Err(ContractError::CannotFinalize {});
+    }
+    let was_open = prop.status == Status::Open;
+    prop.update_status(&env.block);
+    if prop.status != Status::Passed {
+        return Err(ContractError::WrongExecuteStatus {});
+    }
+
+    // vetoers get the same chance as on proposals with messages
+    let cfg = CONFIG.load(deps.storage)?;
+    if let Some(executable_at) = cfg.executable_at(deps.storage, proposal_id, &prop)? {
+        if !executable_at.is_expired(&env.block) {
+            return Err(ContractError::TimelockNotElapsed {});
+        }
+    }
+
+    prop.status = Status::Completed;
+    proposals().save(deps.storage, proposal_id, &prop)?;
+    if was_open {
+        forget_voters(deps.storage, proposal_id)?;
+    }
+
+    let response = match prop.deposit {
+        Some(deposit) => {
+            Response::new().add_message(deposit.get_return_deposit_message(&prop.proposer)?)
+        }
+        None => Response::new(),
+    };
+    Ok(response
+        .add_attribute("action", "finalize")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
 pub fn execute_close(
     deps: DepsMut,
     env: Env,
@@ -258,8 +530,23 @@ pub fn execute_close(
 ) -> Result<Response<Empty>, ContractError> {
     // anyone can trigger this if the vote passed
 
-    let mut prop = PROPOSALS.load(deps.storage, proposal_id)?;
-    if [Status::Executed, Status::Rejected, Status::Passed].contains(&prop.status) {
+    let mut prop = proposals().load(deps.storage, proposal_id)?;
+    // vetoed proposals can be closed right away. They are already final and their deposit
+    // was settled by the veto, so they stay vetoed
+    if prop.status == Status::Vetoed {
+        return Ok(Response::new()
+            .add_attribute("action", "close")
+            .add_attribute("sender", info.sender)
+            .add_attribute("proposal_id", proposal_id.to_string()));
+    }
+    if [
+        Status::Executed,
+        Status::Completed,
+        Status::Rejected,
+        Status::Passed,
+    ]
+    .contains(&prop.status)
+    {
         return Err(ContractError::WrongCloseStatus {});
     }
     // Avoid closing of Passed due to expiration proposals
@@ -270,9 +557,13 @@ pub fn execute_close(
         return Err(ContractError::NotExpired {});
     }
 
+    let was_open = prop.status == Status::Open;
     // set it to failed
     prop.status = Status::Rejected;
-    PROPOSALS.save(deps.storage, proposal_id, &prop)?;
+    proposals().save(deps.storage, proposal_id, &prop)?;
+    if was_open {
+        forget_voters(deps.storage, proposal_id)?;
+    }
 
     // Refund the deposit if we have been configured to do so.
     let mut response = Response::new();
@@ -288,20 +579,149 @@ pub fn execute_close(
         .add_attribute("proposal_id", proposal_id.to_string()))
 }
 
+pub fn execute_veto(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response<Empty>, ContractError> {
+    // only the configured vetoers can veto
+    let cfg = CONFIG.load(deps.storage)?;
+    match &cfg.timelock {
+        Some(timelock) if timelock.vetoers.contains(&info.sender) => {}
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+
+    let mut prop = proposals().load(deps.storage, proposal_id)?;
+    let was_open = prop.status == Status::Open;
+    prop.update_status(&env.block);
+    if prop.status != Status::Passed {
+        return Err(ContractError::WrongVetoStatus {});
+    }
+    if let Some(executable_at) = cfg.executable_at(deps.storage, proposal_id, &prop)? {
+        if executable_at.is_expired(&env.block) {
+            return Err(ContractError::WrongVetoStatus {});
+        }
+    }
+
+    prop.status = Status::Vetoed;
+    proposals().save(deps.storage, proposal_id, &prop)?;
+    if was_open {
+        forget_voters(deps.storage, proposal_id)?;
+    }
+
+    // a vetoed proposal failed, so the deposit is refunded like for rejected proposals
+    let mut response = Response::new();
+    if let Some(deposit) = prop.deposit {
+        if deposit.refund_failed_proposals {
+            response = response.add_message(deposit.get_return_deposit_message(&prop.proposer)?)
+        }
+    }
+
+    Ok(response
+        .add_attribute("action", "veto")
+        .add_attribute("sender", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string()))
+}
+
 pub fn execute_membership_hook(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    _diffs: Vec<MemberDiff>,
+    diffs: Vec<MemberDiff>,
+    height: Option<u64>,
 ) -> Result<Response<Empty>, ContractError> {
-    // This is now a no-op
-    // But we leave the authorization check as a demo
+    // only the group contract can report membership changes
     let cfg = CONFIG.load(deps.storage)?;
     if info.sender != cfg.group_addr.0 {
         return Err(ContractError::Unauthorized {});
     }
 
-    Ok(Response::default())
+    // Weight changes are ignored, as votes use the weight snapshot from the start of
+    // the proposal. Removed members have their ballots excluded from open proposals.
+    // The removal is recorded at the height the group applied it, if it reports one.
+    let removed_at = height.unwrap_or(env.block.height);
+    let mut adjusted = vec![];
+    for diff in diffs {
+        let voter = deps.api.addr_validate(&diff.key)?;
+        if diff.new.is_some() {
+            REMOVED_VOTERS.remove(deps.storage, &voter);
+            continue;
+        }
+        REMOVED_VOTERS.save(deps.storage, &voter, &removed_at)?;
+
+        let proposal_ids = VOTER_PROPOSALS
+            .prefix(&voter)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for proposal_id in proposal_ids {
+            VOTER_PROPOSALS.remove(deps.storage, (&voter, proposal_id));
+
+            let mut prop = proposals().load(deps.storage, proposal_id)?;
+            prop.update_status(&env.block);
+            if prop.status != Status::Open {
+                continue;
+            }
+            // keep the ballot (with no weight), so the voter cannot vote again
+            let key = (proposal_id, &voter);
+            let mut ballot = BALLOTS.load(deps.storage, key)?;
+            prop.votes.remove_vote(ballot.vote, ballot.weight);
+            ballot.weight = 0;
+            BALLOTS.save(deps.storage, key, &ballot)?;
+
+            prop.update_status(&env.block);
+            proposals().save(deps.storage, proposal_id, &prop)?;
+            if prop.status != Status::Open {
+                forget_voters(deps.storage, proposal_id)?;
+            }
+            adjusted.push(proposal_id);
+        }
+
+        // ballots the removed member cast as a delegate are dropped from open proposals,
+        // so their delegators can still vote themselves
+        let delegated = DELEGATED_BALLOTS
+            .sub_prefix(&voter)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (proposal_id, delegator) in delegated {
+            DELEGATED_BALLOTS.remove(deps.storage, (&voter, proposal_id, &delegator));
+
+            let mut prop = proposals().load(deps.storage, proposal_id)?;
+            prop.update_status(&env.block);
+            if prop.status != Status::Open {
+                continue;
+            }
+            let key = (proposal_id, &delegator);
+            let ballot = BALLOTS.load(deps.storage, key)?;
+            prop.votes.remove_vote(ballot.vote, ballot.weight);
+            BALLOTS.remove(deps.storage, key);
+            BALLOT_DELEGATES.remove(deps.storage, key);
+            VOTER_PROPOSALS.remove(deps.storage, (&delegator, proposal_id));
+
+            prop.update_status(&env.block);
+            proposals().save(deps.storage, proposal_id, &prop)?;
+            if prop.status != Status::Open {
+                forget_voters(deps.storage, proposal_id)?;
+            }
+            adjusted.push(proposal_id);
+        }
+    }
+    adjusted.sort_unstable();
+    adjusted.dedup();
+
+    // empty attribute values are rejected, which would fail the group update
+    let adjusted = if adjusted.is_empty() {
+        "none".to_string()
+    } else {
+        adjusted
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    Ok(Response::new()
+        .add_attribute("action", "member_changed_hook")
+        .add_attribute("adjusted_proposals", adjusted))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -310,13 +730,16 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Threshold {} => to_binary(&query_threshold(deps)?),
         QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, env, proposal_id)?),
         QueryMsg::Vote { proposal_id, voter } => to_binary(&query_vote(deps, proposal_id, voter)?),
-        QueryMsg::ListProposals { start_after, limit } => {
-            to_binary(&list_proposals(deps, env, start_after, limit)?)
-        }
+        QueryMsg::ListProposals {
+            start_after,
+            limit,
+            status,
+        } => to_binary(&list_proposals(deps, env, start_after, limit, status)?),
         QueryMsg::ReverseProposals {
             start_before,
             limit,
-        } => to_binary(&reverse_proposals(deps, env, start_before, limit)?),
+            status,
+        } => to_binary(&reverse_proposals(deps, env, start_before, limit, status)?),
         QueryMsg::ListVotes {
             proposal_id,
             start_after,
@@ -327,6 +750,13 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             to_binary(&list_voters(deps, start_after, limit)?)
         }
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::ExecutableAt { proposal_id } => {
+            to_binary(&query_executable_at(deps, env, proposal_id)?)
+        }
+        QueryMsg::Delegate { address } => to_binary(&query_delegate(deps, address)?),
+        QueryMsg::VoteDelegate { proposal_id, voter } => {
+            to_binary(&query_vote_delegate(deps, proposal_id, voter)?)
+        }
     }
 }
 
@@ -340,8 +770,35 @@ fn query_config(deps: Deps) -> StdResult<Config> {
     CONFIG.load(deps.storage)
 }
 
+fn query_executable_at(deps: Deps, env: Env, id: u64) -> StdResult<ExecutableAtResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let prop = proposals().load(deps.storage, id)?;
+    let executable_at = if prop.current_status(&env.block) == Status::Passed {
+        cfg.executable_at(deps.storage, id, &prop)?
+    } else {
+        None
+    };
+    Ok(ExecutableAtResponse { executable_at })
+}
+
+fn query_delegate(deps: Deps, address: String) -> StdResult<DelegateResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let delegate = DELEGATES
+        .may_load(deps.storage, &address)?
+        .map(String::from);
+    Ok(DelegateResponse { delegate })
+}
+
+fn query_vote_delegate(deps: Deps, proposal_id: u64, voter: String) -> StdResult<DelegateResponse> {
+    let voter = deps.api.addr_validate(&voter)?;
+    let delegate = BALLOT_DELEGATES
+        .may_load(deps.storage, (proposal_id, &voter))?
+        .map(String::from);
+    Ok(DelegateResponse { delegate })
+}
+
 fn query_proposal(deps: Deps, env: Env, id: u64) -> StdResult<ProposalResponse> {
-    let prop = PROPOSALS.load(deps.storage, id)?;
+    let prop = proposals().load(deps.storage, id)?;
     let status = prop.current_status(&env.block);
     let threshold = prop.threshold.to_response(prop.total_weight);
     Ok(ProposalResponse {
@@ -366,13 +823,14 @@ fn list_proposals(
     env: Env,
     start_after: Option<u64>,
     limit: Option<u32>,
+    status: Option<Status>,
 ) -> StdResult<ProposalListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
-    let proposals = PROPOSALS
-        .range(deps.storage, start, None, Order::Ascending)
-        .take(limit)
+    let proposals = range_proposals(deps.storage, status, start, None, Order::Ascending)
         .map(|p| map_proposal(&env.block, p))
+        .filter(|p| matches_status(p, status))
+        .take(limit)
         .collect::<StdResult<_>>()?;
 
     Ok(ProposalListResponse { proposals })
@@ -383,18 +841,46 @@ fn reverse_proposals(
     env: Env,
     start_before: Option<u64>,
     limit: Option<u32>,
+    status: Option<Status>,
 ) -> StdResult<ProposalListResponse> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let end = start_before.map(Bound::exclusive);
-    let props: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, None, end, Order::Descending)
-        .take(limit)
-        .map(|p| map_proposal(&env.block, p))
-        .collect();
+    let props: StdResult<Vec<_>> =
+        range_proposals(deps.storage, status, None, end, Order::Descending)
+            .map(|p| map_proposal(&env.block, p))
+            .filter(|p| matches_status(p, status))
+            .take(limit)
+            .collect();
 
     Ok(ProposalListResponse { proposals: props? })
 }
 
+// Iterates over the proposals with the given stored status, or over all of them
+fn range_proposals<'a>(
+    storage: &'a dyn Storage,
+    status: Option<Status>,
+    min: Option<Bound<'a, u64>>,
+    max: Option<Bound<'a, u64>>,
+    order: Order,
+) -> Box<dyn Iterator<Item = StdResult<(u64, Proposal)>> + 'a> {
+    match status {
+        Some(status) => proposals()
+            .idx
+            .status
+            .prefix(status as u8)
+            .range(storage, min, max, order),
+        None => proposals().range(storage, min, max, order),
+    }
+}
+
+// The stored status may be stale (e.g. after expiration), so we check the current one
+fn matches_status(prop: &StdResult<ProposalResponse>, status: Option<Status>) -> bool {
+    match (prop, status) {
+        (Ok(prop), Some(status)) => prop.status == status,
+        _ => true,
+    }
+}
+
 fn map_proposal(
     block: &BlockInfo,
     item: StdResult<(u64, Proposal)>,
@@ -483,7 +969,7 @@ fn list_voters(
 
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::{coin, coins, Addr, BankMsg, Coin, Decimal, Timestamp, Uint128};
+    use cosmwasm_std::{coin, coins, Addr, BankMsg, Coin, Decimal, Record, Timestamp, Uint128};
 
     use cw2::{query_contract_info, ContractVersion};
     use cw20::{Cw20Coin, UncheckedDenom};
@@ -493,9 +979,10 @@ mod tests {
     use cw_multi_test::{
         next_block, App, AppBuilder, BankSudo, Contract, ContractWrapper, Executor, SudoMsg,
     };
-    use cw_utils::{Duration, Threshold};
+    use cw_utils::Threshold;
 
     use super::*;
+    use crate::state::Timelock;
 
     const OWNER: &str = "admin0001";
     const VOTER1: &str = "voter0001";
@@ -554,6 +1041,8 @@ mod tests {
         let msg = cw4_group::msg::InstantiateMsg {
             admin: Some(OWNER.into()),
             members,
+            isolate_hooks: None,
+            hook_height: None,
         };
         app.instantiate_contract(group_id, Addr::unchecked(OWNER), &msg, &[], "group", None)
             .unwrap()
@@ -573,6 +1062,8 @@ mod tests {
             group_addr: group.to_string(),
             threshold,
             max_voting_period,
+            min_voting_period: None,
+            timelock: None,
             executor,
             proposal_deposit,
         };
@@ -678,6 +1169,7 @@ mod tests {
             description,
             msgs,
             latest: None,
+            voting_period: None,
         }
     }
 
@@ -688,6 +1180,7 @@ mod tests {
             description,
             msgs: vec![],
             latest: None,
+            voting_period: None,
         }
     }
 
@@ -709,6 +1202,8 @@ mod tests {
                 quorum: Decimal::percent(1),
             },
             max_voting_period,
+            min_voting_period: None,
+            timelock: None,
             executor: None,
             proposal_deposit: None,
         };
@@ -732,6 +1227,8 @@ mod tests {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 100 },
             max_voting_period,
+            min_voting_period: None,
+            timelock: None,
             executor: None,
             proposal_deposit: None,
         };
@@ -755,6 +1252,8 @@ mod tests {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 1 },
             max_voting_period,
+            min_voting_period: None,
+            timelock: None,
             executor: None,
             proposal_deposit: None,
         };
@@ -775,6 +1274,7 @@ mod tests {
             ContractVersion {
                 contract: CONTRACT_NAME.to_string(),
                 version: CONTRACT_VERSION.to_string(),
+                commit_hash: None,
             },
             version,
         );
@@ -826,6 +1326,7 @@ mod tests {
             description: "Do we reward her?".to_string(),
             msgs,
             latest: Some(Expiration::AtHeight(123456)),
+            voting_period: None,
         };
         let err = app
             .execute_contract(
@@ -866,6 +1367,129 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_propose_voting_period_bounds() {
+        let mut app = mock_app(&[]);
+
+        let group_addr = instantiate_group(&mut app, vec![member(OWNER, 1), member(VOTER1, 1)]);
+        let flex_id = app.store_code(contract_flex());
+        let mut instantiate_msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 2 },
+            max_voting_period: Duration::Time(1000),
+            min_voting_period: Some(Duration::Time(2000)),
+            timelock: None,
+            executor: None,
+            proposal_deposit: None,
+        };
+
+        // minimum above maximum fails
+        let err = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg,
+                &[],
+                "min above max",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(
+            ContractError::VotingPeriodTooLong {},
+            err.downcast().unwrap()
+        );
+
+        // bounds in different units fail
+        instantiate_msg.min_voting_period = Some(Duration::Height(10));
+        let err = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg,
+                &[],
+                "mixed units",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongVotingPeriod {}, err.downcast().unwrap());
+
+        instantiate_msg.min_voting_period = Some(Duration::Time(100));
+        let flex_addr = app
+            .instantiate_contract(
+                flex_id,
+                Addr::unchecked(OWNER),
+                &instantiate_msg,
+                &[],
+                "flex",
+                None,
+            )
+            .unwrap();
+
+        let config: Config = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Config {})
+            .unwrap();
+        assert_eq!(config.min_voting_period, Some(Duration::Time(100)));
+        assert_eq!(config.max_voting_period, Duration::Time(1000));
+
+        let propose = |voting_period| {
+            let (msgs, title, description) = proposal_info();
+            ExecuteMsg::Propose {
+                title,
+                description,
+                msgs,
+                latest: None,
+                voting_period,
+            }
+        };
+
+        let cases = [
+            (Duration::Time(50), ContractError::VotingPeriodTooShort {}),
+            (Duration::Time(2000), ContractError::VotingPeriodTooLong {}),
+            (Duration::Height(500), ContractError::WrongVotingPeriod {}),
+        ];
+        for (period, expected) in cases {
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OWNER),
+                    flex_addr.clone(),
+                    &propose(Some(period)),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(expected, err.downcast().unwrap());
+        }
+
+        // a period within bounds sets the expiration
+        let now = app.block_info().time;
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            flex_addr.clone(),
+            &propose(Some(Duration::Time(500))),
+            &[],
+        )
+        .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 1 })
+            .unwrap();
+        assert_eq!(prop.expires, Expiration::AtTime(now.plus_seconds(500)));
+
+        // no period falls back to the maximum
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            flex_addr.clone(),
+            &propose(None),
+            &[],
+        )
+        .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id: 2 })
+            .unwrap();
+        assert_eq!(prop.expires, Expiration::AtTime(now.plus_seconds(1000)));
+    }
+
     fn get_tally(app: &App, flex_addr: &str, proposal_id: u64) -> u64 {
         // Get all the voters on the proposal
         let voters = QueryMsg::ListVotes {
@@ -955,6 +1579,7 @@ mod tests {
         let list_query = QueryMsg::ListProposals {
             start_after: None,
             limit: None,
+            status: None,
         };
         let res: ProposalListResponse = app
             .wrap()
@@ -983,6 +1608,7 @@ mod tests {
         let list_query = QueryMsg::ReverseProposals {
             start_before: None,
             limit: Some(1),
+            status: None,
         };
         let res: ProposalListResponse = app
             .wrap()
@@ -1010,9 +1636,290 @@ mod tests {
     }
 
     #[test]
-    fn test_vote_works() {
-        let init_funds = coins(10, "BTC");
-        let mut app = mock_app(&init_funds);
+    fn fixed_and_flex_share_proposal_shape() {
+        let mut app = mock_app(&coins(20, "TOKEN"));
+        let deposit = UncheckedDepositInfo {
+            amount: Uint128::new(10),
+            denom: UncheckedDenom::Native("TOKEN".to_string()),
+            refund_failed_proposals: true,
+        };
+        let threshold = Threshold::AbsoluteCount { weight: 3 };
+        let voting_period = Duration::Time(2000000);
+
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            threshold.clone(),
+            voting_period,
+            vec![],
+            false,
+            None,
+            Some(deposit.clone()),
+        );
+
+        // fixed multisig with the same voters and rules, created in the same block
+        let fixed_id = app.store_code(Box::new(ContractWrapper::new(
+            cw3_fixed_multisig::contract::execute,
+            cw3_fixed_multisig::contract::instantiate,
+            cw3_fixed_multisig::contract::query,
+        )));
+        let voters = [
+            (OWNER, 0),
+            (VOTER1, 1),
+            (VOTER2, 2),
+            (VOTER3, 3),
+            (VOTER4, 12),
+            (VOTER5, 5),
+        ]
+        .iter()
+        .map(|&(addr, weight)| cw3_fixed_multisig::msg::Voter {
+            addr: addr.into(),
+            weight,
+        })
+        .collect();
+        let msg = cw3_fixed_multisig::msg::InstantiateMsg {
+            voters,
+            threshold,
+            max_voting_period: voting_period,
+            default_voting_period: None,
+            executor: None,
+            proposal_deposit: Some(deposit),
+        };
+        let fixed_addr = app
+            .instantiate_contract(fixed_id, Addr::unchecked(OWNER), &msg, &[], "fixed", None)
+            .unwrap();
+
+        // the same proposal made to both
+        let (msgs, title, description) = proposal_info();
+        let funds = coins(10, "TOKEN");
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            flex_addr.clone(),
+            &pay_somebody_proposal(),
+            &funds,
+        )
+        .unwrap();
+        let proposal = cw3_fixed_multisig::msg::ExecuteMsg::Propose {
+            title,
+            description,
+            msgs: msgs.clone(),
+            latest: None,
+        };
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            fixed_addr.clone(),
+            &proposal,
+            &funds,
+        )
+        .unwrap();
+
+        // both answer the generic cw3 query with the same shape
+        let query = cw3::Cw3QueryMsg::Proposal { proposal_id: 1 };
+        let from_flex: ProposalResponse = app.wrap().query_wasm_smart(&flex_addr, &query).unwrap();
+        let from_fixed: ProposalResponse =
+            app.wrap().query_wasm_smart(&fixed_addr, &query).unwrap();
+        assert_eq!(from_flex.msgs, msgs);
+        assert_eq!(from_flex.proposer, Addr::unchecked(OWNER));
+        assert_eq!(from_flex.deposit.as_ref().unwrap().amount, Uint128::new(10));
+        assert_eq!(from_flex, from_fixed);
+    }
+
+    #[test]
+    fn list_proposals_by_status() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case_fixed(&mut app, 4, voting_period, init_funds, false);
+
+        let propose = |app: &mut App, proposer: &str| {
+            app.execute_contract(
+                Addr::unchecked(proposer),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap();
+        };
+        let vote = |app: &mut App, voter: &str, proposal_id: u64, vote: Vote| {
+            app.execute_contract(
+                Addr::unchecked(voter),
+                flex_addr.clone(),
+                &ExecuteMsg::Vote { proposal_id, vote },
+                &[],
+            )
+            .unwrap();
+        };
+
+        // 1 passes right away, 2 stays open
+        propose(&mut app, VOTER4);
+        propose(&mut app, VOTER1);
+        // 3 passes and is executed
+        propose(&mut app, VOTER4);
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Execute { proposal_id: 3 },
+            &[],
+        )
+        .unwrap();
+        // 4 is rejected early
+        propose(&mut app, VOTER1);
+        vote(&mut app, VOTER3, 4, Vote::No);
+        vote(&mut app, VOTER4, 4, Vote::No);
+        vote(&mut app, VOTER5, 4, Vote::No);
+        // 5 stays open
+        propose(&mut app, VOTER2);
+
+        let list = |app: &App, start_after, limit, status| -> Vec<u64> {
+            let query = QueryMsg::ListProposals {
+                start_after,
+                limit,
+                status,
+            };
+            let res: ProposalListResponse =
+                app.wrap().query_wasm_smart(&flex_addr, &query).unwrap();
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+        let reverse = |app: &App, start_before, limit, status| -> Vec<u64> {
+            let query = QueryMsg::ReverseProposals {
+                start_before,
+                limit,
+                status,
+            };
+            let res: ProposalListResponse =
+                app.wrap().query_wasm_smart(&flex_addr, &query).unwrap();
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+
+        assert_eq!(list(&app, None, None, None), vec![1, 2, 3, 4, 5]);
+        assert_eq!(list(&app, None, None, Some(Status::Open)), vec![2, 5]);
+        assert_eq!(list(&app, None, None, Some(Status::Passed)), vec![1]);
+        assert_eq!(list(&app, None, None, Some(Status::Executed)), vec![3]);
+        assert_eq!(list(&app, None, None, Some(Status::Rejected)), vec![4]);
+
+        // pagination works with the filter
+        assert_eq!(list(&app, None, Some(1), Some(Status::Open)), vec![2]);
+        assert_eq!(list(&app, Some(2), Some(1), Some(Status::Open)), vec![5]);
+        assert_eq!(
+            list(&app, Some(5), None, Some(Status::Open)),
+            Vec::<u64>::new()
+        );
+        assert_eq!(reverse(&app, None, None, Some(Status::Open)), vec![5, 2]);
+        assert_eq!(reverse(&app, Some(5), Some(1), Some(Status::Open)), vec![2]);
+
+        // after expiration, open proposals are rejected, but are still stored as open,
+        // so a filtered listing skips them altogether
+        app.update_block(expire(voting_period));
+        assert_eq!(
+            list(&app, None, None, Some(Status::Open)),
+            Vec::<u64>::new()
+        );
+        assert_eq!(list(&app, None, None, Some(Status::Rejected)), vec![4]);
+    }
+
+    #[test]
+    fn signaling_proposals_are_finalized() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let voting_period = Duration::Time(2000000);
+        let (flex_addr, _) = setup_test_case_fixed(&mut app, 4, voting_period, init_funds, false);
+
+        // 1 is a passed text proposal, 2 a passed one with messages, 3 an open text proposal
+        for (proposer, proposal) in [
+            (VOTER4, text_proposal()),
+            (VOTER4, pay_somebody_proposal()),
+            (VOTER1, text_proposal()),
+        ] {
+            app.execute_contract(Addr::unchecked(proposer), flex_addr.clone(), &proposal, &[])
+                .unwrap();
+        }
+        let finalize = |app: &mut App, proposal_id| {
+            app.execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &ExecuteMsg::Finalize { proposal_id },
+                &[],
+            )
+        };
+
+        // proposals with messages must be executed instead
+        let err = finalize(&mut app, 2).unwrap_err();
+        assert_eq!(ContractError::CannotFinalize {}, err.downcast().unwrap());
+        // only passed proposals can be finalized
+        let err = finalize(&mut app, 3).unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
+
+        // anyone can finalize a passed text proposal, once
+        let res = finalize(&mut app, 1).unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [
+                ("action", "finalize"),
+                ("sender", SOMEBODY),
+                ("proposal_id", "1"),
+            ],
+        );
+        let err = finalize(&mut app, 1).unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
+        let err = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &ExecuteMsg::Close { proposal_id: 1 },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(ContractError::WrongCloseStatus {}, err.downcast().unwrap());
+
+        // executing a text proposal completes it as well
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &text_proposal(),
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Execute { proposal_id: 4 },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(VOTER4),
+            flex_addr.clone(),
+            &ExecuteMsg::Execute { proposal_id: 2 },
+            &[],
+        )
+        .unwrap();
+
+        // signaling votes are distinguishable from executed ones
+        let list = |status| -> Vec<u64> {
+            let query = QueryMsg::ListProposals {
+                start_after: None,
+                limit: None,
+                status: Some(status),
+            };
+            let res: ProposalListResponse =
+                app.wrap().query_wasm_smart(&flex_addr, &query).unwrap();
+            res.proposals.into_iter().map(|p| p.id).collect()
+        };
+        assert_eq!(list(Status::Completed), vec![1, 4]);
+        assert_eq!(list(Status::Executed), vec![2]);
+    }
+
+    #[test]
+    fn test_vote_works() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
 
         let threshold = Threshold::ThresholdQuorum {
             threshold: Decimal::percent(51),
@@ -1155,7 +2062,7 @@ mod tests {
                 proposal_id,
                 voter: OWNER.into(),
                 vote: Vote::Yes,
-                weight: 0
+                weight: 0,
             }
         );
 
@@ -1171,7 +2078,7 @@ mod tests {
                 proposal_id,
                 voter: VOTER2.into(),
                 vote: Vote::No,
-                weight: 2
+                weight: 2,
             }
         );
 
@@ -1562,6 +2469,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rejected_proposals_are_not_resurrected() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        // 60% yes of the total weight of 23 to pass early, 7 weight for quorum
+        let voting_period = 20000;
+        let (flex_addr, _) = setup_test_case(
+            &mut app,
+            Threshold::ThresholdQuorum {
+                threshold: Decimal::percent(60),
+                quorum: Decimal::percent(30),
+            },
+            Duration::Time(voting_period),
+            init_funds,
+            false,
+            None,
+            None,
+        );
+        let prop_status = |app: &App, proposal_id: u64| -> Status {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+        let vote = |app: &mut App, voter: &str, proposal_id: u64, vote: Vote| {
+            let msg = ExecuteMsg::Vote { proposal_id, vote };
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &msg, &[])
+        };
+        let execute = |app: &mut App, proposal_id: u64| -> ContractError {
+            let msg = ExecuteMsg::Execute { proposal_id };
+            app.execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &msg, &[])
+                .unwrap_err()
+                .downcast()
+                .unwrap()
+        };
+
+        // VOTER5 opens two proposals (5 yes each)
+        for _ in 0..2 {
+            app.execute_contract(
+                Addr::unchecked(VOTER5),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &[],
+            )
+            .unwrap();
+        }
+        app.update_block(next_block);
+
+        // 12 no votes reject the first one early, later yes votes reaching quorum
+        // are still counted but do not change the outcome
+        vote(&mut app, VOTER4, 1, Vote::No).unwrap();
+        assert_eq!(prop_status(&app, 1), Status::Rejected);
+        for voter in [VOTER1, VOTER2, VOTER3] {
+            vote(&mut app, voter, 1, Vote::Yes).unwrap();
+        }
+        assert_eq!(prop_status(&app, 1), Status::Rejected);
+        assert_eq!(execute(&mut app, 1), ContractError::WrongExecuteStatus {});
+
+        // the second one is all yes, but short of quorum, so it cannot execute early
+        assert_eq!(prop_status(&app, 2), Status::Open);
+        assert_eq!(execute(&mut app, 2), ContractError::WrongExecuteStatus {});
+
+        // once expired, it is rejected for good
+        app.update_block(|block| block.time = block.time.plus_seconds(voting_period));
+        assert_eq!(prop_status(&app, 2), Status::Rejected);
+        let err = vote(&mut app, VOTER4, 2, Vote::Yes).unwrap_err();
+        assert_eq!(ContractError::Expired {}, err.downcast().unwrap());
+        assert_eq!(execute(&mut app, 2), ContractError::WrongExecuteStatus {});
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &ExecuteMsg::Close { proposal_id: 2 },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(execute(&mut app, 2), ContractError::WrongExecuteStatus {});
+    }
+
     #[test]
     fn test_close_works() {
         let init_funds = coins(10, "BTC");
@@ -1682,7 +2669,7 @@ mod tests {
         let newbie: &str = "newbie";
         let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
             remove: vec![VOTER3.into()],
-            add: vec![member(VOTER2, 21), member(newbie, 2)],
+            add: vec![member(VOTER2, 21).into(), member(newbie, 2).into()],
         };
         app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
             .unwrap();
@@ -1776,6 +2763,7 @@ mod tests {
             description: "He's trying to steal our money".to_string(),
             msgs: vec![update_msg],
             latest: None,
+            voting_period: None,
         };
         let res = app
             .execute_contract(
@@ -1863,82 +2851,604 @@ mod tests {
         assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
 
         // extra: ensure no one else can call the hook
-        let hook_hack = ExecuteMsg::MemberChangedHook(MemberChangedHookMsg {
-            diffs: vec![MemberDiff::new(VOTER1, Some(1), None)],
-        });
+        let hook_hack = ExecuteMsg::MemberChangedHook(MemberChangedHookMsg::one(MemberDiff::new(
+            VOTER1,
+            Some(1),
+            None,
+        )));
         let err = app
             .execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &hook_hack, &[])
             .unwrap_err();
         assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
     }
 
-    // uses the power from the beginning of the voting period
     #[test]
-    fn percentage_handles_group_changes() {
+    fn member_changed_hook_excludes_removed_voters() {
         let init_funds = coins(10, "BTC");
         let mut app = mock_app(&init_funds);
 
-        // 51% required, which is 12 of the initial 24
-        let threshold = Threshold::ThresholdQuorum {
-            threshold: Decimal::percent(51),
-            quorum: Decimal::percent(1),
-        };
+        let required_weight = 4;
         let voting_period = Duration::Time(20000);
-        let (flex_addr, group_addr) = setup_test_case(
-            &mut app,
-            threshold,
-            voting_period,
-            init_funds,
-            false,
-            None,
-            None,
-        );
+        let (flex_addr, group_addr) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
 
-        // VOTER3 starts a proposal to send some tokens (3/12 votes)
+        // register the multisig as a hook on the group
+        let add_hook = cw4_group::msg::ExecuteMsg::AddHook {
+            addr: flex_addr.to_string(),
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr.clone(), &add_hook, &[])
+            .unwrap();
+
+        // VOTER3 starts a proposal (3/4 votes)
         let proposal = pay_somebody_proposal();
         let res = app
             .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
             .unwrap();
-        // Get the proposal id from the logs
         let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
-        let prop_status = |app: &App| -> Status {
-            let query_prop = QueryMsg::Proposal { proposal_id };
-            let prop: ProposalResponse = app
-                .wrap()
-                .query_wasm_smart(&flex_addr, &query_prop)
-                .unwrap();
-            prop.status
-        };
-
-        // 3/12 votes
-        assert_eq!(prop_status(&app), Status::Open);
 
-        // a few blocks later...
-        app.update_block(|block| block.height += 2);
+        app.update_block(next_block);
 
-        // admin changes the group (3 -> 0, 2 -> 9, 0 -> 29) - total = 56, require 29 to pass
-        let newbie: &str = "newbie";
+        // admin removes VOTER3 and VOTER5 from the group
         let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
-            remove: vec![VOTER3.into()],
-            add: vec![member(VOTER2, 9), member(newbie, 29)],
+            remove: vec![VOTER3.into(), VOTER5.into()],
+            add: vec![],
         };
-        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
             .unwrap();
+        let adjusted = res
+            .events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|a| a.key == "adjusted_proposals")
+            .unwrap();
+        assert_eq!(adjusted.value, proposal_id.to_string());
 
-        // a few blocks later...
-        app.update_block(|block| block.height += 3);
+        // VOTER3's ballot no longer counts
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Open);
+        let vote: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Vote {
+                    proposal_id,
+                    voter: VOTER3.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(vote.vote.unwrap().weight, 0);
 
-        // VOTER2 votes according to original weights: 3 + 2 = 5 / 12 => Open
-        // with updated weights, it would be 3 + 9 = 12 / 12 => Passed
+        // VOTER1 would have passed the proposal with VOTER3's ballot counted
         let yes_vote = ExecuteMsg::Vote {
             proposal_id,
             vote: Vote::Yes,
         };
-        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &yes_vote, &[])
             .unwrap();
-        assert_eq!(prop_status(&app), Status::Open);
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Open);
 
-        // new proposal can be passed single-handedly by newbie
+        // removed members cannot vote (again), despite their snapshot weight
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &yes_vote, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // remaining members can still pass it
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+    }
+
+    #[test]
+    fn voter_index_dropped_once_proposal_leaves_open() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let required_weight = 10;
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
+
+        for delegator in [VOTER1, VOTER2] {
+            let msg = ExecuteMsg::SetDelegate {
+                delegate: Some(VOTER3.into()),
+            };
+            app.execute_contract(Addr::unchecked(delegator), flex_addr.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        // VOTER3 proposes, voting yes for both delegators as well (6/10)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        let voter3 = Addr::unchecked(VOTER3);
+        let indexed = |app: &App| -> Vec<bool> {
+            [VOTER1, VOTER2, VOTER3]
+                .iter()
+                .map(|voter| Addr::unchecked(*voter))
+                .flat_map(|voter| {
+                    let voter_key = VOTER_PROPOSALS.key((&voter, proposal_id));
+                    let delegated_key = DELEGATED_BALLOTS.key((&voter3, proposal_id, &voter));
+                    [voter_key.to_vec(), delegated_key.to_vec()]
+                })
+                .map(|key| {
+                    app.wrap()
+                        .query_wasm_raw(&flex_addr, key)
+                        .unwrap()
+                        .is_some()
+                })
+                .collect()
+        };
+        assert_eq!(indexed(&app), vec![true, true, true, true, true, false]);
+
+        // once VOTER4 passes the proposal, membership changes no longer touch it
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+        assert_eq!(indexed(&app), vec![false; 6]);
+
+        // late votes on the passed proposal are not indexed either
+        app.execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        let key = VOTER_PROPOSALS.key((&Addr::unchecked(VOTER5), proposal_id));
+        let raw = app.wrap().query_wasm_raw(&flex_addr, key.to_vec()).unwrap();
+        assert_eq!(raw, None);
+    }
+
+    #[test]
+    fn removed_voters_use_hook_height() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let required_weight = 4;
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
+
+        let removed_at = |app: &App, voter: &str| -> Option<u64> {
+            let key = REMOVED_VOTERS.key(&Addr::unchecked(voter));
+            app.wrap()
+                .query_wasm_raw(&flex_addr, key.to_vec())
+                .unwrap()
+                .map(|raw| cosmwasm_std::from_slice(&raw).unwrap())
+        };
+
+        // the group reports the height it applied the change at
+        let height = app.block_info().height - 3;
+        let hook = ExecuteMsg::MemberChangedHook(
+            MemberChangedHookMsg::one(MemberDiff::new(VOTER1, Some(1), None)).with_height(height),
+        );
+        app.execute_contract(group_addr.clone(), flex_addr.clone(), &hook, &[])
+            .unwrap();
+        assert_eq!(removed_at(&app, VOTER1), Some(height));
+
+        // without a reported height, the current block is used
+        let hook = ExecuteMsg::MemberChangedHook(MemberChangedHookMsg::one(MemberDiff::new(
+            VOTER2,
+            Some(2),
+            None,
+        )));
+        app.execute_contract(group_addr, flex_addr.clone(), &hook, &[])
+            .unwrap();
+        assert_eq!(removed_at(&app, VOTER2), Some(app.block_info().height));
+    }
+
+    fn query_vote_delegate(
+        app: &App,
+        flex_addr: &Addr,
+        proposal_id: u64,
+        voter: &str,
+    ) -> Option<String> {
+        let res: DelegateResponse = app
+            .wrap()
+            .query_wasm_smart(
+                flex_addr,
+                &QueryMsg::VoteDelegate {
+                    proposal_id,
+                    voter: voter.into(),
+                },
+            )
+            .unwrap();
+        res.delegate
+    }
+
+    #[test]
+    fn delegated_votes_can_be_overridden() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let required_weight = 8;
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
+
+        let set_delegate = |app: &mut App, sender: &str, delegate: Option<&str>| {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                flex_addr.clone(),
+                &ExecuteMsg::SetDelegate {
+                    delegate: delegate.map(String::from),
+                },
+                &[],
+            )
+        };
+
+        // VOTER1 and VOTER2 let VOTER3 vote for them
+        set_delegate(&mut app, VOTER1, Some(VOTER3)).unwrap();
+        set_delegate(&mut app, VOTER2, Some(VOTER3)).unwrap();
+        let res: DelegateResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Delegate {
+                    address: VOTER1.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.delegate, Some(VOTER3.to_string()));
+
+        // no self-delegation, no chains, members only
+        let err = set_delegate(&mut app, VOTER5, Some(VOTER5)).unwrap_err();
+        assert_eq!(ContractError::SelfDelegation {}, err.downcast().unwrap());
+        let err = set_delegate(&mut app, VOTER3, Some(VOTER5)).unwrap_err();
+        assert_eq!(ContractError::DelegationChain {}, err.downcast().unwrap());
+        let err = set_delegate(&mut app, VOTER5, Some(VOTER1)).unwrap_err();
+        assert_eq!(ContractError::DelegationChain {}, err.downcast().unwrap());
+        let err = set_delegate(&mut app, VOTER5, Some(SOMEBODY)).unwrap_err();
+        assert_eq!(ContractError::DelegateNotMember {}, err.downcast().unwrap());
+        let err = set_delegate(&mut app, SOMEBODY, Some(VOTER5)).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        // VOTER5 proposes (5/8 yes)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+
+        // VOTER3 votes no, for themselves and both delegators
+        let vote = |app: &mut App, sender: &str, vote: Vote| {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                flex_addr.clone(),
+                &ExecuteMsg::Vote { proposal_id, vote },
+                &[],
+            )
+        };
+        vote(&mut app, VOTER3, Vote::No).unwrap();
+        let votes: VoteListResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::ListVotes {
+                    proposal_id,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        let delegated: Vec<_> = votes
+            .votes
+            .iter()
+            .filter(|v| {
+                let delegate = query_vote_delegate(&app, &flex_addr, proposal_id, &v.voter);
+                delegate.as_deref() == Some(VOTER3)
+            })
+            .map(|v| (v.voter.as_str(), v.vote, v.weight))
+            .collect();
+        assert_eq!(
+            delegated,
+            vec![(VOTER1, Vote::No, 1), (VOTER2, Vote::No, 2)]
+        );
+
+        // the delegators' own votes replace the delegated ones (7/8, then 8/8 yes)
+        vote(&mut app, VOTER2, Vote::Yes).unwrap();
+        let res: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Vote {
+                    proposal_id,
+                    voter: VOTER2.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.vote.unwrap().vote, Vote::Yes);
+        assert_eq!(
+            query_vote_delegate(&app, &flex_addr, proposal_id, VOTER2),
+            None
+        );
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Open);
+
+        vote(&mut app, VOTER1, Vote::Yes).unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+
+        // but they only get to vote once
+        let err = vote(&mut app, VOTER2, Vote::No).unwrap_err();
+        assert_eq!(ContractError::AlreadyVoted {}, err.downcast().unwrap());
+
+        // removing the delegation frees VOTER3 to delegate
+        set_delegate(&mut app, VOTER1, None).unwrap();
+        set_delegate(&mut app, VOTER2, Some(VOTER5)).unwrap();
+        set_delegate(&mut app, VOTER3, Some(VOTER4)).unwrap();
+    }
+
+    #[test]
+    fn delegated_votes_final_once_passed() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let required_weight = 8;
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, _) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
+
+        // VOTER1 lets VOTER3 vote for them
+        app.execute_contract(
+            Addr::unchecked(VOTER1),
+            flex_addr.clone(),
+            &ExecuteMsg::SetDelegate {
+                delegate: Some(VOTER3.into()),
+            },
+            &[],
+        )
+        .unwrap();
+
+        // VOTER5 proposes (5/8 yes)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let vote = |app: &mut App, sender: &str, vote: Vote| {
+            app.execute_contract(
+                Addr::unchecked(sender),
+                flex_addr.clone(),
+                &ExecuteMsg::Vote { proposal_id, vote },
+                &[],
+            )
+        };
+        let status = |app: &App| {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+
+        // VOTER3 votes yes for themselves and VOTER1, which passes it (9/8 yes)
+        vote(&mut app, VOTER3, Vote::Yes).unwrap();
+        assert_eq!(status(&app), Status::Passed);
+
+        // VOTER1 can no longer take back the yes
+        let err = vote(&mut app, VOTER1, Vote::No).unwrap_err();
+        assert_eq!(ContractError::NotOpen {}, err.downcast().unwrap());
+        assert_eq!(status(&app), Status::Passed);
+        let res: VoteResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &flex_addr,
+                &QueryMsg::Vote {
+                    proposal_id,
+                    voter: VOTER1.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(res.vote.unwrap().vote, Vote::Yes);
+        assert_eq!(
+            query_vote_delegate(&app, &flex_addr, proposal_id, VOTER1),
+            Some(VOTER3.to_string())
+        );
+
+        // members without a ballot can still vote, as before
+        vote(&mut app, VOTER4, Vote::No).unwrap();
+        assert_eq!(status(&app), Status::Passed);
+    }
+
+    #[test]
+    fn delegated_votes_dropped_with_removed_delegate() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        let required_weight = 10;
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) =
+            setup_test_case_fixed(&mut app, required_weight, voting_period, init_funds, false);
+
+        // register the multisig as a hook on the group
+        let add_hook = cw4_group::msg::ExecuteMsg::AddHook {
+            addr: flex_addr.to_string(),
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr.clone(), &add_hook, &[])
+            .unwrap();
+
+        for delegator in [VOTER1, VOTER2] {
+            let msg = ExecuteMsg::SetDelegate {
+                delegate: Some(VOTER3.into()),
+            };
+            app.execute_contract(Addr::unchecked(delegator), flex_addr.clone(), &msg, &[])
+                .unwrap();
+        }
+
+        // VOTER3 proposes, voting yes for both delegators as well (6/10)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let query_vote = |app: &App, voter: &str| -> Option<VoteInfo> {
+            let res: VoteResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &flex_addr,
+                    &QueryMsg::Vote {
+                        proposal_id,
+                        voter: voter.into(),
+                    },
+                )
+                .unwrap();
+            res.vote
+        };
+        assert_eq!(
+            query_vote_delegate(&app, &flex_addr, proposal_id, VOTER2),
+            Some(VOTER3.to_string())
+        );
+
+        app.update_block(next_block);
+
+        // admin removes VOTER3 from the group, dropping the votes cast for others
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER3.into()],
+            add: vec![],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+            .unwrap();
+        assert_eq!(query_vote(&app, VOTER3).unwrap().weight, 0);
+        assert_eq!(query_vote(&app, VOTER1), None);
+        assert_eq!(query_vote(&app, VOTER2), None);
+        assert_eq!(
+            query_vote_delegate(&app, &flex_addr, proposal_id, VOTER2),
+            None
+        );
+
+        // delegators can vote themselves now, the proposal would have passed with
+        // the delegated votes still counted (3 + 5 + 6 = 14/10)
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        for voter in [VOTER1, VOTER2, VOTER5] {
+            app.execute_contract(Addr::unchecked(voter), flex_addr.clone(), &yes_vote, &[])
+                .unwrap();
+        }
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Open);
+
+        app.execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        let prop: ProposalResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+            .unwrap();
+        assert_eq!(prop.status, Status::Passed);
+
+        // delegating to a removed member is not possible
+        let msg = ExecuteMsg::SetDelegate {
+            delegate: Some(VOTER3.into()),
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER5), flex_addr, &msg, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::DelegateNotMember {}, err.downcast().unwrap());
+    }
+
+    // uses the power from the beginning of the voting period
+    #[test]
+    fn percentage_handles_group_changes() {
+        let init_funds = coins(10, "BTC");
+        let mut app = mock_app(&init_funds);
+
+        // 51% required, which is 12 of the initial 24
+        let threshold = Threshold::ThresholdQuorum {
+            threshold: Decimal::percent(51),
+            quorum: Decimal::percent(1),
+        };
+        let voting_period = Duration::Time(20000);
+        let (flex_addr, group_addr) = setup_test_case(
+            &mut app,
+            threshold,
+            voting_period,
+            init_funds,
+            false,
+            None,
+            None,
+        );
+
+        // VOTER3 starts a proposal to send some tokens (3/12 votes)
+        let proposal = pay_somebody_proposal();
+        let res = app
+            .execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &proposal, &[])
+            .unwrap();
+        // Get the proposal id from the logs
+        let proposal_id: u64 = res.custom_attrs(1)[2].value.parse().unwrap();
+        let prop_status = |app: &App| -> Status {
+            let query_prop = QueryMsg::Proposal { proposal_id };
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &query_prop)
+                .unwrap();
+            prop.status
+        };
+
+        // 3/12 votes
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 2);
+
+        // admin changes the group (3 -> 0, 2 -> 9, 0 -> 29) - total = 56, require 29 to pass
+        let newbie: &str = "newbie";
+        let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
+            remove: vec![VOTER3.into()],
+            add: vec![member(VOTER2, 9).into(), member(newbie, 29).into()],
+        };
+        app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
+            .unwrap();
+
+        // a few blocks later...
+        app.update_block(|block| block.height += 3);
+
+        // VOTER2 votes according to original weights: 3 + 2 = 5 / 12 => Open
+        // with updated weights, it would be 3 + 9 = 12 / 12 => Passed
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER2), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app), Status::Open);
+
+        // new proposal can be passed single-handedly by newbie
         let proposal = pay_somebody_proposal();
         let res = app
             .execute_contract(Addr::unchecked(newbie), flex_addr.clone(), &proposal, &[])
@@ -2005,7 +3515,7 @@ mod tests {
         let newbie: &str = "newbie";
         let update_msg = cw4_group::msg::ExecuteMsg::UpdateMembers {
             remove: vec![VOTER3.into()],
-            add: vec![member(VOTER2, 9), member(newbie, 29)],
+            add: vec![member(VOTER2, 9).into(), member(newbie, 29).into()],
         };
         app.execute_contract(Addr::unchecked(OWNER), group_addr, &update_msg, &[])
             .unwrap();
@@ -2079,6 +3589,21 @@ mod tests {
         // 9 of 15 is 60% absolute threshold, but less than 12 (80% quorum needed)
         assert_eq!(prop_status(&app), Status::Open);
 
+        // so it cannot be executed early either
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let err = app
+            .execute_contract(
+                Addr::unchecked(SOMEBODY),
+                flex_addr.clone(),
+                &execution,
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
+
         // add 3 weight no vote and we hit quorum and this passes
         let no_vote = ExecuteMsg::Vote {
             proposal_id,
@@ -2087,6 +3612,304 @@ mod tests {
         app.execute_contract(Addr::unchecked(VOTER3), flex_addr.clone(), &no_vote, &[])
             .unwrap();
         assert_eq!(prop_status(&app), Status::Passed);
+
+        // the outcome can no longer change, so it executes before the voting period ends
+        app.execute_contract(
+            Addr::unchecked(SOMEBODY),
+            flex_addr.clone(),
+            &execution,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(prop_status(&app), Status::Executed);
+    }
+
+    // sets up a group and a multisig passing with 4 votes, with OWNER allowed to veto
+    // during a 100 second timelock. Returns the multisig address.
+    fn setup_with_timelock(app: &mut App, proposal_deposit: Option<UncheckedDepositInfo>) -> Addr {
+        let group_addr = instantiate_group(
+            app,
+            vec![
+                member(OWNER, 0),
+                member(VOTER1, 1),
+                member(VOTER3, 3),
+                member(VOTER4, 12),
+            ],
+        );
+        app.update_block(next_block);
+
+        let flex_id = app.store_code(contract_flex());
+        let msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 4 },
+            max_voting_period: Duration::Time(20000),
+            min_voting_period: None,
+            timelock: Some(Timelock {
+                duration: Duration::Time(100),
+                vetoers: vec![Addr::unchecked(OWNER)],
+            }),
+            executor: None,
+            proposal_deposit,
+        };
+        let flex_addr = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+            .unwrap();
+        app.send_tokens(Addr::unchecked(OWNER), flex_addr.clone(), &coins(10, "BTC"))
+            .unwrap();
+        app.update_block(next_block);
+        flex_addr
+    }
+
+    #[test]
+    fn timelock_must_match_voting_period_unit() {
+        let mut app = mock_app(&[]);
+        let group_addr = instantiate_group(&mut app, vec![member(OWNER, 1)]);
+        let flex_id = app.store_code(contract_flex());
+
+        let msg = InstantiateMsg {
+            group_addr: group_addr.to_string(),
+            threshold: Threshold::AbsoluteCount { weight: 1 },
+            max_voting_period: Duration::Time(20000),
+            min_voting_period: None,
+            timelock: Some(Timelock {
+                duration: Duration::Height(10),
+                vetoers: vec![],
+            }),
+            executor: None,
+            proposal_deposit: None,
+        };
+        let err = app
+            .instantiate_contract(flex_id, Addr::unchecked(OWNER), &msg, &[], "flex", None)
+            .unwrap_err();
+        assert_eq!(ContractError::WrongTimelock {}, err.downcast().unwrap());
+    }
+
+    /// Copy of the whole app state (contract storage, balances, block), so that a test can
+    /// run several independent scenarios from one setup. Uploaded code is not part of it.
+    struct AppSnapshot {
+        storage: Vec<Record>,
+        block: BlockInfo,
+    }
+
+    fn snapshot(app: &App) -> AppSnapshot {
+        let storage =
+            app.read_module(|_, _, storage| storage.range(None, None, Order::Ascending).collect());
+        AppSnapshot {
+            storage,
+            block: app.block_info(),
+        }
+    }
+
+    fn restore(app: &mut App, snapshot: &AppSnapshot) {
+        app.init_modules(|_, _, storage| {
+            let keys: Vec<_> = storage
+                .range(None, None, Order::Ascending)
+                .map(|(key, _)| key)
+                .collect();
+            for key in keys {
+                storage.remove(&key);
+            }
+            for (key, value) in &snapshot.storage {
+                storage.set(key, value);
+            }
+        });
+        app.set_block(snapshot.block.clone());
+    }
+
+    #[test]
+    fn veto_or_execute_after_timelock() {
+        let mut app = mock_app(&coins(10, "BTC"));
+        let flex_addr = setup_with_timelock(&mut app, None);
+
+        let prop_status = |app: &App, proposal_id: u64| -> Status {
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            prop.status
+        };
+        let btc = |app: &App, addr: &str| app.wrap().query_balance(addr, "BTC").unwrap().amount;
+
+        // VOTER3 proposes, VOTER1 passes it with their vote a block later
+        app.execute_contract(
+            Addr::unchecked(VOTER3),
+            flex_addr.clone(),
+            &pay_somebody_proposal(),
+            &[],
+        )
+        .unwrap();
+        let proposal_id = 1;
+        assert_eq!(prop_status(&app, proposal_id), Status::Open);
+        let res: ExecutableAtResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::ExecutableAt { proposal_id })
+            .unwrap();
+        assert_eq!(res.executable_at, None);
+
+        app.update_block(next_block);
+        let yes_vote = ExecuteMsg::Vote {
+            proposal_id,
+            vote: Vote::Yes,
+        };
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &yes_vote, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, proposal_id), Status::Passed);
+
+        // the timelock starts when the proposal passes
+        let res: ExecutableAtResponse = app
+            .wrap()
+            .query_wasm_smart(&flex_addr, &QueryMsg::ExecutableAt { proposal_id })
+            .unwrap();
+        let passed_at = app.block_info().time;
+        assert_eq!(
+            res.executable_at,
+            Some(Expiration::AtTime(passed_at.plus_seconds(100)))
+        );
+
+        // cannot execute during the timelock
+        let execution = ExecuteMsg::Execute { proposal_id };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &execution, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::TimelockNotElapsed {},
+            err.downcast().unwrap()
+        );
+        let passed = snapshot(&app);
+
+        // first branch: the proposal is vetoed, only vetoers can do that
+        let veto = ExecuteMsg::Veto { proposal_id };
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER4), flex_addr.clone(), &veto, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+        let res = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &veto, &[])
+            .unwrap();
+        assert_eq!(
+            res.custom_attrs(1),
+            [("action", "veto"), ("sender", OWNER), ("proposal_id", "1"),],
+        );
+        assert_eq!(prop_status(&app, proposal_id), Status::Vetoed);
+
+        // vetoed proposals can neither be executed nor vetoed again, even after the timelock
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &execution, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::WrongExecuteStatus {},
+            err.downcast().unwrap()
+        );
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &veto, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongVetoStatus {}, err.downcast().unwrap());
+
+        // but they can be closed, staying vetoed
+        let closing = ExecuteMsg::Close { proposal_id };
+        app.execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &closing, &[])
+            .unwrap();
+        assert_eq!(prop_status(&app, proposal_id), Status::Vetoed);
+
+        // second branch: nobody vetoes, one second before the timelock elapses
+        // execution still fails
+        restore(&mut app, &passed);
+        assert_eq!(prop_status(&app, proposal_id), Status::Passed);
+        app.update_block(|block| block.time = block.time.plus_seconds(99));
+        let err = app
+            .execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &execution, &[])
+            .unwrap_err();
+        assert_eq!(
+            ContractError::TimelockNotElapsed {},
+            err.downcast().unwrap()
+        );
+
+        // exactly at the boundary, veto is no longer possible but execution is
+        app.update_block(|block| block.time = block.time.plus_seconds(1));
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &veto, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongVetoStatus {}, err.downcast().unwrap());
+        app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &execution, &[])
+            .unwrap();
+        assert_eq!(btc(&app, SOMEBODY), Uint128::new(1));
+
+        // executed proposals cannot be vetoed
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &veto, &[])
+            .unwrap_err();
+        assert_eq!(ContractError::WrongVetoStatus {}, err.downcast().unwrap());
+        assert_eq!(prop_status(&app, proposal_id), Status::Executed);
+
+        // the snapshot was copied, so neither branch leaked into it
+        restore(&mut app, &passed);
+        assert_eq!(prop_status(&app, proposal_id), Status::Passed);
+        assert_eq!(btc(&app, SOMEBODY), Uint128::zero());
+        assert_eq!(btc(&app, flex_addr.as_str()), Uint128::new(10));
+    }
+
+    #[test]
+    fn vetoed_deposit_follows_refund_policy() {
+        for refund_failed_proposals in [true, false] {
+            let mut app = mock_app(&coins(10, "BTC"));
+            app.sudo(SudoMsg::Bank(BankSudo::Mint {
+                to_address: VOTER3.to_string(),
+                amount: coins(10, "TOKEN"),
+            }))
+            .unwrap();
+            let flex_addr = setup_with_timelock(
+                &mut app,
+                Some(UncheckedDepositInfo {
+                    amount: Uint128::new(10),
+                    denom: UncheckedDenom::Native("TOKEN".to_string()),
+                    refund_failed_proposals,
+                }),
+            );
+            let token = |app: &App| app.wrap().query_balance(VOTER3, "TOKEN").unwrap().amount;
+
+            app.execute_contract(
+                Addr::unchecked(VOTER3),
+                flex_addr.clone(),
+                &pay_somebody_proposal(),
+                &coins(10, "TOKEN"),
+            )
+            .unwrap();
+            let proposal_id = 1;
+            app.update_block(next_block);
+            let yes_vote = ExecuteMsg::Vote {
+                proposal_id,
+                vote: Vote::Yes,
+            };
+            app.execute_contract(Addr::unchecked(VOTER1), flex_addr.clone(), &yes_vote, &[])
+                .unwrap();
+            assert_eq!(token(&app), Uint128::zero());
+
+            // a veto fails the proposal, the deposit is settled like for rejected ones
+            let veto = ExecuteMsg::Veto { proposal_id };
+            app.execute_contract(Addr::unchecked(OWNER), flex_addr.clone(), &veto, &[])
+                .unwrap();
+            let refunded = if refund_failed_proposals {
+                Uint128::new(10)
+            } else {
+                Uint128::zero()
+            };
+            assert_eq!(token(&app), refunded);
+
+            // closing keeps it vetoed and does not refund again
+            let closing = ExecuteMsg::Close { proposal_id };
+            for _ in 0..2 {
+                app.execute_contract(Addr::unchecked(SOMEBODY), flex_addr.clone(), &closing, &[])
+                    .unwrap();
+            }
+            let prop: ProposalResponse = app
+                .wrap()
+                .query_wasm_smart(&flex_addr, &QueryMsg::Proposal { proposal_id })
+                .unwrap();
+            assert_eq!(prop.status, Status::Vetoed);
+            assert_eq!(token(&app), refunded);
+        }
     }
 
     #[test]
@@ -2108,6 +3931,8 @@ mod tests {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 10 },
             max_voting_period: Duration::Time(10),
+            min_voting_period: None,
+            timelock: None,
             executor: None,
             proposal_deposit: Some(UncheckedDepositInfo {
                 amount: Uint128::new(1),
@@ -2136,6 +3961,8 @@ mod tests {
             group_addr: group_addr.to_string(),
             threshold: Threshold::AbsoluteCount { weight: 10 },
             max_voting_period: Duration::Time(10),
+            min_voting_period: None,
+            timelock: None,
             executor: None,
             proposal_deposit: Some(UncheckedDepositInfo {
                 amount: Uint128::zero(),
@@ -2186,6 +4013,7 @@ mod tests {
                     ],
                     mint: None,
                     marketing: None,
+                    import_from: None,
                 },
                 &[],
                 "Token",
@@ -30,6 +30,15 @@ pub enum ContractError {
     #[error("Wrong expiration option")]
     WrongExpiration {},
 
+    #[error("Voting period must use the same unit as the configured bounds")]
+    WrongVotingPeriod {},
+
+    #[error("Voting period is shorter than the configured minimum")]
+    VotingPeriodTooShort {},
+
+    #[error("Voting period is longer than the configured maximum")]
+    VotingPeriodTooLong {},
+
     #[error("Already voted on this proposal")]
     AlreadyVoted {},
 
@@ -39,6 +48,27 @@ pub enum ContractError {
     #[error("Cannot close completed or passed proposals")]
     WrongCloseStatus {},
 
+    #[error("Timelock must use the same unit as the voting period")]
+    WrongTimelock {},
+
+    #[error("Proposal timelock has not elapsed yet")]
+    TimelockNotElapsed {},
+
+    #[error("Only passed proposals can be vetoed, before their timelock elapses")]
+    WrongVetoStatus {},
+
+    #[error("Proposals with messages must be executed, not finalized")]
+    CannotFinalize {},
+
+    #[error("Cannot delegate to yourself")]
+    SelfDelegation {},
+
+    #[error("Delegate must be a member of the group")]
+    DelegateNotMember {},
+
+    #[error("Delegates cannot delegate, and delegators cannot be delegated to")]
+    DelegationChain {},
+
     #[error("{0}")]
     Payment(#[from] PaymentError),
 
@@ -1,10 +1,10 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{CosmosMsg, Empty};
-use cw3::{UncheckedDepositInfo, Vote};
+use cw3::{Status, UncheckedDepositInfo, Vote};
 use cw4::MemberChangedHookMsg;
 use cw_utils::{Duration, Expiration, Threshold};
 
-use crate::state::Executor;
+use crate::state::{Executor, Timelock};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -12,11 +12,17 @@ pub struct InstantiateMsg {
     pub group_addr: String,
     pub threshold: Threshold,
     pub max_voting_period: Duration,
+    /// Shortest voting period a proposer may request. If unset, any period
+    /// up to `max_voting_period` is accepted.
+    /// Must use the same unit (height or time) as `max_voting_period`.
+    pub min_voting_period: Option<Duration>,
     // who is able to execute passed proposals
     // None means that anyone can execute
     pub executor: Option<Executor>,
     /// The cost of creating a proposal (if any).
     pub proposal_deposit: Option<UncheckedDepositInfo>,
+    /// Delay between passing and execution, during which passed proposals can be vetoed.
+    pub timelock: Option<Timelock>,
 }
 
 // TODO: add some T variants? Maybe good enough as fixed Empty for now
@@ -28,17 +34,37 @@ pub enum ExecuteMsg {
         msgs: Vec<CosmosMsg<Empty>>,
         // note: we ignore API-spec'd earliest if passed, always opens immediately
         latest: Option<Expiration>,
+        /// Voting period for this proposal, within the configured min/max.
+        /// Defaults to `max_voting_period` if not set.
+        voting_period: Option<Duration>,
     },
     Vote {
         proposal_id: u64,
         vote: Vote,
     },
+    /// Executes a passed proposal. This is possible before it expires, as soon as no
+    /// remaining votes could change the outcome (quorum included).
     Execute {
         proposal_id: u64,
     },
     Close {
         proposal_id: u64,
     },
+    /// Marks a passed proposal without messages as Completed. Anyone can call this.
+    Finalize {
+        proposal_id: u64,
+    },
+    /// Vetoes a passed proposal before its timelock elapses. Only callable by the configured vetoers.
+    /// The deposit is refunded if failed proposals are refunded.
+    Veto {
+        proposal_id: u64,
+    },
+    /// Sets (or with None, removes) a standing delegate, who votes with the sender's weight
+    /// on proposals the sender does not vote on themselves. The sender can replace such a vote
+    /// with their own while the proposal is open. Only callable by group members.
+    SetDelegate {
+        delegate: Option<String>,
+    },
     /// Handles update hook messages from the group contract
     MemberChangedHook(MemberChangedHookMsg),
 }
@@ -51,15 +77,22 @@ pub enum QueryMsg {
     Threshold {},
     #[returns(cw3::ProposalResponse)]
     Proposal { proposal_id: u64 },
+    /// Lists proposals in ascending order, optionally only those with the given status.
+    /// The filter walks proposals by their stored status, which is only updated by
+    /// transactions. Proposals that changed status through expiration alone are
+    /// not listed under their new status, so filtered results may be incomplete.
     #[returns(cw3::ProposalListResponse)]
     ListProposals {
         start_after: Option<u64>,
         limit: Option<u32>,
+        status: Option<Status>,
     },
+    /// Same as `ListProposals`, in descending order
     #[returns(cw3::ProposalListResponse)]
     ReverseProposals {
         start_before: Option<u64>,
         limit: Option<u32>,
+        status: Option<Status>,
     },
     #[returns(cw3::VoteResponse)]
     Vote { proposal_id: u64, voter: String },
@@ -79,4 +112,24 @@ pub enum QueryMsg {
     /// Gets the current configuration.
     #[returns(crate::state::Config)]
     Config {},
+    /// Returns when a passed proposal can be executed, given the configured timelock.
+    #[returns(ExecutableAtResponse)]
+    ExecutableAt { proposal_id: u64 },
+    /// Returns the standing delegate of the given member, if any.
+    #[returns(DelegateResponse)]
+    Delegate { address: String },
+    /// Returns the delegate who cast the given voter's ballot on their behalf, if any.
+    #[returns(DelegateResponse)]
+    VoteDelegate { proposal_id: u64, voter: String },
+}
+
+#[cw_serde]
+pub struct ExecutableAtResponse {
+    /// None if there is no timelock, or the proposal is not (or no longer) passed
+    pub executable_at: Option<Expiration>,
+}
+
+#[cw_serde]
+pub struct DelegateResponse {
+    pub delegate: Option<String>,
 }
@@ -1,9 +1,9 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, QuerierWrapper};
-use cw3::DepositInfo;
+use cosmwasm_std::{Addr, BlockInfo, Empty, QuerierWrapper, StdResult, Storage};
+use cw3::{DepositInfo, Proposal};
 use cw4::Cw4Contract;
-use cw_storage_plus::Item;
-use cw_utils::{Duration, Threshold};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::{Duration, Expiration, Threshold};
 
 use crate::error::ContractError;
 
@@ -16,10 +16,21 @@ pub enum Executor {
     Only(Addr),
 }
 
+/// Delays execution of passed proposals, so they can still be vetoed
+#[cw_serde]
+pub struct Timelock {
+    /// How long a passed proposal must wait before it can be executed.
+    /// Must use the same unit (height or time) as `max_voting_period`.
+    pub duration: Duration,
+    /// Addresses allowed to veto passed proposals until the timelock elapses
+    pub vetoers: Vec<Addr>,
+}
+
 #[cw_serde]
 pub struct Config {
     pub threshold: Threshold,
     pub max_voting_period: Duration,
+    pub min_voting_period: Option<Duration>,
     // Total weight and voters are queried from this contract
     pub group_addr: Cw4Contract,
     // who is able to execute passed proposals
@@ -27,9 +38,56 @@ pub struct Config {
     pub executor: Option<Executor>,
     /// The price, if any, of creating a new proposal.
     pub proposal_deposit: Option<DepositInfo>,
+    /// Optional delay between passing and execution, during which proposals can be vetoed.
+    pub timelock: Option<Timelock>,
 }
 
 impl Config {
+    /// Checks that a requested voting period uses the same unit as the configured
+    /// bounds and lies within them.
+    pub fn validate_voting_period(&self, period: Duration) -> Result<(), ContractError> {
+        if let Some(min) = self.min_voting_period {
+            match (min, period) {
+                (Duration::Height(min), Duration::Height(p))
+                | (Duration::Time(min), Duration::Time(p)) => {
+                    if p < min {
+                        return Err(ContractError::VotingPeriodTooShort {});
+                    }
+                }
+                _ => return Err(ContractError::WrongVotingPeriod {}),
+            }
+        }
+        match (self.max_voting_period, period) {
+            (Duration::Height(max), Duration::Height(p))
+            | (Duration::Time(max), Duration::Time(p)) => {
+                if p > max {
+                    return Err(ContractError::VotingPeriodTooLong {});
+                }
+            }
+            _ => return Err(ContractError::WrongVotingPeriod {}),
+        }
+        Ok(())
+    }
+
+    /// Returns when the given passed proposal can be executed, or None if there is no timelock.
+    pub fn executable_at(
+        &self,
+        storage: &dyn Storage,
+        proposal_id: u64,
+        prop: &Proposal,
+    ) -> StdResult<Option<Expiration>> {
+        let timelock = match &self.timelock {
+            Some(timelock) => timelock,
+            None => return Ok(None),
+        };
+        let executable_at = match PASSED_AT.may_load(storage, proposal_id)? {
+            Some(block) => timelock.duration.after(&block),
+            // passed on expiration rather than by a vote
+            None => (prop.expires + timelock.duration)?,
+        };
+        Ok(Some(executable_at))
+    }
+
     // Executor can be set in 3 ways:
     // - Member: any member of the voting group is authorized
     // - Only: only passed address is authorized
@@ -55,3 +113,40 @@ impl Config {
 
 // unique items
 pub const CONFIG: Item<Config> = Item::new("config");
+
+// Same storage layout as `cw3_fixed_multisig::state::PROPOSALS`, plus an index on the
+// stored status. All proposal writes must go through this to keep the index up to date.
+pub struct ProposalIndexes<'a> {
+    pub status: MultiIndex<'a, u8, Proposal, u64>,
+}
+
+impl<'a> IndexList<Proposal> for ProposalIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Proposal>> + '_> {
+        let v: Vec<&dyn Index<Proposal>> = vec![&self.status];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn proposals<'a>() -> IndexedMap<'a, u64, Proposal, ProposalIndexes<'a>> {
+    let indexes = ProposalIndexes {
+        status: MultiIndex::new(|_, p| p.status as u8, "proposals", "proposals__status"),
+    };
+    IndexedMap::new("proposals", indexes)
+}
+
+// reverse index of ballots on open proposals, so membership changes can find the proposals a
+// voter took part in. Entries are dropped once the proposal is no longer open
+pub const VOTER_PROPOSALS: Map<(&Addr, u64), Empty> = Map::new("voter_proposals");
+// block in which a proposal passed by a vote, used to enforce the timelock
+pub const PASSED_AT: Map<u64, BlockInfo> = Map::new("passed_at");
+// block height at which a voter was removed from the group, as reported by the group hook
+pub const REMOVED_VOTERS: Map<&Addr, u64> = Map::new("removed_voters");
+// standing vote delegations, from delegator to delegate
+pub const DELEGATES: Map<&Addr, Addr> = Map::new("delegates");
+// reverse index of delegations, (delegate, delegator)
+pub const DELEGATORS: Map<(&Addr, &Addr), Empty> = Map::new("delegators");
+// ballots a delegate cast for others, (delegate, proposal_id, delegator), so they can be
+// dropped if the delegate is removed from the group. Only kept while the proposal is open
+pub const DELEGATED_BALLOTS: Map<(&Addr, u64, &Addr), Empty> = Map::new("delegated_ballots");
+// delegate who cast the ballot stored under (proposal_id, voter), if it was cast for the voter
+pub const BALLOT_DELEGATES: Map<(u64, &Addr), Addr> = Map::new("ballot_delegates");
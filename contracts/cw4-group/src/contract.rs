@@ -1,37 +1,52 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult,
-    SubMsg, Uint64,
+    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response,
+    StdError, StdResult, Storage, SubMsg, Uint64,
 };
-use cw2::set_contract_version;
+use cw2::{ensure_from_older_version, set_contract_version};
 use cw4::{
     Member, MemberChangedHookMsg, MemberDiff, MemberListResponse, MemberResponse,
     TotalWeightResponse,
 };
-use cw_storage_plus::Bound;
+use cw_storage_plus::{Bound, Index, PrefixBound};
 use cw_utils::maybe_addr;
+use semver::Version;
 
 use crate::error::ContractError;
 use crate::helpers::validate_unique_members;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{ADMIN, HOOKS, MEMBERS, TOTAL};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, MemberWithMetadata, MemberWithMetadataListResponse,
+    MemberWithMetadataResponse, MigrateMsg, PendingAdminResponse, QueryMsg,
+};
+use crate::state::{
+    members, record_member_change, ADMIN, BATCH_DIFFS, HOOKS, HOOK_HEIGHT, ISOLATE_HOOKS,
+    MEMBER_CHANGES, METADATA, PENDING_ADMIN, PENDING_BATCH, PRUNED_HEIGHT, TOTAL,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw4-group";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Maximum size of the metadata of a member, in bytes
+pub const MAX_METADATA_LEN: usize = 256;
+
+/// Reply id of the first hook when hooks are isolated, the others follow in order
+const HOOK_REPLY_ID: u64 = 1;
+
 // Note, you can use StdResult in some functions where you do not
 // make use of the custom errors
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    create(deps, msg.admin, msg.members, env.block.height)?;
+    create(deps.branch(), msg.admin, msg.members, env.block.height)?;
+    ISOLATE_HOOKS.save(deps.storage, &msg.isolate_hooks.unwrap_or_default())?;
+    HOOK_HEIGHT.save(deps.storage, &msg.hook_height.unwrap_or_default())?;
     Ok(Response::default())
 }
 
@@ -40,11 +55,13 @@ pub fn instantiate(
 pub fn create(
     mut deps: DepsMut,
     admin: Option<String>,
-    mut members: Vec<Member>,
+    initial_members: Vec<Member>,
     height: u64,
 ) -> Result<(), ContractError> {
-    validate_unique_members(&mut members)?;
-    let members = members; // let go of mutability
+    let mut initial_members: Vec<MemberWithMetadata> =
+        initial_members.into_iter().map(Into::into).collect();
+    validate_unique_members(&mut initial_members)?;
+    let initial_members = initial_members; // let go of mutability
 
     let admin_addr = admin
         .map(|admin| deps.api.addr_validate(&admin))
@@ -52,11 +69,12 @@ pub fn create(
     ADMIN.set(deps.branch(), admin_addr)?;
 
     let mut total = Uint64::zero();
-    for member in members.into_iter() {
+    for member in initial_members.into_iter() {
         let member_weight = Uint64::from(member.weight);
         total = total.checked_add(member_weight)?;
         let member_addr = deps.api.addr_validate(&member.addr)?;
-        MEMBERS.save(deps.storage, &member_addr, &member_weight.u64(), height)?;
+        members().save(deps.storage, &member_addr, &member_weight.u64(), height)?;
+        record_member_change(deps.storage, &member_addr, height)?;
     }
     TOTAL.save(deps.storage, &total.u64(), height)?;
 
@@ -73,28 +91,88 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     let api = deps.api;
     match msg {
-        ExecuteMsg::UpdateAdmin { admin } => Ok(ADMIN.execute_update_admin(
-            deps,
-            info,
-            admin.map(|admin| api.addr_validate(&admin)).transpose()?,
-        )?),
+        ExecuteMsg::UpdateAdmin { admin } => {
+            let admin = admin.map(|admin| api.addr_validate(&admin)).transpose()?;
+            // an offer made by the previous admin is void
+            PENDING_ADMIN.remove(deps.storage);
+            Ok(ADMIN.execute_update_admin(deps, info, admin)?)
+        }
+        ExecuteMsg::OfferAdmin { new_admin } => execute_offer_admin(deps, info, new_admin),
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, info),
+        ExecuteMsg::RevokeAdminOffer {} => execute_revoke_admin_offer(deps, info),
         ExecuteMsg::UpdateMembers { add, remove } => {
             execute_update_members(deps, env, info, add, remove)
         }
+        ExecuteMsg::UpdateMembersBatch {
+            batch_id,
+            add,
+            remove,
+            done,
+        } => execute_update_members_batch(deps, env, info, batch_id, add, remove, done),
         ExecuteMsg::AddHook { addr } => {
             Ok(HOOKS.execute_add_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
         }
         ExecuteMsg::RemoveHook { addr } => {
             Ok(HOOKS.execute_remove_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
         }
+        ExecuteMsg::PruneHistory {
+            up_to_height,
+            limit,
+        } => execute_prune_history(deps, env, info, up_to_height, limit),
+    }
+}
+
+pub fn execute_offer_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "offer_admin")
+        .add_attribute("pending_admin", new_admin)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoAdminOffer {})?;
+    if pending != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    PENDING_ADMIN.remove(deps.storage);
+    ADMIN.set(deps, Some(pending))?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", &info.sender)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_revoke_admin_offer(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    if PENDING_ADMIN.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoAdminOffer {});
     }
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_admin_offer")
+        .add_attribute("sender", info.sender))
 }
 
 pub fn execute_update_members(
     mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    add: Vec<Member>,
+    add: Vec<MemberWithMetadata>,
     remove: Vec<String>,
 ) -> Result<Response, ContractError> {
     let attributes = vec![
@@ -104,27 +182,136 @@ pub fn execute_update_members(
         attr("sender", &info.sender),
     ];
 
+    // the hooks would get this diff before the one of the open batch
+    if let Some(batch_id) = PENDING_BATCH.may_load(deps.storage)? {
+        return Err(ContractError::BatchInProgress { batch_id });
+    }
+
     // make the local update
     let diff = update_members(deps.branch(), env.block.height, info.sender, add, remove)?;
     // call all registered hooks
-    let messages = HOOKS.prepare_hooks(deps.storage, |h| {
-        diff.clone().into_cosmos_msg(h).map(SubMsg::new)
-    })?;
+    let messages = prepare_hooks(deps.storage, diff, env.block.height)?;
     Ok(Response::new()
         .add_submessages(messages)
         .add_attributes(attributes))
 }
 
+pub fn execute_update_members_batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    batch_id: u64,
+    add: Vec<MemberWithMetadata>,
+    remove: Vec<String>,
+    done: bool,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    match PENDING_BATCH.may_load(deps.storage)? {
+        Some(pending) if pending != batch_id => {
+            return Err(ContractError::BatchInProgress { batch_id: pending })
+        }
+        _ => {}
+    }
+
+    let attributes = vec![
+        attr("action", "update_members_batch"),
+        attr("batch_id", batch_id.to_string()),
+        attr("added", add.len().to_string()),
+        attr("removed", remove.len().to_string()),
+        attr("done", done.to_string()),
+        attr("sender", &info.sender),
+    ];
+
+    // make the local update, and merge the diff into the pending one
+    let diff = update_members(deps.branch(), env.block.height, info.sender, add, remove)?;
+    for change in diff.diffs {
+        let addr = deps.api.addr_validate(&change.key)?;
+        let merged = match BATCH_DIFFS.may_load(deps.storage, &addr)? {
+            Some(pending) => MemberDiff::new(pending.key, pending.old, change.new),
+            None => change,
+        };
+        if merged.old == merged.new {
+            BATCH_DIFFS.remove(deps.storage, &addr);
+        } else {
+            BATCH_DIFFS.save(deps.storage, &addr, &merged)?;
+        }
+    }
+
+    if !done {
+        PENDING_BATCH.save(deps.storage, &batch_id)?;
+        return Ok(Response::new().add_attributes(attributes));
+    }
+
+    // send the combined diff to all registered hooks
+    PENDING_BATCH.remove(deps.storage);
+    let pending = BATCH_DIFFS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut diffs = Vec::with_capacity(pending.len());
+    for (addr, diff) in pending {
+        BATCH_DIFFS.remove(deps.storage, &addr);
+        diffs.push(diff);
+    }
+    let messages = if diffs.is_empty() {
+        vec![]
+    } else {
+        let diff = MemberChangedHookMsg::new(diffs);
+        prepare_hooks(deps.storage, diff, env.block.height)?
+    };
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attributes(attributes))
+}
+
+/// Builds the messages sending `diff` to all registered hooks, see `InstantiateMsg::isolate_hooks`
+/// and `InstantiateMsg::hook_height`
+fn prepare_hooks(
+    storage: &dyn Storage,
+    mut diff: MemberChangedHookMsg,
+    height: u64,
+) -> StdResult<Vec<SubMsg>> {
+    if HOOK_HEIGHT.may_load(storage)?.unwrap_or_default() {
+        diff = diff.with_height(height);
+    }
+    let prep = |h| diff.clone().into_cosmos_msg(h).map(SubMsg::new);
+    if ISOLATE_HOOKS.may_load(storage)?.unwrap_or_default() {
+        HOOKS.prepare_hooks_with_reply(storage, HOOK_REPLY_ID, prep)
+    } else {
+        HOOKS.prepare_hooks(storage, prep)
+    }
+}
+
+/// Only called for isolated hooks which failed. The member update is kept.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let hook = HOOKS
+        .hook_for_reply(deps.storage, HOOK_REPLY_ID, msg.id)?
+        .ok_or(ContractError::UnknownReplyId { id: msg.id })?;
+    let error = msg.result.into_result().err().unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", "hook_failed")
+        .add_attribute("hook", hook)
+        .add_attribute("error", error))
+}
+
 // the logic from execute_update_members extracted for easier import
 pub fn update_members(
     deps: DepsMut,
     height: u64,
     sender: Addr,
-    mut to_add: Vec<Member>,
+    mut to_add: Vec<MemberWithMetadata>,
     to_remove: Vec<String>,
 ) -> Result<MemberChangedHookMsg, ContractError> {
     validate_unique_members(&mut to_add)?;
     let to_add = to_add; // let go of mutability
+    if to_add
+        .iter()
+        .any(|m| m.metadata.as_ref().map_or(0, String::len) > MAX_METADATA_LEN)
+    {
+        return Err(ContractError::MetadataTooLong {
+            max: MAX_METADATA_LEN,
+        });
+    }
 
     ADMIN.assert_admin(deps.as_ref(), &sender)?;
 
@@ -134,27 +321,34 @@ pub fn update_members(
     // add all new members and update total
     for add in to_add.into_iter() {
         let add_addr = deps.api.addr_validate(&add.addr)?;
-        MEMBERS.update(deps.storage, &add_addr, height, |old| -> StdResult<_> {
+        members().update(deps.storage, &add_addr, height, |old| -> StdResult<_> {
             total = total.checked_sub(Uint64::from(old.unwrap_or_default()))?;
             total = total.checked_add(Uint64::from(add.weight))?;
             diffs.push(MemberDiff::new(add.addr, old, Some(add.weight)));
             Ok(add.weight)
         })?;
+        record_member_change(deps.storage, &add_addr, height)?;
+        match add.metadata {
+            Some(metadata) => METADATA.save(deps.storage, &add_addr, &metadata)?,
+            None => METADATA.remove(deps.storage, &add_addr),
+        }
     }
 
     for remove in to_remove.into_iter() {
         let remove_addr = deps.api.addr_validate(&remove)?;
-        let old = MEMBERS.may_load(deps.storage, &remove_addr)?;
+        let old = members().may_load(deps.storage, &remove_addr)?;
         // Only process this if they were actually in the list before
         if let Some(weight) = old {
             diffs.push(MemberDiff::new(remove, Some(weight), None));
             total = total.checked_sub(Uint64::from(weight))?;
-            MEMBERS.remove(deps.storage, &remove_addr, height)?;
+            members().remove(deps.storage, &remove_addr, height)?;
+            record_member_change(deps.storage, &remove_addr, height)?;
+            METADATA.remove(deps.storage, &remove_addr);
         }
     }
 
     TOTAL.save(deps.storage, &total.u64(), height)?;
-    Ok(MemberChangedHookMsg { diffs })
+    Ok(MemberChangedHookMsg::new(diffs))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -167,15 +361,102 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ListMembers { start_after, limit } => {
             to_binary(&query_list_members(deps, start_after, limit)?)
         }
+        QueryMsg::MemberWithMetadata {
+            addr,
+            at_height: height,
+        } => to_binary(&query_member_with_metadata(deps, addr, height)?),
+        QueryMsg::ListMembersWithMetadata { start_after, limit } => {
+            to_binary(&query_list_members_with_metadata(deps, start_after, limit)?)
+        }
+        QueryMsg::ListMembersByWeight { start_after, limit } => {
+            to_binary(&query_list_members_by_weight(deps, start_after, limit)?)
+        }
         QueryMsg::TotalWeight { at_height: height } => {
             to_binary(&query_total_weight(deps, height)?)
         }
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::PendingAdmin {} => to_binary(&query_pending_admin(deps)?),
         QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
     }
 }
 
+// settings for pruning
+const DEFAULT_PRUNE_LIMIT: u32 = 100;
+
+pub fn execute_prune_history(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    up_to_height: u64,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    if up_to_height > env.block.height {
+        return Err(ContractError::PruneInFuture {
+            height: env.block.height,
+        });
+    }
+    let limit = limit.unwrap_or(DEFAULT_PRUNE_LIMIT) as usize;
+
+    // anything below this height is unreliable from now on, even if we hit the limit
+    let pruned = PRUNED_HEIGHT.may_load(deps.storage)?.unwrap_or_default();
+    PRUNED_HEIGHT.save(deps.storage, &pruned.max(up_to_height))?;
+
+    // changes at up_to_height and later are all we need to answer queries from there on
+    let member_changes = MEMBER_CHANGES
+        .prefix_range(
+            deps.storage,
+            None,
+            Some(PrefixBound::exclusive(up_to_height)),
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (height, addr) in member_changes.iter() {
+        members().changelog().remove(deps.storage, (addr, *height));
+        MEMBER_CHANGES.remove(deps.storage, (*height, addr));
+    }
+    let total_changes = TOTAL
+        .changelog()
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::exclusive(up_to_height)),
+            Order::Ascending,
+        )
+        .take(limit - member_changes.len())
+        .collect::<StdResult<Vec<_>>>()?;
+    for height in total_changes.iter() {
+        TOTAL.changelog().remove(deps.storage, *height);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "prune_history")
+        .add_attribute("up_to_height", up_to_height.to_string())
+        .add_attribute(
+            "pruned",
+            (member_changes.len() + total_changes.len()).to_string(),
+        )
+        .add_attribute("sender", info.sender))
+}
+
+// historical queries before the pruned height would silently return wrong data
+fn assert_not_pruned(deps: Deps, height: u64) -> StdResult<()> {
+    let pruned = PRUNED_HEIGHT.may_load(deps.storage)?.unwrap_or_default();
+    if height < pruned {
+        return Err(StdError::generic_err(format!(
+            "History before height {} was pruned",
+            pruned
+        )));
+    }
+    Ok(())
+}
+
 pub fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWeightResponse> {
+    if let Some(h) = height {
+        assert_not_pruned(deps, h)?;
+    }
     let weight = match height {
         Some(h) => TOTAL.may_load_at_height(deps.storage, h),
         None => TOTAL.may_load(deps.storage),
@@ -186,13 +467,35 @@ pub fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWei
 
 pub fn query_member(deps: Deps, addr: String, height: Option<u64>) -> StdResult<MemberResponse> {
     let addr = deps.api.addr_validate(&addr)?;
+    if let Some(h) = height {
+        assert_not_pruned(deps, h)?;
+    }
     let weight = match height {
-        Some(h) => MEMBERS.may_load_at_height(deps.storage, &addr, h),
-        None => MEMBERS.may_load(deps.storage, &addr),
+        Some(h) => members().may_load_at_height(deps.storage, &addr, h),
+        None => members().may_load(deps.storage, &addr),
     }?;
     Ok(MemberResponse { weight })
 }
 
+pub fn query_pending_admin(deps: Deps) -> StdResult<PendingAdminResponse> {
+    let admin = ADMIN.query_admin(deps)?.admin;
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?.map(String::from);
+    Ok(PendingAdminResponse {
+        admin,
+        pending_admin,
+    })
+}
+
+pub fn query_member_with_metadata(
+    deps: Deps,
+    addr: String,
+    height: Option<u64>,
+) -> StdResult<MemberWithMetadataResponse> {
+    let weight = query_member(deps, addr.clone(), height)?.weight;
+    let metadata = METADATA.may_load(deps.storage, &deps.api.addr_validate(&addr)?)?;
+    Ok(MemberWithMetadataResponse { weight, metadata })
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -206,7 +509,7 @@ pub fn query_list_members(
     let addr = maybe_addr(deps.api, start_after)?;
     let start = addr.as_ref().map(Bound::exclusive);
 
-    let members = MEMBERS
+    let members = members()
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -219,3 +522,87 @@ pub fn query_list_members(
 
     Ok(MemberListResponse { members })
 }
+
+pub fn query_list_members_by_weight(
+    deps: Deps,
+    start_after: Option<Member>,
+    limit: Option<u32>,
+) -> StdResult<MemberListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|m| deps.api.addr_validate(&m.addr).map(|addr| (m.weight, addr)))
+        .transpose()?;
+    let end = start
+        .as_ref()
+        .map(|(weight, addr)| Bound::exclusive((*weight, addr)));
+
+    let members = members()
+        .idx
+        .weight
+        .range(deps.storage, None, end, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(addr, weight)| Member {
+                addr: addr.into(),
+                weight,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(MemberListResponse { members })
+}
+
+pub fn query_list_members_with_metadata(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<MemberWithMetadataListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let addr = maybe_addr(deps.api, start_after)?;
+    let start = addr.as_ref().map(Bound::exclusive);
+
+    let members = members()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (addr, weight) = item?;
+            let metadata = METADATA.may_load(deps.storage, &addr)?;
+            Ok(MemberWithMetadata {
+                addr: addr.into(),
+                weight,
+                metadata,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(MemberWithMetadataListResponse { members })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // member weights keep their format, as cw4 raw queries read them directly,
+    // and members stored before metadata existed simply have none
+    let stored = ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // the weight index and MEMBER_CHANGES came with 1.1.0, fill them for the members stored before
+    if stored < Version::new(1, 1, 0) {
+        let members = members();
+        let all = members
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (addr, weight) in all {
+            members
+                .idx
+                .weight
+                .save(deps.storage, addr.as_bytes(), &weight)?;
+        }
+        let changes = members
+            .changelog()
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (addr, height) in changes {
+            record_member_change(deps.storage, &addr, height)?;
+        }
+    }
+    Ok(Response::default())
+}
@@ -20,6 +20,21 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("No admin transfer is pending")]
+    NoAdminOffer {},
+
     #[error("Message contained duplicate member: {member}")]
     DuplicateMember { member: String },
+
+    #[error("Member metadata cannot be longer than {max} bytes")]
+    MetadataTooLong { max: usize },
+
+    #[error("Member batch {batch_id} is still in progress")]
+    BatchInProgress { batch_id: u64 },
+
+    #[error("Cannot prune history after the current height {height}")]
+    PruneInFuture { height: u64 },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
 }
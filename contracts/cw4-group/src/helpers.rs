@@ -4,7 +4,8 @@ use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{to_binary, Addr, CosmosMsg, StdResult, WasmMsg};
 use cw4::{Cw4Contract, Member};
 
-use crate::{msg::ExecuteMsg, ContractError};
+use crate::msg::{ExecuteMsg, MemberWithMetadata};
+use crate::ContractError;
 
 /// Cw4GroupContract is a wrapper around Cw4Contract that provides a lot of helpers
 /// for working with cw4-group contracts.
@@ -36,13 +37,14 @@ impl Cw4GroupContract {
     }
 
     pub fn update_members(&self, remove: Vec<String>, add: Vec<Member>) -> StdResult<CosmosMsg> {
+        let add = add.into_iter().map(Into::into).collect();
         let msg = ExecuteMsg::UpdateMembers { remove, add };
         self.encode_msg(msg)
     }
 }
 
 /// Sorts the slice and verifies all member addresses are unique.
-pub fn validate_unique_members(members: &mut [Member]) -> Result<(), ContractError> {
+pub fn validate_unique_members(members: &mut [MemberWithMetadata]) -> Result<(), ContractError> {
     members.sort_by(|a, b| a.addr.cmp(&b.addr));
     for (a, b) in members.iter().zip(members.iter().skip(1)) {
         if a.addr == b.addr {
@@ -0,0 +1,380 @@
+#![cfg(test)]
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdError, StdResult,
+};
+use cw4::{Member, MemberChangedHookMsg, MemberDiff, MemberListResponse};
+use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+
+use crate::contract::{execute, instantiate, migrate, query, reply};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, MemberWithMetadata, MemberWithMetadataListResponse, MigrateMsg,
+    QueryMsg,
+};
+use crate::ContractError;
+
+const OWNER: &str = "owner";
+
+fn contract_group() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query)
+        .with_migrate(migrate)
+        .with_reply(reply);
+    Box::new(contract)
+}
+
+// A hook receiver remembering every MemberChangedHook message it got
+#[cw_serde]
+enum ReceiverExecuteMsg {
+    MemberChangedHook(MemberChangedHookMsg),
+}
+
+const RECEIVED: Item<Vec<MemberChangedHookMsg>> = Item::new("received");
+
+fn receiver_instantiate(deps: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+    RECEIVED.save(deps.storage, &vec![])?;
+    Ok(Response::new())
+}
+
+fn receiver_execute(
+    deps: DepsMut,
+    _: Env,
+    _: MessageInfo,
+    msg: ReceiverExecuteMsg,
+) -> StdResult<Response> {
+    let ReceiverExecuteMsg::MemberChangedHook(hook) = msg;
+    RECEIVED.update(deps.storage, |mut received| -> StdResult<_> {
+        received.push(hook);
+        Ok(received)
+    })?;
+    Ok(Response::new())
+}
+
+fn receiver_query(deps: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+    to_binary(&RECEIVED.load(deps.storage)?)
+}
+
+fn contract_receiver() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(receiver_execute, receiver_instantiate, receiver_query);
+    Box::new(contract)
+}
+
+fn failing_execute(
+    _: DepsMut,
+    _: Env,
+    _: MessageInfo,
+    _: ReceiverExecuteMsg,
+) -> StdResult<Response> {
+    Err(StdError::generic_err("hook rejected"))
+}
+
+fn contract_failing_receiver() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(failing_execute, receiver_instantiate, receiver_query);
+    Box::new(contract)
+}
+
+fn member<T: Into<String>>(addr: T, weight: u64) -> Member {
+    Member {
+        addr: addr.into(),
+        weight,
+    }
+}
+
+// Returns (group address, hook receiver addresses)
+fn setup(
+    app: &mut App,
+    isolate_hooks: Option<bool>,
+    hook_height: Option<bool>,
+) -> (Addr, Vec<Addr>) {
+    let group_id = app.store_code(contract_group());
+    let group = app
+        .instantiate_contract(
+            group_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                admin: Some(OWNER.into()),
+                members: vec![member("alice", 5), member("bob", 3)],
+                isolate_hooks,
+                hook_height,
+            },
+            &[],
+            "group",
+            Some(OWNER.into()),
+        )
+        .unwrap();
+
+    let receiver_id = app.store_code(contract_receiver());
+    let receivers: Vec<_> = (0..2)
+        .map(|i| {
+            app.instantiate_contract(
+                receiver_id,
+                Addr::unchecked(OWNER),
+                &Empty {},
+                &[],
+                format!("receiver {}", i),
+                None,
+            )
+            .unwrap()
+        })
+        .collect();
+    for receiver in &receivers {
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            group.clone(),
+            &ExecuteMsg::AddHook {
+                addr: receiver.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+    (group, receivers)
+}
+
+fn batch(
+    app: &mut App,
+    group: &Addr,
+    batch_id: u64,
+    add: Vec<Member>,
+    remove: Vec<&str>,
+    done: bool,
+) -> anyhow::Result<AppResponse> {
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        group.clone(),
+        &ExecuteMsg::UpdateMembersBatch {
+            batch_id,
+            add: add.into_iter().map(Into::into).collect(),
+            remove: remove.into_iter().map(String::from).collect(),
+            done,
+        },
+        &[],
+    )
+}
+
+fn received(app: &App, receiver: &Addr) -> Vec<MemberChangedHookMsg> {
+    app.wrap().query_wasm_smart(receiver, &Empty {}).unwrap()
+}
+
+#[test]
+fn batch_delivers_one_combined_diff() {
+    let mut app = App::default();
+    let (group, receivers) = setup(&mut app, None, Some(true));
+
+    batch(
+        &mut app,
+        &group,
+        1,
+        vec![member("carl", 2)],
+        vec!["bob"],
+        false,
+    )
+    .unwrap();
+    // members are updated right away, but nothing is sent yet
+    let members: MemberListResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &group,
+            &QueryMsg::ListMembers {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(members.members, vec![member("alice", 5), member("carl", 2)]);
+    for receiver in &receivers {
+        assert_eq!(received(&app, receiver), vec![]);
+    }
+
+    // a different batch cannot start, neither can a plain update
+    let err = batch(&mut app, &group, 2, vec![member("dave", 1)], vec![], true).unwrap_err();
+    assert_eq!(
+        ContractError::BatchInProgress { batch_id: 1 },
+        err.downcast().unwrap()
+    );
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            group.clone(),
+            &ExecuteMsg::UpdateMembers {
+                remove: vec![],
+                add: vec![member("dave", 1).into()],
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::BatchInProgress { batch_id: 1 },
+        err.downcast().unwrap()
+    );
+
+    // bob comes back with the same weight, carl changes again
+    batch(&mut app, &group, 1, vec![member("carl", 4)], vec![], false).unwrap();
+    batch(
+        &mut app,
+        &group,
+        1,
+        vec![member("bob", 3)],
+        vec!["alice"],
+        true,
+    )
+    .unwrap();
+
+    // stamped with the height of the closing batch, as this group asked for it
+    let expected = vec![MemberChangedHookMsg::new(vec![
+        MemberDiff::new("alice", Some(5), None),
+        MemberDiff::new("carl", None, Some(4)),
+    ])
+    .with_height(app.block_info().height)];
+    for receiver in &receivers {
+        assert_eq!(received(&app, receiver), expected);
+    }
+
+    // a new batch can start now
+    batch(&mut app, &group, 2, vec![member("dave", 1)], vec![], true).unwrap();
+    for receiver in &receivers {
+        assert_eq!(received(&app, receiver).len(), 2);
+    }
+}
+
+fn add_failing_hook(app: &mut App, group: &Addr) -> Addr {
+    let failing_id = app.store_code(contract_failing_receiver());
+    let failing = app
+        .instantiate_contract(
+            failing_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "failing receiver",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        group.clone(),
+        &ExecuteMsg::AddHook {
+            addr: failing.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    failing
+}
+
+fn update_members(app: &mut App, group: &Addr, add: Vec<Member>) -> anyhow::Result<AppResponse> {
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        group.clone(),
+        &ExecuteMsg::UpdateMembers {
+            remove: vec![],
+            add: add.into_iter().map(Into::into).collect(),
+        },
+        &[],
+    )
+}
+
+fn weight(app: &App, group: &Addr, addr: &str) -> Option<u64> {
+    cw4::Cw4Contract::new(group.clone())
+        .is_member(&app.wrap(), &Addr::unchecked(addr), None)
+        .unwrap()
+}
+
+#[test]
+fn failing_hook_aborts_update_by_default() {
+    let mut app = App::default();
+    let (group, receivers) = setup(&mut app, None, None);
+    add_failing_hook(&mut app, &group);
+
+    update_members(&mut app, &group, vec![member("carl", 2)]).unwrap_err();
+    assert_eq!(weight(&app, &group, "carl"), None);
+    for receiver in &receivers {
+        assert_eq!(received(&app, receiver), vec![]);
+    }
+}
+
+#[test]
+fn isolated_hooks_keep_update_when_one_fails() {
+    let mut app = App::default();
+    let (group, receivers) = setup(&mut app, Some(true), None);
+    let failing = add_failing_hook(&mut app, &group);
+
+    let res = update_members(&mut app, &group, vec![member("carl", 2)]).unwrap();
+    assert_eq!(weight(&app, &group, "carl"), Some(2));
+    let expected = vec![MemberChangedHookMsg::one(MemberDiff::new(
+        "carl",
+        None,
+        Some(2),
+    ))];
+    for receiver in &receivers {
+        assert_eq!(received(&app, receiver), expected);
+    }
+
+    // the failure is reported by the group
+    let failed = res
+        .events
+        .iter()
+        .find(|e| {
+            e.attributes
+                .iter()
+                .any(|a| a.key == "action" && a.value == "hook_failed")
+        })
+        .unwrap();
+    let hook = failed.attributes.iter().find(|a| a.key == "hook").unwrap();
+    assert_eq!(hook.value, failing.as_str());
+}
+
+#[test]
+fn migrate_keeps_weight_only_members() {
+    let mut app = App::default();
+    let (group, _) = setup(&mut app, None, None);
+    let new_id = app.store_code(contract_group());
+    app.migrate_contract(
+        Addr::unchecked(OWNER),
+        group.clone(),
+        &MigrateMsg {},
+        new_id,
+    )
+    .unwrap();
+
+    // members from before keep their weight, and are visible to cw4 raw queries
+    let cw4 = cw4::Cw4Contract::new(group.clone());
+    let weight = cw4
+        .is_member(&app.wrap(), &Addr::unchecked("alice"), None)
+        .unwrap();
+    assert_eq!(weight, Some(5));
+    let query = QueryMsg::ListMembersWithMetadata {
+        start_after: None,
+        limit: None,
+    };
+    let members: MemberWithMetadataListResponse =
+        app.wrap().query_wasm_smart(&group, &query).unwrap();
+    assert_eq!(
+        members.members,
+        vec![member("alice", 5).into(), member("bob", 3).into()]
+    );
+
+    // and metadata can be added afterwards
+    let carl = MemberWithMetadata {
+        addr: "carl".into(),
+        weight: 1,
+        metadata: Some("Carl".into()),
+    };
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        group.clone(),
+        &ExecuteMsg::UpdateMembers {
+            remove: vec![],
+            add: vec![carl.clone()],
+        },
+        &[],
+    )
+    .unwrap();
+    let members: MemberWithMetadataListResponse =
+        app.wrap().query_wasm_smart(&group, &query).unwrap();
+    assert_eq!(members.members[2], carl);
+    let weight = cw4
+        .is_member(&app.wrap(), &Addr::unchecked("carl"), None)
+        .unwrap();
+    assert_eq!(weight, Some(1));
+}
@@ -17,6 +17,7 @@ For more information on this contract, please check out the
 pub mod contract;
 pub mod error;
 pub mod helpers;
+mod integration_tests;
 pub mod msg;
 pub mod state;
 
@@ -7,22 +7,70 @@ pub struct InstantiateMsg {
     /// Omit it to make the group immutable.
     pub admin: Option<String>,
     pub members: Vec<Member>,
+    /// If true, a failing hook does not abort the member update. The failure is
+    /// only reported in a `hook_failed` event. Defaults to false
+    pub isolate_hooks: Option<bool>,
+    /// If true, hook messages carry the height the diffs were applied at, see
+    /// `MemberChangedHookMsg::height`. Only enable it if all hooks accept that field.
+    /// Defaults to false
+    pub hook_height: Option<bool>,
+}
+
+/// A member with an optional metadata blob (eg. display name or an external id)
+/// of at most `MAX_METADATA_LEN` bytes. Updating a member replaces its metadata.
+#[cw_serde]
+pub struct MemberWithMetadata {
+    pub addr: String,
+    pub weight: u64,
+    pub metadata: Option<String>,
+}
+
+impl From<Member> for MemberWithMetadata {
+    fn from(member: Member) -> Self {
+        MemberWithMetadata {
+            addr: member.addr,
+            weight: member.weight,
+            metadata: None,
+        }
+    }
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    /// Change the admin
+    /// Change the admin right away. Deprecated in favor of OfferAdmin, as a typo
+    /// in the new admin cannot be undone. Kept for cw4 compatibility
     UpdateAdmin { admin: Option<String> },
+    /// Offer the admin role to new_admin, replacing any previous offer. Must be called by Admin
+    OfferAdmin { new_admin: String },
+    /// Complete the transfer. Must be called by the address the admin role was offered to
+    AcceptAdmin {},
+    /// Cancel the pending offer. Must be called by Admin
+    RevokeAdminOffer {},
     /// apply a diff to the existing members.
     /// remove is applied after add, so if an address is in both, it is removed
     UpdateMembers {
         remove: Vec<String>,
-        add: Vec<Member>,
+        add: Vec<MemberWithMetadata>,
+    },
+    /// apply a diff like UpdateMembers, as one part of a larger update.
+    /// The diffs of all parts are combined and only sent to the hooks once `done` is set.
+    /// Only one batch can be in progress, other updates are rejected until it is done
+    UpdateMembersBatch {
+        batch_id: u64,
+        remove: Vec<String>,
+        add: Vec<MemberWithMetadata>,
+        done: bool,
     },
     /// Add a new hook to be informed of all membership changes. Must be called by Admin
     AddHook { addr: String },
     /// Remove a hook. Must be called by Admin
     RemoveHook { addr: String },
+    /// Delete snapshot history older than up_to_height, at most limit entries per call.
+    /// Historical queries before that height fail afterwards. Must be called by Admin
+    PruneHistory {
+        up_to_height: u64,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
@@ -30,6 +78,9 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     #[returns(cw_controllers::AdminResponse)]
     Admin {},
+    /// Shows the current admin and the address the admin role is offered to, if any
+    #[returns(PendingAdminResponse)]
+    PendingAdmin {},
     #[returns(cw4::TotalWeightResponse)]
     TotalWeight { at_height: Option<u64> },
     #[returns(cw4::MemberListResponse)]
@@ -37,12 +88,51 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Lists members ordered by weight, highest first.
+    /// start_after is the last member of the previous page.
+    #[returns(cw4::MemberListResponse)]
+    ListMembersByWeight {
+        start_after: Option<Member>,
+        limit: Option<u32>,
+    },
     #[returns(cw4::MemberResponse)]
     Member {
         addr: String,
         at_height: Option<u64>,
     },
+    /// Like Member, but also returns the current metadata
+    #[returns(MemberWithMetadataResponse)]
+    MemberWithMetadata {
+        addr: String,
+        at_height: Option<u64>,
+    },
+    /// Like ListMembers, but also returns the metadata of each member
+    #[returns(MemberWithMetadataListResponse)]
+    ListMembersWithMetadata {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Shows all registered hooks.
     #[returns(cw_controllers::HooksResponse)]
     Hooks {},
 }
+
+#[cw_serde]
+pub struct PendingAdminResponse {
+    pub admin: Option<String>,
+    pub pending_admin: Option<String>,
+}
+
+#[cw_serde]
+pub struct MemberWithMetadataResponse {
+    pub weight: Option<u64>,
+    pub metadata: Option<String>,
+}
+
+#[cw_serde]
+pub struct MemberWithMetadataListResponse {
+    pub members: Vec<MemberWithMetadata>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
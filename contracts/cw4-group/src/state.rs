@@ -1,13 +1,23 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Empty, StdResult, Storage};
 use cw4::{
-    MEMBERS_CHANGELOG, MEMBERS_CHECKPOINTS, MEMBERS_KEY, TOTAL_KEY, TOTAL_KEY_CHANGELOG,
-    TOTAL_KEY_CHECKPOINTS,
+    MemberDiff, MEMBERS_CHANGELOG, MEMBERS_CHECKPOINTS, MEMBERS_KEY, TOTAL_KEY,
+    TOTAL_KEY_CHANGELOG, TOTAL_KEY_CHECKPOINTS,
 };
 use cw_controllers::{Admin, Hooks};
-use cw_storage_plus::{SnapshotItem, SnapshotMap, Strategy};
+use cw_storage_plus::{
+    Index, IndexList, IndexedSnapshotMap, Item, Map, MultiIndex, SnapshotItem, Strategy,
+};
 
 pub const ADMIN: Admin = Admin::new("admin");
-pub const HOOKS: Hooks = Hooks::new("cw4-hooks");
+/// address the admin role was offered to with OfferAdmin
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+/// Maximum number of hooks, so that member updates stay within gas limits
+pub const MAX_HOOKS: u32 = 32;
+pub const HOOKS: Hooks = Hooks::with_max("cw4-hooks", MAX_HOOKS);
+/// whether hooks are dispatched with reply_on_error, see `InstantiateMsg::isolate_hooks`
+pub const ISOLATE_HOOKS: Item<bool> = Item::new("isolate_hooks");
+/// whether hook messages carry the height, see `InstantiateMsg::hook_height`
+pub const HOOK_HEIGHT: Item<bool> = Item::new("hook_height");
 
 pub const TOTAL: SnapshotItem<u64> = SnapshotItem::new(
     TOTAL_KEY,
@@ -16,9 +26,48 @@ pub const TOTAL: SnapshotItem<u64> = SnapshotItem::new(
     Strategy::EveryBlock,
 );
 
-pub const MEMBERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
-    MEMBERS_KEY,
-    MEMBERS_CHECKPOINTS,
-    MEMBERS_CHANGELOG,
-    Strategy::EveryBlock,
-);
+/// metadata of the members, only the current value is kept
+pub const METADATA: Map<&Addr, String> = Map::new("member_metadata");
+
+/// id of the UpdateMembersBatch in progress, if any
+pub const PENDING_BATCH: Item<u64> = Item::new("pending_batch");
+/// combined diff of the batch in progress, not yet sent to the hooks
+pub const BATCH_DIFFS: Map<&Addr, MemberDiff> = Map::new("batch_diffs");
+
+/// history before this height was removed by PruneHistory
+pub const PRUNED_HEIGHT: Item<u64> = Item::new("pruned_height");
+
+/// (height, member) of every entry in the members changelog, so PruneHistory
+/// can walk it in height order
+pub const MEMBER_CHANGES: Map<(u64, &Addr), Empty> = Map::new("member_changes");
+
+pub struct MemberIndexes<'a> {
+    // pk goes to second tuple element
+    pub weight: MultiIndex<'a, u64, u64, &'a Addr>,
+}
+
+impl<'a> IndexList<u64> for MemberIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<u64>> + '_> {
+        let v: Vec<&dyn Index<u64>> = vec![&self.weight];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Notes the changelog entry `members()` writes when `addr` changes at `height`
+pub fn record_member_change(storage: &mut dyn Storage, addr: &Addr, height: u64) -> StdResult<()> {
+    MEMBER_CHANGES.save(storage, (height, addr), &Empty {})
+}
+
+/// Members with their weight history. The weight index only reflects the current state.
+pub fn members<'a>() -> IndexedSnapshotMap<'a, &'a Addr, u64, MemberIndexes<'a>> {
+    let indexes = MemberIndexes {
+        weight: MultiIndex::new(|_, &w| w, MEMBERS_KEY, "members__weight"),
+    };
+    IndexedSnapshotMap::new(
+        MEMBERS_KEY,
+        MEMBERS_CHECKPOINTS,
+        MEMBERS_CHANGELOG,
+        Strategy::EveryBlock,
+        indexes,
+    )
+}
@@ -1,13 +1,22 @@
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_slice, Addr, Api, DepsMut, OwnedDeps, Querier, Storage, SubMsg};
+use cosmwasm_std::{
+    attr, from_slice, Addr, Api, DepsMut, Order, OwnedDeps, Querier, StdError, StdResult, Storage,
+    SubMsg,
+};
 use cw4::{member_key, Member, MemberChangedHookMsg, MemberDiff, TOTAL_KEY};
 use cw_controllers::{AdminError, HookError};
+use cw_storage_plus::Index;
 
 use crate::contract::{
-    execute, instantiate, query_list_members, query_member, query_total_weight, update_members,
+    execute, instantiate, migrate, query_list_members, query_list_members_by_weight,
+    query_list_members_with_metadata, query_member, query_member_with_metadata,
+    query_pending_admin, query_total_weight, update_members, MAX_METADATA_LEN,
+};
+use crate::msg::{
+    ExecuteMsg, InstantiateMsg, MemberWithMetadata, MemberWithMetadataResponse, MigrateMsg,
+    PendingAdminResponse,
 };
-use crate::msg::{ExecuteMsg, InstantiateMsg};
-use crate::state::{ADMIN, HOOKS};
+use crate::state::{members, ADMIN, HOOKS, HOOK_HEIGHT, MEMBER_CHANGES};
 use crate::ContractError;
 
 const INIT_ADMIN: &str = "juan";
@@ -28,6 +37,8 @@ fn set_up(deps: DepsMut) {
                 weight: 6,
             },
         ],
+        isolate_hooks: None,
+        hook_height: None,
     };
     let info = mock_info("creator", &[]);
     instantiate(deps, mock_env(), info, msg).unwrap();
@@ -85,6 +96,8 @@ fn duplicate_members_instantiation() {
                 weight: 6,
             },
         ],
+        isolate_hooks: None,
+        hook_height: None,
     };
     let info = mock_info("creator", &[]);
     let err = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
@@ -103,13 +116,15 @@ fn duplicate_members_execution() {
     set_up(deps.as_mut());
 
     let add = vec![
-        Member {
+        MemberWithMetadata {
             addr: USER3.into(),
             weight: 15,
+            metadata: None,
         },
-        Member {
+        MemberWithMetadata {
             addr: USER3.into(),
             weight: 11,
+            metadata: None,
         },
     ];
 
@@ -150,7 +165,7 @@ fn assert_users<S: Storage, A: Api, Q: Querier>(
     // this is only valid if we are not doing a historical query
     if height.is_none() {
         // compute expected metrics
-        let weights = vec![user1_weight, user2_weight, user3_weight];
+        let weights = [user1_weight, user2_weight, user3_weight];
         let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
         let count = weights.iter().filter(|x| x.is_some()).count();
 
@@ -169,9 +184,10 @@ fn add_new_remove_old_member() {
     set_up(deps.as_mut());
 
     // add a new one and remove existing one
-    let add = vec![Member {
+    let add = vec![MemberWithMetadata {
         addr: USER3.into(),
         weight: 15,
+        metadata: None,
     }];
     let remove = vec![USER1.into()];
 
@@ -218,9 +234,10 @@ fn add_old_remove_new_member() {
     set_up(deps.as_mut());
 
     // add a new one and remove existing one
-    let add = vec![Member {
+    let add = vec![MemberWithMetadata {
         addr: USER1.into(),
         weight: 4,
+        metadata: None,
     }];
     let remove = vec![USER3.into()];
 
@@ -245,13 +262,15 @@ fn add_and_remove_same_member() {
 
     // USER1 is updated and remove in the same call, we should remove this an add member3
     let add = vec![
-        Member {
+        MemberWithMetadata {
             addr: USER1.into(),
             weight: 20,
+            metadata: None,
         },
-        Member {
+        MemberWithMetadata {
             addr: USER3.into(),
             weight: 5,
+            metadata: None,
         },
     ];
     let remove = vec![USER1.into()];
@@ -357,20 +376,22 @@ fn hooks_fire() {
     let add_msg2 = ExecuteMsg::AddHook {
         addr: contract2.clone(),
     };
-    for msg in vec![add_msg, add_msg2] {
+    for msg in [add_msg, add_msg2] {
         let _ = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
     }
 
     // make some changes - add 3, remove 2, and update 1
     // USER1 is updated and remove in the same call, we should remove this an add member3
     let add = vec![
-        Member {
+        MemberWithMetadata {
             addr: USER1.into(),
             weight: 20,
+            metadata: None,
         },
-        Member {
+        MemberWithMetadata {
             addr: USER3.into(),
             weight: 5,
+            metadata: None,
         },
     ];
     let remove = vec![USER2.into()];
@@ -378,7 +399,7 @@ fn hooks_fire() {
 
     // admin updates properly
     assert_users(&deps, Some(11), Some(6), None, None);
-    let res = execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
     assert_users(&deps, Some(20), None, Some(5), None);
 
     // ensure 2 messages for the 2 hooks
@@ -390,13 +411,30 @@ fn hooks_fire() {
         MemberDiff::new(USER1, Some(11), Some(20)),
         MemberDiff::new(USER2, Some(6), None),
     ];
-    let hook_msg = MemberChangedHookMsg { diffs };
-    let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1).unwrap());
-    let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2).unwrap());
+    let hook_msg = MemberChangedHookMsg::new(diffs);
+    let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(&contract1).unwrap());
+    let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(&contract2).unwrap());
     dbg!(&res.messages);
     dbg!(&msg1);
     dbg!(&msg2);
     assert_eq!(res.messages, vec![msg1, msg2]);
+
+    // once enabled, the hooks also get the height
+    HOOK_HEIGHT.save(deps.as_mut().storage, &true).unwrap();
+    let msg = ExecuteMsg::UpdateMembers {
+        remove: vec![],
+        add: vec![MemberWithMetadata {
+            addr: USER3.into(),
+            weight: 6,
+            metadata: None,
+        }],
+    };
+    let res = execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap();
+    let hook_msg = MemberChangedHookMsg::one(MemberDiff::new(USER3, Some(5), Some(6)))
+        .with_height(mock_env().block.height);
+    let msg1 = SubMsg::new(hook_msg.clone().into_cosmos_msg(contract1).unwrap());
+    let msg2 = SubMsg::new(hook_msg.into_cosmos_msg(contract2).unwrap());
+    assert_eq!(res.messages, vec![msg1, msg2]);
 }
 
 #[test]
@@ -437,3 +475,467 @@ fn total_at_height() {
     let total = query_total_weight(deps.as_ref(), Some(height + 1)).unwrap();
     assert_eq!(17, total.weight);
 }
+
+#[test]
+fn prune_history() {
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+    let height = mock_env().block.height;
+
+    // churn members over a few blocks
+    for (delta, weight) in [(5, 3), (10, 8), (15, 1)] {
+        let add = vec![MemberWithMetadata {
+            addr: USER3.into(),
+            weight,
+            metadata: None,
+        }];
+        let admin = Addr::unchecked(INIT_ADMIN);
+        update_members(deps.as_mut(), height + delta, admin, add, vec![]).unwrap();
+    }
+    assert_users(&deps, Some(11), Some(6), Some(3), Some(height + 6));
+    assert_users(&deps, Some(11), Some(6), Some(8), Some(height + 11));
+
+    let mut env = mock_env();
+    env.block.height += 20;
+    let prune = |up_to_height, limit| ExecuteMsg::PruneHistory {
+        up_to_height,
+        limit,
+    };
+
+    // only the admin can prune, and not in the future
+    let info = mock_info(USER1, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, prune(height + 10, None)).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    let info = mock_info(INIT_ADMIN, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, prune(height + 21, None)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::PruneInFuture {
+            height: height + 20
+        }
+    );
+
+    // prune in batches: 3 member and 2 total changes before height + 10
+    let info = mock_info(INIT_ADMIN, &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        prune(height + 10, Some(3)),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[2], attr("pruned", "3"));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        prune(height + 10, Some(3)),
+    )
+    .unwrap();
+    assert_eq!(res.attributes[2], attr("pruned", "2"));
+    let res = execute(deps.as_mut(), env, info, prune(height + 10, Some(3))).unwrap();
+    assert_eq!(res.attributes[2], attr("pruned", "0"));
+
+    // newer history is intact
+    assert_users(&deps, Some(11), Some(6), Some(3), Some(height + 10));
+    assert_users(&deps, Some(11), Some(6), Some(8), Some(height + 11));
+    assert_users(&deps, Some(11), Some(6), Some(1), Some(height + 16));
+    assert_users(&deps, Some(11), Some(6), Some(1), None);
+
+    // older history is gone
+    let err = query_member(deps.as_ref(), USER3.into(), Some(height + 6)).unwrap_err();
+    let pruned = StdError::generic_err(format!("History before height {} was pruned", height + 10));
+    assert_eq!(err, pruned);
+    let err = query_total_weight(deps.as_ref(), Some(height + 9)).unwrap_err();
+    assert_eq!(err, pruned);
+
+    // only the remaining changes are left to walk through next time
+    let user3 = Addr::unchecked(USER3);
+    assert_eq!(
+        member_changes(&deps),
+        vec![(height + 10, user3.clone()), (height + 15, user3)]
+    );
+}
+
+// (height, member) of all entries in the members changelog, ordered by height
+fn member_changes(deps: &OwnedDeps<impl Storage, impl Api, impl Querier>) -> Vec<(u64, Addr)> {
+    MEMBER_CHANGES
+        .keys(&deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()
+        .unwrap()
+}
+
+#[test]
+fn list_members_by_weight() {
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+    let height = mock_env().block.height;
+
+    let member = |addr: &str, weight| Member {
+        addr: addr.into(),
+        weight,
+    };
+    let by_weight = |deps: &OwnedDeps<_, _, _>, start_after, limit| {
+        query_list_members_by_weight(deps.as_ref(), start_after, limit)
+            .unwrap()
+            .members
+    };
+    assert_eq!(
+        by_weight(&deps, None, None),
+        vec![member(USER1, 11), member(USER2, 6)]
+    );
+
+    // the index follows weight changes, additions and removals
+    let admin = Addr::unchecked(INIT_ADMIN);
+    let add = vec![member(USER3, 6).into(), member(USER2, 20).into()];
+    update_members(deps.as_mut(), height + 1, admin.clone(), add, vec![]).unwrap();
+    assert_eq!(
+        by_weight(&deps, None, None),
+        vec![member(USER2, 20), member(USER1, 11), member(USER3, 6)]
+    );
+    let remove = vec![USER1.into()];
+    update_members(deps.as_mut(), height + 2, admin, vec![], remove).unwrap();
+    assert_eq!(
+        by_weight(&deps, None, None),
+        vec![member(USER2, 20), member(USER3, 6)]
+    );
+
+    // paginate, also between members with equal weight (ordered by address, descending)
+    let admin = Addr::unchecked(INIT_ADMIN);
+    let add = vec![member(USER1, 6).into()];
+    update_members(deps.as_mut(), height + 3, admin, add, vec![]).unwrap();
+    let page = by_weight(&deps, None, Some(2));
+    assert_eq!(page, vec![member(USER2, 20), member(USER1, 6)]);
+    let page = by_weight(&deps, page.last().cloned(), Some(2));
+    assert_eq!(page, vec![member(USER3, 6)]);
+    let page = by_weight(&deps, page.last().cloned(), Some(2));
+    assert_eq!(page, vec![]);
+
+    // history is still kept for removed members
+    assert_users(&deps, Some(11), Some(20), Some(6), Some(height + 2));
+    assert_users(&deps, None, Some(20), Some(6), Some(height + 3));
+}
+
+#[test]
+fn member_metadata() {
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+    let height = mock_env().block.height;
+    let admin = Addr::unchecked(INIT_ADMIN);
+
+    // instantiated members have no metadata
+    let res = query_member_with_metadata(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(res.weight, Some(11));
+    assert_eq!(res.metadata, None);
+
+    let add = vec![
+        MemberWithMetadata {
+            addr: USER1.into(),
+            weight: 11,
+            metadata: Some("Alice".into()),
+        },
+        MemberWithMetadata {
+            addr: USER3.into(),
+            weight: 2,
+            metadata: Some("a".repeat(MAX_METADATA_LEN)),
+        },
+    ];
+    update_members(deps.as_mut(), height + 1, admin.clone(), add, vec![]).unwrap();
+    let res = query_member_with_metadata(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(res.metadata, Some("Alice".into()));
+    // the plain cw4 query is unchanged
+    let res = query_member(deps.as_ref(), USER1.into(), None).unwrap();
+    assert_eq!(res.weight, Some(11));
+
+    let members = query_list_members_with_metadata(deps.as_ref(), None, None)
+        .unwrap()
+        .members;
+    assert_eq!(
+        members
+            .iter()
+            .map(|m| m.metadata.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            None,
+            Some("a".repeat(MAX_METADATA_LEN)),
+            Some("Alice".into())
+        ]
+    );
+
+    // too long metadata is rejected, without changing anything
+    let add = vec![MemberWithMetadata {
+        addr: USER2.into(),
+        weight: 30,
+        metadata: Some("a".repeat(MAX_METADATA_LEN + 1)),
+    }];
+    let err = update_members(deps.as_mut(), height + 2, admin.clone(), add, vec![]).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MetadataTooLong {
+            max: MAX_METADATA_LEN
+        }
+    );
+    assert_users(&deps, Some(11), Some(6), Some(2), None);
+
+    // updating without metadata clears it, as does removal
+    let add = vec![MemberWithMetadata {
+        addr: USER1.into(),
+        weight: 12,
+        metadata: None,
+    }];
+    let remove = vec![USER3.into()];
+    update_members(deps.as_mut(), height + 3, admin, add, remove).unwrap();
+    let members = query_list_members_with_metadata(deps.as_ref(), None, None)
+        .unwrap()
+        .members;
+    assert!(members.iter().all(|m| m.metadata.is_none()));
+    let res = query_member_with_metadata(deps.as_ref(), USER3.into(), None).unwrap();
+    assert_eq!(
+        res,
+        MemberWithMetadataResponse {
+            weight: None,
+            metadata: None
+        }
+    );
+}
+
+#[test]
+fn two_step_admin_transfer() {
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+    let new_admin = "successor";
+    let offer = |new_admin: &str| ExecuteMsg::OfferAdmin {
+        new_admin: new_admin.into(),
+    };
+
+    // nothing to accept or revoke yet
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(new_admin, &[]),
+        ExecuteMsg::AcceptAdmin {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoAdminOffer {});
+
+    // only the admin can offer
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        offer(USER1),
+    )
+    .unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+
+    // an offer with a typo is overwritten by a new one
+    let admin_info = mock_info(INIT_ADMIN, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        offer("sucessor"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin_info.clone(),
+        offer(new_admin),
+    )
+    .unwrap();
+    let res = query_pending_admin(deps.as_ref()).unwrap();
+    assert_eq!(
+        res,
+        PendingAdminResponse {
+            admin: Some(INIT_ADMIN.into()),
+            pending_admin: Some(new_admin.into()),
+        }
+    );
+
+    // the admin did not change yet, and nobody else can accept
+    for sender in ["sucessor", USER1, INIT_ADMIN] {
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+    assert!(ADMIN
+        .is_admin(deps.as_ref(), &Addr::unchecked(INIT_ADMIN))
+        .unwrap());
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(new_admin, &[]),
+        ExecuteMsg::AcceptAdmin {},
+    )
+    .unwrap();
+    let res = query_pending_admin(deps.as_ref()).unwrap();
+    assert_eq!(
+        res,
+        PendingAdminResponse {
+            admin: Some(new_admin.into()),
+            pending_admin: None,
+        }
+    );
+
+    // the old admin lost its rights, the new one can revoke its own offers
+    let err = execute(deps.as_mut(), mock_env(), admin_info, offer(INIT_ADMIN)).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+    let new_admin_info = mock_info(new_admin, &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        new_admin_info.clone(),
+        offer(USER1),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        new_admin_info.clone(),
+        ExecuteMsg::RevokeAdminOffer {},
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(USER1, &[]),
+        ExecuteMsg::AcceptAdmin {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoAdminOffer {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        new_admin_info,
+        ExecuteMsg::RevokeAdminOffer {},
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoAdminOffer {});
+}
+
+#[test]
+fn update_admin_attributes() {
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+
+    let msg = ExecuteMsg::UpdateAdmin {
+        admin: Some(USER1.into()),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_admin"),
+            attr("admin", USER1),
+            attr("old_admin", INIT_ADMIN),
+            attr("sender", INIT_ADMIN),
+        ]
+    );
+
+    // clearing the admin makes the group immutable
+    let msg = ExecuteMsg::UpdateAdmin { admin: None };
+    let res = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap();
+    assert_eq!(res.attributes[1], attr("admin", "None"));
+    let msg = ExecuteMsg::UpdateAdmin {
+        admin: Some(USER1.into()),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap_err();
+    assert_eq!(err, AdminError::NotAdmin {}.into());
+}
+
+#[test]
+fn migrate_fills_weight_index() {
+    const CONTRACT_NAME: &str = "crates.io:cw4-group";
+
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+
+    // a group from before the weight index has only the members themselves
+    for (addr, weight) in [(USER1, 11), (USER2, 6)] {
+        members()
+            .idx
+            .weight
+            .remove(&mut deps.storage, addr.as_bytes(), &weight)
+            .unwrap();
+    }
+    let by_weight = |deps: &OwnedDeps<_, _, _>| {
+        query_list_members_by_weight(deps.as_ref(), None, None)
+            .unwrap()
+            .members
+    };
+    assert_eq!(by_weight(&deps), vec![]);
+    // nor does it know the heights of its changes
+    let changes = member_changes(&deps);
+    assert_eq!(changes.len(), 2);
+    for (height, addr) in changes.iter() {
+        MEMBER_CHANGES.remove(&mut deps.storage, (*height, addr));
+    }
+
+    cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "1.0.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        by_weight(&deps),
+        vec![
+            Member {
+                addr: USER1.into(),
+                weight: 11
+            },
+            Member {
+                addr: USER2.into(),
+                weight: 6
+            },
+        ]
+    );
+    assert_eq!(member_changes(&deps), changes);
+    // the history is left alone
+    assert_eq!(
+        query_member(deps.as_ref(), USER1.into(), Some(mock_env().block.height))
+            .unwrap()
+            .weight,
+        None
+    );
+}
+
+#[test]
+fn migrate_checks_contract_version() {
+    const CONTRACT_NAME: &str = "crates.io:cw4-group";
+    const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+    let mut deps = mock_dependencies();
+    set_up(deps.as_mut());
+
+    // a newer stored version cannot be downgraded
+    cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "999.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(format!(
+            "Cannot migrate from newer version (999.0.0) to older ({})",
+            CONTRACT_VERSION
+        )))
+    );
+
+    // nor can another contract be migrated into a group
+    cw2::set_contract_version(&mut deps.storage, "crate:cw20-base", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(format!(
+            "Cannot migrate from crate:cw20-base to {}",
+            CONTRACT_NAME
+        )))
+    );
+
+    // an older group is upgraded to the current version
+    cw2::set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    let version = cw2::get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.version, CONTRACT_VERSION);
+}
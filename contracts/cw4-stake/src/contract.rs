@@ -1,8 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, from_slice, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+    coins, from_slice, to_binary, Addr, BankMsg, Binary, Decimal, Deps, DepsMut, Env, MessageInfo,
+    Order, Reply, Response, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 
 use cw2::set_contract_version;
@@ -15,37 +15,59 @@ use cw_storage_plus::Bound;
 use cw_utils::{maybe_addr, NativeBalance};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedResponse};
-use crate::state::{Config, ADMIN, CLAIMS, CONFIG, HOOKS, MEMBERS, STAKE, TOTAL};
+use crate::msg::{
+    ClaimResponse, ClaimStatus, ClaimsResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg,
+    StakedResponse, TotalClaimsResponse, TotalStakedResponse,
+};
+use crate::state::{
+    next_claim_id, update_claims_total, update_stake, Claim, Config, ADMIN, CLAIMS, CLAIMS_TOTAL,
+    CONFIG, HOOKS, MEMBERS, SLASHERS, STAKE, TOTAL, TOTAL_STAKED,
+};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw4-stake";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Default limit on outstanding claims per address, which keeps claiming within gas limits
+pub const DEFAULT_MAX_CLAIMS: u32 = 32;
+
+/// Reply id of the first hook when hooks are isolated, the others follow in order
+const HOOK_REPLY_ID: u64 = 1;
+
 // Note, you can use StdResult in some functions where you do not
 // make use of the custom errors
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    if !msg.weight_scaling.is_valid() {
+        return Err(ContractError::InvalidWeightScaling {});
+    }
     let api = deps.api;
     ADMIN.set(deps.branch(), maybe_addr(api, msg.admin)?)?;
 
     // min_bond is at least 1, so 0 stake -> non-membership
     let min_bond = std::cmp::max(msg.min_bond, Uint128::new(1));
+    // same for min_unbond, so there are no empty claims
+    let min_unbond = std::cmp::max(msg.min_unbond.unwrap_or_default(), Uint128::new(1));
 
     let config = Config {
         denom: msg.denom,
-        tokens_per_weight: msg.tokens_per_weight,
+        weight_scaling: msg.weight_scaling,
         min_bond,
         unbonding_period: msg.unbonding_period,
+        min_unbond,
+        max_claims: msg.max_claims.unwrap_or(DEFAULT_MAX_CLAIMS),
+        isolate_hooks: msg.isolate_hooks.unwrap_or_default(),
+        hook_height: msg.hook_height.unwrap_or_default(),
     };
     CONFIG.save(deps.storage, &config)?;
-    TOTAL.save(deps.storage, &0)?;
+    TOTAL.save(deps.storage, &0, env.block.height)?;
+    TOTAL_STAKED.save(deps.storage, &Uint128::zero(), env.block.height)?;
 
     Ok(Response::default())
 }
@@ -69,9 +91,22 @@ pub fn execute(
         ExecuteMsg::RemoveHook { addr } => {
             Ok(HOOKS.execute_remove_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
         }
+        ExecuteMsg::AddSlasher { addr } => {
+            Ok(SLASHERS.execute_add_slasher(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
+        }
+        ExecuteMsg::RemoveSlasher { addr } => {
+            Ok(SLASHERS.execute_remove_slasher(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
+        }
+        ExecuteMsg::Slash { addr, portion } => {
+            execute_slash(deps, env, info, api.addr_validate(&addr)?, portion)
+        }
         ExecuteMsg::Bond {} => execute_bond(deps, env, Balance::from(info.funds), info.sender),
         ExecuteMsg::Unbond { tokens: amount } => execute_unbond(deps, env, info, amount),
         ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::CancelClaim { id } => execute_cancel_claim(deps, env, info, id),
+        ExecuteMsg::SweepClaims { addresses, limit } => {
+            execute_sweep_claims(deps, env, addresses, limit)
+        }
         ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
     }
 }
@@ -102,8 +137,8 @@ pub fn execute_bond(
     }?;
 
     // update the sender's stake
-    let new_stake = STAKE.update(deps.storage, &sender, |stake| -> StdResult<_> {
-        Ok(stake.unwrap_or_default() + amount)
+    let new_stake = update_stake(deps.storage, &sender, env.block.height, |stake| {
+        Ok(stake + amount)
     })?;
 
     let messages = update_membership(
@@ -114,11 +149,21 @@ pub fn execute_bond(
         env.block.height,
     )?;
 
-    Ok(Response::new()
+    let mut res = Response::new()
         .add_submessages(messages)
         .add_attribute("action", "bond")
         .add_attribute("amount", amount)
-        .add_attribute("sender", sender))
+        .add_attribute("sender", &sender);
+
+    // release any matured claims on the way
+    let release = release_claims(deps.storage, &env, &sender, None)?;
+    if !release.is_zero() {
+        let (amount_str, message) = payout(&cfg, &sender, release)?;
+        res = res
+            .add_submessage(message)
+            .add_attribute("claimed", amount_str);
+    }
+    Ok(res)
 }
 
 pub fn execute_receive(
@@ -150,19 +195,36 @@ pub fn execute_unbond(
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if amount < cfg.min_unbond {
+        return Err(ContractError::UnbondTooSmall {
+            min: cfg.min_unbond,
+        });
+    }
+    let outstanding = CLAIMS
+        .prefix(&info.sender)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if outstanding >= cfg.max_claims as usize {
+        return Err(ContractError::TooManyClaims {
+            max: cfg.max_claims,
+        });
+    }
+
     // reduce the sender's stake - aborting if insufficient
-    let new_stake = STAKE.update(deps.storage, &info.sender, |stake| -> StdResult<_> {
-        Ok(stake.unwrap_or_default().checked_sub(amount)?)
+    let new_stake = update_stake(deps.storage, &info.sender, env.block.height, |stake| {
+        Ok(stake.checked_sub(amount)?)
     })?;
 
     // provide them a claim
-    let cfg = CONFIG.load(deps.storage)?;
-    CLAIMS.create_claim(
-        deps.storage,
-        &info.sender,
+    let id = next_claim_id(deps.storage)?;
+    let claim = Claim {
         amount,
-        cfg.unbonding_period.after(&env.block),
-    )?;
+        created_at: env.block.height,
+        release_at: cfg.unbonding_period.after(&env.block),
+    };
+    CLAIMS.save(deps.storage, (&info.sender, id), &claim)?;
+    update_claims_total(deps.storage, amount, Uint128::zero())?;
 
     let messages = update_membership(
         deps.storage,
@@ -176,6 +238,98 @@ pub fn execute_unbond(
         .add_submessages(messages)
         .add_attribute("action", "unbond")
         .add_attribute("amount", amount)
+        .add_attribute("sender", info.sender)
+        .add_attribute("claim_id", id.to_string()))
+}
+
+pub fn execute_cancel_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    // only claims of the sender, which are not yet released, can be cancelled
+    let key = (&info.sender, id);
+    let claim = CLAIMS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::ClaimNotFound { id })?;
+    if claim.release_at.is_expired(&env.block) {
+        return Err(ContractError::CannotCancelClaim {});
+    }
+    CLAIMS.remove(deps.storage, key);
+    update_claims_total(deps.storage, Uint128::zero(), claim.amount)?;
+
+    // stake the tokens again, just like bond
+    let new_stake = update_stake(deps.storage, &info.sender, env.block.height, |stake| {
+        Ok(stake + claim.amount)
+    })?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let messages = update_membership(
+        deps.storage,
+        info.sender.clone(),
+        new_stake,
+        &cfg,
+        env.block.height,
+    )?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "cancel_claim")
+        .add_attribute("amount", claim.amount)
+        .add_attribute("sender", info.sender)
+        .add_attribute("claim_id", id.to_string()))
+}
+
+pub fn execute_slash(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addr: Addr,
+    portion: Decimal,
+) -> Result<Response, ContractError> {
+    SLASHERS.assert_slasher(deps.as_ref(), &info.sender)?;
+    if portion > Decimal::one() {
+        return Err(ContractError::InvalidSlashPortion {});
+    }
+    // multiplying by the remaining portion rounds down, so slashing never creates tokens
+    let remaining = Decimal::one() - portion;
+
+    let stake = STAKE.may_load(deps.storage, &addr)?.unwrap_or_default();
+    let new_stake = update_stake(deps.storage, &addr, env.block.height, |stake| {
+        Ok(stake * remaining)
+    })?;
+    let mut slashed = stake - new_stake;
+
+    // pending claims are slashed too
+    let claims = CLAIMS
+        .prefix(&addr)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut slashed_claims = Uint128::zero();
+    for (id, mut claim) in claims {
+        let amount = claim.amount * remaining;
+        slashed_claims += claim.amount - amount;
+        claim.amount = amount;
+        CLAIMS.save(deps.storage, (&addr, id), &claim)?;
+    }
+    update_claims_total(deps.storage, Uint128::zero(), slashed_claims)?;
+    slashed += slashed_claims;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let messages = update_membership(
+        deps.storage,
+        addr.clone(),
+        new_stake,
+        &cfg,
+        env.block.height,
+    )?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attribute("action", "slash")
+        .add_attribute("addr", addr)
+        .add_attribute("portion", portion.to_string())
+        .add_attribute("slashed", slashed)
         .add_attribute("sender", info.sender))
 }
 
@@ -217,25 +371,41 @@ fn update_membership(
     }?;
 
     // update total
-    TOTAL.update(storage, |total| -> StdResult<_> {
-        Ok(total + new.unwrap_or_default() - old.unwrap_or_default())
+    TOTAL.update(storage, height, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default() + new.unwrap_or_default() - old.unwrap_or_default())
     })?;
 
     // alert the hooks
-    let diff = MemberDiff::new(sender, old, new);
-    HOOKS.prepare_hooks(storage, |h| {
-        MemberChangedHookMsg::one(diff.clone())
-            .into_cosmos_msg(h)
-            .map(SubMsg::new)
-    })
+    let mut diff = MemberChangedHookMsg::one(MemberDiff::new(sender, old, new));
+    if cfg.hook_height {
+        diff = diff.with_height(height);
+    }
+    let prep = |h| diff.clone().into_cosmos_msg(h).map(SubMsg::new);
+    if cfg.isolate_hooks {
+        HOOKS.prepare_hooks_with_reply(storage, HOOK_REPLY_ID, prep)
+    } else {
+        HOOKS.prepare_hooks(storage, prep)
+    }
+}
+
+/// Only called for isolated hooks which failed. The stake change is kept.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let hook = HOOKS
+        .hook_for_reply(deps.storage, HOOK_REPLY_ID, msg.id)?
+        .ok_or(ContractError::UnknownReplyId { id: msg.id })?;
+    let error = msg.result.into_result().err().unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", "hook_failed")
+        .add_attribute("hook", hook)
+        .add_attribute("error", error))
 }
 
 fn calc_weight(stake: Uint128, cfg: &Config) -> Option<u64> {
     if stake < cfg.min_bond {
         None
     } else {
-        let w = stake.u128() / (cfg.tokens_per_weight.u128());
-        Some(w as u64)
+        Some(cfg.weight_scaling.weight(stake))
     }
 }
 
@@ -244,42 +414,102 @@ pub fn execute_claim(
     env: Env,
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
+    let release = release_claims(deps.storage, &env, &info.sender, None)?;
     if release.is_zero() {
         return Err(ContractError::NothingToClaim {});
     }
 
     let config = CONFIG.load(deps.storage)?;
-    let (amount_str, message) = match &config.denom {
+    let (amount_str, message) = payout(&config, &info.sender, release)?;
+
+    Ok(Response::new()
+        .add_submessage(message)
+        .add_attribute("action", "claim")
+        .add_attribute("tokens", amount_str)
+        .add_attribute("sender", info.sender))
+}
+
+pub fn execute_sweep_claims(
+    deps: DepsMut,
+    env: Env,
+    addresses: Vec<String>,
+    limit: u32,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut remaining = limit as usize;
+    let mut res = Response::new().add_attribute("action", "sweep_claims");
+    for addr in addresses {
+        if remaining == 0 {
+            break;
+        }
+        let addr = deps.api.addr_validate(&addr)?;
+        let release = release_claims(deps.storage, &env, &addr, Some(&mut remaining))?;
+        // addresses without matured claims are skipped, not an error
+        if release.is_zero() {
+            continue;
+        }
+        let (amount_str, message) = payout(&config, &addr, release)?;
+        res = res
+            .add_submessage(message)
+            .add_attribute("claimed", format!("{}: {}", addr, amount_str));
+    }
+    Ok(res)
+}
+
+/// Removes the matured claims of `addr` and returns the total amount released.
+/// If `limit` is given, at most that many claims are released and it is decreased accordingly.
+fn release_claims(
+    storage: &mut dyn Storage,
+    env: &Env,
+    addr: &Addr,
+    limit: Option<&mut usize>,
+) -> StdResult<Uint128> {
+    let mature = CLAIMS
+        .prefix(addr)
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, claim)) => claim.release_at.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .take(limit.as_deref().copied().unwrap_or(usize::MAX))
+        .collect::<StdResult<Vec<_>>>()?;
+    if let Some(limit) = limit {
+        *limit -= mature.len();
+    }
+    let mut release = Uint128::zero();
+    for (id, claim) in mature {
+        CLAIMS.remove(storage, (addr, id));
+        release += claim.amount;
+    }
+    update_claims_total(storage, Uint128::zero(), release)?;
+    Ok(release)
+}
+
+/// Builds the message sending `amount` staking tokens to `recipient`
+fn payout(config: &Config, recipient: &Addr, amount: Uint128) -> StdResult<(String, SubMsg)> {
+    match &config.denom {
         Denom::Native(denom) => {
-            let amount_str = coin_to_string(release, denom.as_str());
-            let amount = coins(release.u128(), denom);
+            let amount_str = coin_to_string(amount, denom.as_str());
             let message = SubMsg::new(BankMsg::Send {
-                to_address: info.sender.to_string(),
-                amount,
+                to_address: recipient.to_string(),
+                amount: coins(amount.u128(), denom),
             });
-            (amount_str, message)
+            Ok((amount_str, message))
         }
         Denom::Cw20(addr) => {
-            let amount_str = coin_to_string(release, addr.as_str());
+            let amount_str = coin_to_string(amount, addr.as_str());
             let transfer = Cw20ExecuteMsg::Transfer {
-                recipient: info.sender.clone().into(),
-                amount: release,
+                recipient: recipient.into(),
+                amount,
             };
             let message = SubMsg::new(WasmMsg::Execute {
                 contract_addr: addr.into(),
                 msg: to_binary(&transfer)?,
                 funds: vec![],
             });
-            (amount_str, message)
+            Ok((amount_str, message))
         }
-    };
-
-    Ok(Response::new()
-        .add_submessage(message)
-        .add_attribute("action", "claim")
-        .add_attribute("tokens", amount_str)
-        .add_attribute("sender", info.sender))
+    }
 }
 
 #[inline]
@@ -288,7 +518,7 @@ fn coin_to_string(amount: Uint128, denom: &str) -> String {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Member {
             addr,
@@ -297,18 +527,32 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ListMembers { start_after, limit } => {
             to_binary(&list_members(deps, start_after, limit)?)
         }
-        QueryMsg::TotalWeight {} => to_binary(&query_total_weight(deps)?),
-        QueryMsg::Claims { address } => {
-            to_binary(&CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
+        QueryMsg::TotalWeight { at_height: height } => {
+            to_binary(&query_total_weight(deps, height)?)
         }
+        QueryMsg::Claims {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&query_claims(deps, env, address, start_after, limit)?),
+        QueryMsg::TotalClaims {} => to_binary(&query_total_claims(deps)?),
         QueryMsg::Staked { address } => to_binary(&query_staked(deps, address)?),
+        QueryMsg::StakedAt { address, height } => {
+            to_binary(&query_staked_at(deps, address, height)?)
+        }
+        QueryMsg::TotalStakedAt { height } => to_binary(&query_total_staked_at(deps, height)?),
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
         QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+        QueryMsg::Slashers {} => to_binary(&SLASHERS.query_slashers(deps)?),
     }
 }
 
-fn query_total_weight(deps: Deps) -> StdResult<TotalWeightResponse> {
-    let weight = TOTAL.load(deps.storage)?;
+fn query_total_weight(deps: Deps, height: Option<u64>) -> StdResult<TotalWeightResponse> {
+    let weight = match height {
+        Some(h) => TOTAL.may_load_at_height(deps.storage, h),
+        None => TOTAL.may_load(deps.storage),
+    }?
+    .unwrap_or_default();
     Ok(TotalWeightResponse { weight })
 }
 
@@ -319,6 +563,29 @@ pub fn query_staked(deps: Deps, addr: String) -> StdResult<StakedResponse> {
     Ok(StakedResponse { stake, denom })
 }
 
+pub fn query_staked_at(deps: Deps, addr: String, height: u64) -> StdResult<StakedResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let stake = STAKE
+        .may_load_at_height(deps.storage, &addr, height)?
+        .unwrap_or_default();
+    let denom = CONFIG.load(deps.storage)?.denom;
+    Ok(StakedResponse { stake, denom })
+}
+
+pub fn query_total_staked_at(deps: Deps, height: u64) -> StdResult<TotalStakedResponse> {
+    let total = TOTAL_STAKED
+        .may_load_at_height(deps.storage, height)?
+        .unwrap_or_default();
+    let denom = CONFIG.load(deps.storage)?.denom;
+    Ok(TotalStakedResponse { total, denom })
+}
+
+pub fn query_total_claims(deps: Deps) -> StdResult<TotalClaimsResponse> {
+    let total = CLAIMS_TOTAL.may_load(deps.storage)?.unwrap_or_default();
+    let denom = CONFIG.load(deps.storage)?.denom;
+    Ok(TotalClaimsResponse { total, denom })
+}
+
 fn query_member(deps: Deps, addr: String, height: Option<u64>) -> StdResult<MemberResponse> {
     let addr = deps.api.addr_validate(&addr)?;
     let weight = match height {
@@ -332,6 +599,42 @@ fn query_member(deps: Deps, addr: String, height: Option<u64>) -> StdResult<Memb
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 
+fn query_claims(
+    deps: Deps,
+    env: Env,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ClaimsResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let claims = CLAIMS
+        .prefix(&addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, claim)| {
+                let status = if claim.release_at.is_expired(&env.block) {
+                    ClaimStatus::Releasable
+                } else {
+                    ClaimStatus::Unbonding
+                };
+                ClaimResponse {
+                    id,
+                    amount: claim.amount,
+                    created_at: claim.created_at,
+                    release_at: claim.release_at,
+                    status,
+                }
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(ClaimsResponse { claims })
+}
+
 fn list_members(
     deps: Deps,
     start_after: Option<String>,
@@ -359,14 +662,16 @@ fn list_members(
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{
-        coin, from_slice, CosmosMsg, OverflowError, OverflowOperation, StdError, Storage,
+        attr, coin, from_slice, CosmosMsg, OverflowError, OverflowOperation, StdError, Storage,
+        SubMsgResult,
     };
     use cw20::Denom;
     use cw4::{member_key, TOTAL_KEY};
-    use cw_controllers::{AdminError, Claim, HookError};
-    use cw_utils::Duration;
+    use cw_controllers::{AdminError, Claim, HookError, SlasherError};
+    use cw_utils::{Duration, Expiration};
 
     use crate::error::ContractError;
+    use crate::msg::WeightScaling;
 
     use super::*;
 
@@ -397,9 +702,13 @@ mod tests {
     ) {
         let msg = InstantiateMsg {
             denom: Denom::Native("stake".to_string()),
-            tokens_per_weight,
+            weight_scaling: WeightScaling::Linear { tokens_per_weight },
             min_bond,
             unbonding_period,
+            min_unbond: None,
+            max_claims: None,
+            isolate_hooks: None,
+            hook_height: None,
             admin: Some(INIT_ADMIN.into()),
         };
         let info = mock_info("creator", &[]);
@@ -409,9 +718,15 @@ mod tests {
     fn cw20_instantiate(deps: DepsMut, unbonding_period: Duration) {
         let msg = InstantiateMsg {
             denom: Denom::Cw20(Addr::unchecked(CW20_ADDRESS)),
-            tokens_per_weight: TOKENS_PER_WEIGHT,
+            weight_scaling: WeightScaling::Linear {
+                tokens_per_weight: TOKENS_PER_WEIGHT,
+            },
             min_bond: MIN_BOND,
             unbonding_period,
+            min_unbond: None,
+            max_claims: None,
+            isolate_hooks: None,
+            hook_height: None,
             admin: Some(INIT_ADMIN.into()),
         };
         let info = mock_info("creator", &[]);
@@ -472,7 +787,7 @@ mod tests {
         let res = ADMIN.query_admin(deps.as_ref()).unwrap();
         assert_eq!(Some(INIT_ADMIN.into()), res.admin);
 
-        let res = query_total_weight(deps.as_ref()).unwrap();
+        let res = query_total_weight(deps.as_ref(), None).unwrap();
         assert_eq!(0, res.weight);
     }
 
@@ -499,13 +814,19 @@ mod tests {
         let member3 = get_member(deps, USER3.into(), height);
         assert_eq!(member3, user3_weight);
 
+        // compute expected metrics
+        let weights = [user1_weight, user2_weight, user3_weight];
+        let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
+        let count = weights.iter().filter(|x| x.is_some()).count();
+
+        // the total is snapshotted as well
+        let msg = QueryMsg::TotalWeight { at_height: height };
+        let raw = query(deps, mock_env(), msg).unwrap();
+        let total: TotalWeightResponse = from_slice(&raw).unwrap();
+        assert_eq!(sum, total.weight); // 17 - 11 + 15 = 21
+
         // this is only valid if we are not doing a historical query
         if height.is_none() {
-            // compute expected metrics
-            let weights = vec![user1_weight, user2_weight, user3_weight];
-            let sum: u64 = weights.iter().map(|x| x.unwrap_or_default()).sum();
-            let count = weights.iter().filter(|x| x.is_some()).count();
-
             // TODO: more detailed compare?
             let msg = QueryMsg::ListMembers {
                 start_after: None,
@@ -514,10 +835,6 @@ mod tests {
             let raw = query(deps, mock_env(), msg).unwrap();
             let members: MemberListResponse = from_slice(&raw).unwrap();
             assert_eq!(count, members.members.len());
-
-            let raw = query(deps, mock_env(), QueryMsg::TotalWeight {}).unwrap();
-            let total: TotalWeightResponse = from_slice(&raw).unwrap();
-            assert_eq!(sum, total.weight); // 17 - 11 + 15 = 21
         }
     }
 
@@ -607,6 +924,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn staked_at_height() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+        let height = mock_env().block.height;
+
+        bond(deps.as_mut(), 12_000, 7_500, 0, 1);
+        unbond(deps.as_mut(), 4_500, 0, 0, 2);
+        bond(deps.as_mut(), 0, 500, 4_000, 3);
+
+        let staked_at = |deps: Deps, addr: &str, height: u64| {
+            query_staked_at(deps, addr.into(), height)
+                .unwrap()
+                .stake
+                .u128()
+        };
+        let total_at =
+            |deps: Deps, height: u64| query_total_staked_at(deps, height).unwrap().total.u128();
+
+        // heights refer to the beginning of the block, before its changes
+        let expected = [
+            (height + 1, [0, 0, 0]),
+            (height + 2, [12_000, 7_500, 0]),
+            (height + 3, [7_500, 7_500, 0]),
+            (height + 4, [7_500, 8_000, 4_000]),
+        ];
+        for (h, stakes) in expected {
+            for (addr, stake) in [USER1, USER2, USER3].iter().zip(stakes) {
+                assert_eq!(
+                    staked_at(deps.as_ref(), addr, h),
+                    stake,
+                    "{} at {}",
+                    addr,
+                    h
+                );
+            }
+            assert_eq!(total_at(deps.as_ref(), h), stakes.iter().sum::<u128>());
+        }
+        assert_stake(deps.as_ref(), 7_500, 8_000, 4_000);
+
+        // no-op changes leave no history behind
+        let changes = |deps: Deps, addr: &str| {
+            STAKE
+                .changelog()
+                .prefix(&Addr::unchecked(addr))
+                .keys(deps.storage, None, None, Order::Ascending)
+                .count()
+        };
+        assert_eq!(changes(deps.as_ref(), USER1), 2);
+        add_slasher(deps.as_mut(), INIT_ADMIN);
+        slash(deps.as_mut(), INIT_ADMIN, USER1, Decimal::zero()).unwrap();
+        assert_eq!(changes(deps.as_ref(), USER1), 2);
+        assert_eq!(total_at(deps.as_ref(), height + 4), 19_500);
+    }
+
     #[test]
     fn cw20_token_bond() {
         let mut deps = mock_dependencies();
@@ -705,8 +1077,17 @@ mod tests {
         assert_eq!(None, member3_raw);
     }
 
+    // pending claims, in the format of cw-controllers
     fn get_claims(deps: Deps, addr: &Addr) -> Vec<Claim> {
-        CLAIMS.query_claims(deps, addr).unwrap().claims
+        query_claims(deps, mock_env(), addr.to_string(), None, Some(MAX_LIMIT))
+            .unwrap()
+            .claims
+            .into_iter()
+            .map(|c| Claim {
+                amount: c.amount,
+                release_at: c.release_at,
+            })
+            .collect()
     }
 
     #[test]
@@ -843,6 +1224,737 @@ mod tests {
         assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER2)), vec![]);
     }
 
+    #[test]
+    fn cancel_claim_restakes() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+
+        // register a hook
+        let add_msg = ExecuteMsg::AddHook {
+            addr: "hook1".into(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INIT_ADMIN, &[]),
+            add_msg,
+        )
+        .unwrap();
+
+        // unbond twice, creating claims 1 and 2
+        bond(deps.as_mut(), 12_000, 0, 0, 1);
+        unbond(deps.as_mut(), 4_500, 0, 0, 2);
+        unbond(deps.as_mut(), 1_500, 0, 0, 3);
+        assert_users(deps.as_ref(), Some(6), None, None, None);
+
+        let mut env = mock_env();
+        let claims = query_claims(deps.as_ref(), env.clone(), USER1.into(), None, None)
+            .unwrap()
+            .claims;
+        let expected = vec![
+            ClaimResponse {
+                id: 1,
+                amount: Uint128::new(4_500),
+                created_at: env.block.height + 2,
+                release_at: Expiration::AtHeight(env.block.height + 2 + UNBONDING_BLOCKS),
+                status: ClaimStatus::Unbonding,
+            },
+            ClaimResponse {
+                id: 2,
+                amount: Uint128::new(1_500),
+                created_at: env.block.height + 3,
+                release_at: Expiration::AtHeight(env.block.height + 3 + UNBONDING_BLOCKS),
+                status: ClaimStatus::Unbonding,
+            },
+        ];
+        assert_eq!(claims, expected);
+
+        // pagination works
+        let claims = query_claims(deps.as_ref(), env.clone(), USER1.into(), Some(1), None)
+            .unwrap()
+            .claims;
+        assert_eq!(claims, expected[1..]);
+
+        // other users cannot cancel the claim
+        env.block.height += 4;
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER2, &[]),
+            ExecuteMsg::CancelClaim { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ClaimNotFound { id: 1 });
+
+        // cancelling restores the weight and alerts the hooks
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::CancelClaim { id: 1 },
+        )
+        .unwrap();
+        assert_stake(deps.as_ref(), 10_500, 0, 0);
+        assert_users(deps.as_ref(), Some(10), None, None, None);
+        let diff = MemberDiff::new(USER1, Some(6), Some(10));
+        let hook_msg = MemberChangedHookMsg::one(diff);
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(hook_msg.into_cosmos_msg("hook1").unwrap())]
+        );
+
+        // the claim is gone, so it cannot be cancelled twice
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::CancelClaim { id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::ClaimNotFound { id: 1 });
+
+        // once mature, only the second one is left, and releasable
+        env.block.height += UNBONDING_BLOCKS;
+        let statuses: Vec<_> = query_claims(deps.as_ref(), env.clone(), USER1.into(), None, None)
+            .unwrap()
+            .claims
+            .into_iter()
+            .map(|c| c.status)
+            .collect();
+        assert_eq!(statuses, vec![ClaimStatus::Releasable]);
+
+        // mature claims cannot be cancelled anymore
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::CancelClaim { id: 2 },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::CannotCancelClaim {});
+
+        // only the second claim is paid out
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::Claim {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: USER1.into(),
+                amount: coins(1_500, DENOM),
+            })]
+        );
+
+        // the cancelled claim is never paid out
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info(USER1, &[]),
+            ExecuteMsg::Claim {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+        assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER1)), vec![]);
+    }
+
+    fn add_slasher(deps: DepsMut, slasher: &str) {
+        let msg = ExecuteMsg::AddSlasher {
+            addr: slasher.into(),
+        };
+        execute(deps, mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+    }
+
+    fn slash(
+        deps: DepsMut,
+        sender: &str,
+        addr: &str,
+        portion: Decimal,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::Slash {
+            addr: addr.into(),
+            portion,
+        };
+        execute(deps, mock_env(), mock_info(sender, &[]), msg)
+    }
+
+    #[test]
+    fn slash_crosses_weight_boundary() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+        let add_hook = ExecuteMsg::AddHook {
+            addr: "hook1".into(),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INIT_ADMIN, &[]),
+            add_hook,
+        )
+        .unwrap();
+        bond(deps.as_mut(), 11_000, 7_001, 0, 1);
+
+        // only slashers can slash, and only the admin can add them
+        let err = slash(deps.as_mut(), "slasher", USER1, Decimal::percent(50)).unwrap_err();
+        assert_eq!(err, ContractError::Slasher(SlasherError::NotSlasher {}));
+        let msg = ExecuteMsg::AddSlasher {
+            addr: "slasher".into(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Slasher(SlasherError::Admin(AdminError::NotAdmin {}))
+        );
+        add_slasher(deps.as_mut(), "slasher");
+
+        // cannot slash more than everything
+        let err = slash(deps.as_mut(), "slasher", USER1, Decimal::percent(150)).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSlashPortion {});
+
+        // 50% slash reduces stake, weight and total, and alerts the hooks
+        let res = slash(deps.as_mut(), "slasher", USER1, Decimal::percent(50)).unwrap();
+        assert_stake(deps.as_ref(), 5_500, 7_001, 0);
+        assert_users(deps.as_ref(), Some(5), Some(7), None, None);
+        let diff = MemberDiff::new(USER1, Some(11), Some(5));
+        let hook_msg = MemberChangedHookMsg::one(diff);
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(hook_msg.into_cosmos_msg("hook1").unwrap())]
+        );
+
+        // remaining stake is rounded down, dropping below min_bond removes membership
+        let res = slash(deps.as_mut(), "slasher", USER2, Decimal::percent(50)).unwrap();
+        assert_stake(deps.as_ref(), 5_500, 3_500, 0);
+        assert_users(deps.as_ref(), Some(5), None, None, None);
+        let slashed = res.attributes.iter().find(|a| a.key == "slashed").unwrap();
+        assert_eq!(slashed.value, "3501");
+        let diff = MemberDiff::new(USER2, Some(7), None);
+        let hook_msg = MemberChangedHookMsg::one(diff);
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(hook_msg.into_cosmos_msg("hook1").unwrap())]
+        );
+    }
+
+    #[test]
+    fn total_claims_stays_consistent() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+        add_slasher(deps.as_mut(), "slasher");
+        let total = |deps: Deps| query_total_claims(deps).unwrap().total.u128();
+
+        bond(deps.as_mut(), 12_000, 7_500, 0, 1);
+        assert_eq!(total(deps.as_ref()), 0);
+        // claims 1 and 2 for USER1, claim 3 for USER2
+        unbond(deps.as_mut(), 4_000, 0, 0, 2);
+        unbond(deps.as_mut(), 2_000, 1_500, 0, 3);
+        assert_eq!(total(deps.as_ref()), 7_500);
+
+        // cancelling a claim removes it from the total
+        let mut env = mock_env();
+        env.block.height += 4;
+        let msg = ExecuteMsg::CancelClaim { id: 2 };
+        execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), msg).unwrap();
+        assert_eq!(total(deps.as_ref()), 5_500);
+
+        // slashing reduces the pending claims of USER1 only
+        slash(deps.as_mut(), "slasher", USER1, Decimal::percent(50)).unwrap();
+        assert_eq!(total(deps.as_ref()), 2_000 + 1_500);
+
+        // released claims are not owed anymore
+        env.block.height += UNBONDING_BLOCKS;
+        let msg = ExecuteMsg::Claim {};
+        execute(deps.as_mut(), env.clone(), mock_info(USER2, &[]), msg).unwrap();
+        assert_eq!(total(deps.as_ref()), 2_000);
+        let msg = ExecuteMsg::SweepClaims {
+            addresses: vec![USER1.into()],
+            limit: 10,
+        };
+        execute(deps.as_mut(), env, mock_info(USER3, &[]), msg).unwrap();
+        assert_eq!(total(deps.as_ref()), 0);
+
+        // and the query goes through the entry point
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::TotalClaims {}).unwrap();
+        let res: TotalClaimsResponse = from_slice(&res).unwrap();
+        assert_eq!(res.total, Uint128::zero());
+        assert_eq!(res.denom, Denom::Native(DENOM.into()));
+    }
+
+    #[test]
+    fn slash_reduces_pending_claims() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+        add_slasher(deps.as_mut(), "slasher");
+
+        bond(deps.as_mut(), 12_000, 0, 0, 1);
+        unbond(deps.as_mut(), 4_501, 0, 0, 2);
+        assert_stake(deps.as_ref(), 7_499, 0, 0);
+
+        // 10% off both stake and claim, rounding the remainder down
+        let res = slash(deps.as_mut(), "slasher", USER1, Decimal::percent(10)).unwrap();
+        let slashed = res.attributes.iter().find(|a| a.key == "slashed").unwrap();
+        assert_eq!(slashed.value, (750 + 451).to_string());
+        assert_stake(deps.as_ref(), 6_749, 0, 0);
+        assert_users(deps.as_ref(), Some(6), None, None, None);
+
+        let mut env = mock_env();
+        env.block.height += 2;
+        let expires = Duration::Height(UNBONDING_BLOCKS).after(&env.block);
+        assert_eq!(
+            get_claims(deps.as_ref(), &Addr::unchecked(USER1)),
+            vec![Claim::new(4_050, expires)]
+        );
+
+        // only the reduced claim is paid out
+        env.block.height += UNBONDING_BLOCKS;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(USER1, &[]),
+            ExecuteMsg::Claim {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: USER1.into(),
+                amount: coins(4_050, DENOM),
+            })]
+        );
+    }
+
+    #[test]
+    fn unbond_limits() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            denom: Denom::Native(DENOM.to_string()),
+            weight_scaling: WeightScaling::Linear {
+                tokens_per_weight: TOKENS_PER_WEIGHT,
+            },
+            min_bond: MIN_BOND,
+            unbonding_period: Duration::Height(UNBONDING_BLOCKS),
+            min_unbond: Some(Uint128::new(1_000)),
+            max_claims: Some(2),
+            isolate_hooks: None,
+            hook_height: None,
+            admin: Some(INIT_ADMIN.into()),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        bond(deps.as_mut(), 12_000, 0, 0, 1);
+
+        let do_unbond = |deps: DepsMut, amount: u128, height_delta: u64| {
+            let mut env = mock_env();
+            env.block.height += height_delta;
+            let msg = ExecuteMsg::Unbond {
+                tokens: Uint128::new(amount),
+            };
+            execute(deps, env, mock_info(USER1, &[]), msg)
+        };
+
+        // dust unbonds are rejected
+        let err = do_unbond(deps.as_mut(), 999, 2).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::UnbondTooSmall {
+                min: Uint128::new(1_000)
+            }
+        );
+
+        // only two claims can be outstanding
+        do_unbond(deps.as_mut(), 1_000, 2).unwrap();
+        do_unbond(deps.as_mut(), 2_000, 3).unwrap();
+        let err = do_unbond(deps.as_mut(), 1_000, 4).unwrap_err();
+        assert_eq!(err, ContractError::TooManyClaims { max: 2 });
+
+        // existing claims still release, freeing up room for new ones
+        let mut env = mock_env();
+        env.block.height += 2 + UNBONDING_BLOCKS;
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(USER1, &[]),
+            ExecuteMsg::Claim {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: USER1.into(),
+                amount: coins(1_000, DENOM),
+            })]
+        );
+        do_unbond(deps.as_mut(), 1_000, 2 + UNBONDING_BLOCKS).unwrap();
+        assert_stake(deps.as_ref(), 8_000, 0, 0);
+
+        // cancelled claims are removed, so they free up room as well
+        let mut env = mock_env();
+        env.block.height += 2 + UNBONDING_BLOCKS;
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info(USER1, &[]),
+            ExecuteMsg::CancelClaim { id: 3 },
+        )
+        .unwrap();
+        assert_stake(deps.as_ref(), 9_000, 0, 0);
+        assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER1)).len(), 1);
+        do_unbond(deps.as_mut(), 1_000, 2 + UNBONDING_BLOCKS).unwrap();
+        let err = do_unbond(deps.as_mut(), 1_000, 2 + UNBONDING_BLOCKS).unwrap_err();
+        assert_eq!(err, ContractError::TooManyClaims { max: 2 });
+    }
+
+    #[test]
+    fn bond_releases_matured_claims() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+
+        bond(deps.as_mut(), 12_000, 0, 0, 1);
+        unbond(deps.as_mut(), 4_500, 0, 0, 2);
+        unbond(deps.as_mut(), 1_000, 0, 0, 50);
+
+        // only the first claim matured, bonding pays it out with the bond
+        let mut env = mock_env();
+        env.block.height += 2 + UNBONDING_BLOCKS;
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &coins(3_000, DENOM)),
+            ExecuteMsg::Bond {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: USER1.into(),
+                amount: coins(4_500, DENOM),
+            })]
+        );
+        assert_eq!(res.attributes[3], ("claimed", "4500 stake"));
+        assert_stake(deps.as_ref(), 9_500, 0, 0);
+        let expires = Duration::Height(UNBONDING_BLOCKS).after(&{
+            let mut env = mock_env();
+            env.block.height += 50;
+            env.block
+        });
+        assert_eq!(
+            get_claims(deps.as_ref(), &Addr::unchecked(USER1)),
+            vec![Claim::new(1_000, expires)]
+        );
+
+        // nothing more to release, bond sends no funds back
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(USER1, &coins(500, DENOM)),
+            ExecuteMsg::Bond {},
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![]);
+    }
+
+    #[test]
+    fn sweep_claims_across_owners() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+
+        bond(deps.as_mut(), 12_000, 7_500, 4_000, 1);
+        unbond(deps.as_mut(), 1_000, 2_000, 0, 2);
+        unbond(deps.as_mut(), 500, 0, 0, 3);
+
+        let mut env = mock_env();
+        env.block.height += 3 + UNBONDING_BLOCKS;
+        // USER3 has nothing to claim and is skipped, limit stops after two claims
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::SweepClaims {
+                addresses: vec![USER3.into(), USER1.into(), USER2.into()],
+                limit: 2,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: USER1.into(),
+                amount: coins(1_500, DENOM),
+            })]
+        );
+        assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER1)), vec![]);
+        assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER2)).len(), 1);
+
+        // the next sweep picks up the rest
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::SweepClaims {
+                addresses: vec![USER1.into(), USER2.into()],
+                limit: 10,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: USER2.into(),
+                amount: coins(2_000, DENOM),
+            })]
+        );
+
+        // sweeping with nothing left is not an error
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("keeper", &[]),
+            ExecuteMsg::SweepClaims {
+                addresses: vec![USER1.into(), USER2.into(), USER3.into()],
+                limit: 10,
+            },
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![]);
+    }
+
+    #[test]
+    fn weight_scaling_is_monotonic_and_capped() {
+        let curves = [
+            WeightScaling::Linear {
+                tokens_per_weight: Uint128::new(1_000),
+            },
+            WeightScaling::Sqrt {
+                scale: Uint128::new(1),
+            },
+            WeightScaling::Sqrt {
+                scale: Uint128::new(7),
+            },
+            WeightScaling::Capped {
+                tokens_per_weight: Uint128::new(100),
+                max_weight: 250,
+            },
+        ];
+
+        // walk increasing stakes with pseudo-random steps
+        let mut seed: u64 = 12345;
+        let mut stakes = vec![0u128, 1, 2, 3, 4, 99, 100, 101];
+        let mut stake = 0u128;
+        for _ in 0..2_000 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            stake += (seed >> 48) as u128;
+            stakes.push(stake);
+        }
+        stakes.extend([u64::MAX as u128, u128::MAX - 1, u128::MAX]);
+
+        for curve in &curves {
+            let mut last = 0u64;
+            for stake in &stakes {
+                let weight = curve.weight(Uint128::new(*stake));
+                assert!(weight >= last, "{:?} not monotonic at {}", curve, stake);
+                last = weight;
+
+                match curve {
+                    WeightScaling::Sqrt { scale } => {
+                        // exact integer square root
+                        let base = stake / scale.u128();
+                        let w = weight as u128;
+                        assert!(w * w <= base);
+                        assert!((w + 1).checked_mul(w + 1).map_or(true, |sq| sq > base));
+                    }
+                    WeightScaling::Capped { max_weight, .. } => assert!(weight <= *max_weight),
+                    WeightScaling::Linear { .. } => {}
+                }
+            }
+        }
+
+        let sqrt = WeightScaling::Sqrt {
+            scale: Uint128::new(1),
+        };
+        assert_eq!(sqrt.weight(Uint128::new(1_000_000)), 1_000);
+        assert_eq!(sqrt.weight(Uint128::new(999_999)), 999);
+        assert_eq!(sqrt.weight(Uint128::MAX), u64::MAX);
+    }
+
+    fn scaled_instantiate(deps: DepsMut, weight_scaling: WeightScaling) {
+        let msg = InstantiateMsg {
+            denom: Denom::Native(DENOM.to_string()),
+            weight_scaling,
+            min_bond: Uint128::new(1),
+            unbonding_period: Duration::Height(UNBONDING_BLOCKS),
+            min_unbond: None,
+            max_claims: None,
+            isolate_hooks: None,
+            hook_height: None,
+            admin: Some(INIT_ADMIN.into()),
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn sqrt_weights_keep_total_consistent() {
+        let mut deps = mock_dependencies();
+        scaled_instantiate(
+            deps.as_mut(),
+            WeightScaling::Sqrt {
+                scale: Uint128::new(1),
+            },
+        );
+
+        bond(deps.as_mut(), 10_000, 2_500, 3, 1);
+        assert_users(deps.as_ref(), Some(100), Some(50), Some(1), None);
+
+        // adding to the stake moves along the curve, not linearly
+        bond(deps.as_mut(), 30_000, 0, 0, 2);
+        assert_users(deps.as_ref(), Some(200), Some(50), Some(1), None);
+
+        unbond(deps.as_mut(), 0, 2_499, 0, 3);
+        assert_users(deps.as_ref(), Some(200), Some(1), Some(1), None);
+
+        add_slasher(deps.as_mut(), INIT_ADMIN);
+        slash(deps.as_mut(), INIT_ADMIN, USER1, Decimal::percent(75)).unwrap();
+        assert_users(deps.as_ref(), Some(100), Some(1), Some(1), None);
+    }
+
+    #[test]
+    fn capped_weights_keep_total_consistent() {
+        let mut deps = mock_dependencies();
+        scaled_instantiate(
+            deps.as_mut(),
+            WeightScaling::Capped {
+                tokens_per_weight: Uint128::new(1_000),
+                max_weight: 10,
+            },
+        );
+
+        bond(deps.as_mut(), 5_000, 12_000, 0, 1);
+        assert_users(deps.as_ref(), Some(5), Some(10), None, None);
+
+        // stake above the cap does not add weight, and no hooks would fire
+        bond(deps.as_mut(), 0, 50_000, 0, 2);
+        assert_users(deps.as_ref(), Some(5), Some(10), None, None);
+        assert_stake(deps.as_ref(), 5_000, 62_000, 0);
+
+        // unbonding below the cap reduces it again
+        unbond(deps.as_mut(), 0, 55_000, 0, 3);
+        assert_users(deps.as_ref(), Some(5), Some(7), None, None);
+    }
+
+    #[test]
+    fn weight_scaling_rejects_zero_divisor() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            denom: Denom::Native(DENOM.to_string()),
+            weight_scaling: WeightScaling::Sqrt {
+                scale: Uint128::zero(),
+            },
+            min_bond: MIN_BOND,
+            unbonding_period: Duration::Height(UNBONDING_BLOCKS),
+            min_unbond: None,
+            max_claims: None,
+            isolate_hooks: None,
+            hook_height: None,
+            admin: None,
+        };
+        let err =
+            instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidWeightScaling {});
+    }
+
+    #[test]
+    fn isolated_hooks_reply_on_error() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            denom: Denom::Native(DENOM.to_string()),
+            weight_scaling: WeightScaling::Linear {
+                tokens_per_weight: TOKENS_PER_WEIGHT,
+            },
+            min_bond: MIN_BOND,
+            unbonding_period: Duration::Height(UNBONDING_BLOCKS),
+            min_unbond: None,
+            max_claims: None,
+            isolate_hooks: Some(true),
+            hook_height: Some(true),
+            admin: Some(INIT_ADMIN.into()),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        for hook in ["hook1", "hook2"] {
+            let msg = ExecuteMsg::AddHook { addr: hook.into() };
+            execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        }
+
+        // every hook gets its own reply id, and the message carries the height as asked for
+        let info = mock_info(USER1, &coins(12_000, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Bond {}).unwrap();
+        let hook_msg = MemberChangedHookMsg::one(MemberDiff::new(USER1, None, Some(12)))
+            .with_height(mock_env().block.height);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::reply_on_error(hook_msg.clone().into_cosmos_msg("hook1").unwrap(), 1),
+                SubMsg::reply_on_error(hook_msg.into_cosmos_msg("hook2").unwrap(), 2),
+            ]
+        );
+
+        // a failing hook is only reported
+        let failed = Reply {
+            id: 2,
+            result: SubMsgResult::Err("hook rejected".into()),
+        };
+        let res = reply(deps.as_mut(), mock_env(), failed).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "hook_failed"),
+                attr("hook", "hook2"),
+                attr("error", "hook rejected")
+            ]
+        );
+        let unknown = Reply {
+            id: 3,
+            result: SubMsgResult::Err("other".into()),
+        };
+        let err = reply(deps.as_mut(), mock_env(), unknown).unwrap_err();
+        assert_eq!(err, ContractError::UnknownReplyId { id: 3 });
+        assert_users(deps.as_ref(), Some(12), None, None, None);
+    }
+
+    #[test]
+    fn update_admin_attributes() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateAdmin {
+            admin: Some(USER1.into()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(USER1, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Admin(AdminError::NotAdmin {}));
+        let res = execute(deps.as_mut(), mock_env(), mock_info(INIT_ADMIN, &[]), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "update_admin"),
+                attr("admin", USER1),
+                attr("old_admin", INIT_ADMIN),
+                attr("sender", INIT_ADMIN),
+            ]
+        );
+        assert_eq!(
+            ADMIN.query_admin(deps.as_ref()).unwrap().admin,
+            Some(USER1.into())
+        );
+    }
+
     #[test]
     fn add_remove_hooks() {
         // add will over-write and remove have no effect
@@ -931,7 +2043,7 @@ mod tests {
         let add_msg2 = ExecuteMsg::AddHook {
             addr: contract2.clone(),
         };
-        for msg in vec![add_msg, add_msg2] {
+        for msg in [add_msg, add_msg2] {
             let _ = execute(deps.as_mut(), mock_env(), admin_info.clone(), msg).unwrap();
         }
 
@@ -1,7 +1,7 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
-use cw_controllers::{AdminError, HookError};
+use cw_controllers::{AdminError, HookError, SlasherError};
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
@@ -14,12 +14,33 @@ pub enum ContractError {
     #[error("{0}")]
     Hook(#[from] HookError),
 
+    #[error("{0}")]
+    Slasher(#[from] SlasherError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Weight scaling must not divide by zero")]
+    InvalidWeightScaling {},
+
+    #[error("Slash portion must be between 0 and 1")]
+    InvalidSlashPortion {},
+
+    #[error("Must unbond at least {min}")]
+    UnbondTooSmall { min: Uint128 },
+
+    #[error("Cannot have more than {max} outstanding claims")]
+    TooManyClaims { max: u32 },
+
     #[error("No claims that can be released currently")]
     NothingToClaim {},
 
+    #[error("Claim {id} not found")]
+    ClaimNotFound { id: u64 },
+
+    #[error("Only claims still unbonding can be cancelled")]
+    CannotCancelClaim {},
+
     #[error("Must send '{0}' to stake")]
     MissingDenom(String),
 
@@ -37,4 +58,7 @@ pub enum ContractError {
 
     #[error("No data in ReceiveMsg")]
     NoData {},
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
 }
@@ -0,0 +1,179 @@
+#![cfg(test)]
+
+use cosmwasm_std::{to_binary, Addr, Empty, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Denom};
+use cw4::MemberResponse;
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_utils::Duration;
+
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+use crate::msg::{
+    ClaimStatus, ClaimsResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, WeightScaling,
+};
+
+const OWNER: &str = "owner";
+const USER: &str = "user";
+const UNBONDING_BLOCKS: u64 = 10;
+
+fn contract_stake() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query);
+    Box::new(contract)
+}
+
+fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+// Returns (cw20 address, stake address), with USER holding 10_000 tokens
+fn setup(app: &mut App) -> (Addr, Addr) {
+    let cw20_id = app.store_code(contract_cw20());
+    let cw20_addr = app
+        .instantiate_contract(
+            cw20_id,
+            Addr::unchecked(OWNER),
+            &cw20_base::msg::InstantiateMsg {
+                name: "Staking Token".to_string(),
+                symbol: "STK".to_string(),
+                decimals: 6,
+                initial_balances: vec![Cw20Coin {
+                    address: USER.to_string(),
+                    amount: Uint128::new(10_000),
+                }],
+                mint: None,
+                marketing: None,
+                import_from: None,
+            },
+            &[],
+            "cw20",
+            None,
+        )
+        .unwrap();
+
+    let stake_id = app.store_code(contract_stake());
+    let stake_addr = app
+        .instantiate_contract(
+            stake_id,
+            Addr::unchecked(OWNER),
+            &InstantiateMsg {
+                denom: Denom::Cw20(cw20_addr.clone()),
+                weight_scaling: WeightScaling::Linear {
+                    tokens_per_weight: Uint128::new(1_000),
+                },
+                min_bond: Uint128::new(1_000),
+                unbonding_period: Duration::Height(UNBONDING_BLOCKS),
+                min_unbond: None,
+                max_claims: None,
+                isolate_hooks: None,
+                hook_height: None,
+                admin: None,
+            },
+            &[],
+            "stake",
+            None,
+        )
+        .unwrap();
+
+    (cw20_addr, stake_addr)
+}
+
+fn token_balance(app: &App, cw20_addr: &Addr, address: &Addr) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            cw20_addr,
+            &cw20_base::msg::QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+fn weight(app: &App, stake_addr: &Addr) -> Option<u64> {
+    let res: MemberResponse = app
+        .wrap()
+        .query_wasm_smart(
+            stake_addr,
+            &QueryMsg::Member {
+                addr: USER.to_string(),
+                at_height: None,
+            },
+        )
+        .unwrap();
+    res.weight
+}
+
+#[test]
+fn cw20_bond_unbond_claim() {
+    let mut app = App::default();
+    let (cw20_addr, stake_addr) = setup(&mut app);
+    let user = Addr::unchecked(USER);
+
+    // bond by sending tokens through the cw20 contract
+    let send = Cw20ExecuteMsg::Send {
+        contract: stake_addr.to_string(),
+        amount: Uint128::new(6_000),
+        msg: to_binary(&ReceiveMsg::Bond {}).unwrap(),
+    };
+    app.execute_contract(user.clone(), cw20_addr.clone(), &send, &[])
+        .unwrap();
+    assert_eq!(weight(&app, &stake_addr), Some(6));
+    assert_eq!(token_balance(&app, &cw20_addr, &user), Uint128::new(4_000));
+    assert_eq!(
+        token_balance(&app, &cw20_addr, &stake_addr),
+        Uint128::new(6_000)
+    );
+
+    // native tokens cannot be bonded directly
+    let err = app
+        .execute_contract(user.clone(), stake_addr.clone(), &ExecuteMsg::Bond {}, &[])
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MixedNativeAndCw20("Invalid address or denom".to_string()),
+        err.downcast().unwrap()
+    );
+
+    // unbond part of it
+    let unbond = ExecuteMsg::Unbond {
+        tokens: Uint128::new(2_500),
+    };
+    app.execute_contract(user.clone(), stake_addr.clone(), &unbond, &[])
+        .unwrap();
+    assert_eq!(weight(&app, &stake_addr), Some(3));
+
+    // nothing to claim before the unbonding period is over
+    let err = app
+        .execute_contract(user.clone(), stake_addr.clone(), &ExecuteMsg::Claim {}, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+
+    // after it, the tokens are transferred back
+    app.update_block(|block| block.height += UNBONDING_BLOCKS);
+    let claims: ClaimsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &stake_addr,
+            &QueryMsg::Claims {
+                address: USER.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(claims.claims.len(), 1);
+    assert_eq!(claims.claims[0].status, ClaimStatus::Releasable);
+
+    app.execute_contract(user.clone(), stake_addr.clone(), &ExecuteMsg::Claim {}, &[])
+        .unwrap();
+    assert_eq!(token_balance(&app, &cw20_addr, &user), Uint128::new(6_500));
+    assert_eq!(
+        token_balance(&app, &cw20_addr, &stake_addr),
+        Uint128::new(3_500)
+    );
+}
@@ -11,9 +11,11 @@ membership and weight are based on the number of tokens they have staked.
 This is similar to many DAOs.
 
 Only one denom can be bonded with both `min_bond` as the minimum amount
-that must be sent by one address to enter, as well as `tokens_per_weight`,
-which can be used to normalize the weight (eg. if the token is uatom
-and you want 1 weight per ATOM, you can set `tokens_per_weight = 1_000_000`).
+that must be sent by one address to enter, as well as a `weight_scaling`,
+which turns the stake into a weight. The `Linear` scaling uses `tokens_per_weight`
+to normalize the weight (eg. if the token is uatom and you want 1 weight per ATOM,
+you can set `tokens_per_weight = 1_000_000`), while `Sqrt` and `Capped` give large
+stakers less influence.
 
 There is also an unbonding period (`Duration`) which sets how long the
 tokens are frozen before being released. These frozen tokens can neither
@@ -27,6 +29,7 @@ For more information on this contract, please check out the
 
 pub mod contract;
 mod error;
+mod integration_tests;
 pub mod msg;
 pub mod state;
 
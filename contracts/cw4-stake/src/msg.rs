@@ -1,25 +1,77 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Isqrt, Uint128};
 
 use cw20::{Cw20ReceiveMsg, Denom};
-pub use cw_controllers::ClaimsResponse;
-use cw_utils::Duration;
+use cw_utils::{Duration, Expiration};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     /// denom of the token to stake
     pub denom: Denom,
-    pub tokens_per_weight: Uint128,
+    /// how the stake of a member is turned into its weight
+    pub weight_scaling: WeightScaling,
     pub min_bond: Uint128,
     pub unbonding_period: Duration,
+    /// Smallest amount that can be unbonded at once. Defaults to 1
+    pub min_unbond: Option<Uint128>,
+    /// Maximum number of outstanding claims per address. Defaults to `DEFAULT_MAX_CLAIMS`
+    pub max_claims: Option<u32>,
+    /// If true, a failing hook does not abort the stake change. The failure is
+    /// only reported in a `hook_failed` event. Defaults to false
+    pub isolate_hooks: Option<bool>,
+    /// If true, hook messages carry the height the stake change was applied at, see
+    /// `MemberChangedHookMsg::height`. Only enable it if all hooks accept that field.
+    /// Defaults to false
+    pub hook_height: Option<bool>,
 
     // admin can only add/remove hooks, not change other parameters
     pub admin: Option<String>,
 }
 
+#[cw_serde]
+pub enum WeightScaling {
+    /// weight is stake / tokens_per_weight
+    Linear { tokens_per_weight: Uint128 },
+    /// weight is the square root of stake / scale
+    Sqrt { scale: Uint128 },
+    /// weight is stake / tokens_per_weight, but never more than max_weight
+    Capped {
+        tokens_per_weight: Uint128,
+        max_weight: u64,
+    },
+}
+
+impl WeightScaling {
+    /// Returns false if a divisor is zero
+    pub fn is_valid(&self) -> bool {
+        match self {
+            WeightScaling::Linear { tokens_per_weight }
+            | WeightScaling::Capped {
+                tokens_per_weight, ..
+            } => !tokens_per_weight.is_zero(),
+            WeightScaling::Sqrt { scale } => !scale.is_zero(),
+        }
+    }
+
+    /// Computes the weight for the given stake. This is monotonic in the stake,
+    /// rounding down in integer math.
+    pub fn weight(&self, stake: Uint128) -> u64 {
+        let weight = match self {
+            WeightScaling::Linear { tokens_per_weight } => stake.u128() / tokens_per_weight.u128(),
+            WeightScaling::Sqrt { scale } => (stake / *scale).isqrt().u128(),
+            WeightScaling::Capped {
+                tokens_per_weight,
+                max_weight,
+            } => std::cmp::min(stake.u128() / tokens_per_weight.u128(), *max_weight as u128),
+        };
+        u64::try_from(weight).unwrap_or(u64::MAX)
+    }
+}
+
 #[cw_serde]
 pub enum ExecuteMsg {
-    /// Bond will bond all staking tokens sent with the message and update membership weight
+    /// Bond will bond all staking tokens sent with the message and update membership weight.
+    /// Any matured claims of the sender are paid out in the same transaction
     Bond {},
     /// Unbond will start the unbonding process for the given number of tokens.
     /// The sender immediately loses weight from these tokens, and can claim them
@@ -28,6 +80,12 @@ pub enum ExecuteMsg {
     /// Claim is used to claim your native tokens that you previously "unbonded"
     /// after the contract-defined waiting period (eg. 1 week)
     Claim {},
+    /// CancelClaim stakes the tokens of a claim that was not yet released again,
+    /// restoring the sender's weight. The claim is removed
+    CancelClaim { id: u64 },
+    /// SweepClaims pays out matured claims of the given addresses to their owners.
+    /// Anyone can call it. At most `limit` claims are released per call
+    SweepClaims { addresses: Vec<String>, limit: u32 },
 
     /// Change the admin
     UpdateAdmin { admin: Option<String> },
@@ -35,6 +93,13 @@ pub enum ExecuteMsg {
     AddHook { addr: String },
     /// Remove a hook. Must be called by Admin
     RemoveHook { addr: String },
+    /// Add an address allowed to slash stakes. Must be called by Admin
+    AddSlasher { addr: String },
+    /// Remove a slasher. Must be called by Admin
+    RemoveSlasher { addr: String },
+    /// Slash reduces the stake and pending claims of the given address by `portion`.
+    /// Must be called by a slasher
+    Slash { addr: String, portion: Decimal },
 
     /// This accepts a properly-encoded ReceiveMsg from a cw20 contract
     Receive(Cw20ReceiveMsg),
@@ -50,16 +115,29 @@ pub enum ReceiveMsg {
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     /// Claims shows the tokens in process of unbonding for this address
-    #[returns(cw_controllers::ClaimsResponse)]
-    Claims { address: String },
+    #[returns(ClaimsResponse)]
+    Claims {
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Sum of all outstanding claims
+    #[returns(TotalClaimsResponse)]
+    TotalClaims {},
     // Show the number of tokens currently staked by this address.
     #[returns(StakedResponse)]
     Staked { address: String },
+    /// Tokens staked by this address at the beginning of the given block height
+    #[returns(StakedResponse)]
+    StakedAt { address: String, height: u64 },
+    /// Tokens staked by all addresses at the beginning of the given block height
+    #[returns(TotalStakedResponse)]
+    TotalStakedAt { height: u64 },
 
     #[returns(cw_controllers::AdminResponse)]
     Admin {},
     #[returns(cw4::TotalWeightResponse)]
-    TotalWeight {},
+    TotalWeight { at_height: Option<u64> },
     #[returns(cw4::MemberListResponse)]
     ListMembers {
         start_after: Option<String>,
@@ -73,6 +151,32 @@ pub enum QueryMsg {
     /// Shows all registered hooks.
     #[returns(cw_controllers::HooksResponse)]
     Hooks {},
+    /// Shows all registered slashers.
+    #[returns(cw_controllers::SlashersResponse)]
+    Slashers {},
+}
+
+#[cw_serde]
+pub enum ClaimStatus {
+    /// still unbonding
+    Unbonding,
+    /// can be paid out with `Claim{}`
+    Releasable,
+}
+
+#[cw_serde]
+pub struct ClaimResponse {
+    pub id: u64,
+    pub amount: Uint128,
+    /// block height at which the tokens were unbonded
+    pub created_at: u64,
+    pub release_at: Expiration,
+    pub status: ClaimStatus,
+}
+
+#[cw_serde]
+pub struct ClaimsResponse {
+    pub claims: Vec<ClaimResponse>,
 }
 
 #[cw_serde]
@@ -80,3 +184,15 @@ pub struct StakedResponse {
     pub stake: Uint128,
     pub denom: Denom,
 }
+
+#[cw_serde]
+pub struct TotalStakedResponse {
+    pub total: Uint128,
+    pub denom: Denom,
+}
+
+#[cw_serde]
+pub struct TotalClaimsResponse {
+    pub total: Uint128,
+    pub denom: Denom,
+}
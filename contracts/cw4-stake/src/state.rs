@@ -1,26 +1,69 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, StdResult, Storage, Uint128};
 use cw20::Denom;
-use cw4::TOTAL_KEY;
-use cw_controllers::{Admin, Claims, Hooks};
-use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
-use cw_utils::Duration;
+use cw4::{TOTAL_KEY, TOTAL_KEY_CHANGELOG, TOTAL_KEY_CHECKPOINTS};
+use cw_controllers::{Admin, Hooks, Slashers};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
+use cw_utils::{Duration, Expiration};
 
-pub const CLAIMS: Claims = Claims::new("claims");
+use crate::msg::WeightScaling;
+
+/// Tokens in process of unbonding, created by `Unbond`
+#[cw_serde]
+pub struct Claim {
+    pub amount: Uint128,
+    /// block height at which the tokens were unbonded
+    pub created_at: u64,
+    pub release_at: Expiration,
+}
+
+/// Claims by owner and claim id. Paid out and cancelled claims are removed.
+pub const CLAIMS: Map<(&Addr, u64), Claim> = Map::new("stake_claims");
+pub const CLAIM_COUNT: Item<u64> = Item::new("claim_count");
+/// Sum of all outstanding claims
+pub const CLAIMS_TOTAL: Item<Uint128> = Item::new("claims_total");
+
+pub fn next_claim_id(store: &mut dyn Storage) -> StdResult<u64> {
+    let id: u64 = CLAIM_COUNT.may_load(store)?.unwrap_or_default() + 1;
+    CLAIM_COUNT.save(store, &id)?;
+    Ok(id)
+}
+
+/// Adds `add` to and subtracts `sub` from the claims total
+pub fn update_claims_total(store: &mut dyn Storage, add: Uint128, sub: Uint128) -> StdResult<()> {
+    let total = CLAIMS_TOTAL.may_load(store)?.unwrap_or_default();
+    CLAIMS_TOTAL.save(store, &total.checked_add(add)?.checked_sub(sub)?)
+}
 
 #[cw_serde]
 pub struct Config {
     /// denom of the token to stake
     pub denom: Denom,
-    pub tokens_per_weight: Uint128,
+    pub weight_scaling: WeightScaling,
     pub min_bond: Uint128,
     pub unbonding_period: Duration,
+    pub min_unbond: Uint128,
+    pub max_claims: u32,
+    /// hooks are dispatched with reply_on_error, see `InstantiateMsg::isolate_hooks`
+    #[serde(default)]
+    pub isolate_hooks: bool,
+    /// hook messages carry the height, see `InstantiateMsg::hook_height`
+    #[serde(default)]
+    pub hook_height: bool,
 }
 
 pub const ADMIN: Admin = Admin::new("admin");
-pub const HOOKS: Hooks = Hooks::new("cw4-hooks");
+/// Maximum number of hooks, so that stake changes stay within gas limits
+pub const MAX_HOOKS: u32 = 32;
+pub const HOOKS: Hooks = Hooks::with_max("cw4-hooks", MAX_HOOKS);
+pub const SLASHERS: Slashers = Slashers::new("slashers");
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const TOTAL: Item<u64> = Item::new(TOTAL_KEY);
+pub const TOTAL: SnapshotItem<u64> = SnapshotItem::new(
+    TOTAL_KEY,
+    TOTAL_KEY_CHECKPOINTS,
+    TOTAL_KEY_CHANGELOG,
+    Strategy::EveryBlock,
+);
 
 pub const MEMBERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
     cw4::MEMBERS_KEY,
@@ -29,4 +72,40 @@ pub const MEMBERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
     Strategy::EveryBlock,
 );
 
-pub const STAKE: Map<&Addr, Uint128> = Map::new("stake");
+/// Staked tokens by owner. Uses the same primary namespace as the former plain map,
+/// with history only from this version on.
+pub const STAKE: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "stake",
+    "stake__checkpoints",
+    "stake__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Sum of all stakes
+pub const TOTAL_STAKED: SnapshotItem<Uint128> = SnapshotItem::new(
+    "total_staked",
+    "total_staked__checkpoints",
+    "total_staked__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Applies `action` to the stake of `addr` and returns the new stake. The stake and
+/// total are only written (and snapshotted) if the stake actually changed.
+pub fn update_stake<A>(
+    store: &mut dyn Storage,
+    addr: &Addr,
+    height: u64,
+    action: A,
+) -> StdResult<Uint128>
+where
+    A: FnOnce(Uint128) -> StdResult<Uint128>,
+{
+    let old = STAKE.may_load(store, addr)?.unwrap_or_default();
+    let new = action(old)?;
+    if new != old {
+        STAKE.save(store, addr, &new, height)?;
+        let total = TOTAL_STAKED.may_load(store)?.unwrap_or_default();
+        TOTAL_STAKED.save(store, &total.checked_add(new)?.checked_sub(old)?, height)?;
+    }
+    Ok(new)
+}
@@ -4,7 +4,7 @@ use thiserror::Error;
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    attr, Addr, CustomQuery, Deps, DepsMut, MessageInfo, Response, StdError, StdResult,
+    attr, from_slice, Addr, CustomQuery, Deps, DepsMut, MessageInfo, Response, StdError, StdResult,
 };
 use cw_storage_plus::Item;
 
@@ -23,6 +23,9 @@ pub enum AdminError {
 
     #[error("Caller is not admin")]
     NotAdmin {},
+
+    #[error("Caller is not admin, cannot {action}")]
+    NotAdminFor { action: String },
 }
 
 // state/logic
@@ -45,9 +48,19 @@ impl<'a> Admin<'a> {
     /// Returns Ok(true) if this is an admin, Ok(false) if not and an Error if
     /// we hit an error with Api or Storage usage
     pub fn is_admin<Q: CustomQuery>(&self, deps: Deps<Q>, caller: &Addr) -> StdResult<bool> {
-        match self.0.load(deps.storage)? {
-            Some(owner) => Ok(caller == &owner),
-            None => Ok(false),
+        let raw = match deps.storage.get(self.0.as_slice()) {
+            Some(raw) => raw,
+            // the same not found error as load
+            None => return self.0.load(deps.storage).map(|_| false),
+        };
+        // the admin is stored as a JSON string, which can be compared to the caller
+        // without deserializing, unless it needs unescaping
+        match raw.strip_prefix(b"\"").and_then(|r| r.strip_suffix(b"\"")) {
+            Some(owner) if !owner.contains(&b'\\') => Ok(owner == caller.as_bytes()),
+            _ => match from_slice::<Option<Addr>>(&raw)? {
+                Some(owner) => Ok(caller == &owner),
+                None => Ok(false),
+            },
         }
     }
 
@@ -65,6 +78,23 @@ impl<'a> Admin<'a> {
         }
     }
 
+    /// Like assert_admin, but the error names the `action` that was denied,
+    /// eg. `assert_admin_action(deps, &info.sender, "add hook")`
+    pub fn assert_admin_action<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        caller: &Addr,
+        action: &str,
+    ) -> Result<(), AdminError> {
+        if !self.is_admin(deps, caller)? {
+            Err(AdminError::NotAdminFor {
+                action: action.to_string(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn execute_update_admin<C, Q: CustomQuery>(
         &self,
         deps: DepsMut<Q>,
@@ -80,9 +110,11 @@ impl<'a> Admin<'a> {
             Some(admin) => admin.to_string(),
             None => "None".to_string(),
         };
+        // only the admin may update it, so the sender is the old admin
         let attributes = vec![
             attr("action", "update_admin"),
             attr("admin", admin_str),
+            attr("old_admin", &info.sender),
             attr("sender", info.sender),
         ];
 
@@ -145,6 +177,30 @@ mod tests {
         assert_eq!(AdminError::NotAdmin {}, err);
         let err = control.assert_admin(deps.as_ref(), &imposter).unwrap_err();
         assert_eq!(AdminError::NotAdmin {}, err);
+        let err = control
+            .assert_admin_action(deps.as_ref(), &owner, "add hook")
+            .unwrap_err();
+        assert_eq!(
+            AdminError::NotAdminFor {
+                action: "add hook".to_string()
+            },
+            err
+        );
+        assert_eq!(err.to_string(), "Caller is not admin, cannot add hook");
+
+        // admins needing JSON escapes are compared properly too
+        let quoted = Addr::unchecked("big \"boss\"");
+        control.set(deps.as_mut(), Some(quoted.clone())).unwrap();
+        assert!(control.is_admin(deps.as_ref(), &quoted).unwrap());
+        let escaped = Addr::unchecked("big \\\"boss\\\"");
+        assert!(!(control.is_admin(deps.as_ref(), &escaped).unwrap()));
+        control
+            .assert_admin_action(deps.as_ref(), &quoted, "add hook")
+            .unwrap();
+
+        // unset admin is an error, just as for load
+        let empty = Admin::new("empty");
+        empty.is_admin(deps.as_ref(), &owner).unwrap_err();
     }
 
     #[test]
@@ -176,6 +232,15 @@ mod tests {
             .execute_update_admin::<Empty, Empty>(deps.as_mut(), info, new_admin)
             .unwrap();
         assert_eq!(0, res.messages.len());
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "update_admin"),
+                attr("admin", friend.as_str()),
+                attr("old_admin", owner.as_str()),
+                attr("sender", owner.as_str()),
+            ]
+        );
 
         // query shows results
         let res = control.query_admin(deps.as_ref()).unwrap();
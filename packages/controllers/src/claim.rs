@@ -1,12 +1,21 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, BlockInfo, CustomQuery, Deps, StdResult, Storage, Uint128};
-use cw_storage_plus::Map;
+use cw_storage_plus::{Item, Map};
 use cw_utils::Expiration;
 
 // TODO: pull into utils?
 #[cw_serde]
 pub struct ClaimsResponse {
-    pub claims: Vec<Claim>,
+    pub claims: Vec<ClaimInfo>,
+}
+
+/// A pending claim as returned by `Claims::query_claims`
+#[cw_serde]
+pub struct ClaimInfo {
+    pub amount: Uint128,
+    pub release_at: Expiration,
+    /// true if the claim is mature and would be paid out by `claim_tokens` now
+    pub releasable: bool,
 }
 
 // TODO: pull into utils?
@@ -26,11 +35,28 @@ impl Claim {
 }
 
 // TODO: revisit design (split each claim on own key?)
-pub struct Claims<'a>(Map<'a, &'a Addr, Vec<Claim>>);
+pub struct Claims<'a>(Map<'a, &'a Addr, Vec<Claim>>, Item<'a, Uint128>);
 
 impl<'a> Claims<'a> {
-    pub const fn new(storage_key: &'a str) -> Self {
-        Claims(Map::new(storage_key))
+    /// `total_key` stores the sum of all pending claims, which is kept up to date
+    /// on every change so it never has to be computed by iterating
+    pub const fn new(storage_key: &'a str, total_key: &'a str) -> Self {
+        Claims(Map::new(storage_key), Item::new(total_key))
+    }
+
+    /// Returns the amount of tokens locked in all pending claims
+    pub fn total(&self, storage: &dyn Storage) -> StdResult<Uint128> {
+        Ok(self.1.may_load(storage)?.unwrap_or_default())
+    }
+
+    fn add_total(&self, storage: &mut dyn Storage, amount: Uint128) -> StdResult<()> {
+        let total = self.total(storage)?.checked_add(amount)?;
+        self.1.save(storage, &total)
+    }
+
+    fn sub_total(&self, storage: &mut dyn Storage, amount: Uint128) -> StdResult<()> {
+        let total = self.total(storage)?.checked_sub(amount)?;
+        self.1.save(storage, &total)
     }
 
     /// This creates a claim, such that the given address can claim an amount of tokens after
@@ -48,7 +74,28 @@ impl<'a> Claims<'a> {
             claims.push(Claim { amount, release_at });
             Ok(claims)
         })?;
-        Ok(())
+        self.add_total(storage, amount)
+    }
+
+    /// Removes all claims of the address which are not mature yet, for example to stake
+    /// the tokens again. Returns the total amount of the removed claims.
+    pub fn cancel_claims(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+    ) -> StdResult<Uint128> {
+        let mut cancelled = Uint128::zero();
+        self.0.update(storage, addr, |claims| -> StdResult<_> {
+            let (pending, mature): (Vec<_>, _) = claims
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|c| !c.release_at.is_expired(block));
+            cancelled = pending.iter().map(|c| c.amount).sum();
+            Ok(mature)
+        })?;
+        self.sub_total(storage, cancelled)?;
+        Ok(cancelled)
     }
 
     /// This iterates over all mature claims for the address, and removes them, up to an optional cap.
@@ -59,18 +106,47 @@ impl<'a> Claims<'a> {
         addr: &Addr,
         block: &BlockInfo,
         cap: Option<Uint128>,
+    ) -> StdResult<Uint128> {
+        self.release(storage, addr, block, cap, usize::MAX)
+    }
+
+    /// Same as `claim_tokens`, but releases at most `limit` claims, so an address with
+    /// many claims can release them in several transactions.
+    pub fn claim_tokens_limited(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+        cap: Option<Uint128>,
+        limit: usize,
+    ) -> StdResult<Uint128> {
+        self.release(storage, addr, block, cap, limit)
+    }
+
+    fn release(
+        &self,
+        storage: &mut dyn Storage,
+        addr: &Addr,
+        block: &BlockInfo,
+        cap: Option<Uint128>,
+        limit: usize,
     ) -> StdResult<Uint128> {
         let mut to_send = Uint128::zero();
+        let mut released = 0;
         self.0.update(storage, addr, |claim| -> StdResult<_> {
             let (_send, waiting): (Vec<_>, _) =
                 claim.unwrap_or_default().into_iter().partition(|c| {
                     // if mature and we can pay fully, then include in _send
                     if c.release_at.is_expired(block) {
+                        if released >= limit {
+                            return false;
+                        }
                         if let Some(limit) = cap {
                             if to_send + c.amount > limit {
                                 return false;
                             }
                         }
+                        released += 1;
                         // TODO: handle partial paying claims?
                         to_send += c.amount;
                         true
@@ -81,6 +157,7 @@ impl<'a> Claims<'a> {
                 });
             Ok(waiting)
         })?;
+        self.sub_total(storage, to_send)?;
         Ok(to_send)
     }
 
@@ -88,8 +165,19 @@ impl<'a> Claims<'a> {
         &self,
         deps: Deps<Q>,
         address: &Addr,
+        block: &BlockInfo,
     ) -> StdResult<ClaimsResponse> {
-        let claims = self.0.may_load(deps.storage, address)?.unwrap_or_default();
+        let claims = self
+            .0
+            .may_load(deps.storage, address)?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| ClaimInfo {
+                releasable: c.release_at.is_expired(block),
+                amount: c.amount,
+                release_at: c.release_at,
+            })
+            .collect();
         Ok(ClaimsResponse { claims })
     }
 }
@@ -115,7 +203,7 @@ mod test {
     #[test]
     fn can_create_claims() {
         let deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
         // Assert that claims creates a map and there are no keys in the map.
         assert_eq!(
             claims
@@ -131,7 +219,7 @@ mod test {
     #[test]
     fn check_create_claim_updates_map() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -199,7 +287,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_no_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         let amount = claims
             .claim_tokens(
@@ -221,7 +309,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_no_released_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -269,7 +357,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_one_released_claim() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -315,7 +403,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_all_released_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -359,7 +447,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_zero_cap() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -407,7 +495,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_cap_greater_than_pending_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -451,7 +539,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_cap_only_one_claim_released() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -496,7 +584,7 @@ mod test {
     #[test]
     fn test_claim_tokens_with_cap_too_low_no_claims_released() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -540,10 +628,86 @@ mod test {
         assert_eq!(saved_claims[1].release_at, Expiration::AtHeight(5));
     }
 
+    #[test]
+    fn total_tracks_create_cancel_and_release() {
+        let mut deps = mock_dependencies();
+        let claims = Claims::new("claims", "claims_total");
+        let addr = Addr::unchecked("addr");
+        let addr2 = Addr::unchecked("addr2");
+        let mut env = mock_env();
+        env.block.height = 0;
+
+        assert_eq!(claims.total(&deps.storage).unwrap(), Uint128::zero());
+        for height in [10, 20, 30, 100] {
+            claims
+                .create_claim(
+                    deps.as_mut().storage,
+                    &addr,
+                    TEST_AMOUNT.into(),
+                    Expiration::AtHeight(height),
+                )
+                .unwrap();
+        }
+        claims
+            .create_claim(
+                deps.as_mut().storage,
+                &addr2,
+                (TEST_AMOUNT + 100).into(),
+                Expiration::AtHeight(10),
+            )
+            .unwrap();
+        assert_eq!(
+            claims.total(&deps.storage).unwrap(),
+            Uint128::from(5 * TEST_AMOUNT + 100)
+        );
+
+        // release the matured claims of addr in chunks
+        env.block.height = 50;
+        let amount = claims
+            .claim_tokens_limited(deps.as_mut().storage, &addr, &env.block, None, 2)
+            .unwrap();
+        assert_eq!(amount, Uint128::from(2 * TEST_AMOUNT));
+        assert_eq!(
+            claims.total(&deps.storage).unwrap(),
+            Uint128::from(3 * TEST_AMOUNT + 100)
+        );
+        let queried = claims
+            .query_claims(deps.as_ref(), &addr, &env.block)
+            .unwrap()
+            .claims;
+        assert_eq!(
+            queried
+                .iter()
+                .map(|c| (c.release_at, c.releasable))
+                .collect::<Vec<_>>(),
+            vec![
+                (Expiration::AtHeight(30), true),
+                (Expiration::AtHeight(100), false)
+            ]
+        );
+
+        // cancelling only removes the claims which are not mature yet
+        let amount = claims
+            .cancel_claims(deps.as_mut().storage, &addr, &env.block)
+            .unwrap();
+        assert_eq!(amount, Uint128::from(TEST_AMOUNT));
+        assert_eq!(
+            claims.total(&deps.storage).unwrap(),
+            Uint128::from(2 * TEST_AMOUNT + 100)
+        );
+
+        for addr in [&addr, &addr2] {
+            claims
+                .claim_tokens_limited(deps.as_mut().storage, addr, &env.block, None, 2)
+                .unwrap();
+        }
+        assert_eq!(claims.total(&deps.storage).unwrap(), Uint128::zero());
+    }
+
     #[test]
     fn test_query_claims_returns_correct_claims() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -553,21 +717,41 @@ mod test {
                 Expiration::AtHeight(10),
             )
             .unwrap();
+        claims
+            .create_claim(
+                deps.as_mut().storage,
+                &Addr::unchecked("addr"),
+                TEST_AMOUNT.into(),
+                Expiration::AtHeight(100),
+            )
+            .unwrap();
 
+        let mut env = mock_env();
+        env.block.height = 50;
         let queried_claims = claims
-            .query_claims(deps.as_ref(), &Addr::unchecked("addr"))
-            .unwrap();
-        let saved_claims = claims
-            .0
-            .load(deps.as_mut().storage, &Addr::unchecked("addr"))
+            .query_claims(deps.as_ref(), &Addr::unchecked("addr"), &env.block)
             .unwrap();
-        assert_eq!(queried_claims.claims, saved_claims);
+        assert_eq!(
+            queried_claims.claims,
+            vec![
+                ClaimInfo {
+                    amount: (TEST_AMOUNT + 100).into(),
+                    release_at: Expiration::AtHeight(10),
+                    releasable: true,
+                },
+                ClaimInfo {
+                    amount: TEST_AMOUNT.into(),
+                    release_at: Expiration::AtHeight(100),
+                    releasable: false,
+                },
+            ]
+        );
     }
 
     #[test]
     fn test_query_claims_returns_empty_for_non_existent_user() {
         let mut deps = mock_dependencies();
-        let claims = Claims::new("claims");
+        let claims = Claims::new("claims", "claims_total");
 
         claims
             .create_claim(
@@ -579,7 +763,7 @@ mod test {
             .unwrap();
 
         let queried_claims = claims
-            .query_claims(deps.as_ref(), &Addr::unchecked("addr2"))
+            .query_claims(deps.as_ref(), &Addr::unchecked("addr2"), &mock_env().block)
             .unwrap();
 
         assert_eq!(queried_claims.claims.len(), 0);
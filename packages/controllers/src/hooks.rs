@@ -4,8 +4,8 @@ use thiserror::Error;
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    attr, Addr, CustomQuery, Deps, DepsMut, MessageInfo, Response, StdError, StdResult, Storage,
-    SubMsg,
+    attr, Addr, CustomQuery, Deps, DepsMut, MessageInfo, ReplyOn, Response, StdError, StdResult,
+    Storage, SubMsg,
 };
 use cw_storage_plus::Item;
 
@@ -31,23 +31,36 @@ pub enum HookError {
 
     #[error("Given address not registered as a hook")]
     HookNotRegistered {},
+
+    #[error("Cannot register more than {max} hooks")]
+    TooManyHooks { max: u32 },
 }
 
 // store all hook addresses in one item. We cannot have many of them before the contract becomes unusable anyway.
-pub struct Hooks<'a>(Item<'a, Vec<Addr>>);
+pub struct Hooks<'a>(Item<'a, Vec<Addr>>, Option<u32>);
 
 impl<'a> Hooks<'a> {
     pub const fn new(storage_key: &'a str) -> Self {
-        Hooks(Item::new(storage_key))
+        Hooks(Item::new(storage_key), None)
+    }
+
+    /// Same as `new`, but `add_hook` fails once `max` hooks are registered,
+    /// which bounds the gas used to dispatch them
+    pub const fn with_max(storage_key: &'a str, max: u32) -> Self {
+        Hooks(Item::new(storage_key), Some(max))
     }
 
     pub fn add_hook(&self, storage: &mut dyn Storage, addr: Addr) -> Result<(), HookError> {
         let mut hooks = self.0.may_load(storage)?.unwrap_or_default();
-        if !hooks.iter().any(|h| h == &addr) {
-            hooks.push(addr);
-        } else {
+        if hooks.iter().any(|h| h == &addr) {
             return Err(HookError::HookAlreadyRegistered {});
         }
+        if let Some(max) = self.1 {
+            if hooks.len() >= max as usize {
+                return Err(HookError::TooManyHooks { max });
+            }
+        }
+        hooks.push(addr);
         Ok(self.0.save(storage, &hooks)?)
     }
 
@@ -74,6 +87,49 @@ impl<'a> Hooks<'a> {
             .collect()
     }
 
+    /// Like `prepare_hooks`, but every message is sent with `reply_on_error` and the id
+    /// `reply_id_base` plus the position of the hook. A failing hook then calls back into
+    /// the `reply` entry point of the contract instead of aborting the whole transaction.
+    /// Use `hook_for_reply` there to find out which hook failed.
+    pub fn prepare_hooks_with_reply<F: Fn(Addr) -> StdResult<SubMsg>>(
+        &self,
+        storage: &dyn Storage,
+        reply_id_base: u64,
+        prep: F,
+    ) -> StdResult<Vec<SubMsg>> {
+        self.prepare_hooks(storage, prep)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, msg)| {
+                Ok(SubMsg {
+                    id: reply_id_base + i as u64,
+                    reply_on: ReplyOn::Error,
+                    ..msg
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the hook a message created by `prepare_hooks_with_reply` went to,
+    /// or `None` if the reply id does not belong to one
+    pub fn hook_for_reply(
+        &self,
+        storage: &dyn Storage,
+        reply_id_base: u64,
+        reply_id: u64,
+    ) -> StdResult<Option<Addr>> {
+        let index = match reply_id.checked_sub(reply_id_base) {
+            Some(index) => index as usize,
+            None => return Ok(None),
+        };
+        let mut hooks = self.0.may_load(storage)?.unwrap_or_default();
+        if index < hooks.len() {
+            Ok(Some(hooks.swap_remove(index)))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn execute_add_hook<C, Q: CustomQuery>(
         &self,
         admin: &Admin,
@@ -128,4 +184,71 @@ impl<'a> Hooks<'a> {
     }
 }
 
-// TODO: add test coverage
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{to_binary, WasmMsg};
+
+    use super::*;
+
+    #[test]
+    fn hook_count_is_capped() {
+        let mut deps = mock_dependencies();
+        let hooks = Hooks::with_max("hooks", 2);
+
+        hooks
+            .add_hook(&mut deps.storage, Addr::unchecked("one"))
+            .unwrap();
+        let err = hooks
+            .add_hook(&mut deps.storage, Addr::unchecked("one"))
+            .unwrap_err();
+        assert_eq!(err, HookError::HookAlreadyRegistered {});
+        hooks
+            .add_hook(&mut deps.storage, Addr::unchecked("two"))
+            .unwrap();
+        let err = hooks
+            .add_hook(&mut deps.storage, Addr::unchecked("three"))
+            .unwrap_err();
+        assert_eq!(err, HookError::TooManyHooks { max: 2 });
+
+        // removing one makes room again
+        hooks
+            .remove_hook(&mut deps.storage, Addr::unchecked("one"))
+            .unwrap();
+        hooks
+            .add_hook(&mut deps.storage, Addr::unchecked("three"))
+            .unwrap();
+    }
+
+    #[test]
+    fn hooks_with_reply() {
+        let mut deps = mock_dependencies();
+        let hooks = Hooks::new("hooks");
+        hooks
+            .add_hook(&mut deps.storage, Addr::unchecked("one"))
+            .unwrap();
+        hooks
+            .add_hook(&mut deps.storage, Addr::unchecked("two"))
+            .unwrap();
+
+        let msgs = hooks
+            .prepare_hooks_with_reply(&deps.storage, 100, |h| {
+                Ok(SubMsg::new(WasmMsg::Execute {
+                    contract_addr: h.into(),
+                    msg: to_binary(&"hook")?,
+                    funds: vec![],
+                }))
+            })
+            .unwrap();
+        assert_eq!(
+            msgs.iter().map(|m| (m.id, &m.reply_on)).collect::<Vec<_>>(),
+            vec![(100, &ReplyOn::Error), (101, &ReplyOn::Error)]
+        );
+
+        let hook = |id| hooks.hook_for_reply(&deps.storage, 100, id).unwrap();
+        assert_eq!(hook(99), None);
+        assert_eq!(hook(100), Some(Addr::unchecked("one")));
+        assert_eq!(hook(101), Some(Addr::unchecked("two")));
+        assert_eq!(hook(102), None);
+    }
+}
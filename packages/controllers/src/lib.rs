@@ -13,11 +13,14 @@ goes for smaller scale units.
 Supported controllers:
 
 * Admin (`UpdateAdmin` handler, `Admin` querier, set_admin and is_admin methods)
+* Slashers (admin-managed list of addresses allowed to slash stake)
 */
 mod admin;
 mod claim;
 mod hooks;
+mod slashers;
 
 pub use admin::{Admin, AdminError, AdminResponse};
-pub use claim::{Claim, Claims, ClaimsResponse};
+pub use claim::{Claim, ClaimInfo, Claims, ClaimsResponse};
 pub use hooks::{HookError, Hooks, HooksResponse};
+pub use slashers::{SlasherError, Slashers, SlashersResponse};
@@ -0,0 +1,203 @@
+use schemars::JsonSchema;
+use std::fmt;
+use thiserror::Error;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    attr, Addr, CustomQuery, Deps, DepsMut, MessageInfo, Response, StdError, StdResult, Storage,
+};
+use cw_storage_plus::Item;
+
+use crate::admin::{Admin, AdminError};
+
+#[cw_serde]
+pub struct SlashersResponse {
+    pub slashers: Vec<String>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SlasherError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Admin(#[from] AdminError),
+
+    #[error("Given address already registered as a slasher")]
+    SlasherAlreadyRegistered {},
+
+    #[error("Given address not registered as a slasher")]
+    SlasherNotRegistered {},
+
+    #[error("Caller is not a slasher")]
+    NotSlasher {},
+}
+
+// store all slasher addresses in one item, like hooks, as we only expect a few of them
+pub struct Slashers<'a>(Item<'a, Vec<Addr>>);
+
+impl<'a> Slashers<'a> {
+    pub const fn new(storage_key: &'a str) -> Self {
+        Slashers(Item::new(storage_key))
+    }
+
+    pub fn add_slasher(&self, storage: &mut dyn Storage, addr: Addr) -> Result<(), SlasherError> {
+        let mut slashers = self.0.may_load(storage)?.unwrap_or_default();
+        if !slashers.iter().any(|s| s == &addr) {
+            slashers.push(addr);
+        } else {
+            return Err(SlasherError::SlasherAlreadyRegistered {});
+        }
+        Ok(self.0.save(storage, &slashers)?)
+    }
+
+    pub fn remove_slasher(
+        &self,
+        storage: &mut dyn Storage,
+        addr: Addr,
+    ) -> Result<(), SlasherError> {
+        let mut slashers = self.0.may_load(storage)?.unwrap_or_default();
+        if let Some(p) = slashers.iter().position(|x| x == &addr) {
+            slashers.remove(p);
+        } else {
+            return Err(SlasherError::SlasherNotRegistered {});
+        }
+        Ok(self.0.save(storage, &slashers)?)
+    }
+
+    pub fn is_slasher<Q: CustomQuery>(&self, deps: Deps<Q>, addr: &Addr) -> StdResult<bool> {
+        let slashers = self.0.may_load(deps.storage)?.unwrap_or_default();
+        Ok(slashers.iter().any(|s| s == addr))
+    }
+
+    /// Like is_slasher but returns SlasherError::NotSlasher if not a slasher
+    pub fn assert_slasher<Q: CustomQuery>(
+        &self,
+        deps: Deps<Q>,
+        caller: &Addr,
+    ) -> Result<(), SlasherError> {
+        if !self.is_slasher(deps, caller)? {
+            Err(SlasherError::NotSlasher {})
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn execute_add_slasher<C, Q: CustomQuery>(
+        &self,
+        admin: &Admin,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+        addr: Addr,
+    ) -> Result<Response<C>, SlasherError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        admin.assert_admin(deps.as_ref(), &info.sender)?;
+        self.add_slasher(deps.storage, addr.clone())?;
+
+        let attributes = vec![
+            attr("action", "add_slasher"),
+            attr("slasher", addr),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn execute_remove_slasher<C, Q: CustomQuery>(
+        &self,
+        admin: &Admin,
+        deps: DepsMut<Q>,
+        info: MessageInfo,
+        addr: Addr,
+    ) -> Result<Response<C>, SlasherError>
+    where
+        C: Clone + fmt::Debug + PartialEq + JsonSchema,
+    {
+        admin.assert_admin(deps.as_ref(), &info.sender)?;
+        self.remove_slasher(deps.storage, addr.clone())?;
+
+        let attributes = vec![
+            attr("action", "remove_slasher"),
+            attr("slasher", addr),
+            attr("sender", info.sender),
+        ];
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn query_slashers<Q: CustomQuery>(&self, deps: Deps<Q>) -> StdResult<SlashersResponse> {
+        let slashers = self.0.may_load(deps.storage)?.unwrap_or_default();
+        let slashers = slashers.into_iter().map(String::from).collect();
+        Ok(SlashersResponse { slashers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_info};
+    use cosmwasm_std::Empty;
+
+    #[test]
+    fn add_remove_slashers() {
+        let mut deps = mock_dependencies();
+        let admin = Admin::new("admin");
+        let slashers = Slashers::new("slashers");
+
+        let owner = Addr::unchecked("owner");
+        let slasher = Addr::unchecked("slasher");
+        admin.set(deps.as_mut(), Some(owner.clone())).unwrap();
+
+        // only admin can add
+        let err = slashers
+            .execute_add_slasher::<Empty, Empty>(
+                &admin,
+                deps.as_mut(),
+                mock_info("other", &[]),
+                slasher.clone(),
+            )
+            .unwrap_err();
+        assert_eq!(err, SlasherError::Admin(AdminError::NotAdmin {}));
+
+        slashers
+            .execute_add_slasher::<Empty, Empty>(
+                &admin,
+                deps.as_mut(),
+                mock_info(owner.as_str(), &[]),
+                slasher.clone(),
+            )
+            .unwrap();
+        assert!(slashers.is_slasher(deps.as_ref(), &slasher).unwrap());
+        assert_eq!(
+            slashers.query_slashers(deps.as_ref()).unwrap().slashers,
+            vec![slasher.to_string()]
+        );
+
+        // cannot add twice
+        let err = slashers
+            .add_slasher(deps.as_mut().storage, slasher.clone())
+            .unwrap_err();
+        assert_eq!(err, SlasherError::SlasherAlreadyRegistered {});
+
+        // remove it again
+        slashers
+            .execute_remove_slasher::<Empty, Empty>(
+                &admin,
+                deps.as_mut(),
+                mock_info(owner.as_str(), &[]),
+                slasher.clone(),
+            )
+            .unwrap();
+        assert_eq!(
+            slashers
+                .assert_slasher(deps.as_ref(), &slasher)
+                .unwrap_err(),
+            SlasherError::NotSlasher {}
+        );
+        let err = slashers
+            .remove_slasher(deps.as_mut().storage, slasher)
+            .unwrap_err();
+        assert_eq!(err, SlasherError::SlasherNotRegistered {});
+    }
+}
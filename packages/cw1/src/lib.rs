@@ -24,7 +24,9 @@ For more information on this specification, please check out the
 pub mod helpers;
 pub mod msg;
 pub mod query;
+pub mod summary;
 
 pub use crate::helpers::Cw1Contract;
 pub use crate::msg::Cw1ExecuteMsg;
 pub use crate::query::{CanExecuteResponse, Cw1QueryMsg};
+pub use crate::summary::msg_type;
@@ -0,0 +1,102 @@
+use cosmwasm_std::{BankMsg, CosmosMsg, WasmMsg};
+#[cfg(feature = "staking")]
+use cosmwasm_std::{DistributionMsg, StakingMsg};
+#[cfg(feature = "stargate")]
+use cosmwasm_std::{GovMsg, IbcMsg};
+
+/// Short, stable name of a message's type, like `bank/send` or `wasm/execute`, for use in
+/// event attributes. Variants this build doesn't know about are reported as `unknown`.
+pub fn msg_type<T>(msg: &CosmosMsg<T>) -> &'static str {
+    match msg {
+        CosmosMsg::Bank(bank) => match bank {
+            BankMsg::Send { .. } => "bank/send",
+            BankMsg::Burn { .. } => "bank/burn",
+            _ => "bank/unknown",
+        },
+        CosmosMsg::Custom(_) => "custom",
+        #[cfg(feature = "staking")]
+        CosmosMsg::Staking(staking) => match staking {
+            StakingMsg::Delegate { .. } => "staking/delegate",
+            StakingMsg::Undelegate { .. } => "staking/undelegate",
+            StakingMsg::Redelegate { .. } => "staking/redelegate",
+            _ => "staking/unknown",
+        },
+        #[cfg(feature = "staking")]
+        CosmosMsg::Distribution(distribution) => match distribution {
+            DistributionMsg::SetWithdrawAddress { .. } => "distribution/set_withdraw_address",
+            DistributionMsg::WithdrawDelegatorReward { .. } => {
+                "distribution/withdraw_delegator_reward"
+            }
+            _ => "distribution/unknown",
+        },
+        #[cfg(feature = "stargate")]
+        CosmosMsg::Stargate { .. } => "stargate",
+        #[cfg(feature = "stargate")]
+        CosmosMsg::Ibc(ibc) => match ibc {
+            IbcMsg::Transfer { .. } => "ibc/transfer",
+            IbcMsg::SendPacket { .. } => "ibc/send_packet",
+            IbcMsg::CloseChannel { .. } => "ibc/close_channel",
+            _ => "ibc/unknown",
+        },
+        #[cfg(feature = "stargate")]
+        CosmosMsg::Gov(GovMsg::Vote { .. }) => "gov/vote",
+        CosmosMsg::Wasm(wasm) => match wasm {
+            WasmMsg::Execute { .. } => "wasm/execute",
+            WasmMsg::Instantiate { .. } => "wasm/instantiate",
+            WasmMsg::Migrate { .. } => "wasm/migrate",
+            WasmMsg::UpdateAdmin { .. } => "wasm/update_admin",
+            WasmMsg::ClearAdmin { .. } => "wasm/clear_admin",
+            _ => "wasm/unknown",
+        },
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::{coins, Binary, Empty};
+
+    #[test]
+    fn msg_types() {
+        let cases: Vec<(CosmosMsg, _)> = vec![
+            (
+                BankMsg::Send {
+                    to_address: "recipient".to_owned(),
+                    amount: coins(1, "token"),
+                }
+                .into(),
+                "bank/send",
+            ),
+            (
+                BankMsg::Burn {
+                    amount: coins(1, "token"),
+                }
+                .into(),
+                "bank/burn",
+            ),
+            (
+                WasmMsg::Execute {
+                    contract_addr: "contract".to_owned(),
+                    msg: Binary::default(),
+                    funds: vec![],
+                }
+                .into(),
+                "wasm/execute",
+            ),
+            (
+                WasmMsg::ClearAdmin {
+                    contract_addr: "contract".to_owned(),
+                }
+                .into(),
+                "wasm/clear_admin",
+            ),
+            (CosmosMsg::Custom(Empty {}), "custom"),
+        ];
+
+        for (msg, expected) in cases {
+            assert_eq!(msg_type(&msg), expected);
+        }
+    }
+}
@@ -18,8 +18,11 @@ For more information on this specification, please check out the
 */
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{CustomQuery, QuerierWrapper, QueryRequest, StdResult, Storage, WasmQuery};
+use cosmwasm_std::{
+    CustomQuery, QuerierWrapper, QueryRequest, StdError, StdResult, Storage, WasmQuery,
+};
 use cw_storage_plus::Item;
+use semver::Version;
 
 pub const CONTRACT: Item<ContractVersion> = Item::new("contract_info");
 
@@ -33,6 +36,10 @@ pub struct ContractVersion {
     /// the only code that needs to understand the version parsing is code that knows how to
     /// migrate from the given contract (and is tied to it's implementation somehow)
     pub version: String,
+    /// commit_hash optionally records the source revision the wasm was built from, so the
+    /// deployed code can be matched against a reproducible build
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_hash: Option<String>,
 }
 
 /// get_contract_version can be use in migrate to read the previous version of this contract
@@ -50,10 +57,63 @@ pub fn set_contract_version<T: Into<String>, U: Into<String>>(
     let val = ContractVersion {
         contract: name.into(),
         version: version.into(),
+        commit_hash: None,
     };
     CONTRACT.save(store, &val)
 }
 
+/// set_contract_version_with_commit_hash works like set_contract_version, but also records
+/// the source revision the contract was built from
+pub fn set_contract_version_with_commit_hash<T: Into<String>, U: Into<String>, V: Into<String>>(
+    store: &mut dyn Storage,
+    name: T,
+    version: U,
+    commit_hash: V,
+) -> StdResult<()> {
+    let val = ContractVersion {
+        contract: name.into(),
+        version: version.into(),
+        commit_hash: Some(commit_hash.into()),
+    };
+    CONTRACT.save(store, &val)
+}
+
+/// ensure_from_older_version should be called at the start of migrate. It checks that the stored
+/// contract name matches `name` and that the stored version is not newer than `new_version`
+/// (compared as semver), then stores `new_version`. The original version is returned, so the
+/// migration can decide which state upgrades to run.
+pub fn ensure_from_older_version(
+    store: &mut dyn Storage,
+    name: &str,
+    new_version: &str,
+) -> StdResult<Version> {
+    let version: Version = new_version.parse().map_err(from_semver)?;
+    let stored = get_contract_version(store)?;
+    let stored_version: Version = stored.version.parse().map_err(from_semver)?;
+
+    if name != stored.contract {
+        let msg = format!("Cannot migrate from {} to {}", stored.contract, name);
+        return Err(StdError::generic_err(msg));
+    }
+    if stored_version > version {
+        let msg = format!(
+            "Cannot migrate from newer version ({}) to older ({})",
+            stored.version, new_version
+        );
+        return Err(StdError::generic_err(msg));
+    }
+    if stored_version < version {
+        // nothing to write when re-migrating to the same version
+        set_contract_version(store, name, new_version)?;
+    }
+
+    Ok(stored_version)
+}
+
+fn from_semver(err: semver::Error) -> StdError {
+    StdError::generic_err(format!("Semver: {}", err))
+}
+
 /// This will make a raw_query to another contract to determine the current version it
 /// claims to be. This should not be trusted, but could be used as a quick filter
 /// if the other contract exists and claims to be a cw20-base contract for example.
@@ -95,7 +155,68 @@ mod tests {
         let expected = ContractVersion {
             contract: contract_name.to_string(),
             version: contract_version.to_string(),
+            commit_hash: None,
         };
         assert_eq!(expected, loaded);
     }
+
+    #[test]
+    fn commit_hash_is_optional() {
+        let mut store = MockStorage::new();
+
+        set_contract_version_with_commit_hash(&mut store, "crate:demo", "0.1.0", "abc123").unwrap();
+        let loaded = get_contract_version(&store).unwrap();
+        assert_eq!(loaded.commit_hash, Some("abc123".to_string()));
+
+        // versions stored before the field existed still load
+        store.set(
+            CONTRACT.as_slice(),
+            br#"{"contract":"crate:demo","version":"0.1.0"}"#,
+        );
+        let loaded = get_contract_version(&store).unwrap();
+        assert_eq!(loaded.commit_hash, None);
+
+        // and no hash is written as the old format
+        set_contract_version(&mut store, "crate:demo", "0.2.0").unwrap();
+        assert_eq!(
+            store.get(CONTRACT.as_slice()).unwrap(),
+            br#"{"contract":"crate:demo","version":"0.2.0"}"#.to_vec()
+        );
+    }
+
+    #[test]
+    fn ensure_from_older_version_updates() {
+        let mut store = MockStorage::new();
+        set_contract_version(&mut store, "demo", "0.4.0").unwrap();
+
+        // semver ordering, not string ordering
+        let original = ensure_from_older_version(&mut store, "demo", "0.10.0").unwrap();
+        assert_eq!(original, "0.4.0".parse::<Version>().unwrap());
+        assert_eq!(get_contract_version(&store).unwrap().version, "0.10.0");
+
+        // same version is accepted
+        ensure_from_older_version(&mut store, "demo", "0.10.0").unwrap();
+    }
+
+    #[test]
+    fn ensure_from_older_version_rejects() {
+        let mut store = MockStorage::new();
+        set_contract_version(&mut store, "demo", "0.10.0").unwrap();
+
+        let err = ensure_from_older_version(&mut store, "demo", "0.9.0").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Cannot migrate from newer version"));
+
+        let err = ensure_from_older_version(&mut store, "other", "0.11.0").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Cannot migrate from demo to other"));
+
+        let err = ensure_from_older_version(&mut store, "demo", "v1").unwrap_err();
+        assert!(err.to_string().contains("Semver"));
+
+        // nothing was changed
+        assert_eq!(get_contract_version(&store).unwrap().version, "0.10.0");
+    }
 }
@@ -0,0 +1,36 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Coin, Uint128};
+
+use crate::Cw20Coin;
+
+/// An amount of either a native token or a cw20 token
+#[cw_serde]
+pub enum Amount {
+    Native(Coin),
+    Cw20(Cw20Coin),
+}
+
+impl Amount {
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            Amount::Native(c) => c.amount,
+            Amount::Cw20(c) => c.amount,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amount().is_zero()
+    }
+}
+
+impl From<Coin> for Amount {
+    fn from(coin: Coin) -> Amount {
+        Amount::Native(coin)
+    }
+}
+
+impl From<Cw20Coin> for Amount {
+    fn from(coin: Cw20Coin) -> Amount {
+        Amount::Cw20(coin)
+    }
+}
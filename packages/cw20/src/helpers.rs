@@ -1,7 +1,7 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, CustomQuery, QuerierWrapper, QueryRequest, StdResult, Uint128,
-    WasmMsg, WasmQuery,
+    to_binary, Addr, Binary, CosmosMsg, CustomQuery, QuerierWrapper, QueryRequest, StdResult,
+    Uint128, WasmMsg, WasmQuery,
 };
 
 use crate::{
@@ -31,6 +31,33 @@ impl Cw20Contract {
         .into())
     }
 
+    /// Transfer `amount` tokens of this contract to `recipient`
+    pub fn transfer<T: Into<String>>(&self, recipient: T, amount: Uint128) -> StdResult<CosmosMsg> {
+        self.call(Cw20ExecuteMsg::Transfer {
+            recipient: recipient.into(),
+            amount,
+        })
+    }
+
+    /// Send `amount` tokens of this contract to `contract`, triggering its `Receive` handler with `msg`
+    pub fn send<T: Into<String>>(
+        &self,
+        contract: T,
+        amount: Uint128,
+        msg: Binary,
+    ) -> StdResult<CosmosMsg> {
+        self.call(Cw20ExecuteMsg::Send {
+            contract: contract.into(),
+            amount,
+            msg,
+        })
+    }
+
+    /// Burn `amount` tokens of this contract
+    pub fn burn(&self, amount: Uint128) -> StdResult<CosmosMsg> {
+        self.call(Cw20ExecuteMsg::Burn { amount })
+    }
+
     fn encode_smart_query<CQ: CustomQuery>(
         &self,
         msg: Cw20QueryMsg,
@@ -103,3 +130,44 @@ impl Cw20Contract {
         self.minter(querier).is_ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand_built(msg: &Cw20ExecuteMsg) -> CosmosMsg {
+        WasmMsg::Execute {
+            contract_addr: "token".to_string(),
+            msg: to_binary(msg).unwrap(),
+            funds: vec![],
+        }
+        .into()
+    }
+
+    #[test]
+    fn execute_helpers_match_hand_built_messages() {
+        let token = Cw20Contract(Addr::unchecked("token"));
+        let amount = Uint128::new(1234);
+
+        let msg = token.transfer("recipient", amount).unwrap();
+        let expected = hand_built(&Cw20ExecuteMsg::Transfer {
+            recipient: "recipient".to_string(),
+            amount,
+        });
+        assert_eq!(msg, expected);
+        assert_eq!(to_binary(&msg).unwrap(), to_binary(&expected).unwrap());
+
+        let payload = Binary::from(b"{\"action\":{}}");
+        let msg = token.send("contract", amount, payload.clone()).unwrap();
+        let expected = hand_built(&Cw20ExecuteMsg::Send {
+            contract: "contract".to_string(),
+            amount,
+            msg: payload,
+        });
+        assert_eq!(to_binary(&msg).unwrap(), to_binary(&expected).unwrap());
+
+        let msg = token.burn(amount).unwrap();
+        let expected = hand_built(&Cw20ExecuteMsg::Burn { amount });
+        assert_eq!(to_binary(&msg).unwrap(), to_binary(&expected).unwrap());
+    }
+}
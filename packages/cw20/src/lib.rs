@@ -11,6 +11,7 @@ For more information on this specification, please check out the
 
 pub use cw_utils::Expiration;
 
+pub use crate::amount::Amount;
 pub use crate::balance::Balance;
 pub use crate::coin::{Cw20Coin, Cw20CoinVerified};
 pub use crate::denom::{Denom, UncheckedDenom};
@@ -22,8 +23,9 @@ pub use crate::query::{
     AllowanceResponse, BalanceResponse, Cw20QueryMsg, DownloadLogoResponse, MarketingInfoResponse,
     MinterResponse, SpenderAllowanceInfo, TokenInfoResponse,
 };
-pub use crate::receiver::Cw20ReceiveMsg;
+pub use crate::receiver::{verified_receive, Cw20ReceiveError, Cw20ReceiveMsg};
 
+mod amount;
 mod balance;
 mod coin;
 mod denom;
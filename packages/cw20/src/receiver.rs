@@ -1,5 +1,10 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, Uint128, WasmMsg};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Binary, CosmosMsg, MessageInfo, StdError, StdResult, Uint128,
+    WasmMsg,
+};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
 
 /// Cw20ReceiveMsg should be de/serialized under `Receive()` variant in a ExecuteMsg
 #[cw_serde]
@@ -27,6 +32,36 @@ impl Cw20ReceiveMsg {
         };
         Ok(execute.into())
     }
+
+    /// parses the message the sender attached for the receiving contract
+    pub fn into_inner<M: DeserializeOwned>(self) -> StdResult<M> {
+        from_binary(&self.msg)
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Cw20ReceiveError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Expected tokens from {expected}, got a call from {sender}")]
+    UnexpectedToken { expected: String, sender: String },
+}
+
+/// Checks that a `Receive` call comes from the expected token contract, as any contract
+/// can send a `Cw20ReceiveMsg`, and parses the attached message.
+pub fn verified_receive<M: DeserializeOwned>(
+    info: &MessageInfo,
+    expected_token: &Addr,
+    msg: &Cw20ReceiveMsg,
+) -> Result<M, Cw20ReceiveError> {
+    if &info.sender != expected_token {
+        return Err(Cw20ReceiveError::UnexpectedToken {
+            expected: expected_token.to_string(),
+            sender: info.sender.to_string(),
+        });
+    }
+    Ok(from_binary(&msg.msg)?)
 }
 
 // This is just a helper to properly serialize the above message
@@ -35,3 +70,62 @@ impl Cw20ReceiveMsg {
 enum ReceiverExecuteMsg {
     Receive(Cw20ReceiveMsg),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::mock_info;
+
+    #[cw_serde]
+    enum HookMsg {
+        Deposit { memo: String },
+    }
+
+    fn receive_msg(msg: Binary) -> Cw20ReceiveMsg {
+        Cw20ReceiveMsg {
+            sender: "alice".to_string(),
+            amount: Uint128::new(100),
+            msg,
+        }
+    }
+
+    #[test]
+    fn verified_receive_checks_token() {
+        let token = Addr::unchecked("token");
+        let hook = HookMsg::Deposit {
+            memo: "hi".to_string(),
+        };
+        let msg = receive_msg(to_binary(&hook).unwrap());
+
+        let parsed: HookMsg = verified_receive(&mock_info("token", &[]), &token, &msg).unwrap();
+        assert_eq!(parsed, hook);
+        assert_eq!(msg.into_inner::<HookMsg>().unwrap(), hook);
+
+        // any contract can send a Cw20ReceiveMsg, claiming tokens it does not have
+        let msg = receive_msg(to_binary(&hook).unwrap());
+        let err =
+            verified_receive::<HookMsg>(&mock_info("spoofer", &[]), &token, &msg).unwrap_err();
+        assert_eq!(
+            err,
+            Cw20ReceiveError::UnexpectedToken {
+                expected: "token".to_string(),
+                sender: "spoofer".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_inner_msg() {
+        let token = Addr::unchecked("token");
+        let msg = receive_msg(Binary::from(br#"{"withdraw":{}}"#));
+
+        let err = verified_receive::<HookMsg>(&mock_info("token", &[]), &token, &msg).unwrap_err();
+        assert!(matches!(
+            err,
+            Cw20ReceiveError::Std(StdError::ParseErr { .. })
+        ));
+        let err = msg.into_inner::<HookMsg>().unwrap_err();
+        assert!(matches!(err, StdError::ParseErr { .. }));
+    }
+}
@@ -83,12 +83,12 @@ impl DepositInfo {
         }
     }
 
-    pub fn get_take_deposit_messages(
+    pub fn get_take_deposit_messages<T>(
         &self,
         depositor: &Addr,
         contract: &Addr,
-    ) -> StdResult<Vec<CosmosMsg>> {
-        let take_deposit_msg: Vec<CosmosMsg> = if let DepositInfo {
+    ) -> StdResult<Vec<CosmosMsg<T>>> {
+        let take_deposit_msg: Vec<CosmosMsg<T>> = if let DepositInfo {
             amount,
             denom: Denom::Cw20(address),
             ..
@@ -116,7 +116,7 @@ impl DepositInfo {
         Ok(take_deposit_msg)
     }
 
-    pub fn get_return_deposit_message(&self, depositor: &Addr) -> StdResult<CosmosMsg> {
+    pub fn get_return_deposit_message<T>(&self, depositor: &Addr) -> StdResult<CosmosMsg<T>> {
         let message = match &self.denom {
             Denom::Native(denom) => BankMsg::Send {
                 to_address: depositor.to_string(),
@@ -9,12 +9,12 @@ use crate::{DepositInfo, Status, Vote};
 const PRECISION_FACTOR: u128 = 1_000_000_000;
 
 #[cw_serde]
-pub struct Proposal {
+pub struct Proposal<T = Empty> {
     pub title: String,
     pub description: String,
     pub start_height: u64,
     pub expires: Expiration,
-    pub msgs: Vec<CosmosMsg<Empty>>,
+    pub msgs: Vec<CosmosMsg<T>>,
     pub status: Status,
     /// pass requirements
     pub threshold: Threshold,
@@ -29,7 +29,7 @@ pub struct Proposal {
     pub deposit: Option<DepositInfo>,
 }
 
-impl Proposal {
+impl<T> Proposal<T> {
     /// current_status is non-mutable and returns what the status should be.
     /// (designed for queries)
     pub fn current_status(&self, block: &BlockInfo) -> Status {
@@ -153,6 +153,16 @@ impl Votes {
             Vote::Veto => self.veto += weight,
         }
     }
+
+    /// takes back a previously added vote, e.g. when a voter changes their mind
+    pub fn remove_vote(&mut self, vote: Vote, weight: u64) {
+        match vote {
+            Vote::Yes => self.yes -= weight,
+            Vote::Abstain => self.abstain -= weight,
+            Vote::No => self.no -= weight,
+            Vote::Veto => self.veto -= weight,
+        }
+    }
 }
 
 // this is a helper function so Decimal works with u64 rather than Uint128
@@ -189,6 +199,12 @@ mod test {
         assert_eq!(votes.no, 10);
         assert_eq!(votes.veto, 20);
         assert_eq!(votes.abstain, 40);
+
+        votes.remove_vote(Vote::No, 10);
+        votes.add_vote(Vote::Yes, 10);
+        assert_eq!(votes.total(), 105);
+        assert_eq!(votes.yes, 45);
+        assert_eq!(votes.no, 0);
     }
 
     #[test]
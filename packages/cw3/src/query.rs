@@ -63,6 +63,9 @@ pub struct ProposalResponse<T = Empty> {
     /// valid information for existing proposals.
     pub threshold: ThresholdResponse,
     pub proposer: Addr,
+    /// The deposit paid when opening the proposal, if the contract requires one.
+    /// Defaults to `None` so responses from contracts predating deposits still parse.
+    #[serde(default)]
     pub deposit: Option<DepositInfo>,
 }
 
@@ -80,6 +83,10 @@ pub enum Status {
     Passed = 4,
     /// voting is over it passed, and the proposal was executed
     Executed = 5,
+    /// voting is over and it did pass, but execution was vetoed
+    Vetoed = 6,
+    /// voting is over and a proposal without messages (signaling vote) passed and was finalized
+    Completed = 7,
 }
 
 #[cw_serde]
@@ -122,3 +129,26 @@ pub struct VoterDetail {
     pub addr: String,
     pub weight: u64,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::from_slice;
+
+    #[test]
+    fn proposal_response_without_deposit() {
+        let json = br#"{
+            "id": 1,
+            "title": "Pay somebody",
+            "description": "Do I pay her?",
+            "msgs": [],
+            "status": "open",
+            "expires": {"at_height": 12345},
+            "threshold": {"absolute_count": {"weight": 3, "total_weight": 5}},
+            "proposer": "somebody"
+        }"#;
+        let res: ProposalResponse = from_slice(json).unwrap();
+        assert_eq!(res.deposit, None);
+        assert_eq!(res.proposer, Addr::unchecked("somebody"));
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, WasmMsg};
 
@@ -21,6 +23,11 @@ impl MemberDiff {
             new: new_weight,
         }
     }
+
+    /// The change this diff makes to the total weight of the group
+    pub fn total_delta(&self) -> i128 {
+        i128::from(self.new.unwrap_or_default()) - i128::from(self.old.unwrap_or_default())
+    }
 }
 
 /// MemberChangedHookMsg should be de/serialized under `MemberChangedHook()` variant in a ExecuteMsg.
@@ -28,15 +35,43 @@ impl MemberDiff {
 #[cw_serde]
 pub struct MemberChangedHookMsg {
     pub diffs: Vec<MemberDiff>,
+    /// The block height at which the group applied the diffs, if the sender provides it.
+    /// Snapshot queries for any later height see the new weights.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
 }
 
 impl MemberChangedHookMsg {
     pub fn one(diff: MemberDiff) -> Self {
-        MemberChangedHookMsg { diffs: vec![diff] }
+        Self::new(vec![diff])
     }
 
     pub fn new(diffs: Vec<MemberDiff>) -> Self {
-        MemberChangedHookMsg { diffs }
+        MemberChangedHookMsg {
+            diffs,
+            height: None,
+        }
+    }
+
+    /// stamps the height at which the diffs were applied
+    pub fn with_height(mut self, height: u64) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// The change all diffs make to the total weight of the group
+    pub fn total_delta(&self) -> i128 {
+        self.diffs.iter().map(MemberDiff::total_delta).sum()
+    }
+
+    /// Applies the diffs to a local copy of the member weights, keyed by address
+    pub fn apply_to(&self, members: &mut BTreeMap<String, u64>) {
+        for diff in &self.diffs {
+            match diff.new {
+                Some(weight) => members.insert(diff.key.clone(), weight),
+                None => members.remove(&diff.key),
+            };
+        }
     }
 
     /// serializes the message
@@ -63,3 +98,66 @@ impl MemberChangedHookMsg {
 enum MemberChangedExecuteMsg {
     MemberChangedHook(MemberChangedHookMsg),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::from_slice;
+
+    fn members(list: &[(&str, u64)]) -> BTreeMap<String, u64> {
+        list.iter().map(|&(k, w)| (k.to_string(), w)).collect()
+    }
+
+    #[test]
+    fn apply_to_members() {
+        let mut local = members(&[("alice", 5), ("bob", 3)]);
+        let msg = MemberChangedHookMsg::new(vec![
+            MemberDiff::new("carl", None, Some(2)),
+            MemberDiff::new("alice", Some(5), Some(7)),
+            MemberDiff::new("bob", Some(3), None),
+        ]);
+        msg.apply_to(&mut local);
+        assert_eq!(local, members(&[("alice", 7), ("carl", 2)]));
+
+        // removing someone unknown changes nothing
+        MemberChangedHookMsg::one(MemberDiff::new("dave", Some(1), None)).apply_to(&mut local);
+        assert_eq!(local, members(&[("alice", 7), ("carl", 2)]));
+    }
+
+    #[test]
+    fn total_delta() {
+        assert_eq!(MemberDiff::new("carl", None, Some(2)).total_delta(), 2);
+        assert_eq!(MemberDiff::new("alice", Some(5), Some(7)).total_delta(), 2);
+        assert_eq!(MemberDiff::new("bob", Some(3), None).total_delta(), -3);
+        assert_eq!(
+            MemberDiff::new("max", Some(u64::MAX), None).total_delta(),
+            -i128::from(u64::MAX)
+        );
+
+        let msg = MemberChangedHookMsg::new(vec![
+            MemberDiff::new("carl", None, Some(2)),
+            MemberDiff::new("bob", Some(3), None),
+        ]);
+        assert_eq!(msg.total_delta(), -1);
+    }
+
+    #[test]
+    fn height_is_optional() {
+        let msg: MemberChangedHookMsg = from_slice(br#"{"diffs":[]}"#).unwrap();
+        assert_eq!(msg.height, None);
+
+        let encoded = MemberChangedHookMsg::new(vec![]).into_binary().unwrap();
+        assert_eq!(
+            encoded.as_slice(),
+            br#"{"member_changed_hook":{"diffs":[]}}"#
+        );
+        let encoded = MemberChangedHookMsg::new(vec![])
+            .with_height(12)
+            .into_binary()
+            .unwrap();
+        assert_eq!(
+            encoded.as_slice(),
+            br#"{"member_changed_hook":{"diffs":[],"height":12}}"#
+        );
+    }
+}